@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use winit::{
     application::ApplicationHandler,
@@ -7,16 +8,21 @@ use winit::{
 };
 
 use crate::game::config::GameConfig;
+use crate::game::edge::EdgeTransfer;
 use crate::game::input::{GameAction, InputState};
 use crate::game::inventory::Inventory;
 use crate::game::recipes::RecipeIndex;
 use crate::game::world::{Direction, EntityId, StructureKind, WorldState};
 use crate::hyperbolic::poincare::{canonical_polygon, polygon_disk_radius, Complex, TilingConfig};
-use crate::hyperbolic::cell_id::CellId;
-use crate::hyperbolic::tiling::format_cell_id;
+use crate::hyperbolic::cell_id::{self, CellId};
+use crate::hyperbolic::tiling::{format_cell_id, TileAddr};
+use crate::net::protocol::{Cursor, PlayerAction, PlayerId};
+use crate::net::session::{NetSession, Welcome};
+use crate::net::snapshot::Snapshot;
+use crate::net::NetMode;
 use crate::render::camera::Camera;
 use crate::render::engine::{project_to_screen, RenderEngine};
-use crate::render::instances::{BeltInstance, ItemInstance, MachineInstance};
+use crate::render::instances::{BeltInstance, ItemInstance, MachineInstance, PipeInstance};
 use crate::render::mesh::build_polygon_mesh;
 use crate::sim::belt::BeltNetwork;
 use crate::sim::tick::GameLoop;
@@ -40,6 +46,9 @@ struct BeltDrag {
     fixed_coord: i32,
     /// The last grid coordinate placed along the free axis
     last_free: i32,
+    /// Facing of the placed structures, in this tile's grid. Turns with the
+    /// grid when the drag crosses into a turned neighbor.
+    direction: Direction,
 }
 
 /// UI-only state extracted from App: flash notifications, drag state, cursor,
@@ -62,6 +71,20 @@ pub struct UiState {
     pub splitter_panel_entity: Option<EntityId>,
     /// Currently inspected storage entity (opens the storage panel).
     pub storage_panel_entity: Option<EntityId>,
+    /// Currently inspected sink entity (opens the sink panel).
+    pub sink_panel_entity: Option<EntityId>,
+    /// Currently inspected loader entity (opens the loader panel).
+    pub loader_panel_entity: Option<EntityId>,
+    /// Currently inspected train station (opens the station panel).
+    pub station_panel_entity: Option<EntityId>,
+    /// Currently inspected train (opens the train panel).
+    pub train_panel: Option<crate::sim::rail::TrainId>,
+    /// Currently inspected belt (opens the belt panel).
+    pub belt_panel_entity: Option<EntityId>,
+    /// Currently inspected combinator (opens the combinator panel).
+    pub combinator_panel_entity: Option<EntityId>,
+    /// First terminal picked while laying a signal wire.
+    pub wire_start: Option<crate::sim::circuit::WireEnd>,
 }
 
 impl UiState {
@@ -80,11 +103,36 @@ impl UiState {
             machine_panel_entity: None,
             splitter_panel_entity: None,
             storage_panel_entity: None,
+            sink_panel_entity: None,
+            loader_panel_entity: None,
+            station_panel_entity: None,
+            train_panel: None,
+            belt_panel_entity: None,
+            combinator_panel_entity: None,
+            wire_start: None,
         }
     }
 
     fn is_panel_open(&self) -> bool {
-        self.settings_open || self.inventory_open || self.machine_panel_entity.is_some() || self.splitter_panel_entity.is_some() || self.storage_panel_entity.is_some()
+        self.settings_open || self.inventory_open || self.is_inspecting()
+    }
+
+    /// Whether any entity inspection panel is open.
+    fn is_inspecting(&self) -> bool {
+        self.machine_panel_entity.is_some() || self.splitter_panel_entity.is_some() || self.storage_panel_entity.is_some() || self.sink_panel_entity.is_some() || self.loader_panel_entity.is_some() || self.station_panel_entity.is_some() || self.train_panel.is_some() || self.belt_panel_entity.is_some() || self.combinator_panel_entity.is_some()
+    }
+
+    /// Close every entity inspection panel.
+    fn close_inspect_panels(&mut self) {
+        self.machine_panel_entity = None;
+        self.splitter_panel_entity = None;
+        self.storage_panel_entity = None;
+        self.sink_panel_entity = None;
+        self.loader_panel_entity = None;
+        self.station_panel_entity = None;
+        self.train_panel = None;
+        self.belt_panel_entity = None;
+        self.combinator_panel_entity = None;
     }
 }
 
@@ -102,31 +150,63 @@ pub struct App {
     machine_pool: crate::sim::machine::MachinePool,
     splitter_pool: crate::sim::splitter::SplitterPool,
     storage_pool: crate::sim::storage::StoragePool,
+    sink_pool: crate::sim::sink::SinkPool,
+    loader_pool: crate::sim::loader::LoaderPool,
+    fluid_network: crate::sim::fluid::FluidNetwork,
+    rail_network: crate::sim::rail::RailNetwork,
+    circuit_network: crate::sim::circuit::CircuitNetwork,
+    cell_sleep: crate::sim::sleep::CellSleep,
     power_network: crate::sim::power::PowerNetwork,
+    /// Multiplayer session, if hosting or joined.
+    net: Option<NetSession>,
+    /// Cell last reported to the other players.
+    sent_cursor: Option<Cursor>,
     ui: UiState,
     grid_enabled: bool,
     klein_half_side: f64,
 }
 
 impl App {
-    pub fn new(cfg: TilingConfig) -> Self {
+    pub fn new(cfg: TilingConfig, net: Option<NetMode>) -> Self {
         let config = GameConfig::load();
         let input_state = InputState::new(config.key_bindings.clone());
+        let net = net.map(|mode| {
+            let session = NetSession::start(&mode, cfg.q, config.debug.free_placement)
+                .expect("failed to start multiplayer session");
+            if let Some(addr) = session.local_addr() {
+                log::info!("hosting on {addr}");
+            }
+            session
+        });
+        let mut world = WorldState::new();
+        world.set_corner_exclusion(config.gameplay.corner_exclusion as i32);
+        let mut camera = Camera::new();
+        camera.projection = config.graphics.projection;
         Self {
             cfg,
             renderer: None,
-            camera: Camera::new(),
+            camera,
             game_loop: GameLoop::new(),
             input_state,
             config,
             inventory: Inventory::starting_inventory(),
             recipes: RecipeIndex::new(),
-            world: WorldState::new(),
+            world,
             belt_network: BeltNetwork::new(),
             machine_pool: crate::sim::machine::MachinePool::new(),
             splitter_pool: crate::sim::splitter::SplitterPool::new(),
             storage_pool: crate::sim::storage::StoragePool::new(),
-            power_network: crate::sim::power::PowerNetwork::new(),
+            sink_pool: crate::sim::sink::SinkPool::new(),
+            loader_pool: crate::sim::loader::LoaderPool::new(),
+            fluid_network: crate::sim::fluid::FluidNetwork::new(),
+            rail_network: crate::sim::rail::RailNetwork::new()
+                .with_rules(crate::hyperbolic::rewrite::rules_for(cfg.q)),
+            circuit_network: crate::sim::circuit::CircuitNetwork::new(),
+            cell_sleep: crate::sim::sleep::CellSleep::new(),
+            power_network: crate::sim::power::PowerNetwork::new()
+                .with_rules(crate::hyperbolic::rewrite::rules_for(cfg.q)),
+            net,
+            sent_cursor: None,
             ui: UiState::new(),
             grid_enabled: false,
             klein_half_side: {
@@ -227,7 +307,7 @@ impl App {
         let combined = inv_view.compose(&tile_xform);
         // Transform snapped local Poincare coords back to view-space disk
         let world_disk = combined.apply(result.local_disk);
-        let bowl = crate::hyperbolic::embedding::disk_to_bowl(world_disk);
+        let bowl = self.camera.projection.project(world_disk);
         let elevation = running.extra_elevation.get(&result.tile_idx).copied().unwrap_or(0.0);
         let world_pos = glam::Vec3::new(bowl[0], bowl[1] + elevation, bowl[2]);
 
@@ -245,34 +325,329 @@ impl App {
     }
 
     /// Place a single structure at the given tile address and grid position.
-    /// Returns true if placement succeeded.
-    fn try_place_at(&mut self, tile_idx: usize, address: &[u8], grid_xy: (i32, i32), mode: &PlacementMode) -> bool {
-        if !self.config.debug.free_placement && self.inventory.count(mode.item) == 0 {
+    /// Returns true if placement succeeded (or, in multiplayer, was sent).
+    fn try_place_at(&mut self, address: &CellId, grid_xy: (i32, i32), mode: &PlacementMode) -> bool {
+        self.perform(PlayerAction::Place {
+            address: TileAddr::from_slice(address.word()),
+            grid_xy,
+            item: mode.item,
+            direction: mode.direction,
+        })
+    }
+
+    /// Carry out a local player's action: directly when playing alone, or
+    /// by handing it to the session, which applies it on every peer's same
+    /// tick. Returns whether it took effect (always true once sent).
+    fn perform(&mut self, action: PlayerAction) -> bool {
+        match &mut self.net {
+            Some(net) => {
+                net.submit(action);
+                true
+            }
+            None => self.apply_action(action, true),
+        }
+    }
+
+    /// Apply an action to the world. Feedback is flashed only for the local
+    /// player's own actions.
+    fn apply_action(&mut self, action: PlayerAction, local: bool) -> bool {
+        match action {
+            PlayerAction::Place { address, grid_xy, item, direction } => {
+                let Some((tile_idx, cell)) = self.tile_for(&address) else {
+                    return false;
+                };
+                if !self.place_structure(tile_idx, &cell, grid_xy, item, direction) {
+                    return false;
+                }
+                if local {
+                    self.flash_at_grid(tile_idx, grid_xy, format!("{} {}", item.display_name(), direction.arrow_char()), 0.4);
+                }
+                true
+            }
+            PlayerAction::Remove { address, grid_xy } => {
+                let Some((tile_idx, cell)) = self.tile_for(&address) else {
+                    return false;
+                };
+                let Some(item) = self.remove_structure(&cell, grid_xy) else {
+                    return false;
+                };
+                if local {
+                    self.flash_at_grid(tile_idx, grid_xy, format!("-{}", item.display_name()), 0.4);
+                }
+                true
+            }
+            PlayerAction::Rotate { address, grid_xy } => {
+                let Some((tile_idx, cell)) = self.tile_for(&address) else {
+                    return false;
+                };
+                let Some(new_dir) = self.rotate_structure(&cell, grid_xy) else {
+                    return false;
+                };
+                if local {
+                    self.flash_at_grid(tile_idx, grid_xy, format!("{}", new_dir.arrow_char()), 0.3);
+                }
+                true
+            }
+            PlayerAction::SetRecipe { machine, recipe } => {
+                // The index comes from a peer; one the machine can't run
+                // would panic on its next tick
+                let Some(machine_type) = self.machine_pool.machine_type(machine) else {
+                    return false;
+                };
+                if recipe.is_some_and(|r| !self.recipes.runs_on(machine_type, r)) {
+                    return false;
+                }
+                self.machine_pool.set_recipe(machine, recipe);
+                true
+            }
+            PlayerAction::Wire { from, to } => {
+                use crate::game::items::ItemId;
+                if !self.wire_end_valid(from) || !self.wire_end_valid(to) {
+                    return false;
+                }
+                let free = self.config.debug.free_placement;
+                let (wired, label) = if !free && self.inventory.count(ItemId::SignalWire) == 0 {
+                    (false, "No signal wire")
+                } else if self.circuit_network.connect(from, to) {
+                    if !free {
+                        self.inventory.remove(ItemId::SignalWire, 1);
+                    }
+                    (true, "Wired")
+                } else {
+                    (false, "Already wired")
+                };
+                if local {
+                    self.flash_at_cursor(label);
+                }
+                wired
+            }
+            PlayerAction::SetCondition { entity, condition } => {
+                // Only these obey a condition; anything else would leave a
+                // stray entry behind
+                let switchable = matches!(
+                    self.world.kind(entity),
+                    Some(StructureKind::Belt | StructureKind::Machine(_) | StructureKind::Splitter)
+                );
+                if switchable {
+                    self.circuit_network.set_condition(entity, condition);
+                }
+                switchable
+            }
+            PlayerAction::SetCombinator { combinator, config } => {
+                self.circuit_network.set_combinator_config(combinator, config);
+                true
+            }
+            PlayerAction::SetSplitterFilter { splitter, belt, filter } => {
+                self.splitter_pool.set_filter(splitter, belt, filter);
+                true
+            }
+            PlayerAction::SetSplitterPriority { splitter, belt, priority } => {
+                self.splitter_pool.set_priority(splitter, belt, priority);
+                true
+            }
+            PlayerAction::SetWhitelist { storage, items } => {
+                self.storage_pool.set_whitelist(storage, items);
+                true
+            }
+            PlayerAction::SetSlotLock { storage, slot, lock } => {
+                self.storage_pool.set_slot_lock(storage, slot, lock);
+                true
+            }
+            PlayerAction::SetOutputMode { storage, port, mode } => {
+                self.storage_pool.set_output_mode(storage, port, mode);
+                true
+            }
+            PlayerAction::SetSinkFilter { sink, filter } => {
+                self.sink_pool.set_filter(sink, filter);
+                true
+            }
+            PlayerAction::ResetSinkCounts { sink } => {
+                self.sink_pool.reset_counts(sink);
+                true
+            }
+            PlayerAction::SetLoaderMode { loader, mode } => self.set_loader_mode(loader, mode),
+            PlayerAction::SetLoaderFilter { loader, filter } => {
+                self.loader_pool.set_filter(loader, filter);
+                true
+            }
+            PlayerAction::MovePort { machine, port, cell_offset, side } => {
+                if !self.machine_pool.move_port(machine, port, cell_offset, side) {
+                    return false;
+                }
+                self.reconnect_machine_ports(machine);
+                true
+            }
+            PlayerAction::ResetPorts { machine } => {
+                self.machine_pool.reset_ports(machine);
+                self.reconnect_machine_ports(machine);
+                true
+            }
+            PlayerAction::SetStationMode { station, mode } => {
+                self.rail_network.set_station_mode(station, mode);
+                true
+            }
+            PlayerAction::AddStop { train, station } => {
+                // The station may have been removed since the stop was chosen
+                if self.rail_network.station(station).is_none() {
+                    return false;
+                }
+                self.rail_network.push_stop(train, station);
+                true
+            }
+            PlayerAction::RemoveStop { train, index } => {
+                self.rail_network.remove_stop(train, index);
+                true
+            }
+            PlayerAction::SetTrainRunning { train, running } => {
+                self.rail_network.set_running(train, running);
+                true
+            }
+            PlayerAction::SetSensor { belt, on } => self.belt_network.set_sensor(belt, on),
+        }
+    }
+
+    /// Whether a wire may attach to `end`: the main terminal of anything
+    /// that reads or obeys signals, or a combinator's output. A wire action
+    /// from another player may name an entity removed since it was sent.
+    fn wire_end_valid(&self, end: crate::sim::circuit::WireEnd) -> bool {
+        use crate::sim::circuit::Terminal;
+        match self.world.kind(end.entity) {
+            Some(
+                StructureKind::Belt
+                | StructureKind::Machine(_)
+                | StructureKind::Splitter
+                | StructureKind::Storage,
+            ) => end.terminal == Terminal::Main,
+            Some(StructureKind::ArithmeticCombinator | StructureKind::DeciderCombinator) => true,
+            _ => false,
+        }
+    }
+
+    /// The cell an action's `address` names, canonicalised once here, and
+    /// its index in the render tiling, creating it and its edge neighbours
+    /// if they aren't loaded. Cross-tile links then resolve the same way
+    /// wherever the camera is, which keeps multiplayer peers with different
+    /// views in agreement. None for an address that isn't a word.
+    fn tile_for(&mut self, address: &[u8]) -> Option<(usize, CellId)> {
+        if !crate::hyperbolic::rewrite::is_valid_word(address) {
+            return None;
+        }
+        let tiling = &mut self.renderer.as_mut()?.tiling;
+        let cell = tiling.canonical_cell(address);
+        let idx = tiling.ensure_tile(&cell);
+        for neighbor in tiling.tiles[idx].neighbors.clone() {
+            tiling.ensure_tile(&neighbor);
+        }
+        Some((idx, cell))
+    }
+
+    /// Show a short label at a grid cell, projected to the screen.
+    fn flash_at_grid(&mut self, tile_idx: usize, grid_xy: (i32, i32), label: String, duration: f32) {
+        if let Some(pos) = self.grid_screen_pos(tile_idx, grid_xy) {
+            self.ui.flash_label = label;
+            self.ui.flash_screen_pos = Some(pos);
+            self.ui.flash_timer = duration;
+        }
+    }
+
+    /// Screen position (in egui points) of a grid cell on a loaded tile, if
+    /// it is in front of the camera.
+    fn grid_screen_pos(&self, tile_idx: usize, grid_xy: (i32, i32)) -> Option<(f32, f32)> {
+        let running = self.renderer.as_ref()?;
+        let width = running.gpu.config.width as f32;
+        let height = running.gpu.config.height as f32;
+        let scale = running.gpu.window.scale_factor() as f32;
+        let aspect = width / height;
+        let view_proj = self.camera.build_view_proj(aspect);
+        let khs = self.klein_half_side;
+        let divisions = 64.0_f64;
+
+        let inv_view = self.camera.local.inverse();
+        let tile_xform = running.tiling.tiles[tile_idx].transform;
+        let combined = inv_view.compose(&tile_xform);
+
+        let snap_kx = (grid_xy.0 as f64 / divisions) * 2.0 * khs;
+        let snap_ky = (grid_xy.1 as f64 / divisions) * 2.0 * khs;
+        let kr2 = snap_kx * snap_kx + snap_ky * snap_ky;
+        let denom = 1.0 + (1.0 - kr2).max(0.0).sqrt();
+        let local_disk = Complex::new(snap_kx / denom, snap_ky / denom);
+
+        let world_disk = combined.apply(local_disk);
+        let bowl = self.camera.projection.project(world_disk);
+        let elevation = running.extra_elevation.get(&tile_idx).copied().unwrap_or(0.0);
+        let world_pos = glam::Vec3::new(bowl[0], bowl[1] + elevation, bowl[2]);
+
+        project_to_screen(world_pos, &view_proj, width, height).map(|(px, py)| (px / scale, py / scale))
+    }
+
+    /// Place a structure and wire it into every simulation system.
+    fn place_structure(
+        &mut self,
+        tile_idx: usize,
+        address: &CellId,
+        grid_xy: (i32, i32),
+        item: crate::game::items::ItemId,
+        direction: Direction,
+    ) -> bool {
+        if !self.config.debug.free_placement && self.inventory.count(item) == 0 {
             return false;
         }
-        let entity = match self.world.place(address, grid_xy, mode.item, mode.direction) {
+        if matches!(item, crate::game::items::ItemId::Locomotive | crate::game::items::ItemId::CargoWagon) {
+            return self.try_place_rolling_stock(address, grid_xy, item);
+        }
+        let entity = match self.world.place(address, grid_xy, item, direction) {
             Some(e) => e,
             None => return false, // occupied or not placeable
         };
         if !self.config.debug.free_placement {
-            self.inventory.remove(mode.item, 1);
+            self.inventory.remove(item, 1);
+        }
+        // Occupied cells stay loaded when the camera flies away from them
+        if let Some(re) = &mut self.renderer {
+            re.tiling.pin(address);
         }
 
         // Register belt with simulation network
-        if mode.item == crate::game::items::ItemId::Belt {
+        if item == crate::game::items::ItemId::Belt {
             self.belt_network.on_belt_placed(
-                entity, address, grid_xy.0, grid_xy.1, mode.direction, &self.world,
+                entity, address, grid_xy.0, grid_xy.1, direction, &self.world,
             );
             // Establish cross-tile transport line links
-            self.check_cross_tile_belt_link(entity, tile_idx, address, grid_xy, mode.direction);
+            self.check_cross_tile_belt_link(entity, tile_idx, address, grid_xy, direction);
+        }
+
+        // Register pipe with the fluid network and join it to its neighbours
+        if item == crate::game::items::ItemId::Pipe {
+            self.fluid_network.add_pipe(entity);
+            self.connect_pipe(entity, tile_idx, address, grid_xy);
+        }
+
+        // Pumps keep no state of their own beyond their pipe attachments
+        if item == crate::game::items::ItemId::Pump {
+            self.attach_fluid_ports(entity);
+        }
+
+        // Register rail and join it to its neighbours; stations alongside gain a stop
+        if item == crate::game::items::ItemId::Rail {
+            self.rail_network.add_rail(entity, address);
+            self.connect_rail(entity, tile_idx, address, grid_xy);
+        }
+        if item == crate::game::items::ItemId::TrainStation {
+            self.rail_network.add_station(entity);
+            self.refresh_station(entity);
+        }
+        if let Some(kind @ (StructureKind::Rail | StructureKind::Storage)) = StructureKind::from_item(item) {
+            let (w, h) = kind.footprint();
+            self.refresh_adjacent_stations(address, grid_xy, direction.rotate_footprint(w, h));
         }
 
         // Register machine with simulation pool and auto-connect ports
         if let Some(crate::game::world::StructureKind::Machine(mt)) =
-            crate::game::world::StructureKind::from_item(mode.item)
+            crate::game::world::StructureKind::from_item(item)
         {
             self.machine_pool.add(entity, mt);
-            self.auto_connect_machine_ports(entity, address, grid_xy, mode.direction, mt);
+            self.auto_connect_machine_ports(entity, address, grid_xy, direction);
+            self.attach_fluid_ports(entity);
             // Register machine as power consumer
             let exempt = mt == crate::game::items::MachineType::Source;
             self.power_network.add(
@@ -287,7 +662,7 @@ impl App {
         }
 
         // Register power structures as producers
-        match crate::game::world::StructureKind::from_item(mode.item) {
+        match crate::game::world::StructureKind::from_item(item) {
             Some(crate::game::world::StructureKind::PowerNode) => {
                 self.power_network.add(
                     entity,
@@ -314,57 +689,56 @@ impl App {
         }
 
         // Register splitter with simulation pool and connect to adjacent belts
-        if mode.item == crate::game::items::ItemId::Splitter {
+        if item == crate::game::items::ItemId::Splitter {
             self.splitter_pool.add(entity);
             self.auto_connect_splitter_to_belts(entity, address, grid_xy);
         }
 
         // Register storage building with simulation pool and auto-connect ports
-        if mode.item == crate::game::items::ItemId::Storage {
+        if item == crate::game::items::ItemId::Storage {
             self.storage_pool.add(entity);
-            self.auto_connect_storage_to_belts(entity, address, grid_xy, mode.direction);
+            self.auto_connect_storage_to_belts(entity, address, grid_xy, direction);
         }
 
-        // Auto-connect belt to adjacent machines, splitters, and storage
-        if mode.item == crate::game::items::ItemId::Belt {
-            self.auto_connect_belt_to_machines(entity, address, grid_xy, mode.direction);
-            self.auto_connect_belt_to_splitters(entity, address, grid_xy, mode.direction);
-            self.auto_connect_belt_to_storage(entity, address, grid_xy, mode.direction);
+        // Register sink with simulation pool and connect to an adjacent feeding belt
+        if item == crate::game::items::ItemId::Void {
+            self.sink_pool.add(entity);
+            self.auto_connect_sink_to_belts(entity, address, grid_xy, direction);
         }
 
-        // Flash feedback
-        let running = self.renderer.as_ref().unwrap();
-        let width = running.gpu.config.width as f32;
-        let height = running.gpu.config.height as f32;
-        let scale = running.gpu.window.scale_factor() as f32;
-        let aspect = width / height;
-        let view_proj = self.camera.build_view_proj(aspect);
-        let khs = self.klein_half_side;
-        let divisions = 64.0_f64;
-
-        let inv_view = self.camera.local.inverse();
-        let tile_xform = running.tiling.tiles[tile_idx].transform;
-        let combined = inv_view.compose(&tile_xform);
+        // Auto-connect belt to adjacent machines, splitters, storage, and sinks
+        if item == crate::game::items::ItemId::Belt {
+            self.auto_connect_belt_to_machines(entity, address, grid_xy, direction);
+            self.auto_connect_belt_to_splitters(entity, address, grid_xy, direction);
+            self.auto_connect_belt_to_storage(entity, address, grid_xy, direction);
+            self.auto_connect_belt_to_sink(entity, address, grid_xy, direction);
+        }
 
-        let snap_kx = (grid_xy.0 as f64 / divisions) * 2.0 * khs;
-        let snap_ky = (grid_xy.1 as f64 / divisions) * 2.0 * khs;
-        let kr2 = snap_kx * snap_kx + snap_ky * snap_ky;
-        let denom = 1.0 + (1.0 - kr2).max(0.0).sqrt();
-        let local_disk = Complex::new(snap_kx / denom, snap_ky / denom);
+        // Register combinators with the circuit network
+        match StructureKind::from_item(item) {
+            Some(StructureKind::ArithmeticCombinator) => {
+                self.circuit_network.add_combinator(entity, crate::sim::circuit::CombinatorConfig::default_arithmetic());
+            }
+            Some(StructureKind::DeciderCombinator) => {
+                self.circuit_network.add_combinator(entity, crate::sim::circuit::CombinatorConfig::default_decider());
+            }
+            _ => {}
+        }
 
-        let world_disk = combined.apply(local_disk);
-        let bowl = crate::hyperbolic::embedding::disk_to_bowl(world_disk);
-        let elevation = running.extra_elevation.get(&tile_idx).copied().unwrap_or(0.0);
-        let world_pos = glam::Vec3::new(bowl[0], bowl[1] + elevation, bowl[2]);
+        // Register loader and attach it to the building in front / belt behind
+        if item == crate::game::items::ItemId::Loader {
+            self.loader_pool.add(entity);
+            self.connect_loader(entity, address);
+        }
 
-        if let Some((px, py)) = project_to_screen(world_pos, &view_proj, width, height) {
-            self.ui.flash_label = format!(
-                "{} {}",
-                mode.item.display_name(),
-                mode.direction.arrow_char(),
-            );
-            self.ui.flash_screen_pos = Some((px / scale, py / scale));
-            self.ui.flash_timer = 0.4;
+        // Existing loaders next to a new belt, machine, or storage may now have
+        // something to attach to
+        if let Some(kind @ (StructureKind::Belt | StructureKind::Machine(_) | StructureKind::Storage)) =
+            StructureKind::from_item(item)
+        {
+            let (w, h) = kind.footprint();
+            let footprint = direction.rotate_footprint(w, h);
+            self.reconnect_adjacent_loaders(address, grid_xy, footprint);
         }
         true
     }
@@ -376,11 +750,13 @@ impl App {
     /// Cross-tile check triggers when the neighbor position is:
     /// - Off-tile (outside -32..=32), OR
     /// - At the shared edge (±32) with no same-direction belt on this tile.
+    ///
+    /// No link is made where the crossing falls in a corner exclusion zone.
     fn check_cross_tile_belt_link(
         &mut self,
         entity: EntityId,
         tile_idx: usize,
-        tile_addr: &[u8],
+        tile_addr: &CellId,
         grid_xy: (i32, i32),
         direction: Direction,
     ) {
@@ -393,55 +769,251 @@ impl App {
         // Determine which neighbor positions need cross-tile checks.
         // Off-tile always needs it; ±32 needs it only if no belt exists there
         // on this tile (the edge is shared between adjacent tiles).
-        let check_ahead = !is_within_tile(ahead.0, ahead.1)
+        let at_corner = |(x, y): (i32, i32)| self.world.in_corner_zone((x.clamp(-32, 32), y.clamp(-32, 32)));
+        let check_ahead = (!is_within_tile(ahead.0, ahead.1)
             || ((ahead.0.abs() == 32 || ahead.1.abs() == 32)
-                && find_same_dir_belt_at(&self.world, tile_addr, ahead, direction).is_none());
-        let check_behind = !is_within_tile(behind.0, behind.1)
+                && find_same_dir_belt_at(&self.world, tile_addr, ahead, direction).is_none()))
+            && !at_corner(ahead);
+        let check_behind = (!is_within_tile(behind.0, behind.1)
             || ((behind.0.abs() == 32 || behind.1.abs() == 32)
-                && find_same_dir_belt_at(&self.world, tile_addr, behind, direction).is_none());
+                && find_same_dir_belt_at(&self.world, tile_addr, behind, direction).is_none()))
+            && !at_corner(behind);
 
         // Output connection: this belt's flow exits toward ahead
         if check_ahead {
-            let edge = direction.tiling_edge_index();
-            let mirror = cross_tile_mirror(ahead);
-            let running = self.renderer.as_ref().unwrap();
-            if let Some(neighbor_id) = running.tiling.neighbor_tile_id(tile_idx, edge) {
-                if let Some(neighbor_entity) = find_same_dir_belt_at(
-                    &self.world, neighbor_id.word(), mirror, direction,
-                ) {
-                    self.belt_network.link_output_to_input(entity, neighbor_entity);
-                }
+            let tile = &self.renderer.as_ref().unwrap().tiling.tiles[tile_idx];
+            let transfer = EdgeTransfer::from_tile(tile, direction);
+            let (mirror, far_direction) = transfer.map(ahead, direction);
+            if let Some(neighbor_entity) = find_same_dir_belt_at(
+                &self.world, &transfer.cell, mirror, far_direction,
+            ) {
+                self.belt_network.link_output_to_input(entity, neighbor_entity);
             }
         }
 
         // Input connection: items would enter this belt from behind
         if check_behind {
-            let edge = direction.opposite().tiling_edge_index();
-            let mirror = cross_tile_mirror(behind);
-            let running = self.renderer.as_ref().unwrap();
-            if let Some(neighbor_id) = running.tiling.neighbor_tile_id(tile_idx, edge) {
-                if let Some(neighbor_entity) = find_same_dir_belt_at(
-                    &self.world, neighbor_id.word(), mirror, direction,
-                ) {
-                    self.belt_network.link_output_to_input(neighbor_entity, entity);
+            let tile = &self.renderer.as_ref().unwrap().tiling.tiles[tile_idx];
+            let transfer = EdgeTransfer::from_tile(tile, direction.opposite());
+            let (mirror, far_direction) = transfer.map(behind, direction);
+            if let Some(neighbor_entity) = find_same_dir_belt_at(
+                &self.world, &transfer.cell, mirror, far_direction,
+            ) {
+                self.belt_network.link_output_to_input(neighbor_entity, entity);
+            }
+        }
+    }
+
+    /// After a pipe is placed, link it to the pipes in the four neighbouring
+    /// cells (following tile edges like `check_cross_tile_belt_link`) and
+    /// attach any machine or pump fluid port that faces its cell.
+    fn connect_pipe(&mut self, entity: EntityId, tile_idx: usize, tile_addr: &CellId, grid_xy: (i32, i32)) {
+        for dir in [Direction::North, Direction::East, Direction::South, Direction::West] {
+            let neighbor = self.edge_neighbor(tile_idx, tile_addr, grid_xy, dir, |addr, xy| {
+                self.world.underlay_at(addr, xy)
+            });
+            if let Some((other, back)) = neighbor.filter(|&(o, _)| o != entity) {
+                self.fluid_network.link_pipes(entity, other, dir, back);
+            }
+
+            let (dx, dy) = dir.grid_offset_i32();
+            let cell = (grid_xy.0 + dx, grid_xy.1 + dy);
+            let owner = self.world.tile_entities(tile_addr).and_then(|e| e.get(&cell).copied());
+            if let Some(owner) = owner {
+                self.attach_fluid_ports(owner);
+            }
+        }
+    }
+
+    /// Find the entity `lookup` reports one step in `dir` from `grid_xy`, and
+    /// the side of it that faces back. Falls back to the neighbouring tile
+    /// when the step leaves this tile or lands on the shared edge row, so
+    /// links survive tile boundaries.
+    fn edge_neighbor(
+        &self,
+        tile_idx: usize,
+        tile_addr: &CellId,
+        grid_xy: (i32, i32),
+        dir: Direction,
+        lookup: impl Fn(&CellId, (i32, i32)) -> Option<EntityId>,
+    ) -> Option<(EntityId, Direction)> {
+        use crate::sim::belt::is_within_tile;
+
+        let (dx, dy) = dir.grid_offset_i32();
+        let cell = (grid_xy.0 + dx, grid_xy.1 + dy);
+        if is_within_tile(cell.0, cell.1) {
+            if let Some(e) = lookup(tile_addr, cell) {
+                return Some((e, dir.opposite()));
+            }
+        }
+        let on_edge = !is_within_tile(cell.0, cell.1) || cell.0.abs() == 32 || cell.1.abs() == 32;
+        if !on_edge {
+            return None;
+        }
+        let tile = &self.renderer.as_ref().unwrap().tiling.tiles[tile_idx];
+        let transfer = EdgeTransfer::from_tile(tile, dir);
+        let (far_cell, far_dir) = transfer.map(cell, dir);
+        lookup(&transfer.cell, far_cell).map(|e| (e, far_dir.opposite()))
+    }
+
+    /// Join a newly placed rail to the rails around it, across tile edges too.
+    fn connect_rail(&mut self, entity: EntityId, tile_idx: usize, tile_addr: &CellId, grid_xy: (i32, i32)) {
+        for dir in [Direction::North, Direction::East, Direction::South, Direction::West] {
+            let neighbor = self.edge_neighbor(tile_idx, tile_addr, grid_xy, dir, |addr, xy| {
+                self.world
+                    .tile_entities(addr)
+                    .and_then(|e| e.get(&xy).copied())
+                    .filter(|&e| self.world.kind(e) == Some(StructureKind::Rail))
+            });
+            if let Some((other, back)) = neighbor.filter(|&(o, _)| o != entity) {
+                self.rail_network.link_rails(entity, other, dir, back);
+            }
+        }
+    }
+
+    /// Distinct entities of `kind` in cells sharing an edge with a footprint
+    /// (diagonal corners excluded), in scan order.
+    fn edge_adjacent(
+        &self,
+        tile_addr: &CellId,
+        origin: (i32, i32),
+        footprint: (i32, i32),
+        kind: StructureKind,
+    ) -> Vec<EntityId> {
+        let Some(entities) = self.world.tile_entities(tile_addr) else {
+            return Vec::new();
+        };
+        let (w, h) = footprint;
+        let (x0, y0, x1, y1) = (origin.0, origin.1, origin.0 + w - 1, origin.1 + h - 1);
+        let mut found = Vec::new();
+        for y in y0 - 1..=y1 + 1 {
+            for x in x0 - 1..=x1 + 1 {
+                let outside_x = x < x0 || x > x1;
+                let outside_y = y < y0 || y > y1;
+                if outside_x == outside_y {
+                    continue; // inside the footprint, or a diagonal corner
+                }
+                if let Some(&e) = entities.get(&(x, y)) {
+                    if self.world.kind(e) == Some(kind) && !found.contains(&e) {
+                        found.push(e);
+                    }
                 }
             }
         }
+        found
+    }
+
+    /// Recompute which rails and storage a station touches.
+    fn refresh_station(&mut self, station: EntityId) {
+        let Some(pos) = self.world.position(station) else {
+            return;
+        };
+        let (tile_addr, origin) = (self.world.cell(pos.cell).clone(), (pos.gx as i32, pos.gy as i32));
+        let facing = self.world.direction(station).unwrap_or(Direction::North);
+        let (w, h) = StructureKind::Station.footprint();
+        let footprint = facing.rotate_footprint(w, h);
+        let stops = self.edge_adjacent(&tile_addr, origin, footprint, StructureKind::Rail);
+        let storages = self.edge_adjacent(&tile_addr, origin, footprint, StructureKind::Storage);
+        self.rail_network.set_station_neighbors(station, stops, storages);
+    }
+
+    /// Refresh every station touching a footprint (after placing or removing
+    /// a rail or storage there).
+    fn refresh_adjacent_stations(&mut self, tile_addr: &CellId, origin: (i32, i32), footprint: (i32, i32)) {
+        for station in self.edge_adjacent(tile_addr, origin, footprint, StructureKind::Station) {
+            self.refresh_station(station);
+        }
+    }
+
+    /// Put a locomotive on the rail at `grid_xy`, or couple a wagon to the
+    /// locomotive standing there. Returns true if the item was used.
+    fn try_place_rolling_stock(&mut self, address: &CellId, grid_xy: (i32, i32), item: crate::game::items::ItemId) -> bool {
+        let Some(rail) = self
+            .world
+            .tile_entities(address)
+            .and_then(|e| e.get(&grid_xy).copied())
+            .filter(|&e| self.world.kind(e) == Some(StructureKind::Rail))
+        else {
+            return false;
+        };
+        let placed = if item == crate::game::items::ItemId::Locomotive {
+            self.rail_network.spawn_train(rail).is_some()
+        } else {
+            self.rail_network
+                .train_at(rail)
+                .is_some_and(|id| self.rail_network.add_wagon(id))
+        };
+        if placed && !self.config.debug.free_placement {
+            self.inventory.remove(item, 1);
+        }
+        placed
+    }
+
+    /// Refund a removed train: locomotive, wagons, and any cargo aboard.
+    fn refund_train(&mut self, train: crate::sim::rail::Train) {
+        use crate::game::items::ItemId;
+
+        if self.ui.train_panel == Some(train.id) {
+            self.ui.train_panel = None;
+        }
+        self.inventory.add(ItemId::Locomotive, 1);
+        for wagon in &train.wagons {
+            self.inventory.add(ItemId::CargoWagon, 1);
+            for &(item, count) in &wagon.cargo {
+                self.inventory.add(item, count);
+            }
+        }
+    }
+
+    /// (Re)attach a machine's or pump's fluid ports to the pipes in the cells
+    /// they face. Safe to call repeatedly; old attachments are dropped first.
+    fn attach_fluid_ports(&mut self, owner: EntityId) {
+        use crate::sim::fluid::FluidEnd;
+        use crate::sim::inserter::{rotate_port_defs, structure_fluid_port_layout, PortKind};
+
+        let Some(kind) = self.world.kind(owner) else {
+            return;
+        };
+        let defs = structure_fluid_port_layout(kind);
+        if defs.is_empty() {
+            return;
+        }
+        let Some(pos) = self.world.position(owner) else {
+            return;
+        };
+        let (tile_addr, origin) = (self.world.cell(pos.cell).clone(), (pos.gx as i32, pos.gy as i32));
+        let facing = self.world.direction(owner).unwrap_or(Direction::North);
+
+        self.fluid_network.detach_owner(owner);
+        for port in rotate_port_defs(defs, kind.footprint(), facing) {
+            let (sx, sy) = port.side.grid_offset_i32();
+            let cell = (origin.0 + port.cell_offset.0 + sx, origin.1 + port.cell_offset.1 + sy);
+            let Some(pipe) = self.world.underlay_at(&tile_addr, cell) else {
+                continue;
+            };
+            let end = match (kind, port.kind) {
+                (StructureKind::Pump, PortKind::Input) => FluidEnd::PumpInput,
+                (StructureKind::Pump, PortKind::Output) => FluidEnd::PumpOutput,
+                (_, PortKind::Input) => FluidEnd::MachineInput,
+                (_, PortKind::Output) => FluidEnd::MachineOutput,
+            };
+            self.fluid_network.attach(owner, pipe, port.side.opposite(), end);
+        }
     }
 
     /// When a machine is placed, check each port's specific adjacent cell for a belt.
     /// Uses `cell_offset` to check only the exact cell where each port lives.
+    /// Ports come from the machine's per-entity layout, so custom layouts apply.
     fn auto_connect_machine_ports(
         &mut self,
         machine_entity: EntityId,
-        tile_addr: &[u8],
+        tile_addr: &CellId,
         grid_xy: (i32, i32),
         facing: Direction,
-        machine_type: crate::game::items::MachineType,
     ) {
-        use crate::sim::inserter::{belt_compatible_with_port, rotated_ports, PortKind};
+        use crate::sim::inserter::{belt_compatible_with_port, PortKind};
 
-        for port in rotated_ports(machine_type, facing) {
+        for port in self.machine_pool.rotated_ports(machine_entity, facing) {
             let (dx, dy) = port.side.grid_offset_i32();
             // The port lives at origin + cell_offset; check the adjacent cell on that side
             let port_cell = (grid_xy.0 + port.cell_offset.0, grid_xy.1 + port.cell_offset.1);
@@ -477,16 +1049,15 @@ impl App {
     }
 
     /// When a belt is placed, check all 4 adjacent cells for machines and connect ports.
-    /// When a belt is placed, check all 4 adjacent cells for machines and connect ports.
-    /// Uses `port_at_cell_on_side` to match by the port's exact cell offset.
+    /// Matches the machine's per-entity port layout by the port's exact cell offset.
     fn auto_connect_belt_to_machines(
         &mut self,
         belt_entity: EntityId,
-        tile_addr: &[u8],
+        tile_addr: &CellId,
         grid_xy: (i32, i32),
         belt_dir: Direction,
     ) {
-        use crate::sim::inserter::{belt_compatible_with_port, port_at_cell_on_side, PortKind};
+        use crate::sim::inserter::{belt_compatible_with_port, PortKind};
 
         for &check_dir in &[Direction::North, Direction::East, Direction::South, Direction::West] {
             let (dx, dy) = check_dir.grid_offset_i32();
@@ -494,7 +1065,7 @@ impl App {
 
             if let Some(entities) = self.world.tile_entities(tile_addr) {
                 if let Some(&adj_entity) = entities.get(&adj) {
-                    if let Some(StructureKind::Machine(mt)) = self.world.kind(adj_entity) {
+                    if let Some(StructureKind::Machine(_)) = self.world.kind(adj_entity) {
                         if let Some(facing) = self.world.direction(adj_entity) {
                             // Compute cell offset of `adj` within the machine's footprint
                             if let Some(origin) = self.world.position(adj_entity) {
@@ -503,12 +1074,13 @@ impl App {
                                     adj.1 - origin.gy as i32,
                                 );
                                 // Check if there's a port at this cell on the side facing the belt
-                                if let Some(port) = port_at_cell_on_side(
-                                    mt,
-                                    facing,
-                                    cell_offset,
-                                    check_dir.opposite(),
-                                ) {
+                                let side = check_dir.opposite();
+                                if let Some(port) = self
+                                    .machine_pool
+                                    .rotated_ports(adj_entity, facing)
+                                    .into_iter()
+                                    .find(|p| p.side == side && p.cell_offset == cell_offset)
+                                {
                                     if belt_compatible_with_port(&port, belt_dir) {
                                         match port.kind {
                                             PortKind::Input => {
@@ -542,7 +1114,7 @@ impl App {
     fn auto_connect_belt_to_splitters(
         &mut self,
         belt_entity: EntityId,
-        tile_addr: &[u8],
+        tile_addr: &CellId,
         grid_xy: (i32, i32),
         belt_dir: Direction,
     ) {
@@ -580,7 +1152,7 @@ impl App {
     fn auto_connect_splitter_to_belts(
         &mut self,
         splitter_entity: EntityId,
-        tile_addr: &[u8],
+        tile_addr: &CellId,
         grid_xy: (i32, i32),
     ) {
         for &check_dir in &[Direction::North, Direction::East, Direction::South, Direction::West] {
@@ -626,7 +1198,7 @@ impl App {
     fn auto_connect_belt_to_storage(
         &mut self,
         belt_entity: EntityId,
-        tile_addr: &[u8],
+        tile_addr: &CellId,
         grid_xy: (i32, i32),
         belt_dir: Direction,
     ) {
@@ -678,12 +1250,145 @@ impl App {
         }
     }
 
+    /// When a belt is placed, check all 4 adjacent cells for a sink whose input
+    /// port faces the belt, and connect the belt's output to it.
+    fn auto_connect_belt_to_sink(
+        &mut self,
+        belt_entity: EntityId,
+        tile_addr: &CellId,
+        grid_xy: (i32, i32),
+        belt_dir: Direction,
+    ) {
+        use crate::sim::inserter::{belt_compatible_with_port, structure_port_at_cell_on_side, PortKind};
+
+        for &check_dir in &[Direction::North, Direction::East, Direction::South, Direction::West] {
+            let (dx, dy) = check_dir.grid_offset_i32();
+            let adj = (grid_xy.0 + dx, grid_xy.1 + dy);
+
+            let Some(&adj_entity) = self.world.tile_entities(tile_addr).and_then(|e| e.get(&adj)) else {
+                continue;
+            };
+            if self.world.kind(adj_entity) != Some(StructureKind::Sink) {
+                continue;
+            }
+            let Some(facing) = self.world.direction(adj_entity) else {
+                continue;
+            };
+            if let Some(port) = structure_port_at_cell_on_side(
+                StructureKind::Sink,
+                facing,
+                (0, 0),
+                check_dir.opposite(),
+            ) {
+                if port.kind == PortKind::Input && belt_compatible_with_port(&port, belt_dir) {
+                    self.belt_network.connect_belt_to_sink_input(belt_entity, adj_entity);
+                }
+            }
+        }
+    }
+
+    /// When a sink is placed, connect an existing belt feeding its input port.
+    fn auto_connect_sink_to_belts(
+        &mut self,
+        sink_entity: EntityId,
+        tile_addr: &CellId,
+        grid_xy: (i32, i32),
+        facing: Direction,
+    ) {
+        use crate::sim::inserter::{belt_compatible_with_port, rotated_structure_ports};
+
+        for port in rotated_structure_ports(StructureKind::Sink, facing) {
+            let (dx, dy) = port.side.grid_offset_i32();
+            let adj = (grid_xy.0 + dx, grid_xy.1 + dy);
+
+            let Some(&belt_entity) = self.world.tile_entities(tile_addr).and_then(|e| e.get(&adj)) else {
+                continue;
+            };
+            if self.world.kind(belt_entity) != Some(StructureKind::Belt) {
+                continue;
+            }
+            if let Some(belt_dir) = self.world.direction(belt_entity) {
+                if belt_compatible_with_port(&port, belt_dir) {
+                    self.belt_network.connect_belt_to_sink_input(belt_entity, sink_entity);
+                }
+            }
+        }
+    }
+
+    /// Resolve a loader's attachments from scratch: the storage or machine in
+    /// the cell it faces, and the belt in the cell behind it. In `Load` mode the
+    /// belt must flow into the loader; in `Unload` mode it must flow away.
+    fn connect_loader(&mut self, loader_entity: EntityId, tile_addr: &CellId) {
+        use crate::sim::loader::{LoaderMode, LoaderTarget};
+
+        self.belt_network.disconnect_loader_ports(loader_entity);
+        self.loader_pool.set_belt(loader_entity, None);
+        self.loader_pool.set_target(loader_entity, None);
+
+        let Some(mode) = self.loader_pool.get(loader_entity).map(|s| s.mode) else {
+            return;
+        };
+        let (Some(pos), Some(facing)) =
+            (self.world.position(loader_entity), self.world.direction(loader_entity))
+        else {
+            return;
+        };
+        let cell = (pos.gx as i32, pos.gy as i32);
+        let (dx, dy) = facing.grid_offset_i32();
+        let Some(entities) = self.world.tile_entities(tile_addr) else {
+            return;
+        };
+        let front = entities.get(&(cell.0 + dx, cell.1 + dy)).copied();
+        let back = entities.get(&(cell.0 - dx, cell.1 - dy)).copied();
+
+        let target = front.and_then(|e| match self.world.kind(e) {
+            Some(StructureKind::Storage) => Some(LoaderTarget::Storage(e)),
+            Some(StructureKind::Machine(_)) => Some(LoaderTarget::Machine(e)),
+            _ => None,
+        });
+        self.loader_pool.set_target(loader_entity, target);
+
+        let Some(belt) = back.filter(|&e| self.world.kind(e) == Some(StructureKind::Belt)) else {
+            return;
+        };
+        let belt_dir = self.world.direction(belt);
+        let connected = match mode {
+            LoaderMode::Load => {
+                belt_dir == Some(facing)
+                    && self.belt_network.connect_belt_to_loader(belt, loader_entity)
+            }
+            LoaderMode::Unload => {
+                belt_dir == Some(facing.opposite())
+                    && self.belt_network.connect_loader_to_belt(belt, loader_entity)
+            }
+        };
+        if connected {
+            self.loader_pool.set_belt(loader_entity, Some(belt));
+        }
+    }
+
+    /// Re-resolve every loader in the ring of cells around a footprint.
+    fn reconnect_adjacent_loaders(&mut self, tile_addr: &CellId, origin: (i32, i32), footprint: (i32, i32)) {
+        let Some(entities) = self.world.tile_entities(tile_addr) else {
+            return;
+        };
+        let (w, h) = footprint;
+        let loaders: Vec<EntityId> = (origin.1 - 1..=origin.1 + h)
+            .flat_map(|y| (origin.0 - 1..=origin.0 + w).map(move |x| (x, y)))
+            .filter_map(|cell| entities.get(&cell).copied())
+            .filter(|&e| self.world.kind(e) == Some(StructureKind::Loader))
+            .collect();
+        for loader in loaders {
+            self.connect_loader(loader, tile_addr);
+        }
+    }
+
     /// When a storage building is placed, scan adjacent cells for existing belts
     /// and connect them to the storage's ports.
     fn auto_connect_storage_to_belts(
         &mut self,
         storage_entity: EntityId,
-        tile_addr: &[u8],
+        tile_addr: &CellId,
         grid_xy: (i32, i32),
         facing: Direction,
     ) {
@@ -737,7 +1442,14 @@ impl App {
         let running = self.renderer.as_ref().unwrap();
         let cell_id = running.tiling.tiles[result.tile_idx].id.clone();
 
-        if self.try_place_at(result.tile_idx, cell_id.word(), result.grid_xy, &mode) {
+        // Wires join two clicked structures rather than occupying a cell
+        if mode.item == crate::game::items::ItemId::SignalWire {
+            self.handle_wire_click(&cell_id, result.grid_xy);
+            return;
+        }
+        self.ui.wire_start = None;
+
+        if self.try_place_at(&cell_id, result.grid_xy, &mode) {
             // Lock drag axis parallel to the belt's facing direction
             let horizontal = matches!(mode.direction, Direction::East | Direction::West);
             let (fixed_coord, last_free) = if horizontal {
@@ -751,10 +1463,65 @@ impl App {
                 horizontal,
                 fixed_coord,
                 last_free,
+                direction: mode.direction,
             });
         }
     }
 
+    /// Signal wire placement: the first click picks a terminal, the second
+    /// runs a wire to another terminal. Clicking the same terminal twice
+    /// cancels. Combinators expose their output terminal on the front cell.
+    fn handle_wire_click(&mut self, address: &CellId, grid_xy: (i32, i32)) {
+        use crate::sim::circuit::WireEnd;
+        let Some(&entity) = self.world.tile_entities(address).and_then(|e| e.get(&grid_xy)) else {
+            return;
+        };
+        let end = match self.world.kind(entity) {
+            Some(
+                StructureKind::Belt
+                | StructureKind::Machine(_)
+                | StructureKind::Splitter
+                | StructureKind::Storage,
+            ) => WireEnd::main(entity),
+            Some(kind @ (StructureKind::ArithmeticCombinator | StructureKind::DeciderCombinator)) => {
+                let (Some(pos), Some(facing)) = (self.world.position(entity), self.world.direction(entity)) else {
+                    return;
+                };
+                let (w, h) = kind.footprint();
+                let (fx, fy) = facing.rotate_cell(0, 0, w, h);
+                if (grid_xy.0 - pos.gx as i32, grid_xy.1 - pos.gy as i32) == (fx, fy) {
+                    WireEnd::output(entity)
+                } else {
+                    WireEnd::main(entity)
+                }
+            }
+            _ => return,
+        };
+
+        match self.ui.wire_start.take() {
+            None => {
+                self.ui.wire_start = Some(end);
+                self.flash_at_cursor("Wire from here");
+            }
+            Some(start) if start == end => self.flash_at_cursor("Wire cancelled"),
+            Some(start) => {
+                // Applying the wire flashes how it went
+                self.perform(PlayerAction::Wire { from: start, to: end });
+            }
+        }
+    }
+
+    /// Flash a short label at the cursor.
+    fn flash_at_cursor(&mut self, label: &str) {
+        let Some(pos) = self.ui.cursor_pos else {
+            return;
+        };
+        let scale = self.renderer.as_ref().unwrap().gpu.window.scale_factor() as f32;
+        self.ui.flash_label = label.to_string();
+        self.ui.flash_screen_pos = Some((pos.x as f32 / scale, pos.y as f32 / scale));
+        self.ui.flash_timer = 0.4;
+    }
+
     fn handle_placement_drag(&mut self, sx: f64, sy: f64) {
         let mode = match self.ui.placement_mode.as_ref() {
             Some(m) => m.clone(),
@@ -772,6 +1539,7 @@ impl App {
         let last_free = drag.last_free;
         let old_id = drag.id.clone();
         let old_tile_idx = drag.tile_idx;
+        let mode = PlacementMode { direction: drag.direction, ..mode };
         let khs = self.klein_half_side;
 
         // Compute cursor's virtual (unclamped) grid position on the drag tile.
@@ -814,7 +1582,7 @@ impl App {
             let mut current = last_free + step;
             loop {
                 let grid_xy = if horizontal { (current, fixed_coord) } else { (fixed_coord, current) };
-                self.try_place_at(old_tile_idx, old_id.word(), grid_xy, &mode);
+                self.try_place_at(&old_id, grid_xy, &mode);
                 if current == target_free { break; }
                 current += step;
             }
@@ -833,13 +1601,20 @@ impl App {
                 let mut current = last_free + step;
                 loop {
                     let grid_xy = if horizontal { (current, fixed_coord) } else { (fixed_coord, current) };
-                    self.try_place_at(old_tile_idx, old_id.word(), grid_xy, &mode);
+                    self.try_place_at(&old_id, grid_xy, &mode);
                     if current == old_target { break; }
                     current += step;
                 }
             }
 
             if old_target == old_edge {
+                // Corners are where q cells meet; the drag can't cross there
+                let edge_xy = if horizontal { (old_edge, fixed_coord) } else { (fixed_coord, old_edge) };
+                if self.world.in_corner_zone(edge_xy) {
+                    if let Some(d) = self.ui.belt_drag.as_mut() { d.last_free = old_target; }
+                    return;
+                }
+
                 // Reached the edge — find the neighboring tile via cursor
                 let result = match self.find_clicked_tile(sx, sy) {
                     Some(r) => r,
@@ -861,12 +1636,34 @@ impl App {
                     (result.tile_idx, running.tiling.tiles[result.tile_idx].id.clone())
                 };
 
-                // For {4,n} (even p), grids align straight across edges.
-                // ±32 on adjacent tiles is the SAME physical edge, so skip it
-                // on the new tile to avoid overlapping belts.
-                // old gx=+32 → new tile starts at gx=-31 (not -32).
-                let inward: i32 = if old_edge > 0 { 1 } else { -1 };
-                let new_start = -old_edge + inward;
+                // The new tile's grid may be turned relative to this one, so
+                // the free axis, fixed coordinate and facing all come from
+                // the edge transfer. ±32 on adjacent tiles is the SAME
+                // physical edge, so start one square inside the new tile to
+                // avoid overlapping belts.
+                let heading = match (horizontal, old_edge > 0) {
+                    (true, true) => Direction::East,
+                    (true, false) => Direction::West,
+                    (false, true) => Direction::South,
+                    (false, false) => Direction::North,
+                };
+                let transfer = {
+                    let running = self.renderer.as_ref().unwrap();
+                    EdgeTransfer::from_tile(&running.tiling.tiles[old_tile_idx], heading)
+                };
+                if transfer.cell != new_cell_id {
+                    // Cursor is over some other tile, e.g. past a corner
+                    if let Some(d) = self.ui.belt_drag.as_mut() { d.last_free = old_target; }
+                    return;
+                }
+                let (dx, dy) = heading.grid_offset_i32();
+                let edge_xy = if horizontal { (old_edge, fixed_coord) } else { (fixed_coord, old_edge) };
+                let (start_xy, new_heading) = transfer.map((edge_xy.0 + dx, edge_xy.1 + dy), heading);
+                let horizontal = matches!(new_heading, Direction::East | Direction::West);
+                let (ix, iy) = new_heading.grid_offset_i32();
+                let inward = ix + iy;
+                let (fixed_coord, new_start) = if horizontal { (start_xy.1, start_xy.0) } else { (start_xy.0, start_xy.1) };
+                let mode = PlacementMode { direction: transfer.direction(mode.direction), ..mode };
                 let new_free = if horizontal { result.grid_xy.0 } else { result.grid_xy.1 };
                 let clamp_lo = new_start.min(new_start + (MAX_DRAG_STEP - 1) * inward);
                 let clamp_hi = new_start.max(new_start + (MAX_DRAG_STEP - 1) * inward);
@@ -876,7 +1673,7 @@ impl App {
                 let mut current = new_start;
                 loop {
                     let grid_xy = if horizontal { (current, fixed_coord) } else { (fixed_coord, current) };
-                    self.try_place_at(new_tile_idx, new_cell_id.word(), grid_xy, &mode);
+                    self.try_place_at(&new_cell_id, grid_xy, &mode);
                     if current == new_target { break; }
                     current += inward;
                 }
@@ -888,6 +1685,7 @@ impl App {
                     horizontal,
                     fixed_coord,
                     last_free: new_target,
+                    direction: mode.direction,
                 });
             } else {
                 // Haven't reached edge yet; update position
@@ -908,7 +1706,7 @@ impl App {
             let running = self.renderer.as_ref().unwrap();
             running.tiling.tiles[result.tile_idx].id.clone()
         };
-        let entities = match self.world.tile_entities(address.word()) {
+        let entities = match self.world.tile_entities(&address) {
             Some(e) => e,
             None => return,
         };
@@ -922,6 +1720,15 @@ impl App {
         self.belt_network.spawn_item_on_entity(entity, crate::game::items::ItemId::NullSet);
     }
 
+    /// Belt entity under the given screen position, if any.
+    fn belt_at_cursor(&self, sx: f64, sy: f64) -> Option<EntityId> {
+        let result = self.find_clicked_tile(sx, sy)?;
+        let running = self.renderer.as_ref()?;
+        let address = &running.tiling.tiles[result.tile_idx].id;
+        let &entity = self.world.tile_entities(address)?.get(&result.grid_xy)?;
+        matches!(self.world.kind(entity), Some(StructureKind::Belt)).then_some(entity)
+    }
+
     /// Try to open the machine panel if the clicked grid cell contains a machine.
     /// Returns true if a machine was found and the panel was opened.
     fn try_open_machine_panel(&mut self, sx: f64, sy: f64) -> bool {
@@ -933,7 +1740,7 @@ impl App {
             let running = self.renderer.as_ref().unwrap();
             running.tiling.tiles[result.tile_idx].id.clone()
         };
-        let entities = match self.world.tile_entities(address.word()) {
+        let entities = match self.world.tile_entities(&address) {
             Some(e) => e,
             None => return false,
         };
@@ -943,23 +1750,53 @@ impl App {
         };
         match self.world.kind(entity) {
             Some(StructureKind::Machine(_)) => {
-                self.ui.splitter_panel_entity = None;
-                self.ui.storage_panel_entity = None;
+                self.ui.close_inspect_panels();
                 self.ui.machine_panel_entity = Some(entity);
                 true
             }
             Some(StructureKind::Splitter) => {
-                self.ui.machine_panel_entity = None;
-                self.ui.storage_panel_entity = None;
+                self.ui.close_inspect_panels();
                 self.ui.splitter_panel_entity = Some(entity);
                 true
             }
             Some(StructureKind::Storage) => {
-                self.ui.machine_panel_entity = None;
-                self.ui.splitter_panel_entity = None;
+                self.ui.close_inspect_panels();
                 self.ui.storage_panel_entity = Some(entity);
                 true
             }
+            Some(StructureKind::Sink) => {
+                self.ui.close_inspect_panels();
+                self.ui.sink_panel_entity = Some(entity);
+                true
+            }
+            Some(StructureKind::Loader) => {
+                self.ui.close_inspect_panels();
+                self.ui.loader_panel_entity = Some(entity);
+                true
+            }
+            Some(StructureKind::Station) => {
+                self.ui.close_inspect_panels();
+                self.ui.station_panel_entity = Some(entity);
+                true
+            }
+            Some(StructureKind::Belt) => {
+                self.ui.close_inspect_panels();
+                self.ui.belt_panel_entity = Some(entity);
+                true
+            }
+            Some(StructureKind::ArithmeticCombinator | StructureKind::DeciderCombinator) => {
+                self.ui.close_inspect_panels();
+                self.ui.combinator_panel_entity = Some(entity);
+                true
+            }
+            Some(StructureKind::Rail) => match self.rail_network.train_at(entity) {
+                Some(train) => {
+                    self.ui.close_inspect_panels();
+                    self.ui.train_panel = Some(train);
+                    true
+                }
+                None => false,
+            },
             _ => false,
         }
     }
@@ -975,23 +1812,59 @@ impl App {
             let running = self.renderer.as_ref().unwrap();
             running.tiling.tiles[result.tile_idx].id.clone()
         };
-        let entities = match self.world.tile_entities(address.word()) {
-            Some(e) => e,
-            None => return false,
-        };
-        let &entity = match entities.get(&result.grid_xy) {
-            Some(e) => e,
-            None => return false,
-        };
+        self.perform(PlayerAction::Remove {
+            address: TileAddr::from_slice(address.word()),
+            grid_xy: result.grid_xy,
+        })
+    }
 
-        let kind = match self.world.kind(entity) {
-            Some(k) => k,
-            None => return false,
+    /// Remove the structure at a grid cell, refunding it to the inventory.
+    /// Returns the item it came back as.
+    fn remove_structure(&mut self, address: &CellId, grid_xy: (i32, i32)) -> Option<crate::game::items::ItemId> {
+        // The main layer is destroyed first; a pipe underneath goes once the cell is clear
+        let top = self
+            .world
+            .tile_entities(address)
+            .and_then(|e| e.get(&grid_xy).copied());
+        let underlay = top.is_none();
+
+        // A train on the rail comes off before the rail itself
+        if let Some(train) = top.and_then(|e| self.rail_network.train_at(e)) {
+            let train = self.rail_network.remove_train(train)?;
+            self.refund_train(train);
+            return Some(crate::game::items::ItemId::Locomotive);
+        }
+
+        let entity = top.or_else(|| self.world.underlay_at(address, grid_xy))?;
+
+        let kind = self.world.kind(entity)?;
+
+        // Stations alongside a storage lose it once it is gone
+        let stations = match (kind, self.world.position(entity)) {
+            (StructureKind::Storage, Some(pos)) => {
+                let origin = (pos.gx as i32, pos.gy as i32);
+                let facing = self.world.direction(entity).unwrap_or(Direction::North);
+                let (w, h) = kind.footprint();
+                self.edge_adjacent(address, origin, facing.rotate_footprint(w, h), StructureKind::Station)
+            }
+            _ => Vec::new(),
         };
 
+        // Cut any signal wires; the wire items come back with the structure
+        let wires = self.circuit_network.remove_entity(entity);
+        if wires > 0 {
+            self.inventory.add(crate::game::items::ItemId::SignalWire, wires as u32);
+        }
+        if self.ui.wire_start.is_some_and(|end| end.entity == entity) {
+            self.ui.wire_start = None;
+        }
+
         // Unregister from simulation systems
         match kind {
             StructureKind::Belt => {
+                if self.ui.belt_panel_entity == Some(entity) {
+                    self.ui.belt_panel_entity = None;
+                }
                 // Clean up splitter connections before removing belt from network
                 let (output_splitter, input_splitter) =
                     self.belt_network.line_splitter_connections(entity);
@@ -1003,6 +1876,7 @@ impl App {
                     self.splitter_pool.disconnect_belt(se, entity);
                     self.splitter_pool.detect_mode(se);
                 }
+                self.loader_pool.disconnect_belt(entity);
                 self.belt_network.on_belt_removed(entity);
             }
             StructureKind::Machine(_) => {
@@ -1010,6 +1884,8 @@ impl App {
                 if self.ui.machine_panel_entity == Some(entity) {
                     self.ui.machine_panel_entity = None;
                 }
+                self.loader_pool.clear_target(entity);
+                self.fluid_network.detach_owner(entity);
                 self.machine_pool.remove(entity);
                 self.power_network.remove(entity);
             }
@@ -1033,49 +1909,67 @@ impl App {
                     }
                 }
                 self.belt_network.disconnect_storage_ports(entity);
+                self.loader_pool.clear_target(entity);
                 self.storage_pool.remove(entity);
             }
+            StructureKind::Sink => {
+                if self.ui.sink_panel_entity == Some(entity) {
+                    self.ui.sink_panel_entity = None;
+                }
+                self.belt_network.disconnect_sink_ports(entity);
+                self.sink_pool.remove(entity);
+            }
+            StructureKind::Loader => {
+                if self.ui.loader_panel_entity == Some(entity) {
+                    self.ui.loader_panel_entity = None;
+                }
+                self.belt_network.disconnect_loader_ports(entity);
+                self.loader_pool.remove(entity);
+            }
+            StructureKind::Pipe => {
+                self.fluid_network.remove_pipe(entity);
+            }
+            StructureKind::Pump => {
+                self.fluid_network.detach_owner(entity);
+            }
+            StructureKind::Rail => {
+                for train in self.rail_network.remove_rail(entity) {
+                    self.refund_train(train);
+                }
+            }
+            StructureKind::Station => {
+                if self.ui.station_panel_entity == Some(entity) {
+                    self.ui.station_panel_entity = None;
+                }
+                self.rail_network.remove_station(entity);
+            }
+            StructureKind::ArithmeticCombinator | StructureKind::DeciderCombinator => {
+                if self.ui.combinator_panel_entity == Some(entity) {
+                    self.ui.combinator_panel_entity = None;
+                }
+            }
             StructureKind::PowerNode | StructureKind::PowerSource => {
                 self.power_network.remove(entity);
             }
         }
 
         // Remove from world (handles multi-cell footprints)
-        if let Some(item) = self.world.remove(address.word(), result.grid_xy) {
-            self.inventory.add(item, 1);
-
-            // Flash feedback
-            let running = self.renderer.as_ref().unwrap();
-            let width = running.gpu.config.width as f32;
-            let height = running.gpu.config.height as f32;
-            let scale = running.gpu.window.scale_factor() as f32;
-            let aspect = width / height;
-            let view_proj = self.camera.build_view_proj(aspect);
-
-            let inv_view = self.camera.local.inverse();
-            let tile_xform = running.tiling.tiles[result.tile_idx].transform;
-            let combined = inv_view.compose(&tile_xform);
-
-            let khs = self.klein_half_side;
-            let divisions = 64.0_f64;
-            let snap_kx = (result.grid_xy.0 as f64 / divisions) * 2.0 * khs;
-            let snap_ky = (result.grid_xy.1 as f64 / divisions) * 2.0 * khs;
-            let kr2 = snap_kx * snap_kx + snap_ky * snap_ky;
-            let denom = 1.0 + (1.0 - kr2).max(0.0).sqrt();
-            let local_disk = Complex::new(snap_kx / denom, snap_ky / denom);
-            let world_disk = combined.apply(local_disk);
-            let bowl = crate::hyperbolic::embedding::disk_to_bowl(world_disk);
-            let elevation = running.extra_elevation.get(&result.tile_idx).copied().unwrap_or(0.0);
-            let world_pos = glam::Vec3::new(bowl[0], bowl[1] + elevation, bowl[2]);
-
-            if let Some((px, py)) = project_to_screen(world_pos, &view_proj, width, height) {
-                self.ui.flash_label = format!("-{}", item.display_name());
-                self.ui.flash_screen_pos = Some((px / scale, py / scale));
-                self.ui.flash_timer = 0.4;
+        let removed = if underlay {
+            self.world.remove_underlay(address, grid_xy)
+        } else {
+            self.world.remove(address, grid_xy)
+        };
+        let item = removed?;
+        self.inventory.add(item, 1);
+        if self.world.tile_entities(address).is_none() && self.world.underlay_entities(address).is_none() {
+            if let Some(re) = &mut self.renderer {
+                re.tiling.unpin(address);
             }
-            return true;
         }
-        false
+        for station in stations {
+            self.refresh_station(station);
+        }
+        Some(item)
     }
 
     /// Rotate the structure at the given screen position 90° clockwise.
@@ -1089,87 +1983,64 @@ impl App {
             let running = self.renderer.as_ref().unwrap();
             running.tiling.tiles[result.tile_idx].id.clone()
         };
-        let entities = match self.world.tile_entities(address.word()) {
-            Some(e) => e,
-            None => return false,
-        };
-        let &entity = match entities.get(&result.grid_xy) {
-            Some(e) => e,
-            None => return false,
-        };
-
-        let kind = match self.world.kind(entity) {
-            Some(k) => k,
-            None => return false,
-        };
+        self.perform(PlayerAction::Rotate {
+            address: TileAddr::from_slice(address.word()),
+            grid_xy: result.grid_xy,
+        })
+    }
 
-        // Only rotate machines, storage, and power structures (not belts — belt direction is functional)
-        let machine_type = match kind {
-            StructureKind::Machine(mt) => Some(mt),
-            StructureKind::Storage | StructureKind::PowerSource => None,
-            _ => return false,
+    /// Rotate the structure at a grid cell 90° clockwise and rewire its
+    /// ports. Returns the new facing.
+    fn rotate_structure(&mut self, address: &CellId, grid_xy: (i32, i32)) -> Option<Direction> {
+        let &entity = self.world.tile_entities(address)?.get(&grid_xy)?;
+        let kind = self.world.kind(entity)?;
+
+        // Only rotate machines, storage, sinks, loaders, pumps, and power structures (not belts — belt direction is functional)
+        let is_machine = match kind {
+            StructureKind::Machine(_) => true,
+            StructureKind::Storage
+            | StructureKind::Sink
+            | StructureKind::Loader
+            | StructureKind::Pump
+            | StructureKind::PowerSource => false,
+            _ => return None,
         };
 
         // Disconnect old belt connections for machines and storage
-        if machine_type.is_some() {
+        if is_machine {
             self.belt_network.disconnect_machine_ports(entity);
         }
         if kind == StructureKind::Storage {
             self.belt_network.disconnect_storage_ports(entity);
         }
+        if kind == StructureKind::Sink {
+            self.belt_network.disconnect_sink_ports(entity);
+        }
 
         // Rotate direction
-        let new_dir = match self.world.rotate_cw(entity) {
-            Some(d) => d,
-            None => return false,
-        };
+        let new_dir = self.world.rotate_cw(entity)?;
 
         // Auto-reconnect ports for machines and storage
-        if let Some(mt) = machine_type {
-            let origin = match self.world.position(entity) {
-                Some(p) => (p.gx as i32, p.gy as i32),
-                None => return false,
-            };
-            self.auto_connect_machine_ports(entity, address.word(), origin, new_dir, mt);
+        if is_machine {
+            let origin = self.world.position(entity).map(|p| (p.gx as i32, p.gy as i32))?;
+            self.auto_connect_machine_ports(entity, address, origin, new_dir);
         }
         if kind == StructureKind::Storage {
-            let origin = match self.world.position(entity) {
-                Some(p) => (p.gx as i32, p.gy as i32),
-                None => return false,
-            };
-            self.auto_connect_storage_to_belts(entity, address.word(), origin, new_dir);
+            let origin = self.world.position(entity).map(|p| (p.gx as i32, p.gy as i32))?;
+            self.auto_connect_storage_to_belts(entity, address, origin, new_dir);
         }
-
-        // Flash feedback
-        let running = self.renderer.as_ref().unwrap();
-        let width = running.gpu.config.width as f32;
-        let height = running.gpu.config.height as f32;
-        let scale = running.gpu.window.scale_factor() as f32;
-        let aspect = width / height;
-        let view_proj = self.camera.build_view_proj(aspect);
-
-        let inv_view = self.camera.local.inverse();
-        let tile_xform = running.tiling.tiles[result.tile_idx].transform;
-        let combined = inv_view.compose(&tile_xform);
-
-        let khs = self.klein_half_side;
-        let divisions = 64.0_f64;
-        let snap_kx = (result.grid_xy.0 as f64 / divisions) * 2.0 * khs;
-        let snap_ky = (result.grid_xy.1 as f64 / divisions) * 2.0 * khs;
-        let kr2 = snap_kx * snap_kx + snap_ky * snap_ky;
-        let denom = 1.0 + (1.0 - kr2).max(0.0).sqrt();
-        let local_disk = Complex::new(snap_kx / denom, snap_ky / denom);
-        let world_disk = combined.apply(local_disk);
-        let bowl = crate::hyperbolic::embedding::disk_to_bowl(world_disk);
-        let elevation = running.extra_elevation.get(&result.tile_idx).copied().unwrap_or(0.0);
-        let world_pos = glam::Vec3::new(bowl[0], bowl[1] + elevation, bowl[2]);
-
-        if let Some((px, py)) = project_to_screen(world_pos, &view_proj, width, height) {
-            self.ui.flash_label = format!("{}", new_dir.arrow_char());
-            self.ui.flash_screen_pos = Some((px / scale, py / scale));
-            self.ui.flash_timer = 0.3;
+        if kind == StructureKind::Sink {
+            let origin = self.world.position(entity).map(|p| (p.gx as i32, p.gy as i32))?;
+            self.auto_connect_sink_to_belts(entity, address, origin, new_dir);
         }
-        true
+        if kind == StructureKind::Loader {
+            self.connect_loader(entity, address);
+        }
+        if is_machine || kind == StructureKind::Pump {
+            self.attach_fluid_ports(entity);
+        }
+
+        Some(new_dir)
     }
 
     fn modify_terrain(&mut self, sx: f64, sy: f64, delta: f32) {
@@ -1186,6 +2057,13 @@ impl App {
         let render_camera = self.game_loop.interpolated_camera()
             .unwrap_or_else(|| self.camera.snapshot());
 
+        let hovered_belt = match (self.ui.placement_mode.is_none(), self.ui.cursor_pos) {
+            (true, Some(pos)) => self.belt_at_cursor(pos.x, pos.y),
+            _ => None,
+        };
+
+        let remote_cursors = self.remote_cursor_positions();
+
         let re = self.renderer.as_mut().unwrap();
         let aspect = re.width() / re.height();
         let view_proj = render_camera.build_view_proj(aspect);
@@ -1193,13 +2071,45 @@ impl App {
 
         // Visibility culling + instanced tile rendering setup
         let visible = re.visible_tiles(&inv_view);
-        re.build_tile_instances(&visible, &view_proj, self.grid_enabled, self.klein_half_side as f32);
+        re.build_tile_instances(
+            &visible,
+            &view_proj,
+            self.grid_enabled,
+            self.klein_half_side as f32,
+            self.world.corner_exclusion() as f32,
+            self.camera.projection,
+        );
+
+        // Build pipe instances from the underlay layer of visible tiles
+        re.pipe_instances.clear();
+        for &(tile_idx, combined) in &visible {
+            let tile = &re.tiling.tiles[tile_idx];
+            let pipes = match self.world.underlay_entities(&tile.id) {
+                Some(p) => p,
+                None => continue,
+            };
+            for (&(gx, gy), &entity) in pipes {
+                let Some(seg) = self.fluid_network.segment(entity) else {
+                    continue;
+                };
+                let fill = seg.contents.amount as f32 / crate::sim::fluid::PIPE_CAPACITY as f32;
+                re.pipe_instances.push(PipeInstance {
+                    mobius_a: [combined.a.re as f32, combined.a.im as f32],
+                    mobius_b: [combined.b.re as f32, combined.b.im as f32],
+                    grid_pos: [gx as f32, gy as f32],
+                    fill,
+                    fluid_color: seg.contents.fluid.map(|f| f.color()).unwrap_or([0.0; 3]),
+                    connections: self.fluid_network.connection_mask(entity),
+                });
+            }
+        }
+        re.pipe_instances.upload(&re.gpu.device, &re.gpu.queue);
 
         // Build belt instances from visible tiles + world state
         re.belt_instances.clear();
         for &(tile_idx, combined) in &visible {
             let tile = &re.tiling.tiles[tile_idx];
-            let entities = match self.world.tile_entities(tile.id.word()) {
+            let entities = match self.world.tile_entities(&tile.id) {
                 Some(e) => e,
                 None => continue,
             };
@@ -1232,7 +2142,7 @@ impl App {
         re.machine_instances.clear();
         for &(tile_idx, combined) in &visible {
             let tile = &re.tiling.tiles[tile_idx];
-            let entities = match self.world.tile_entities(tile.id.word()) {
+            let entities = match self.world.tile_entities(&tile.id) {
                 Some(e) => e,
                 None => continue,
             };
@@ -1258,10 +2168,19 @@ impl App {
                     Some(StructureKind::PowerSource) => (7.0, false),
                     Some(StructureKind::Splitter) => (8.0, false),
                     Some(StructureKind::Storage) => (9.0, false),
+                    Some(StructureKind::Sink) => (10.0, false),
+                    Some(StructureKind::Loader) => (11.0, false),
+                    Some(StructureKind::Pump) => (12.0, false),
+                    Some(StructureKind::Rail) => (13.0, false),
+                    Some(StructureKind::Station) => (14.0, false),
+                    Some(StructureKind::ArithmeticCombinator) => (17.0, false),
+                    Some(StructureKind::DeciderCombinator) => (18.0, false),
                     _ => continue,
                 };
 
-                let progress = if has_pool_entry {
+                let progress = if has_pool_entry && self.machine_pool.is_enabled(entity) == Some(false) {
+                    -1.0 // Switched off by a circuit condition: dimmed like an idle machine
+                } else if has_pool_entry {
                     self.machine_pool
                         .state(entity)
                         .map(|s| match s {
@@ -1278,10 +2197,24 @@ impl App {
                 } else if machine_type_float == 9.0 {
                     // Storage: encode fill fraction (0.0-1.0) in progress field
                     self.storage_pool.fill_fraction(entity)
+                } else if machine_type_float == 13.0 {
+                    // Rail: encode track connection bitmask in progress field
+                    self.rail_network.connection_mask(entity) as f32
+                } else if machine_type_float == 11.0 {
+                    // Loader: encode mode in progress field (0 = load, 1 = unload)
+                    match self.loader_pool.get(entity).map(|s| s.mode) {
+                        Some(crate::sim::loader::LoaderMode::Unload) => 1.0,
+                        _ => 0.0,
+                    }
                 } else {
                     -1.0 // Power nodes are always "idle" visually
                 };
 
+                let ports = self
+                    .machine_pool
+                    .ports(entity)
+                    .map(MachineInstance::encode_ports)
+                    .unwrap_or(0);
                 let power_sat = self.power_network.satisfaction(entity).unwrap_or(-1.0);
                 let facing = self.world.direction(entity).unwrap_or(Direction::North);
                 let facing_float = facing.rotations_from_north() as f32;
@@ -1294,6 +2227,36 @@ impl App {
                     progress,
                     power_sat,
                     facing: facing_float,
+                    ports,
+                });
+            }
+        }
+
+        // Trains ride on top of their rails: locomotive at the head, one wagon per trail rail
+        let tile_xforms: HashMap<&CellId, _> = visible
+            .iter()
+            .map(|&(tile_idx, combined)| (&re.tiling.tiles[tile_idx].id, combined))
+            .collect();
+        for train in self.rail_network.trains() {
+            let wagons = train.trail.iter().zip(&train.wagons).map(|(&rail, wagon)| {
+                (rail, 16.0, wagon.total() as f32 / crate::sim::rail::WAGON_CAPACITY as f32)
+            });
+            for (rail, machine_type, progress) in std::iter::once((train.head, 15.0, -1.0)).chain(wagons) {
+                let Some(pos) = self.world.position(rail) else {
+                    continue;
+                };
+                let Some(combined) = tile_xforms.get(self.world.cell(pos.cell)) else {
+                    continue;
+                };
+                re.machine_instances.push(MachineInstance {
+                    mobius_a: [combined.a.re as f32, combined.a.im as f32],
+                    mobius_b: [combined.b.re as f32, combined.b.im as f32],
+                    grid_pos: [pos.gx as f32, pos.gy as f32],
+                    machine_type,
+                    progress,
+                    power_sat: -1.0,
+                    facing: train.heading.rotations_from_north() as f32,
+                    ports: 0,
                 });
             }
         }
@@ -1305,7 +2268,7 @@ impl App {
         let divisions = 64.0;
         for &(tile_idx, combined) in &visible {
             let tile = &re.tiling.tiles[tile_idx];
-            let entities = match self.world.tile_entities(tile.id.word()) {
+            let entities = match self.world.tile_entities(&tile.id) {
                 Some(e) => e,
                 None => continue,
             };
@@ -1354,6 +2317,7 @@ impl App {
         // Label overlay
         if re.render.labels_enabled {
             let egui_ctx = re.egui.ctx.clone();
+            let projection = self.camera.projection;
             let area = egui::Area::new(egui::Id::new("tile_labels"))
                 .order(egui::Order::Background)
                 .interactable(false);
@@ -1365,7 +2329,7 @@ impl App {
                         continue;
                     }
                     let elevation = re.extra_elevation.get(&tile_idx).copied().unwrap_or(0.0);
-                    let hyp = crate::hyperbolic::embedding::disk_to_bowl(disk_center);
+                    let hyp = projection.project(disk_center);
                     let world_pos = glam::Vec3::new(hyp[0], hyp[1] + elevation, hyp[2]);
                     if let Some((sx, sy)) = project_to_screen(world_pos, &view_proj, width, height) {
                         let lx = sx / scale;
@@ -1393,6 +2357,7 @@ impl App {
             &mut self.input_state,
             &mut self.ui.rebinding,
         );
+        self.camera.projection = self.config.graphics.projection;
 
         // Inventory window
         crate::ui::inventory::inventory_window(
@@ -1413,6 +2378,10 @@ impl App {
             self.config.debug.free_placement,
         );
 
+        // Edits made in the panels below go through `perform`, which needs
+        // `&mut self`; they are applied once the renderer borrow ends
+        let mut edits = Vec::new();
+
         // Machine inspection panel
         if let Some(entity) = self.ui.machine_panel_entity {
             let egui_ctx = re.egui.ctx.clone();
@@ -1425,7 +2394,13 @@ impl App {
             ) {
                 match action {
                     crate::ui::machine::MachineAction::SetRecipe(e, recipe_idx) => {
-                        self.machine_pool.set_recipe(e, recipe_idx);
+                        edits.push(PlayerAction::SetRecipe { machine: e, recipe: recipe_idx });
+                    }
+                    crate::ui::machine::MachineAction::MovePort { machine, port, cell_offset, side } => {
+                        edits.push(PlayerAction::MovePort { machine, port, cell_offset, side });
+                    }
+                    crate::ui::machine::MachineAction::ResetPorts(e) => {
+                        edits.push(PlayerAction::ResetPorts { machine: e });
                     }
                     crate::ui::machine::MachineAction::Close => {
                         self.ui.machine_panel_entity = None;
@@ -1444,6 +2419,12 @@ impl App {
                 &self.world,
             ) {
                 match action {
+                    crate::ui::splitter::SplitterAction::SetFilter { splitter, belt, filter } => {
+                        edits.push(PlayerAction::SetSplitterFilter { splitter, belt, filter });
+                    }
+                    crate::ui::splitter::SplitterAction::SetPriority { splitter, belt, priority } => {
+                        edits.push(PlayerAction::SetSplitterPriority { splitter, belt, priority });
+                    }
                     crate::ui::splitter::SplitterAction::Close => {
                         self.ui.splitter_panel_entity = None;
                     }
@@ -1461,6 +2442,15 @@ impl App {
                 &self.belt_network,
             ) {
                 match action {
+                    crate::ui::storage::StorageAction::SetWhitelist(e, items) => {
+                        edits.push(PlayerAction::SetWhitelist { storage: e, items });
+                    }
+                    crate::ui::storage::StorageAction::SetSlotLock(e, slot, lock) => {
+                        edits.push(PlayerAction::SetSlotLock { storage: e, slot, lock });
+                    }
+                    crate::ui::storage::StorageAction::SetOutputMode(e, port, mode) => {
+                        edits.push(PlayerAction::SetOutputMode { storage: e, port, mode });
+                    }
                     crate::ui::storage::StorageAction::Close => {
                         self.ui.storage_panel_entity = None;
                     }
@@ -1468,6 +2458,140 @@ impl App {
             }
         }
 
+        // Sink inspection panel
+        if let Some(entity) = self.ui.sink_panel_entity {
+            let egui_ctx = re.egui.ctx.clone();
+            if let Some(action) = crate::ui::sink::sink_panel(&egui_ctx, entity, &self.sink_pool) {
+                match action {
+                    crate::ui::sink::SinkAction::SetFilter(e, filter) => {
+                        edits.push(PlayerAction::SetSinkFilter { sink: e, filter });
+                    }
+                    crate::ui::sink::SinkAction::ResetCounts(e) => {
+                        edits.push(PlayerAction::ResetSinkCounts { sink: e });
+                    }
+                    crate::ui::sink::SinkAction::Close => {
+                        self.ui.sink_panel_entity = None;
+                    }
+                }
+            }
+        }
+
+        // Loader inspection panel
+        if let Some(entity) = self.ui.loader_panel_entity {
+            let egui_ctx = re.egui.ctx.clone();
+            if let Some(action) = crate::ui::loader::loader_panel(&egui_ctx, entity, &self.loader_pool) {
+                match action {
+                    crate::ui::loader::LoaderAction::ToggleMode(e) => {
+                        if let Some(state) = self.loader_pool.get(e) {
+                            edits.push(PlayerAction::SetLoaderMode { loader: e, mode: state.mode.toggled() });
+                        }
+                    }
+                    crate::ui::loader::LoaderAction::SetFilter(e, filter) => {
+                        edits.push(PlayerAction::SetLoaderFilter { loader: e, filter });
+                    }
+                    crate::ui::loader::LoaderAction::Close => {
+                        self.ui.loader_panel_entity = None;
+                    }
+                }
+            }
+        }
+
+        // Train station inspection panel
+        if let Some(entity) = self.ui.station_panel_entity {
+            let egui_ctx = re.egui.ctx.clone();
+            if let Some(action) = crate::ui::rail::station_panel(&egui_ctx, entity, &self.rail_network) {
+                match action {
+                    crate::ui::rail::StationAction::SetMode(e, mode) => {
+                        edits.push(PlayerAction::SetStationMode { station: e, mode });
+                    }
+                    crate::ui::rail::StationAction::Close => {
+                        self.ui.station_panel_entity = None;
+                    }
+                }
+            }
+        }
+
+        // Train inspection panel
+        if let Some(id) = self.ui.train_panel {
+            let egui_ctx = re.egui.ctx.clone();
+            match crate::ui::rail::train_panel(&egui_ctx, id, &self.rail_network) {
+                Some(crate::ui::rail::TrainAction::AddStop(t, station)) => {
+                    edits.push(PlayerAction::AddStop { train: t, station });
+                }
+                Some(crate::ui::rail::TrainAction::RemoveStop(t, index)) => {
+                    edits.push(PlayerAction::RemoveStop { train: t, index });
+                }
+                Some(crate::ui::rail::TrainAction::SetRunning(t, running)) => {
+                    edits.push(PlayerAction::SetTrainRunning { train: t, running });
+                }
+                Some(crate::ui::rail::TrainAction::Close) => {
+                    self.ui.train_panel = None;
+                }
+                None => {
+                    // The train may have been removed while the panel was open
+                    if self.rail_network.train(id).is_none() {
+                        self.ui.train_panel = None;
+                    }
+                }
+            }
+        }
+
+        // Belt inspection panel
+        if let Some(entity) = self.ui.belt_panel_entity {
+            let egui_ctx = re.egui.ctx.clone();
+            match crate::ui::belt::belt_panel(&egui_ctx, entity, &self.belt_network) {
+                Some(crate::ui::belt::BeltAction::SetSensor(e, on)) => {
+                    edits.push(PlayerAction::SetSensor { belt: e, on });
+                }
+                Some(crate::ui::belt::BeltAction::Close) => {
+                    self.ui.belt_panel_entity = None;
+                }
+                None => {}
+            }
+        }
+
+        // Combinator inspection panel
+        if let Some(entity) = self.ui.combinator_panel_entity {
+            let egui_ctx = re.egui.ctx.clone();
+            let (action, closed) =
+                crate::ui::circuit::combinator_panel(&egui_ctx, entity, &self.circuit_network);
+            if let Some(crate::ui::circuit::CircuitAction::SetCombinator(e, config)) = action {
+                edits.push(PlayerAction::SetCombinator { combinator: e, config });
+            }
+            if closed {
+                self.ui.combinator_panel_entity = None;
+            }
+        }
+
+        // Circuit window alongside an inspected machine, splitter, storage, or belt
+        let circuit_target = self
+            .ui
+            .machine_panel_entity
+            .or(self.ui.splitter_panel_entity)
+            .or(self.ui.belt_panel_entity)
+            .map(|e| (e, true))
+            .or(self.ui.storage_panel_entity.map(|e| (e, false)));
+        if let Some((entity, switchable)) = circuit_target {
+            let egui_ctx = re.egui.ctx.clone();
+            if let Some(crate::ui::circuit::CircuitAction::SetCondition(e, condition)) =
+                crate::ui::circuit::circuit_panel(&egui_ctx, entity, &self.circuit_network, switchable)
+            {
+                edits.push(PlayerAction::SetCondition { entity: e, condition });
+            }
+        }
+
+        // Sensor readout under the cursor
+        if let (Some(entity), Some(pos)) = (hovered_belt, self.ui.cursor_pos) {
+            let scale = re.gpu.window.scale_factor() as f32;
+            let egui_ctx = re.egui.ctx.clone();
+            crate::ui::belt::sensor_tooltip(
+                &egui_ctx,
+                egui::pos2(pos.x as f32 / scale, pos.y as f32 / scale),
+                entity,
+                &self.belt_network,
+            );
+        }
+
         // Debug click flash
         if self.ui.flash_timer > 0.0 {
             if let Some((fx, fy)) = self.ui.flash_screen_pos {
@@ -1498,6 +2622,29 @@ impl App {
             }
         }
 
+        // Other players' cursors
+        if !remote_cursors.is_empty() {
+            let egui_ctx = re.egui.ctx.clone();
+            egui::Area::new(egui::Id::new("player_cursors"))
+                .order(egui::Order::Foreground)
+                .fixed_pos(egui::pos2(0.0, 0.0))
+                .interactable(false)
+                .show(&egui_ctx, |ui| {
+                    let painter = ui.painter();
+                    for &(player, (x, y)) in &remote_cursors {
+                        let color = player_color(player);
+                        painter.circle_stroke(egui::pos2(x, y), 7.0, egui::Stroke::new(2.0, color));
+                        painter.text(
+                            egui::pos2(x + 9.0, y - 9.0),
+                            egui::Align2::LEFT_BOTTOM,
+                            format!("P{player}"),
+                            egui::FontId::monospace(12.0),
+                            color,
+                        );
+                    }
+                });
+        }
+
         // FPS / UPS debug overlay
         {
             let egui_ctx = re.egui.ctx.clone();
@@ -1509,24 +2656,341 @@ impl App {
                     let fps = self.game_loop.fps;
                     let ups = self.game_loop.ups;
                     let tile_count = re.tiling.tiles.len();
+                    let depth = re
+                        .tiling
+                        .tiles
+                        .get(self.camera.tile)
+                        .map_or(0, |t| cell_id::distance_from_origin(&t.id));
+                    // Compass edge of the camera cell's grid that leads back
+                    // toward the origin cell.
+                    let home = re
+                        .tiling
+                        .tiles
+                        .get(self.camera.tile)
+                        .and_then(|_| re.tiling.first_edge_toward(self.camera.tile, &CellId::origin()))
+                        .map_or("-", |edge| ["E", "S", "W", "N"][edge]);
+                    let asleep = self.cell_sleep.asleep_count();
+                    let lines = self.belt_network.line_count();
+                    let active = self.belt_network.active_line_count();
+                    let machines = self.machine_pool.count;
+                    let working = self.machine_pool.active_count();
+                    let splitters = self.splitter_pool.count();
                     ui.label(
                         egui::RichText::new(format!(
-                            "FPS {fps:.0}  UPS {ups:.0}  Tiles {tile_count}"
+                            "FPS {fps:.0}  UPS {ups:.0}  Tiles {tile_count}  Depth {depth}  Home {home}  Asleep {asleep}  \
+                             Lines {active}/{lines}  Machines {working}/{machines}  Splitters {splitters}"
                         ))
                         .color(egui::Color32::from_rgb(180, 220, 180))
                         .size(13.0)
                         .font(egui::FontId::monospace(13.0)),
                     );
+                    if let Some(net) = &self.net {
+                        let status = match net.player() {
+                            _ if net.is_host() => format!("Hosting  Players {}", net.peer_count() + 1),
+                            Some(player) => format!("Player {player}  Behind {}", net.backlog()),
+                            None => "Joining…".to_string(),
+                        };
+                        ui.label(
+                            egui::RichText::new(format!("{status}  Tick {}", self.game_loop.sim_tick))
+                                .color(egui::Color32::from_rgb(180, 200, 240))
+                                .font(egui::FontId::monospace(13.0)),
+                        );
+                        if let Some((tick, player)) = net.desync() {
+                            ui.label(
+                                egui::RichText::new(format!("DESYNC: player {player} diverged at tick {tick}"))
+                                    .color(egui::Color32::from_rgb(255, 90, 90))
+                                    .font(egui::FontId::monospace(13.0)),
+                            );
+                        }
+                    }
                 });
         }
 
         let full_output = re.egui.end_frame(&window);
 
         // GPU render passes + submit
-        let output = re.draw_and_submit(&full_output)?;
-        output.present();
+        let output = re.draw_and_submit(&full_output);
+        for action in edits {
+            self.perform(action);
+        }
+        output?.present();
         Ok(())
     }
+
+    /// Run the next simulation tick. In a session its frame is applied
+    /// first; a client that hasn't received the frame yet waits instead.
+    fn run_tick(&mut self) -> bool {
+        if let Some(net) = &mut self.net {
+            let Some(frame) = net.next_frame(self.game_loop.sim_tick + 1) else {
+                return false;
+            };
+            let me = net.player();
+            for command in frame.commands {
+                self.apply_action(command.action, Some(command.player) == me);
+            }
+        }
+        self.step_simulation();
+        true
+    }
+
+    /// Advance every simulation system by one tick.
+    fn step_simulation(&mut self) {
+        let tick = self.game_loop.advance_tick();
+        // Solve power network and propagate satisfaction to machines
+        self.power_network.solve();
+        for i in 0..self.machine_pool.count {
+            let entity = self.machine_pool.cold.entity_id[i];
+            if let Some(sat) = self.power_network.satisfaction(entity) {
+                self.machine_pool.set_power_satisfaction(entity, sat);
+            }
+        }
+        self.circuit_network.tick(
+            &self.storage_pool,
+            &mut self.belt_network,
+            &mut self.machine_pool,
+            &mut self.splitter_pool,
+        );
+        self.machine_pool.tick(&self.recipes);
+        self.fluid_network.tick(&mut self.machine_pool);
+        self.belt_network.tick();
+        self.splitter_pool.tick(&mut self.belt_network);
+        self.loader_pool.tick(&mut self.belt_network, &mut self.machine_pool, &mut self.storage_pool);
+        self.rail_network.tick(&mut self.storage_pool);
+        self.belt_network.tick_port_transfers(
+            &mut self.machine_pool,
+            &mut self.storage_pool,
+            &mut self.sink_pool,
+        );
+        self.cell_sleep.tick();
+        if tick.is_multiple_of(crate::sim::state_hash::STATE_HASH_INTERVAL) {
+            let hash = self.state_hash();
+            log::debug!("tick {tick}: state hash {hash:016x}");
+            if let Some(net) = &mut self.net {
+                net.record_hash(tick, hash);
+            }
+        }
+    }
+
+    /// Exchange messages with the other players. Actions need the render
+    /// tiling to resolve cells, so nothing happens until the window is up.
+    fn poll_network(&mut self) {
+        let Some(net) = &mut self.net else {
+            return;
+        };
+        if self.renderer.is_none() {
+            return;
+        }
+        net.poll();
+        if net.is_closed() {
+            log::warn!("lost connection to the host; continuing alone");
+            self.net = None;
+            return;
+        }
+        let wants_snapshot = net.wants_snapshot();
+        if let Some(welcome) = net.take_welcome() {
+            if welcome.q != self.cfg.q {
+                log::error!(
+                    "the host plays on {{4,{}}} but this game is {{4,{}}}; run `octofact 4 {} --join ...` to join it",
+                    welcome.q,
+                    self.cfg.q,
+                    welcome.q
+                );
+                self.net = None;
+                return;
+            }
+            if !self.join_from_snapshot(welcome) {
+                log::error!("the host sent a snapshot this game can't read; continuing alone");
+                self.net = None;
+                return;
+            }
+        }
+        if wants_snapshot {
+            // Load our own snapshot back too, so both ends share its entity
+            // key layout (see `net::snapshot`)
+            let bytes = self.take_snapshot().encode();
+            let snapshot = Snapshot::decode(&bytes).expect("a snapshot just taken reads back");
+            self.restore_snapshot(snapshot);
+            if let Some(net) = &mut self.net {
+                net.send_snapshot(bytes);
+            }
+        }
+
+        let cursor = self.ui.cursor_pos.and_then(|pos| {
+            let result = self.find_clicked_tile(pos.x, pos.y)?;
+            let tile = &self.renderer.as_ref()?.tiling.tiles[result.tile_idx];
+            Some(Cursor { address: TileAddr::from_slice(tile.id.word()), grid_xy: result.grid_xy })
+        });
+        if let Some(cursor) = cursor.filter(|c| self.sent_cursor.as_ref() != Some(c)) {
+            self.sent_cursor = Some(cursor.clone());
+            if let Some(net) = &mut self.net {
+                net.send_cursor(cursor);
+            }
+        }
+    }
+
+    /// Take on the host's world on joining. False if its snapshot doesn't
+    /// decode.
+    fn join_from_snapshot(&mut self, welcome: Welcome) -> bool {
+        let Some(snapshot) = Snapshot::decode(&welcome.snapshot) else {
+            return false;
+        };
+        self.config.debug.free_placement = welcome.free_placement;
+        self.restore_snapshot(snapshot);
+        self.game_loop.sim_tick = welcome.tick;
+        log::info!("joined at tick {}", welcome.tick);
+        true
+    }
+
+    /// Move the simulation out into a snapshot, leaving empty pools behind
+    /// until `restore_snapshot` puts one back.
+    fn take_snapshot(&mut self) -> Snapshot {
+        use std::mem::{replace, take};
+        Snapshot {
+            world: replace(&mut self.world, WorldState::new()),
+            inventory: take(&mut self.inventory),
+            belts: replace(&mut self.belt_network, BeltNetwork::new()),
+            machines: replace(&mut self.machine_pool, crate::sim::machine::MachinePool::new()),
+            splitters: replace(&mut self.splitter_pool, crate::sim::splitter::SplitterPool::new()),
+            storage: replace(&mut self.storage_pool, crate::sim::storage::StoragePool::new()),
+            sinks: replace(&mut self.sink_pool, crate::sim::sink::SinkPool::new()),
+            loaders: replace(&mut self.loader_pool, crate::sim::loader::LoaderPool::new()),
+            fluids: replace(&mut self.fluid_network, crate::sim::fluid::FluidNetwork::new()),
+            rails: replace(&mut self.rail_network, crate::sim::rail::RailNetwork::new()),
+            circuits: replace(&mut self.circuit_network, crate::sim::circuit::CircuitNetwork::new()),
+            power: replace(&mut self.power_network, crate::sim::power::PowerNetwork::new()),
+            sleep: replace(&mut self.cell_sleep, crate::sim::sleep::CellSleep::new()),
+        }
+    }
+
+    /// Replace the simulation with `snapshot`, keeping its occupied cells
+    /// loaded as placing their structures would have.
+    fn restore_snapshot(&mut self, snapshot: Snapshot) {
+        let rules = || crate::hyperbolic::rewrite::rules_for(self.cfg.q);
+        self.world = snapshot.world;
+        self.inventory = snapshot.inventory;
+        self.belt_network = snapshot.belts;
+        self.machine_pool = snapshot.machines;
+        self.splitter_pool = snapshot.splitters;
+        self.storage_pool = snapshot.storage;
+        self.sink_pool = snapshot.sinks;
+        self.loader_pool = snapshot.loaders;
+        self.fluid_network = snapshot.fluids;
+        self.rail_network = snapshot.rails.with_rules(rules());
+        self.circuit_network = snapshot.circuits;
+        self.power_network = snapshot.power.with_rules(rules());
+        self.cell_sleep = snapshot.sleep;
+        if let Some(re) = &mut self.renderer {
+            for cell in self.world.occupied_tiles() {
+                re.tiling.pin(cell);
+            }
+        }
+    }
+
+    /// Screen positions of the other players' cursors whose cells are loaded.
+    fn remote_cursor_positions(&self) -> Vec<(PlayerId, (f32, f32))> {
+        let (Some(net), Some(running)) = (&self.net, &self.renderer) else {
+            return Vec::new();
+        };
+        net.cursors()
+            .filter_map(|(player, cursor)| {
+                if !crate::hyperbolic::rewrite::is_valid_word(&cursor.address) {
+                    return None;
+                }
+                let tile_idx = running.tiling.find_tile(&running.tiling.canonical_cell(&cursor.address))?;
+                Some((player, self.grid_screen_pos(tile_idx, cursor.grid_xy)?))
+            })
+            .collect()
+    }
+
+    /// Deterministic hash of the simulation state (see `sim::state_hash`).
+    fn state_hash(&self) -> u64 {
+        crate::sim::state_hash::state_hash(
+            &self.belt_network,
+            &self.machine_pool,
+            &self.storage_pool,
+            &self.splitter_pool,
+            &self.sink_pool,
+            &self.power_network,
+            &self.fluid_network,
+            &self.rail_network,
+            &self.circuit_network,
+            &self.loader_pool,
+        )
+    }
+
+    /// Put occupied cells outside the active radius to sleep, and wake (and
+    /// catch up) those that have come back into range.
+    fn update_cell_sleep(&mut self) {
+        let Some(running) = &self.renderer else {
+            return;
+        };
+        let Some(camera_tile) = running.tiling.tiles.get(self.camera.tile) else {
+            return;
+        };
+        // Measure from the camera cell's centre by cell word rather than from
+        // tile transforms, which drift for far (pinned) tiles. Widening the
+        // reach by the camera's offset from that centre keeps every cell
+        // within ACTIVE_RADIUS of the camera itself awake.
+        let offset = self.camera.local.inverse().compose(&camera_tile.transform).apply(Complex::ZERO).abs();
+        let reach = 2.0 * (crate::sim::sleep::ACTIVE_RADIUS.atanh() + offset.min(0.999).atanh());
+
+        let mut slept = Vec::new();
+        let mut woken = Vec::new();
+        for cell in self.world.occupied_tiles() {
+            if running.tiling.center_distance(&camera_tile.id, cell) < reach {
+                if let Some(ticks) = self.cell_sleep.wake(cell) {
+                    woken.push((cell.clone(), ticks));
+                }
+            } else if self.cell_sleep.sleep(cell) {
+                slept.push(cell.clone());
+            }
+        }
+
+        for cell in slept {
+            let Some(entities) = self.world.tile_entities(&cell) else {
+                continue;
+            };
+            self.belt_network.sleep_belts(entities.values().copied());
+            for &entity in entities.values() {
+                self.machine_pool.sleep(entity);
+            }
+        }
+        for (cell, ticks) in woken {
+            let Some(entities) = self.world.tile_entities(&cell) else {
+                continue;
+            };
+            self.belt_network.wake_belts(entities.values().copied());
+            for &entity in entities.values() {
+                self.machine_pool.wake(entity, ticks, &self.recipes);
+            }
+        }
+    }
+
+    /// Drop a machine's belt connections and re-run auto-connect against its
+    /// current port layout (after the player moved a port).
+    fn reconnect_machine_ports(&mut self, entity: EntityId) {
+        let (Some(pos), Some(facing)) = (self.world.position(entity), self.world.direction(entity)) else {
+            return;
+        };
+        let tile = self.world.cell(pos.cell).clone();
+        let origin = (pos.gx as i32, pos.gy as i32);
+        self.belt_network.disconnect_machine_ports(entity);
+        self.auto_connect_machine_ports(entity, &tile, origin, facing);
+    }
+
+    /// Switch a loader between loading and unloading and rewire its belt,
+    /// which must now flow the other way. Returns false if the loader is
+    /// gone or already in that mode.
+    fn set_loader_mode(&mut self, entity: EntityId, mode: crate::sim::loader::LoaderMode) -> bool {
+        if self.loader_pool.get(entity).is_none_or(|s| s.mode == mode) {
+            return false;
+        }
+        self.loader_pool.set_mode(entity, mode);
+        if let Some(tile) = self.world.position(entity).map(|p| self.world.cell(p.cell).clone()) {
+            self.connect_loader(entity, &tile);
+        }
+        true
+    }
 }
 
 impl ApplicationHandler for App {
@@ -1674,13 +3138,11 @@ impl ApplicationHandler for App {
                                 if !self.try_open_machine_panel(pos.x, pos.y) {
                                     self.handle_debug_click(pos.x, pos.y);
                                 }
-                            } else if self.ui.machine_panel_entity.is_some() || self.ui.splitter_panel_entity.is_some() || self.ui.storage_panel_entity.is_some() {
+                            } else if self.ui.is_inspecting() {
                                 // Clicking outside while inspection panel is open:
                                 // try to click another building, else close panel
                                 if !self.try_open_machine_panel(pos.x, pos.y) {
-                                    self.ui.machine_panel_entity = None;
-                                    self.ui.splitter_panel_entity = None;
-                                    self.ui.storage_panel_entity = None;
+                                    self.ui.close_inspect_panels();
                                 }
                             }
                         }
@@ -1698,17 +3160,15 @@ impl ApplicationHandler for App {
                     }
                 }
             }
-            WindowEvent::RedrawRequested => {
-                if self.renderer.is_some() {
-                    match self.render_frame() {
-                        Ok(_) => {}
-                        Err(wgpu::SurfaceError::Lost) => {
-                            let gpu = &self.renderer.as_ref().unwrap().gpu;
-                            gpu.surface.configure(&gpu.device, &gpu.config);
-                        }
-                        Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
-                        Err(e) => log::error!("render error: {e:?}"),
+            WindowEvent::RedrawRequested if self.renderer.is_some() => {
+                match self.render_frame() {
+                    Ok(_) => {}
+                    Err(wgpu::SurfaceError::Lost) => {
+                        let gpu = &self.renderer.as_ref().unwrap().gpu;
+                        gpu.surface.configure(&gpu.device, &gpu.config);
                     }
+                    Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
+                    Err(e) => log::error!("render error: {e:?}"),
                 }
             }
             _ => {}
@@ -1730,24 +3190,19 @@ impl ApplicationHandler for App {
 
         // Fixed timestep simulation
         let ticks = self.game_loop.accumulate(frame_dt);
+        self.poll_network();
 
         let ui_open = self.ui_is_open();
+        // Sleeping cells lag behind until woken, so their state depends on
+        // where the camera is; peers in a session must all tick everything.
+        if ticks > 0 && self.net.is_none() {
+            self.update_cell_sleep();
+        }
         for _ in 0..ticks {
             // Save per-tick so prev/curr are always one SIM_DT apart
             // and in adjacent coordinate frames (at most one tile crossing)
             self.game_loop.save_prev_camera(self.camera.snapshot());
-            // Solve power network and propagate satisfaction to machines
-            self.power_network.solve();
-            for i in 0..self.machine_pool.count {
-                let entity = self.machine_pool.cold.entity_id[i];
-                if let Some(sat) = self.power_network.satisfaction(entity) {
-                    self.machine_pool.hot.power_draw[i] = sat;
-                }
-            }
-            self.machine_pool.tick(&self.recipes);
-            self.belt_network.tick();
-            self.splitter_pool.tick(&mut self.belt_network);
-            self.belt_network.tick_port_transfers(&mut self.machine_pool, &mut self.storage_pool);
+            self.run_tick();
             if let Some(running) = &mut self.renderer {
                 self.camera.process_movement(
                     &self.input_state,
@@ -1758,6 +3213,12 @@ impl ApplicationHandler for App {
             }
             self.game_loop.save_curr_camera(self.camera.snapshot());
         }
+        // A client that has fallen behind the host catches up a few ticks at a time
+        let mut catch_up = CLIENT_CATCH_UP_TICKS;
+        while catch_up > 0 && self.net.as_ref().is_some_and(|n| n.backlog() > CLIENT_MAX_BACKLOG) {
+            self.run_tick();
+            catch_up -= 1;
+        }
 
         // Flash timer uses real frame dt for smooth fadeout
         if self.ui.flash_timer > 0.0 {
@@ -1772,19 +3233,30 @@ impl ApplicationHandler for App {
     }
 }
 
-/// Map a grid position at or past the tile edge to the neighbor tile's coordinate.
-/// Tiles share edge positions (±32 ↔ ∓32) and the grid has 64 cells per tile,
-/// so the mapping is a ±64 offset: 32→-32, 33→-31, -32→32, -33→31.
-fn cross_tile_mirror(pos: (i32, i32)) -> (i32, i32) {
-    let mx = if pos.0 > 31 { pos.0 - 64 } else if pos.0 < -31 { pos.0 + 64 } else { pos.0 };
-    let my = if pos.1 > 31 { pos.1 - 64 } else if pos.1 < -31 { pos.1 + 64 } else { pos.1 };
-    (mx, my)
+/// Cursor colour for a player.
+fn player_color(player: PlayerId) -> egui::Color32 {
+    const PALETTE: [(u8, u8, u8); 6] = [
+        (255, 200, 80),
+        (90, 200, 255),
+        (255, 110, 180),
+        (140, 240, 120),
+        (200, 150, 255),
+        (255, 140, 90),
+    ];
+    let (r, g, b) = PALETTE[player as usize % PALETTE.len()];
+    egui::Color32::from_rgb(r, g, b)
 }
 
+/// A client more than this many frames behind the host runs extra ticks.
+const CLIENT_MAX_BACKLOG: usize = 4;
+
+/// Most extra ticks a lagging client runs per rendered frame.
+const CLIENT_CATCH_UP_TICKS: u32 = 8;
+
 /// Find a belt entity at the given tile + grid position with a specific direction.
 fn find_same_dir_belt_at(
     world: &WorldState,
-    tile_addr: &[u8],
+    tile_addr: &CellId,
     grid_xy: (i32, i32),
     direction: Direction,
 ) -> Option<EntityId> {
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use super::input::{GameAction, KeyBind, default_bindings};
+use crate::hyperbolic::embedding::Projection;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GameConfig {
@@ -16,11 +17,21 @@ pub struct GameConfig {
 pub struct GraphicsConfig {
     pub render_distance: u32,
     pub frame_rate_cap: u32,
+    /// How the camera projects the hyperbolic plane.
+    #[serde(default)]
+    pub projection: Projection,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GameplayConfig {
     pub tiling_n: u32,
+    /// Radius, in grid squares, of the no-build zone around each cell corner.
+    #[serde(default = "default_corner_exclusion")]
+    pub corner_exclusion: u32,
+}
+
+fn default_corner_exclusion() -> u32 {
+    super::world::DEFAULT_CORNER_EXCLUSION as u32
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -38,9 +49,11 @@ impl Default for GameConfig {
             graphics: GraphicsConfig {
                 render_distance: 3,
                 frame_rate_cap: 60,
+                projection: Projection::default(),
             },
             gameplay: GameplayConfig {
                 tiling_n: 5,
+                corner_exclusion: default_corner_exclusion(),
             },
             debug: DebugConfig::default(),
         }
@@ -111,18 +124,23 @@ mod tests {
         let config = GameConfig::default();
         assert_eq!(config.graphics.render_distance, 3);
         assert_eq!(config.graphics.frame_rate_cap, 60);
+        assert_eq!(config.graphics.projection, Projection::Bowl);
         assert_eq!(config.gameplay.tiling_n, 5);
+        assert_eq!(config.gameplay.corner_exclusion, 4);
         assert!(!config.key_bindings.is_empty());
     }
 
     #[test]
     fn test_config_toml_roundtrip() {
-        let config = GameConfig::default();
+        let mut config = GameConfig::default();
+        config.graphics.projection = Projection::Band;
         let serialized = toml::to_string_pretty(&config).expect("serialize");
         let deserialized: GameConfig = toml::from_str(&serialized).expect("deserialize");
         assert_eq!(deserialized.graphics.render_distance, config.graphics.render_distance);
         assert_eq!(deserialized.graphics.frame_rate_cap, config.graphics.frame_rate_cap);
+        assert_eq!(deserialized.graphics.projection, Projection::Band);
         assert_eq!(deserialized.gameplay.tiling_n, config.gameplay.tiling_n);
+        assert_eq!(deserialized.gameplay.corner_exclusion, config.gameplay.corner_exclusion);
         assert_eq!(deserialized.key_bindings.len(), config.key_bindings.len());
     }
 }
@@ -0,0 +1,169 @@
+//! Carrying grid positions and headings across cell edges.
+//!
+//! Every cell has its own 64×64 grid in the frame of its canonical word. Two
+//! neighbors share their edge row (+32 on one side is −32 on the other), but
+//! their grids need not be parallel: the neighbor's frame can be a quarter
+//! turn or more round from the frame reached by stepping straight across.
+
+use super::world::Direction;
+use crate::hyperbolic::cell_id::CellId;
+use crate::hyperbolic::rewrite::RewriteRule;
+use crate::hyperbolic::tiling::{edge_crossing, Tile};
+
+/// Grid squares from one edge row to the opposite one.
+const GRID_SPAN: i32 = 64;
+
+/// The crossing out of a cell through one of its edges.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EdgeTransfer {
+    /// Cell on the far side of the edge.
+    pub cell: CellId,
+    /// Heading that leaves through the edge, in the near cell's grid.
+    pub edge: Direction,
+    /// Clockwise quarter turns from the near grid, stepped straight across,
+    /// to the far cell's grid.
+    turns: u8,
+}
+
+impl EdgeTransfer {
+    /// The crossing out of `cell` through the edge `edge` points at.
+    pub fn across(cell: &CellId, edge: Direction, rules: &[RewriteRule]) -> Self {
+        let (neighbor, turns) = edge_crossing(cell, edge.tiling_edge_index(), rules);
+        Self::new(neighbor, edge, turns)
+    }
+
+    /// The same crossing from a tile's cached neighbors, without rewriting.
+    pub fn from_tile(tile: &Tile, edge: Direction) -> Self {
+        let index = edge.tiling_edge_index() as usize;
+        Self::new(tile.neighbors[index].clone(), edge, tile.neighbor_turns[index])
+    }
+
+    /// `frame_turns` turns the crossing frame onto the neighbor's (the tiling's
+    /// convention); positions and headings go the other way round.
+    fn new(cell: CellId, edge: Direction, frame_turns: u8) -> Self {
+        Self { cell, edge, turns: (4 - frame_turns) % 4 }
+    }
+
+    /// Map a grid position on or past the edge row into the far cell's grid.
+    pub fn grid(&self, grid_xy: (i32, i32)) -> (i32, i32) {
+        let (dx, dy) = self.edge.grid_offset_i32();
+        let mut xy = (grid_xy.0 - dx * GRID_SPAN, grid_xy.1 - dy * GRID_SPAN);
+        for _ in 0..self.turns {
+            xy = (-xy.1, xy.0);
+        }
+        xy
+    }
+
+    /// Map a heading in the near cell's grid into the far cell's grid.
+    pub fn direction(&self, direction: Direction) -> Direction {
+        direction.rotate_n_cw(self.turns)
+    }
+
+    /// Map a position and heading in one go.
+    pub fn map(&self, grid_xy: (i32, i32), direction: Direction) -> ((i32, i32), Direction) {
+        (self.grid(grid_xy), self.direction(direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperbolic::poincare::{polygon_disk_radius, Complex, TilingConfig};
+    use crate::hyperbolic::rewrite;
+    use crate::hyperbolic::tiling::TilingState;
+
+    const DIRECTIONS: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+    fn covered(q: u32) -> TilingState {
+        let mut state = TilingState::new(TilingConfig::new(4, q));
+        state.ensure_coverage(Complex::ZERO, 3);
+        state
+    }
+
+    /// Squares on or past the edge row that `edge` leaves through.
+    fn edge_squares(edge: Direction) -> Vec<(i32, i32)> {
+        let (dx, dy) = edge.grid_offset_i32();
+        let mut squares = Vec::new();
+        for depth in [32, 33] {
+            for along in -32..=32 {
+                squares.push(if dx != 0 { (dx * depth, along) } else { (along, dy * depth) });
+            }
+        }
+        squares
+    }
+
+    /// Disk position of a grid square's centre on `tile`.
+    fn square_center(tile: &Tile, cfg: &TilingConfig, (gx, gy): (i32, i32)) -> Complex {
+        let r_p = polygon_disk_radius(cfg);
+        let khs = 2.0 * r_p / (1.0 + r_p * r_p) / std::f64::consts::SQRT_2;
+        let kx = gx as f64 / 64.0 * 2.0 * khs;
+        let ky = gy as f64 / 64.0 * 2.0 * khs;
+        let s = 1.0 + (1.0 - kx * kx - ky * ky).sqrt();
+        tile.transform.apply(Complex::new(kx / s, ky / s))
+    }
+
+    #[test]
+    fn test_round_trip_every_edge() {
+        for q in [5, 6] {
+            let state = covered(q);
+            let rules = rewrite::rules_for(q);
+            let mut turned = 0;
+            for tile in &state.tiles {
+                for edge in DIRECTIONS {
+                    let transfer = EdgeTransfer::from_tile(tile, edge);
+                    assert_eq!(transfer, EdgeTransfer::across(&tile.id, edge, &rules));
+                    turned += (transfer.turns != 0) as usize;
+
+                    let back = EdgeTransfer::across(&transfer.cell, transfer.direction(edge).opposite(), &rules);
+                    assert_eq!(back.cell, tile.id, "{{4,{q}}} {} {edge:?}", tile.id);
+                    for xy in edge_squares(edge) {
+                        for heading in DIRECTIONS {
+                            let (far_xy, far_heading) = transfer.map(xy, heading);
+                            assert!(far_xy.0.abs() <= 32 && far_xy.1.abs() <= 32);
+                            assert_eq!(back.map(far_xy, far_heading), (xy, heading));
+                        }
+                    }
+                }
+            }
+            assert!(turned > 0, "{{4,{q}}}: no crossing exercised a turned neighbor");
+        }
+    }
+
+    #[test]
+    fn test_edge_rows_coincide() {
+        for q in [5, 6] {
+            let state = covered(q);
+            for tile in &state.tiles {
+                for edge in DIRECTIONS {
+                    let transfer = EdgeTransfer::from_tile(tile, edge);
+                    let Some(idx) = state.find_tile(&transfer.cell) else { continue };
+                    let far = &state.tiles[idx];
+                    let (dx, dy) = edge.grid_offset_i32();
+                    for xy in edge_squares(edge).into_iter().filter(|xy| xy.0.abs() <= 32 && xy.1.abs() <= 32) {
+                        // The shared row is the same physical squares on both sides
+                        let far_xy = transfer.grid(xy);
+                        let gap = (square_center(tile, &state.cfg, xy) - square_center(far, &state.cfg, far_xy)).abs();
+                        assert!(gap < 1e-9, "{{4,{q}}} {} {edge:?} {xy:?}: off by {gap}", tile.id);
+
+                        // Walking along the row agrees with the mapped heading
+                        for along in [edge.rotate_cw(), edge.rotate_cw().opposite()] {
+                            let (ax, ay) = along.grid_offset_i32();
+                            let next = (xy.0 + ax, xy.1 + ay);
+                            if next.0.abs() > 32 || next.1.abs() > 32 {
+                                continue;
+                            }
+                            let (ox, oy) = transfer.direction(along).grid_offset_i32();
+                            assert_eq!(transfer.grid(next), (far_xy.0 + ox, far_xy.1 + oy));
+                        }
+
+                        // Stepping across lands one row inside the far cell
+                        let (ix, iy) = transfer.direction(edge).grid_offset_i32();
+                        let past = transfer.grid((xy.0 + dx, xy.1 + dy));
+                        assert_eq!(past, (far_xy.0 + ix, far_xy.1 + iy));
+                        assert_eq!((far_xy.0 * ix + far_xy.1 * iy), -32);
+                    }
+                }
+            }
+        }
+    }
+}
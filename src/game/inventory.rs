@@ -1,7 +1,9 @@
 use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
 use super::items::ItemId;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Inventory {
     items: HashMap<ItemId, u32>,
 }
@@ -18,6 +20,17 @@ impl Inventory {
         inv.add(ItemId::Belt, 2000);
         inv.add(ItemId::Splitter, 100);
         inv.add(ItemId::Storage, 20);
+        inv.add(ItemId::Void, 10);
+        inv.add(ItemId::Loader, 20);
+        inv.add(ItemId::Pipe, 500);
+        inv.add(ItemId::Pump, 10);
+        inv.add(ItemId::Rail, 500);
+        inv.add(ItemId::TrainStation, 4);
+        inv.add(ItemId::Locomotive, 2);
+        inv.add(ItemId::CargoWagon, 8);
+        inv.add(ItemId::SignalWire, 200);
+        inv.add(ItemId::ArithmeticCombinator, 10);
+        inv.add(ItemId::DeciderCombinator, 10);
         inv.add(ItemId::Quadrupole, 1);
         inv
     }
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ItemId {
     #[default]
     NullSet,
@@ -32,6 +32,17 @@ pub enum ItemId {
     Splitter,
     Storage,
     SourceMachine,
+    Void,
+    Loader,
+    Pipe,
+    Pump,
+    Rail,
+    TrainStation,
+    Locomotive,
+    CargoWagon,
+    SignalWire,
+    ArithmeticCombinator,
+    DeciderCombinator,
 }
 
 impl ItemId {
@@ -43,6 +54,8 @@ impl ItemId {
             Image, Belt, AxiomaticScience, Composer, Inverter, Embedder,
             Quotient, Transformer, KnowledgeSheaf, Quadrupole, Dynamo,
             RootOfUnity, Kernel, Quantum, Splitter, Storage, SourceMachine,
+            Void, Loader, Pipe, Pump, Rail, TrainStation, Locomotive,
+            CargoWagon, SignalWire, ArithmeticCombinator, DeciderCombinator,
         ]
     }
 
@@ -77,6 +90,17 @@ impl ItemId {
             Self::Splitter => "Splitter",
             Self::Storage => "Storage",
             Self::SourceMachine => "Source",
+            Self::Void => "Void",
+            Self::Loader => "Loader",
+            Self::Pipe => "Pipe",
+            Self::Pump => "Pump",
+            Self::Rail => "Rail",
+            Self::TrainStation => "Train Station",
+            Self::Locomotive => "Locomotive",
+            Self::CargoWagon => "Cargo Wagon",
+            Self::SignalWire => "Signal Wire",
+            Self::ArithmeticCombinator => "Arithmetic Combinator",
+            Self::DeciderCombinator => "Decider Combinator",
         }
     }
 
@@ -89,7 +113,10 @@ impl ItemId {
             | Self::Square | Self::Cube | Self::StandingWave
             | Self::Function | Self::NeckerCube | Self::Image
             | Self::AxiomaticScience => ItemCategory::Intermediate,
-            Self::Belt | Self::Quadrupole | Self::Dynamo | Self::Splitter | Self::Storage => ItemCategory::Infrastructure,
+            Self::Belt | Self::Quadrupole | Self::Dynamo | Self::Splitter | Self::Storage
+            | Self::Void | Self::Loader | Self::Pipe | Self::Pump | Self::Rail
+            | Self::TrainStation | Self::Locomotive | Self::CargoWagon | Self::SignalWire
+            | Self::ArithmeticCombinator | Self::DeciderCombinator => ItemCategory::Infrastructure,
             Self::Composer | Self::Inverter | Self::Embedder
             | Self::Quotient | Self::Transformer | Self::KnowledgeSheaf
             | Self::SourceMachine => {
@@ -104,7 +131,10 @@ impl ItemId {
             Self::NullSet | Self::Point | Self::Preimage | Self::Wavelet => 0,
             Self::RootOfUnity | Self::Kernel | Self::Quantum
             | Self::Embedder | Self::Quotient | Self::Transformer => 2,
-            Self::SourceMachine | Self::Splitter | Self::Storage => 0,
+            Self::SourceMachine | Self::Splitter | Self::Storage | Self::Void
+            | Self::Loader | Self::Pipe | Self::Pump => 0,
+            Self::Rail | Self::TrainStation | Self::Locomotive | Self::CargoWagon => 1,
+            Self::SignalWire | Self::ArithmeticCombinator | Self::DeciderCombinator => 1,
             _ => 1,
         }
     }
@@ -140,6 +170,17 @@ impl ItemId {
             Self::Splitter => "Universal junction. Merges, splits, or balances item flows depending on belt connections.",
             Self::Storage => "Buffered vault. Stores up to 20 stacks of items.",
             Self::SourceMachine => "Debug machine. Produces any item from nothing.",
+            Self::Void => "Item sink. Destroys anything delivered to it and keeps a tally.",
+            Self::Loader => "Belt coupler. Fills or empties any face of a storage or machine at full belt speed.",
+            Self::Pipe => "Fluid conduit. Runs under belts and joins neighbouring pipes across cell edges.",
+            Self::Pump => "Pressurizes a pipe run. Pressure falls off with distance, so relay pumps are needed every ~40 squares.",
+            Self::Rail => "Track segment. Joins neighbouring rails across cell edges so trains can run spokes to the origin.",
+            Self::TrainStation => "Stops trains on rails along its edge and loads or unloads them from adjacent storage.",
+            Self::Locomotive => "Place on a rail. Runs a looping schedule of stations.",
+            Self::CargoWagon => "Place on a locomotive's rail to couple it. Holds 200 items.",
+            Self::SignalWire => "Click two structures to wire them. Wired storage and belts report their contents as signals.",
+            Self::ArithmeticCombinator => "Computes a signal from its input network and puts the result on its output network.",
+            Self::DeciderCombinator => "Outputs a signal while a condition on its input network holds.",
         }
     }
 
@@ -243,6 +284,61 @@ impl ItemId {
                 primary_color: [0.8, 0.6, 0.3],
                 secondary_color: [0.6, 0.4, 0.15],
             },
+            Self::Void => IconParams {
+                shape: IconShape::Octagon,
+                primary_color: [0.3, 0.2, 0.4],
+                secondary_color: [0.1, 0.05, 0.15],
+            },
+            Self::Loader => IconParams {
+                shape: IconShape::Octagon,
+                primary_color: [0.5, 0.7, 0.9],
+                secondary_color: [0.3, 0.45, 0.65],
+            },
+            Self::Pipe => IconParams {
+                shape: IconShape::Octagon,
+                primary_color: [0.4, 0.55, 0.5],
+                secondary_color: [0.2, 0.35, 0.3],
+            },
+            Self::Pump => IconParams {
+                shape: IconShape::Octagon,
+                primary_color: [0.3, 0.75, 0.6],
+                secondary_color: [0.15, 0.5, 0.4],
+            },
+            Self::Rail => IconParams {
+                shape: IconShape::Octagon,
+                primary_color: [0.55, 0.55, 0.6],
+                secondary_color: [0.4, 0.3, 0.2],
+            },
+            Self::TrainStation => IconParams {
+                shape: IconShape::Octagon,
+                primary_color: [0.7, 0.45, 0.35],
+                secondary_color: [0.5, 0.3, 0.2],
+            },
+            Self::Locomotive => IconParams {
+                shape: IconShape::Octagon,
+                primary_color: [0.85, 0.25, 0.2],
+                secondary_color: [0.3, 0.3, 0.35],
+            },
+            Self::CargoWagon => IconParams {
+                shape: IconShape::Octagon,
+                primary_color: [0.6, 0.5, 0.35],
+                secondary_color: [0.3, 0.3, 0.35],
+            },
+            Self::SignalWire => IconParams {
+                shape: IconShape::Octagon,
+                primary_color: [0.85, 0.2, 0.25],
+                secondary_color: [0.2, 0.6, 0.3],
+            },
+            Self::ArithmeticCombinator => IconParams {
+                shape: IconShape::Octagon,
+                primary_color: [0.3, 0.35, 0.4],
+                secondary_color: [0.9, 0.6, 0.2],
+            },
+            Self::DeciderCombinator => IconParams {
+                shape: IconShape::Octagon,
+                primary_color: [0.3, 0.35, 0.4],
+                secondary_color: [0.3, 0.7, 0.9],
+            },
             // Machines — diamonds
             Self::Composer => IconParams {
                 shape: IconShape::Diamond,
@@ -382,13 +478,42 @@ impl MachineType {
     }
 }
 
+/// Fluids carried by pipes. Unlike items they are measured in volume units
+/// and never ride belts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FluidId {
+    MetricFoam,
+    Coolant,
+}
+
+impl FluidId {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::MetricFoam => "Metric Foam",
+            Self::Coolant => "Coolant",
+        }
+    }
+
+    /// Display colour (linear RGB), shared by the pipe shader and the UI.
+    pub fn color(&self) -> [f32; 3] {
+        match self {
+            Self::MetricFoam => [0.45, 0.75, 0.95],
+            Self::Coolant => [0.95, 0.45, 0.3],
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Recipe {
     pub machine: MachineType,
     pub inputs: Vec<(ItemId, u32)>,
     pub output: ItemId,
-    #[allow(dead_code)] // validated in tests; will be read by machine simulation
+    /// Items produced per craft. Zero for fluid-only recipes.
     pub output_count: u32,
+    /// Fluid consumed per craft, drawn from the machine's fluid input tank.
+    pub fluid_input: Option<(FluidId, u32)>,
+    /// Fluid produced per craft, deposited into the machine's fluid output tank.
+    pub fluid_output: Option<(FluidId, u32)>,
 }
 
 pub fn all_recipes() -> Vec<Recipe> {
@@ -396,53 +521,68 @@ pub fn all_recipes() -> Vec<Recipe> {
     let c = MachineType::Composer;
     let i = MachineType::Inverter;
     let e = MachineType::Embedder;
+    let t = MachineType::Transformer;
     vec![
         // T1 Composition
-        Recipe { machine: c, inputs: vec![(Point, 2)], output: LineSegment, output_count: 1 },
-        Recipe { machine: c, inputs: vec![(Preimage, 3)], output: ExactSequence, output_count: 1 },
-        Recipe { machine: c, inputs: vec![(NullSet, 1)], output: Identity, output_count: 1 },
-        Recipe { machine: c, inputs: vec![(LineSegment, 4)], output: Square, output_count: 1 },
-        Recipe { machine: c, inputs: vec![(Square, 6)], output: Cube, output_count: 1 },
-        Recipe { machine: c, inputs: vec![(Wavelet, 2)], output: StandingWave, output_count: 1 },
+        Recipe { machine: c, inputs: vec![(Point, 2)], output: LineSegment, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Preimage, 3)], output: ExactSequence, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(NullSet, 1)], output: Identity, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(LineSegment, 4)], output: Square, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Square, 6)], output: Cube, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Wavelet, 2)], output: StandingWave, output_count: 1, fluid_input: None, fluid_output: None },
         // T1 Inversion
-        Recipe { machine: i, inputs: vec![(Preimage, 1)], output: Function, output_count: 1 },
-        Recipe { machine: i, inputs: vec![(Cube, 1)], output: NeckerCube, output_count: 1 },
-        Recipe { machine: i, inputs: vec![(Preimage, 1)], output: Image, output_count: 1 },
+        Recipe { machine: i, inputs: vec![(Preimage, 1)], output: Function, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: i, inputs: vec![(Cube, 1)], output: NeckerCube, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: i, inputs: vec![(Preimage, 1)], output: Image, output_count: 1, fluid_input: None, fluid_output: None },
         // Self-bootstrapping
-        Recipe { machine: c, inputs: vec![(LineSegment, 1)], output: Belt, output_count: 1 },
-        Recipe { machine: c, inputs: vec![(Cube, 1)], output: AxiomaticScience, output_count: 1 },
-        Recipe { machine: c, inputs: vec![(Function, 2)], output: Composer, output_count: 1 },
-        Recipe { machine: i, inputs: vec![(Composer, 1)], output: Inverter, output_count: 1 },
-        Recipe { machine: c, inputs: vec![(AxiomaticScience, 12)], output: KnowledgeSheaf, output_count: 1 },
+        Recipe { machine: c, inputs: vec![(LineSegment, 1)], output: Belt, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Cube, 1)], output: AxiomaticScience, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Function, 2)], output: Composer, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: i, inputs: vec![(Composer, 1)], output: Inverter, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(AxiomaticScience, 12)], output: KnowledgeSheaf, output_count: 1, fluid_input: None, fluid_output: None },
         // Infrastructure
-        Recipe { machine: c, inputs: vec![(LineSegment, 4)], output: Splitter, output_count: 1 },
-        Recipe { machine: c, inputs: vec![(Square, 4)], output: Storage, output_count: 1 },
+        Recipe { machine: c, inputs: vec![(LineSegment, 4)], output: Splitter, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Square, 4)], output: Storage, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Belt, 4)], output: Loader, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(LineSegment, 2)], output: Pipe, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Pipe, 4), (Square, 2)], output: Pump, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(LineSegment, 2), (Identity, 1)], output: Rail, output_count: 2, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Rail, 10), (Storage, 2)], output: TrainStation, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Cube, 4), (Function, 2)], output: Locomotive, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Storage, 1), (Square, 4)], output: CargoWagon, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(LineSegment, 1), (Function, 1)], output: SignalWire, output_count: 4, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Function, 4), (Identity, 2)], output: ArithmeticCombinator, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Function, 4), (Image, 2)], output: DeciderCombinator, output_count: 1, fluid_input: None, fluid_output: None },
         // Power chain
-        Recipe { machine: c, inputs: vec![(Identity, 4)], output: Quadrupole, output_count: 1 },
-        Recipe { machine: c, inputs: vec![(Quadrupole, 2)], output: Dynamo, output_count: 1 },
+        Recipe { machine: c, inputs: vec![(Identity, 4)], output: Quadrupole, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: c, inputs: vec![(Quadrupole, 2)], output: Dynamo, output_count: 1, fluid_input: None, fluid_output: None },
         // T2 Embedding
-        Recipe { machine: e, inputs: vec![(Preimage, 1), (Identity, 1)], output: RootOfUnity, output_count: 1 },
-        Recipe { machine: e, inputs: vec![(Identity, 1), (Preimage, 1)], output: Kernel, output_count: 1 },
-        Recipe { machine: e, inputs: vec![(StandingWave, 1), (Cube, 1)], output: Quantum, output_count: 1 },
-        // Source machine: one recipe per item (no inputs required)
-        Recipe { machine: MachineType::Source, inputs: vec![], output: NullSet, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: Point, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: Preimage, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: Wavelet, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: LineSegment, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: ExactSequence, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: Identity, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: Square, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: Cube, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: StandingWave, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: Function, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: NeckerCube, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: Image, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: Belt, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: AxiomaticScience, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: RootOfUnity, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: Kernel, output_count: 1 },
-        Recipe { machine: MachineType::Source, inputs: vec![], output: Quantum, output_count: 1 },
+        Recipe { machine: e, inputs: vec![(Preimage, 1), (Identity, 1)], output: RootOfUnity, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: e, inputs: vec![(Identity, 1), (Preimage, 1)], output: Kernel, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: e, inputs: vec![(StandingWave, 1), (Cube, 1)], output: Quantum, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: e, inputs: vec![(Cube, 1)], output: Quantum, output_count: 1, fluid_input: Some((FluidId::Coolant, 25)), fluid_output: None },
+        // T2 Transformation (fluid processing)
+        Recipe { machine: t, inputs: vec![(Wavelet, 1)], output: StandingWave, output_count: 1, fluid_input: Some((FluidId::MetricFoam, 50)), fluid_output: Some((FluidId::Coolant, 25)) },
+        // Source machine: one recipe per item (no inputs required), plus raw fluid
+        Recipe { machine: MachineType::Source, inputs: vec![], output: NullSet, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: Point, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: Preimage, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: Wavelet, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: LineSegment, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: ExactSequence, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: Identity, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: Square, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: Cube, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: StandingWave, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: Function, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: NeckerCube, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: Image, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: Belt, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: AxiomaticScience, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: RootOfUnity, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: Kernel, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: Quantum, output_count: 1, fluid_input: None, fluid_output: None },
+        Recipe { machine: MachineType::Source, inputs: vec![], output: NullSet, output_count: 0, fluid_input: None, fluid_output: Some((FluidId::MetricFoam, 100)) },
     ]
 }
 
@@ -487,7 +627,7 @@ mod tests {
 
     #[test]
     fn test_all_items_count() {
-        assert_eq!(ItemId::all().len(), 29);
+        assert_eq!(ItemId::all().len(), 40);
     }
 
     #[test]
@@ -499,7 +639,10 @@ mod tests {
                 assert!(*count > 0, "Recipe for {:?} has zero count input", recipe.output);
             }
             assert!(all_items.contains(&recipe.output), "Recipe output {:?} is unknown", recipe.output);
-            assert!(recipe.output_count > 0);
+            assert!(recipe.output_count > 0 || recipe.fluid_output.is_some());
+            for (_, amount) in recipe.fluid_input.iter().chain(&recipe.fluid_output) {
+                assert!(*amount > 0, "Recipe for {:?} has zero fluid amount", recipe.output);
+            }
         }
     }
 }
@@ -4,3 +4,4 @@ pub mod input;
 pub mod config;
 pub mod recipes;
 pub mod world;
+pub mod edge;
@@ -14,7 +14,10 @@ impl RecipeIndex {
         let mut by_machine: HashMap<MachineType, Vec<usize>> = HashMap::new();
 
         for (i, recipe) in all.iter().enumerate() {
-            by_output.entry(recipe.output).or_default().push(i);
+            // Fluid-only recipes have no item output to look up by
+            if recipe.output_count > 0 {
+                by_output.entry(recipe.output).or_default().push(i);
+            }
             by_machine.entry(recipe.machine).or_default().push(i);
         }
 
@@ -35,6 +38,11 @@ impl RecipeIndex {
             .map(|indices| indices.iter().map(|&i| (i, &self.all[i])).collect())
             .unwrap_or_default()
     }
+
+    /// Whether `recipe` (an index into `all`) is one `machine` can run.
+    pub fn runs_on(&self, machine: MachineType, recipe: usize) -> bool {
+        self.by_machine.get(&machine).is_some_and(|indices| indices.contains(&recipe))
+    }
 }
 
 #[cfg(test)]
@@ -66,7 +74,7 @@ mod tests {
     fn test_recipe_index_lookup_by_output() {
         let index = RecipeIndex::new();
         let recipes = index.recipes_for(ItemId::LineSegment);
-        assert!(recipes.len() >= 1);
+        assert!(!recipes.is_empty());
         assert!(recipes.iter().all(|r| r.output == ItemId::LineSegment));
     }
 
@@ -97,6 +105,16 @@ mod tests {
         assert!(!index.can_craft(recipes[0], &inv));
     }
 
+    #[test]
+    fn test_runs_on_only_own_recipes() {
+        let index = RecipeIndex::new();
+        let (composer_recipe, _) = index.recipes_for_machine(MachineType::Composer)[0];
+        assert!(index.runs_on(MachineType::Composer, composer_recipe));
+        assert!(!index.runs_on(MachineType::Source, composer_recipe));
+        assert!(!index.runs_on(MachineType::Composer, index.all.len()));
+        assert!(!index.runs_on(MachineType::Composer, usize::MAX));
+    }
+
     #[test]
     fn test_all_recipes_indexed() {
         let index = RecipeIndex::new();
@@ -107,6 +125,6 @@ mod tests {
     fn test_embedder_recipes() {
         let index = RecipeIndex::new();
         let embedder_recipes = index.recipes_using(MachineType::Embedder);
-        assert_eq!(embedder_recipes.len(), 3);
+        assert_eq!(embedder_recipes.len(), 4);
     }
 }
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 use slotmap::{new_key_type, SecondaryMap, SlotMap};
+use serde::{Deserialize, Serialize};
 use super::items::{ItemId, MachineType};
-use crate::hyperbolic::tiling::TileAddr;
+use crate::hyperbolic::cell_id::{CellId, CellInterner, CellKey};
 
 new_key_type! {
     /// Stable handle into the world's entity storage. Generational index
@@ -9,7 +10,7 @@ new_key_type! {
     pub struct EntityId;
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Direction {
     North,
     East,
@@ -126,7 +127,7 @@ impl Direction {
 /// Functional type of a placed structure. Determines which simulation
 /// system processes it. Simulation pool IDs (BeltId, MachineId, etc.)
 /// will be added in later phases.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StructureKind {
     Belt,
     Machine(MachineType),
@@ -134,6 +135,19 @@ pub enum StructureKind {
     PowerSource, // Dynamo
     Splitter,
     Storage,
+    Sink,
+    Loader,
+    /// Fluid pipe. Lives on the underlay layer, so it can share a cell with
+    /// a belt or any other structure.
+    Pipe,
+    Pump,
+    Rail,
+    /// Train station (8×4). Trains stop on rails along its edge.
+    Station,
+    /// Circuit combinators (1×2). The input terminal is the back cell, the
+    /// output terminal the front cell.
+    ArithmeticCombinator,
+    DeciderCombinator,
 }
 
 impl StructureKind {
@@ -146,9 +160,22 @@ impl StructureKind {
             Self::PowerSource => (2, 2), // Dynamo
             Self::Splitter => (1, 1),
             Self::Storage => (2, 2),
+            Self::Sink => (1, 1),
+            Self::Loader => (1, 1),
+            Self::Pipe => (1, 1),
+            Self::Pump => (2, 2),
+            Self::Rail => (1, 1),
+            Self::Station => (8, 4),
+            Self::ArithmeticCombinator | Self::DeciderCombinator => (1, 2),
         }
     }
 
+    /// Whether this structure is placed on the underlay layer (beneath belts
+    /// and buildings) rather than the main layer.
+    pub fn is_underlay(&self) -> bool {
+        matches!(self, Self::Pipe)
+    }
+
     /// Derive structure kind from the item being placed.
     /// Returns `None` for non-placeable items (raw resources, intermediates).
     pub fn from_item(item: ItemId) -> Option<Self> {
@@ -156,6 +183,14 @@ impl StructureKind {
             ItemId::Belt => Some(Self::Belt),
             ItemId::Splitter => Some(Self::Splitter),
             ItemId::Storage => Some(Self::Storage),
+            ItemId::Void => Some(Self::Sink),
+            ItemId::Loader => Some(Self::Loader),
+            ItemId::Pipe => Some(Self::Pipe),
+            ItemId::Pump => Some(Self::Pump),
+            ItemId::Rail => Some(Self::Rail),
+            ItemId::TrainStation => Some(Self::Station),
+            ItemId::ArithmeticCombinator => Some(Self::ArithmeticCombinator),
+            ItemId::DeciderCombinator => Some(Self::DeciderCombinator),
             ItemId::Quadrupole => Some(Self::PowerNode),
             ItemId::Dynamo => Some(Self::PowerSource),
             ItemId::Composer => Some(Self::Machine(MachineType::Composer)),
@@ -169,14 +204,24 @@ impl StructureKind {
     }
 }
 
-/// Canonical position of a placed entity: tile address + grid coordinates.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// Canonical position of a placed entity: cell + grid coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct GridPos {
-    pub tile: TileAddr,
+    /// Interned by the `WorldState` holding the entity; see `WorldState::cell`.
+    pub cell: CellKey,
     pub gx: i16,
     pub gy: i16,
 }
 
+/// Default corner exclusion radius, in grid squares.
+pub const DEFAULT_CORNER_EXCLUSION: i32 = 4;
+
+/// Whether a grid square lies within `radius` squares of a cell corner, where
+/// q cells meet and nothing may be built. A radius of 0 disables the zones.
+pub fn in_corner_zone(grid_xy: (i32, i32), radius: i32) -> bool {
+    32 - grid_xy.0.abs() < radius && 32 - grid_xy.1.abs() < radius
+}
+
 /// Compute all grid cells occupied by a structure with the given footprint placed at `origin`.
 /// Footprint extends from origin in +x, +y direction.
 pub fn occupied_cells(origin: (i32, i32), footprint: (i32, i32)) -> Vec<(i32, i32)> {
@@ -190,9 +235,14 @@ pub fn occupied_cells(origin: (i32, i32), footprint: (i32, i32)) -> Vec<(i32, i3
     cells
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct WorldState {
-    /// Spatial index: tile address → (grid position → entity). "What's at this square?"
-    tile_grid: HashMap<TileAddr, HashMap<(i32, i32), EntityId>>,
+    /// Every cell the world has stored anything in.
+    cells: CellInterner,
+    /// Spatial index: cell → (grid position → entity). "What's at this square?"
+    tile_grid: HashMap<CellKey, HashMap<(i32, i32), EntityId>>,
+    /// Underlay spatial index (pipes). Shares cells with `tile_grid`.
+    underlay_grid: HashMap<CellKey, HashMap<(i32, i32), EntityId>>,
     /// Primary storage: entity → structure kind.
     structures: SlotMap<EntityId, StructureKind>,
     /// Entity → canonical world position (origin cell of multi-cell structures).
@@ -201,26 +251,47 @@ pub struct WorldState {
     directions: SecondaryMap<EntityId, Direction>,
     /// Entity → source item (for inventory return on removal, display).
     items: SecondaryMap<EntityId, ItemId>,
+    /// Corner exclusion radius enforced by `place` (see `in_corner_zone`).
+    corner_exclusion: i32,
 }
 
 impl WorldState {
     pub fn new() -> Self {
         Self {
+            cells: CellInterner::new(),
             tile_grid: HashMap::new(),
+            underlay_grid: HashMap::new(),
             structures: SlotMap::with_key(),
             positions: SecondaryMap::new(),
             directions: SecondaryMap::new(),
             items: SecondaryMap::new(),
+            corner_exclusion: DEFAULT_CORNER_EXCLUSION,
         }
     }
 
-    /// Place a structure at the given tile address and grid position (origin cell).
+    pub fn corner_exclusion(&self) -> i32 {
+        self.corner_exclusion
+    }
+
+    /// Set the corner exclusion radius. Existing structures are left in place.
+    pub fn set_corner_exclusion(&mut self, radius: i32) {
+        self.corner_exclusion = radius.max(0);
+    }
+
+    /// Whether a grid square is inside a corner exclusion zone.
+    pub fn in_corner_zone(&self, grid_xy: (i32, i32)) -> bool {
+        in_corner_zone(grid_xy, self.corner_exclusion)
+    }
+
+    /// Place a structure at the given cell and grid position (origin cell).
     /// Multi-cell structures occupy all cells in their footprint extending from
     /// origin in +x, +y. Returns the entity ID on success, or `None` if any cell
-    /// in the footprint is occupied or the item isn't a placeable structure.
+    /// in the footprint is occupied, lies in a corner exclusion zone, or the item
+    /// isn't a placeable structure.
+    /// Underlay structures (pipes) only collide with other underlay structures.
     pub fn place(
         &mut self,
-        address: &[u8],
+        cell: &CellId,
         grid_xy: (i32, i32),
         item: ItemId,
         direction: Direction,
@@ -228,8 +299,12 @@ impl WorldState {
         let kind = StructureKind::from_item(item)?;
         let footprint = direction.rotate_footprint(kind.footprint().0, kind.footprint().1);
         let cells = occupied_cells(grid_xy, footprint);
-        let tile_addr = TileAddr::from_slice(address);
-        let tile_slots = self.tile_grid.entry(tile_addr.clone()).or_default();
+        if cells.iter().any(|&cell| self.in_corner_zone(cell)) {
+            return None;
+        }
+        let key = self.cells.intern(cell);
+        let grid = if kind.is_underlay() { &mut self.underlay_grid } else { &mut self.tile_grid };
+        let tile_slots = grid.entry(key).or_default();
 
         // Check all cells in footprint are free
         for &cell in &cells {
@@ -242,7 +317,7 @@ impl WorldState {
         self.positions.insert(
             entity,
             GridPos {
-                tile: tile_addr,
+                cell: key,
                 gx: grid_xy.0 as i16,
                 gy: grid_xy.1 as i16,
             },
@@ -265,9 +340,32 @@ impl WorldState {
             .unwrap_or(false)
     }
 
-    /// All entity positions within a tile. Returns the grid→entity map.
-    pub fn tile_entities(&self, address: &[u8]) -> Option<&HashMap<(i32, i32), EntityId>> {
-        self.tile_grid.get(address)
+    /// The cell a `GridPos` key refers to.
+    pub fn cell(&self, key: CellKey) -> &CellId {
+        self.cells.cell(key)
+    }
+
+    /// All entity positions within a cell. Returns the grid→entity map.
+    pub fn tile_entities(&self, cell: &CellId) -> Option<&HashMap<(i32, i32), EntityId>> {
+        self.tile_grid.get(&self.cells.key(cell)?)
+    }
+
+    /// Every cell holding at least one main-layer structure.
+    pub fn occupied_tiles(&self) -> impl Iterator<Item = &CellId> {
+        self.tile_grid
+            .iter()
+            .filter(|(_, cells)| !cells.is_empty())
+            .map(|(&key, _)| self.cells.cell(key))
+    }
+
+    /// All underlay (pipe) positions within a cell.
+    pub fn underlay_entities(&self, cell: &CellId) -> Option<&HashMap<(i32, i32), EntityId>> {
+        self.underlay_grid.get(&self.cells.key(cell)?)
+    }
+
+    /// The underlay entity at a grid square, if any.
+    pub fn underlay_at(&self, cell: &CellId, grid_xy: (i32, i32)) -> Option<EntityId> {
+        self.underlay_entities(cell)?.get(&grid_xy).copied()
     }
 
     /// Look up an entity's facing direction.
@@ -303,16 +401,52 @@ impl WorldState {
 }
 
 impl WorldState {
-    pub fn remove(&mut self, address: &[u8], grid_xy: (i32, i32)) -> Option<ItemId> {
-        let tile_slots = self.tile_grid.get_mut(address)?;
+    /// Remove the main-layer structure covering a grid square.
+    pub fn remove(&mut self, cell: &CellId, grid_xy: (i32, i32)) -> Option<ItemId> {
+        let key = self.cells.key(cell)?;
+        Self::remove_from(
+            &mut self.tile_grid,
+            &mut self.structures,
+            &mut self.positions,
+            &mut self.directions,
+            &mut self.items,
+            key,
+            grid_xy,
+        )
+    }
+
+    /// Remove the underlay structure (pipe) at a grid square.
+    pub fn remove_underlay(&mut self, cell: &CellId, grid_xy: (i32, i32)) -> Option<ItemId> {
+        let key = self.cells.key(cell)?;
+        Self::remove_from(
+            &mut self.underlay_grid,
+            &mut self.structures,
+            &mut self.positions,
+            &mut self.directions,
+            &mut self.items,
+            key,
+            grid_xy,
+        )
+    }
+
+    fn remove_from(
+        grid: &mut HashMap<CellKey, HashMap<(i32, i32), EntityId>>,
+        structures: &mut SlotMap<EntityId, StructureKind>,
+        positions: &mut SecondaryMap<EntityId, GridPos>,
+        directions: &mut SecondaryMap<EntityId, Direction>,
+        items: &mut SecondaryMap<EntityId, ItemId>,
+        key: CellKey,
+        grid_xy: (i32, i32),
+    ) -> Option<ItemId> {
+        let tile_slots = grid.get_mut(&key)?;
         let &entity = tile_slots.get(&grid_xy)?;
-        let kind = self.structures.get(entity)?;
+        let kind = structures.get(entity)?;
         let canonical = kind.footprint();
-        let facing = self.directions.get(entity).copied().unwrap_or(Direction::North);
+        let facing = directions.get(entity).copied().unwrap_or(Direction::North);
         let footprint = facing.rotate_footprint(canonical.0, canonical.1);
 
         // Find origin for this entity
-        let origin = self.positions.get(entity)?;
+        let origin = positions.get(entity)?;
         let origin_xy = (origin.gx as i32, origin.gy as i32);
         let cells = occupied_cells(origin_xy, footprint);
 
@@ -321,12 +455,12 @@ impl WorldState {
             tile_slots.remove(&cell);
         }
 
-        let item = self.items.remove(entity);
-        self.structures.remove(entity);
-        self.positions.remove(entity);
-        self.directions.remove(entity);
+        let item = items.remove(entity);
+        structures.remove(entity);
+        positions.remove(entity);
+        directions.remove(entity);
         if tile_slots.is_empty() {
-            self.tile_grid.remove(address);
+            grid.remove(&key);
         }
         item
     }
@@ -397,7 +531,7 @@ mod tests {
     #[test]
     fn test_place_and_query() {
         let mut world = WorldState::new();
-        let addr = vec![0, 1];
+        let addr = CellId::from_canonical(vec![0, 1]);
         assert!(world.place(&addr, (5, 10), ItemId::Belt, Direction::North).is_some());
 
         let entities = world.tile_entities(&addr).unwrap();
@@ -411,15 +545,33 @@ mod tests {
     #[test]
     fn test_place_occupied() {
         let mut world = WorldState::new();
-        let addr = vec![0];
+        let addr = CellId::from_canonical(vec![0]);
         assert!(world.place(&addr, (0, 0), ItemId::Belt, Direction::North).is_some());
         assert!(world.place(&addr, (0, 0), ItemId::Quadrupole, Direction::East).is_none());
     }
 
+    #[test]
+    fn test_place_corner_zone() {
+        let mut world = WorldState::new();
+        let addr = CellId::from_canonical(vec![0]);
+        let r = DEFAULT_CORNER_EXCLUSION;
+        // Squares near a corner are refused, squares along the edge are not
+        assert!(world.place(&addr, (32, 32), ItemId::Belt, Direction::North).is_none());
+        assert!(world.place(&addr, (-32, 33 - r), ItemId::Belt, Direction::North).is_none());
+        assert!(world.place(&addr, (-32, 32 - r), ItemId::Belt, Direction::North).is_some());
+        assert!(world.place(&addr, (0, -32), ItemId::Belt, Direction::North).is_some());
+        // A 2x2 footprint with one cell inside the zone is refused
+        assert!(world.place(&addr, (32 - r, 32 - r), ItemId::Composer, Direction::North).is_none());
+        assert!(world.place(&addr, (31 - r, 31 - r), ItemId::Composer, Direction::North).is_some());
+
+        world.set_corner_exclusion(0);
+        assert!(world.place(&addr, (32, 32), ItemId::Belt, Direction::North).is_some());
+    }
+
     #[test]
     fn test_multi_cell_placement() {
         let mut world = WorldState::new();
-        let addr = vec![0];
+        let addr = CellId::from_canonical(vec![0]);
         // Composer is 2x2: occupies (5,5), (6,5), (5,6), (6,6)
         let entity = world.place(&addr, (5, 5), ItemId::Composer, Direction::North).unwrap();
 
@@ -438,7 +590,7 @@ mod tests {
     #[test]
     fn test_multi_cell_overlap_blocked() {
         let mut world = WorldState::new();
-        let addr = vec![0];
+        let addr = CellId::from_canonical(vec![0]);
         // Composer at (5,5) occupies (5,5)-(6,6)
         world.place(&addr, (5, 5), ItemId::Composer, Direction::North).unwrap();
         // Another Composer at (6,6) would overlap at (6,6)
@@ -452,7 +604,7 @@ mod tests {
     #[test]
     fn test_multi_cell_click_any_cell() {
         let mut world = WorldState::new();
-        let addr = vec![0];
+        let addr = CellId::from_canonical(vec![0]);
         let entity = world.place(&addr, (10, 10), ItemId::Composer, Direction::North).unwrap();
 
         // Clicking any cell of the 2x2 machine returns the same entity
@@ -469,7 +621,7 @@ mod tests {
     #[test]
     fn test_multi_cell_remove() {
         let mut world = WorldState::new();
-        let addr = vec![0];
+        let addr = CellId::from_canonical(vec![0]);
         world.place(&addr, (5, 5), ItemId::Composer, Direction::North).unwrap();
         // Remove via any cell in the footprint
         let removed = world.remove(&addr, (6, 6));
@@ -481,7 +633,7 @@ mod tests {
     #[test]
     fn test_3x3_footprint() {
         let mut world = WorldState::new();
-        let addr = vec![0];
+        let addr = CellId::from_canonical(vec![0]);
         // Inverter is 3x3: occupies 9 cells
         let entity = world.place(&addr, (0, 0), ItemId::Inverter, Direction::North).unwrap();
         let entities = world.tile_entities(&addr).unwrap();
@@ -496,7 +648,7 @@ mod tests {
     #[test]
     fn test_3x3_rotated_east_footprint() {
         let mut world = WorldState::new();
-        let addr = vec![0];
+        let addr = CellId::from_canonical(vec![0]);
         // Inverter (3×3) facing East: square footprint stays (3, 3)
         let entity = world.place(&addr, (0, 0), ItemId::Inverter, Direction::East).unwrap();
         let entities = world.tile_entities(&addr).unwrap();
@@ -512,7 +664,7 @@ mod tests {
     #[test]
     fn test_3x3_rotated_east_remove() {
         let mut world = WorldState::new();
-        let addr = vec![0];
+        let addr = CellId::from_canonical(vec![0]);
         world.place(&addr, (0, 0), ItemId::Inverter, Direction::East).unwrap();
         let removed = world.remove(&addr, (2, 2));
         assert_eq!(removed, Some(ItemId::Inverter));
@@ -522,7 +674,7 @@ mod tests {
     #[test]
     fn test_remove() {
         let mut world = WorldState::new();
-        let addr = vec![2];
+        let addr = CellId::from_canonical(vec![2]);
         world.place(&addr, (1, 1), ItemId::Belt, Direction::South).unwrap();
         let removed = world.remove(&addr, (1, 1));
         assert_eq!(removed, Some(ItemId::Belt));
@@ -533,7 +685,7 @@ mod tests {
     #[test]
     fn test_remove_nonexistent() {
         let mut world = WorldState::new();
-        assert!(world.remove(&[0], (0, 0)).is_none());
+        assert!(world.remove(&CellId::from_canonical(vec![0]), (0, 0)).is_none());
     }
 
     #[test]
@@ -553,7 +705,7 @@ mod tests {
     #[test]
     fn test_grid_pos_stored() {
         let mut world = WorldState::new();
-        let addr = vec![3, 1, 4];
+        let addr = CellId::from_canonical(vec![3, 1, 4]);
         world.place(&addr, (32, -16), ItemId::Belt, Direction::East).unwrap();
 
         let entities = world.tile_entities(&addr).unwrap();
@@ -561,13 +713,33 @@ mod tests {
         let pos = world.position(entity).unwrap();
         assert_eq!(pos.gx, 32);
         assert_eq!(pos.gy, -16);
-        assert_eq!(pos.tile.as_slice(), &[3, 1, 4]);
+        assert_eq!(world.cell(pos.cell).word(), &[3, 1, 4]);
+    }
+
+    #[test]
+    fn test_pipe_shares_cell_with_belt() {
+        let mut world = WorldState::new();
+        let addr = CellId::from_canonical(vec![0]);
+        let belt = world.place(&addr, (2, 2), ItemId::Belt, Direction::North).unwrap();
+        let pipe = world.place(&addr, (2, 2), ItemId::Pipe, Direction::North).unwrap();
+        // A second pipe in the same cell collides on the underlay layer
+        assert!(world.place(&addr, (2, 2), ItemId::Pipe, Direction::East).is_none());
+
+        assert_eq!(world.tile_entities(&addr).unwrap()[&(2, 2)], belt);
+        assert_eq!(world.underlay_at(&addr, (2, 2)), Some(pipe));
+
+        // Removing the belt leaves the pipe in place
+        assert_eq!(world.remove(&addr, (2, 2)), Some(ItemId::Belt));
+        assert_eq!(world.underlay_at(&addr, (2, 2)), Some(pipe));
+        assert_eq!(world.remove_underlay(&addr, (2, 2)), Some(ItemId::Pipe));
+        assert!(world.underlay_at(&addr, (2, 2)).is_none());
+        assert!(world.kind(pipe).is_none());
     }
 
     #[test]
     fn test_entity_id_stable_after_other_inserts() {
         let mut world = WorldState::new();
-        let addr = vec![0];
+        let addr = CellId::from_canonical(vec![0]);
         let e1 = world.place(&addr, (0, 0), ItemId::Belt, Direction::North).unwrap();
 
         // Place more entities
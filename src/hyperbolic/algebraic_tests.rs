@@ -272,7 +272,7 @@ mod tests {
         let origin = graph.origin.clone();
         graph.expand_bfs(&origin, 4);
 
-        for (cell_id, _data) in &graph.cells {
+        for cell_id in graph.cells.keys() {
             let word = cell_id.word();
             // Try all 4 orientations: word, word·B, word·BB, word·BBB.
             let oc0 = cell_id::canonicalize(word, &r);
@@ -0,0 +1,650 @@
+//! Shortlex automatic structure for the {4,q} turtle group.
+//!
+//! Built once from the confluent rewrite rules:
+//! - [`WordAcceptor`]: DFA accepting exactly the reduced words (no rule LHS
+//!   occurs as a factor), i.e. the shortlex normal forms of group elements.
+//! - Multipliers: a two-tape automaton over *word differences* that reads a
+//!   normal form `u` left-to-right and produces the normal form of `u·x` for a
+//!   generator `x`, with bounded work per letter and no rewriting.
+//! - [`CellAcceptor`]: DFA accepting exactly the canonical `CellId` words
+//!   (normal forms that are shortlex-least among their 4 orientations).
+//!
+//! A canonical cell word contains one `a` per edge crossed, so counting
+//! accepted words by their number of `a`s gives the exact number of cells at
+//! each distance (the growth series), and walking the DFA enumerates a sphere
+//! without a visited set.
+
+use std::collections::{HashMap, HashSet};
+
+use super::cell_id::{CellId, OrientedCell};
+use super::rewrite::{self, RewriteRule, Word, A, B, B_INV};
+
+/// The three turtle letters, in byte order.
+const LETTERS: [u8; 3] = [A, B, B_INV];
+
+/// Padding symbol for the shorter tape of a two-tape automaton.
+const PAD: u8 = 3;
+
+/// Sentinel word-acceptor state for a tape that has already ended.
+const ENDED: u32 = u32::MAX;
+
+/// Inverse of a turtle letter: a is an involution, B and b swap.
+fn inverse(letter: u8) -> u8 {
+    match letter {
+        A => A,
+        B => B_INV,
+        B_INV => B,
+        _ => unreachable!(),
+    }
+}
+
+/// DFA accepting exactly the words irreducible under the rewrite rules.
+///
+/// States are the proper prefixes of rule LHSs (Aho-Corasick); a transition
+/// that completes an LHS is missing, so the walk dies on the first reducible
+/// prefix.
+pub struct WordAcceptor {
+    transitions: Vec<[Option<u32>; 3]>,
+}
+
+impl WordAcceptor {
+    pub const START: u32 = 0;
+
+    pub fn new(rules: &[RewriteRule]) -> Self {
+        let prefixes: HashSet<&[u8]> = rules
+            .iter()
+            .flat_map(|r| (0..r.lhs.len()).map(|len| &r.lhs[..len]))
+            .collect();
+
+        // Number states breadth-first from the empty prefix.
+        let mut index: HashMap<Word, u32> = HashMap::from([(vec![], Self::START)]);
+        let mut states: Vec<Word> = vec![vec![]];
+        let mut transitions = Vec::new();
+        let mut i = 0;
+        while i < states.len() {
+            let mut row = [None; 3];
+            for (slot, &letter) in LETTERS.iter().enumerate() {
+                let mut text = states[i].clone();
+                text.push(letter);
+                if rules.iter().any(|r| text.ends_with(&r.lhs)) {
+                    continue;
+                }
+                // Longest suffix that is still a prefix of some LHS.
+                let suffix = (0..=text.len())
+                    .map(|start| &text[start..])
+                    .find(|s| prefixes.contains(s))
+                    .unwrap()
+                    .to_vec();
+                let next = *index.entry(suffix.clone()).or_insert_with(|| {
+                    states.push(suffix);
+                    (states.len() - 1) as u32
+                });
+                row[slot] = Some(next);
+            }
+            transitions.push(row);
+            i += 1;
+        }
+
+        Self { transitions }
+    }
+
+    /// Follow one letter, or `None` if the word just became reducible.
+    pub fn step(&self, state: u32, letter: u8) -> Option<u32> {
+        self.transitions[state as usize][letter as usize]
+    }
+
+    /// True if `word` is a shortlex normal form.
+    pub fn accepts(&self, word: &[u8]) -> bool {
+        word.iter()
+            .try_fold(Self::START, |s, &c| self.step(s, c))
+            .is_some()
+    }
+}
+
+/// Word differences `u[..i]⁻¹·v[..i]` between a normal form and its product
+/// with a generator, as group elements in normal form.
+///
+/// The set is finite because the group is hyperbolic; it is taken to be every
+/// normal form no longer than the longest difference exhibited by the rules'
+/// own LHS/RHS pairs. Spare elements only cost states, never correctness:
+/// acceptance still requires the final difference to equal the generator.
+struct Differences {
+    words: Vec<Word>,
+    /// `next[d][s][t]` = normal form of `s⁻¹·d·t` (PAD = nothing), or `None`
+    /// if it falls outside the set.
+    next: Vec<[[Option<u32>; 4]; 4]>,
+}
+
+impl Differences {
+    const IDENTITY: u32 = 0;
+
+    fn new(rules: &[RewriteRule], acceptor: &WordAcceptor) -> Self {
+        let mut bound = 2; // B² = bb is a multiplier target.
+        for rule in rules {
+            for i in 0..=rule.lhs.len().max(rule.rhs.len()) {
+                let mut d = rewrite::inverse(&rule.lhs[..i.min(rule.lhs.len())]);
+                d.extend_from_slice(&rule.rhs[..i.min(rule.rhs.len())]);
+                bound = bound.max(rewrite::reduced(&d, rules).len());
+            }
+        }
+
+        // All normal forms of length ≤ bound, shortest first (identity = 0).
+        let mut words: Vec<Word> = vec![vec![]];
+        let mut layer: Vec<(Word, u32)> = vec![(vec![], WordAcceptor::START)];
+        for _ in 0..bound {
+            let mut next_layer = Vec::new();
+            for (word, state) in &layer {
+                for &c in &LETTERS {
+                    if let Some(s) = acceptor.step(*state, c) {
+                        let mut w = word.clone();
+                        w.push(c);
+                        words.push(w.clone());
+                        next_layer.push((w, s));
+                    }
+                }
+            }
+            layer = next_layer;
+        }
+        let index: HashMap<&[u8], u32> = words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (w.as_slice(), i as u32))
+            .collect();
+
+        let next = words
+            .iter()
+            .map(|d| {
+                let mut row = [[None; 4]; 4];
+                for s in 0..4u8 {
+                    for t in 0..4u8 {
+                        let mut w: Word = if s == PAD { vec![] } else { vec![inverse(s)] };
+                        w.extend_from_slice(d);
+                        if t != PAD {
+                            w.push(t);
+                        }
+                        rewrite::reduce(&mut w, rules);
+                        row[s as usize][t as usize] = index.get(w.as_slice()).copied();
+                    }
+                }
+                row
+            })
+            .collect();
+
+        Self { words, next }
+    }
+
+    fn index_of(&self, word: &[u8]) -> u32 {
+        self.words.iter().position(|w| w == word).unwrap() as u32
+    }
+
+    fn step(&self, d: u32, s: u8, t: u8) -> Option<u32> {
+        self.next[d as usize][s as usize][t as usize]
+    }
+}
+
+/// DFA accepting exactly the canonical `CellId` words.
+///
+/// A normal form `u` is canonical when no `u·B^k` (k = 1..3) has a shortlex
+/// smaller normal form. The DFA tracks, for every k, all multiplier runs that
+/// could still produce such a witness `v`, so it is the subset construction
+/// of three multipliers composed with the shortlex comparison of the tapes.
+pub struct CellAcceptor {
+    transitions: Vec<[Option<u32>; 3]>,
+    accepting: Vec<bool>,
+    /// Fewest `a`s from each state to an accepting state (`u32::MAX` if none).
+    min_to_accept: Vec<u32>,
+}
+
+/// One multiplier run inside a [`CellAcceptor`] state.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Track {
+    /// Which rotation B^k this run multiplies by (0..3 for k = 1..3).
+    rotation: u8,
+    difference: u32,
+    /// Word-acceptor state of the `v` tape, or `ENDED`.
+    v_state: u32,
+    /// Shortlex comparison of `v` against `u` on the letters read so far.
+    order: std::cmp::Ordering,
+}
+
+impl CellAcceptor {
+    pub const START: u32 = 0;
+
+    fn new(acceptor: &WordAcceptor, diffs: &Differences) -> Self {
+        let targets = [
+            diffs.index_of(&[B]),
+            diffs.index_of(&[B_INV, B_INV]),
+            diffs.index_of(&[B_INV]),
+        ];
+        let is_witness = |t: &Track| {
+            t.difference == targets[t.rotation as usize]
+                && (t.v_state == ENDED || t.order == std::cmp::Ordering::Less)
+        };
+
+        type Key = (u32, Vec<Track>);
+        let start: Key = (
+            WordAcceptor::START,
+            (0..3)
+                .map(|rotation| Track {
+                    rotation,
+                    difference: Differences::IDENTITY,
+                    v_state: WordAcceptor::START,
+                    order: std::cmp::Ordering::Equal,
+                })
+                .collect(),
+        );
+
+        let mut index: HashMap<Key, u32> = HashMap::new();
+        index.insert(start.clone(), Self::START);
+        let mut states = vec![start];
+        let mut transitions = Vec::new();
+        let mut accepting = Vec::new();
+        let mut i = 0;
+        while i < states.len() {
+            let (u_state, tracks) = states[i].clone();
+            accepting.push(!tracks.iter().any(is_witness));
+
+            let mut row = [None; 3];
+            for (slot, &s) in LETTERS.iter().enumerate() {
+                let Some(u_next) = acceptor.step(u_state, s) else {
+                    continue;
+                };
+                let mut next_tracks = Vec::new();
+                for track in &tracks {
+                    if track.v_state != ENDED {
+                        for &t in &LETTERS {
+                            let (Some(v_next), Some(d)) = (
+                                acceptor.step(track.v_state, t),
+                                diffs.step(track.difference, s, t),
+                            ) else {
+                                continue;
+                            };
+                            let order = track.order.then(rewrite::shortlex_cmp(&[t], &[s]));
+                            next_tracks.push(Track {
+                                difference: d,
+                                v_state: v_next,
+                                order,
+                                ..*track
+                            });
+                        }
+                    }
+                    if let Some(d) = diffs.step(track.difference, s, PAD) {
+                        next_tracks.push(Track {
+                            difference: d,
+                            v_state: ENDED,
+                            order: std::cmp::Ordering::Equal,
+                            ..*track
+                        });
+                    }
+                }
+                next_tracks.sort();
+                next_tracks.dedup();
+
+                let key = (u_next, next_tracks);
+                let id = *index.entry(key.clone()).or_insert_with(|| {
+                    states.push(key);
+                    (states.len() - 1) as u32
+                });
+                row[slot] = Some(id);
+            }
+            transitions.push(row);
+            i += 1;
+        }
+
+        // Backward 0-1 BFS: `a` costs one, turns are free.
+        let mut min_to_accept = vec![u32::MAX; transitions.len()];
+        let mut changed = true;
+        for (m, &acc) in min_to_accept.iter_mut().zip(&accepting) {
+            if acc {
+                *m = 0;
+            }
+        }
+        while changed {
+            changed = false;
+            for s in 0..transitions.len() {
+                for (slot, next) in transitions[s].iter().enumerate() {
+                    let Some(n) = next else { continue };
+                    let via = min_to_accept[*n as usize]
+                        .saturating_add((LETTERS[slot] == A) as u32);
+                    if via < min_to_accept[s] {
+                        min_to_accept[s] = via;
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        Self { transitions, accepting, min_to_accept }
+    }
+
+    pub fn step(&self, state: u32, letter: u8) -> Option<u32> {
+        self.transitions[state as usize][letter as usize]
+    }
+
+    pub fn is_accepting(&self, state: u32) -> bool {
+        self.accepting[state as usize]
+    }
+
+    /// True if `word` is the canonical word of some cell.
+    pub fn accepts(&self, word: &[u8]) -> bool {
+        word.iter()
+            .try_fold(Self::START, |s, &c| self.step(s, c))
+            .is_some_and(|s| self.is_accepting(s))
+    }
+}
+
+/// Word acceptor, multipliers and cell acceptor for one {4,q} tiling.
+#[allow(dead_code)] // tiling still finds neighbors by rewriting, which is faster per call
+pub struct Automaton {
+    acceptor: WordAcceptor,
+    diffs: Differences,
+    cells: CellAcceptor,
+}
+
+#[allow(dead_code)] // tiling still finds neighbors by rewriting, which is faster per call
+impl Automaton {
+    pub fn new(rules: &[RewriteRule]) -> Self {
+        let acceptor = WordAcceptor::new(rules);
+        let diffs = Differences::new(rules, &acceptor);
+        let cells = CellAcceptor::new(&acceptor, &diffs);
+        Self { acceptor, diffs, cells }
+    }
+
+    pub fn word_acceptor(&self) -> &WordAcceptor {
+        &self.acceptor
+    }
+
+    pub fn cell_acceptor(&self) -> &CellAcceptor {
+        &self.cells
+    }
+
+    /// Normal form of `word·letter` for a normal form `word`.
+    ///
+    /// Runs the multiplier forward over `word`, keeping every live
+    /// (difference, v-state) pair per position, then reads `v` back from the
+    /// one run that ends on `letter`. Work per input letter is bounded by the
+    /// automaton size, independent of the word.
+    pub fn multiply(&self, word: &[u8], letter: u8) -> Word {
+        debug_assert!(self.acceptor.accepts(word), "multiply needs a normal form");
+        let target = self.diffs.index_of(&[letter]);
+
+        // Per position: (difference, v_state, parent index, v letter or PAD).
+        let mut layers: Vec<Vec<(u32, u32, u32, u8)>> =
+            vec![vec![(Differences::IDENTITY, WordAcceptor::START, 0, PAD)]];
+        for &s in word {
+            let prev = layers.last().unwrap();
+            let mut seen: HashSet<(u32, u32)> = HashSet::new();
+            let mut layer = Vec::new();
+            for (parent, &(d, v_state, _, _)) in prev.iter().enumerate() {
+                let mut push = |d: Option<u32>, v: Option<u32>, t: u8| {
+                    if let (Some(d), Some(v)) = (d, v) {
+                        if seen.insert((d, v)) {
+                            layer.push((d, v, parent as u32, t));
+                        }
+                    }
+                };
+                if v_state != ENDED {
+                    for &t in &LETTERS {
+                        push(self.diffs.step(d, s, t), self.acceptor.step(v_state, t), t);
+                    }
+                }
+                push(self.diffs.step(d, s, PAD), Some(ENDED), PAD);
+            }
+            layers.push(layer);
+        }
+
+        // `v` is at most one letter longer than `u`.
+        let last = layers.last().unwrap();
+        let mut tail = None;
+        let mut end = last.iter().position(|&(d, _, _, _)| d == target);
+        if end.is_none() {
+            end = last.iter().enumerate().find_map(|(i, &(d, v_state, _, _))| {
+                if v_state == ENDED {
+                    return None;
+                }
+                LETTERS.iter().find_map(|&t| {
+                    let ok = self.acceptor.step(v_state, t).is_some()
+                        && self.diffs.step(d, PAD, t) == Some(target);
+                    ok.then(|| {
+                        tail = Some(t);
+                        i
+                    })
+                })
+            });
+        }
+        let mut at = end.expect("multiplier has no accepting run");
+
+        let mut v: Word = tail.into_iter().collect();
+        for layer in layers[1..].iter().rev() {
+            let (_, _, parent, t) = layer[at];
+            if t != PAD {
+                v.push(t);
+            }
+            at = parent as usize;
+        }
+        v.reverse();
+        v
+    }
+
+    /// Neighbor of `cell` across canonical edge `edge`, via multipliers.
+    /// Agrees with [`super::cell_id::neighbor`].
+    pub fn neighbor(&self, cell: &CellId, edge: u8) -> OrientedCell {
+        let mut word = cell.word().to_vec();
+        for _ in 0..edge {
+            word = self.multiply(&word, B);
+        }
+        word = self.multiply(&word, A);
+
+        let mut best = word.clone();
+        let mut orientation = 0;
+        for rot in 1..4u8 {
+            word = self.multiply(&word, B);
+            if rewrite::shortlex_cmp(&word, &best) == std::cmp::Ordering::Less {
+                best = word.clone();
+                orientation = rot;
+            }
+        }
+        OrientedCell { id: CellId::from_canonical(best), orientation }
+    }
+
+    /// True if `word` is the canonical word of some cell.
+    pub fn is_canonical_cell(&self, word: &[u8]) -> bool {
+        self.cells.accepts(word)
+    }
+
+    /// Number of cells at each distance 0..=radius from the origin.
+    pub fn growth_series(&self, radius: usize) -> Vec<u64> {
+        let mut series = Vec::with_capacity(radius + 1);
+        let mut layer: HashMap<u32, u64> = HashMap::from([(CellAcceptor::START, 1)]);
+        for _ in 0..=radius {
+            // Close the layer under turns; reduced words have no turn cycles.
+            let mut total: HashMap<u32, u64> = layer.clone();
+            let mut frontier = layer;
+            while !frontier.is_empty() {
+                let mut next: HashMap<u32, u64> = HashMap::new();
+                for (&s, &n) in &frontier {
+                    for t in [B, B_INV] {
+                        if let Some(ns) = self.cells.step(s, t) {
+                            *next.entry(ns).or_default() += n;
+                        }
+                    }
+                }
+                for (&s, &n) in &next {
+                    *total.entry(s).or_default() += n;
+                }
+                frontier = next;
+            }
+
+            series.push(
+                total
+                    .iter()
+                    .filter(|(&s, _)| self.cells.is_accepting(s))
+                    .map(|(_, &n)| n)
+                    .sum(),
+            );
+
+            layer = HashMap::new();
+            for (&s, &n) in &total {
+                if let Some(ns) = self.cells.step(s, A) {
+                    *layer.entry(ns).or_default() += n;
+                }
+            }
+        }
+        series
+    }
+
+    /// All cells at exactly `radius` from the origin, in shortlex order.
+    pub fn sphere(&self, radius: usize) -> Vec<CellId> {
+        let radius = radius as u32;
+        let mut out = Vec::new();
+        let mut word = Vec::new();
+        self.walk_sphere(CellAcceptor::START, 0, radius, &mut word, &mut out);
+        out.sort_by(|x, y| rewrite::shortlex_cmp(x.word(), y.word()));
+        out
+    }
+
+    fn walk_sphere(&self, state: u32, used: u32, radius: u32, word: &mut Word, out: &mut Vec<CellId>) {
+        if used == radius && self.cells.is_accepting(state) {
+            out.push(CellId::from_canonical(word.clone()));
+        }
+        for &c in &LETTERS {
+            let Some(next) = self.cells.step(state, c) else { continue };
+            let used = used + (c == A) as u32;
+            if used.saturating_add(self.cells.min_to_accept[next as usize]) > radius {
+                continue;
+            }
+            word.push(c);
+            self.walk_sphere(next, used, radius, word, out);
+            word.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperbolic::cell_graph::CellGraph;
+    use crate::hyperbolic::cell_id;
+    use crate::hyperbolic::poincare::TilingConfig;
+    use std::collections::HashSet;
+
+    /// All normal forms up to `max_len` letters.
+    fn normal_forms(acceptor: &WordAcceptor, max_len: usize) -> Vec<Word> {
+        let mut out = vec![vec![]];
+        let mut layer = vec![(vec![], WordAcceptor::START)];
+        for _ in 0..max_len {
+            let mut next = Vec::new();
+            for (w, s) in &layer {
+                for &c in &LETTERS {
+                    if let Some(ns) = acceptor.step(*s, c) {
+                        let mut w = w.clone();
+                        w.push(c);
+                        out.push(w.clone());
+                        next.push((w, ns));
+                    }
+                }
+            }
+            layer = next;
+        }
+        out
+    }
+
+    #[test]
+    fn word_acceptor_accepts_exactly_reduced_words() {
+        let rules = rewrite::rules_45();
+        let acceptor = WordAcceptor::new(&rules);
+        let mut words: Vec<Word> = vec![vec![]];
+        for _ in 0..8 {
+            words = words
+                .iter()
+                .flat_map(|w| LETTERS.map(|c| [w.as_slice(), &[c]].concat()))
+                .collect();
+            for w in &words {
+                assert_eq!(
+                    acceptor.accepts(w),
+                    rewrite::reduced(w, &rules) == *w,
+                    "{}",
+                    rewrite::word_to_string(w),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn multiply_matches_reduction() {
+        for q in [5, 6, 7] {
+            let rules = rewrite::rules_for(q);
+            let auto = Automaton::new(&rules);
+            for u in normal_forms(&auto.acceptor, 12) {
+                for &x in &LETTERS {
+                    let mut ux = u.clone();
+                    ux.push(x);
+                    assert_eq!(
+                        auto.multiply(&u, x),
+                        rewrite::reduced(&ux, &rules),
+                        "q={q}: {}·{}",
+                        rewrite::word_to_string(&u),
+                        rewrite::word_to_string(&[x]),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cell_acceptor_matches_canonicalize() {
+        for q in [5, 6] {
+            let rules = rewrite::rules_for(q);
+            let auto = Automaton::new(&rules);
+            for w in normal_forms(&auto.acceptor, 12) {
+                let canonical = cell_id::canonicalize(&w, &rules).id.word() == w.as_slice();
+                assert_eq!(auto.is_canonical_cell(&w), canonical, "q={q}: {}", rewrite::word_to_string(&w));
+            }
+        }
+    }
+
+    #[test]
+    fn neighbor_matches_cell_id() {
+        let rules = rewrite::rules_45();
+        let auto = Automaton::new(&rules);
+        let mut g = CellGraph::new(&TilingConfig::new(4, 5));
+        let origin = g.origin.clone();
+        g.expand_bfs(&origin, 4);
+        for cell in g.cells_within(&origin, 4) {
+            for edge in 0..4 {
+                let expected = cell_id::neighbor(cell, edge, &rules);
+                let got = auto.neighbor(cell, edge);
+                assert_eq!(got.id, expected.id, "{cell} edge {edge}");
+                assert_eq!(got.orientation, expected.orientation);
+            }
+        }
+    }
+
+    #[test]
+    fn growth_and_spheres_match_cells_within() {
+        for q in [5, 6, 7] {
+            let cfg = TilingConfig::new(4, q);
+            let auto = Automaton::new(&rewrite::rules_for(q));
+            let mut g = CellGraph::new(&cfg);
+            let origin = g.origin.clone();
+            g.expand_bfs(&origin, 6);
+
+            let series = auto.growth_series(5);
+            let mut inner: HashSet<CellId> = HashSet::new();
+            for (r, &count) in series.iter().enumerate() {
+                let ball: HashSet<CellId> = g.cells_within(&origin, r).into_iter().cloned().collect();
+                let shell: HashSet<CellId> = ball.difference(&inner).cloned().collect();
+                let sphere: HashSet<CellId> = auto.sphere(r).into_iter().collect();
+                assert_eq!(count as usize, shell.len(), "q={q} r={r}");
+                assert_eq!(sphere, shell, "q={q} r={r}");
+                inner = ball;
+            }
+        }
+    }
+
+    #[test]
+    fn growth_series_45() {
+        let auto = Automaton::new(&rewrite::rules_45());
+        assert_eq!(auto.growth_series(7), vec![1, 4, 12, 28, 64, 148, 340, 780]);
+    }
+}
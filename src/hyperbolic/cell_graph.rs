@@ -1,4 +1,4 @@
-//! Algebraic neighbor graph for {4,5} hyperbolic tiling.
+//! Algebraic neighbor graph for {4,q} hyperbolic tiling.
 //!
 //! `CellGraph` maintains a set of discovered cells with precomputed neighbor
 //! relationships and Mobius transforms, using the confluent rewrite system
@@ -32,7 +32,7 @@ pub struct CellData {
 pub struct CellGraph {
     /// All loaded cells, keyed by canonical CellId.
     pub cells: HashMap<CellId, CellData>,
-    /// Cached rewrite rules for {4,5}.
+    /// Cached rewrite rules for {4,q}.
     rules: Vec<RewriteRule>,
     /// Neighbor transforms for even/odd parity tiles.
     neighbor_xforms: [Vec<Mobius>; 2],
@@ -42,12 +42,12 @@ pub struct CellGraph {
 
 impl CellGraph {
     /// Create a new CellGraph for the given tiling configuration.
-    /// Currently only supports {4,5}.
+    /// Supports any hyperbolic {4,q} (q ≥ 5).
     pub fn new(cfg: &TilingConfig) -> Self {
         assert_eq!(cfg.p, 4, "CellGraph only supports {{4,q}} tilings");
-        assert_eq!(cfg.q, 5, "CellGraph only supports {{4,5}}");
+        assert!(cfg.q >= 5, "CellGraph needs a hyperbolic {{4,q}} (q ≥ 5)");
 
-        let rules = rewrite::rules_45();
+        let rules = rewrite::rules_for(cfg.q);
         let neighbor_xforms = neighbor_transforms(cfg);
         let origin = CellId::origin();
 
@@ -185,8 +185,7 @@ pub fn word_to_mobius(word: &[u8], neighbor_xforms: &[Vec<Mobius>; 2]) -> Mobius
 }
 
 /// Compute Mobius, final facing, and parity from a word.
-/// Used internally for incremental BFS validation.
-fn word_to_mobius_state(word: &[u8], neighbor_xforms: &[Vec<Mobius>; 2]) -> (Mobius, u8, bool) {
+pub fn word_to_mobius_state(word: &[u8], neighbor_xforms: &[Vec<Mobius>; 2]) -> (Mobius, u8, bool) {
     let mut facing: u8 = 0;
     let mut transform = Mobius::identity();
     let mut parity = false;
@@ -385,6 +384,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_higher_q_graphs() {
+        for q in [6, 7] {
+            let mut g = CellGraph::new(&TilingConfig::new(4, q));
+            let vertex: Vec<u8> = (0..q).flat_map(|_| [A, B]).collect();
+            let m = word_to_mobius(&vertex, &g.neighbor_xforms);
+            assert!(m.b.abs() < 1e-6, "(aB)^{q} should be a rotation, |b| = {}", m.b.abs());
+
+            let origin = g.origin.clone();
+            g.expand_bfs(&origin, 3);
+            for (cell_id, data) in &g.cells {
+                for neighbor_id in &data.neighbors {
+                    if let Some(neighbor_data) = g.cells.get(neighbor_id) {
+                        assert!(neighbor_data.neighbors.contains(cell_id), "{{4,{q}}}: asymmetric");
+                    }
+                }
+                let word_center = word_to_mobius(cell_id.word(), &g.neighbor_xforms).apply(Complex::ZERO);
+                let stored_center = data.mobius.apply(Complex::ZERO);
+                assert!((word_center - stored_center).abs() < EPS, "{{4,{q}}}: Mobius mismatch for {cell_id}");
+            }
+        }
+    }
+
     // --- Mobius validation ---
 
     #[test]
@@ -1,18 +1,23 @@
-//! Algebraic cell identity for {4,5} hyperbolic tiling.
+//! Algebraic cell identity for {4,q} hyperbolic tiling.
 //!
 //! A `CellId` is the canonical (shortlex-minimum) reduced word representing a cell,
 //! chosen from the 4 orientations of that cell. An `OrientedCell` pairs a CellId
-//! with an orientation (0–3) tracking which edge the turtle faces.
+//! with an orientation (0–3) tracking which edge the turtle faces. A `CellKey`
+//! is a small interned handle for a CellId, for storage keyed by cell.
 
 
+use std::collections::HashMap;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 
-use super::rewrite::{self, RewriteRule, Word, A, B};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::poincare::{neighbor_transforms, Complex, TilingConfig};
+use super::rewrite::{self, RewriteRule, Word, A, B, B_INV};
 
 /// Canonical cell identity: the shortlex-minimum reduced word among
 /// the 4 orientations of a cell.
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
 pub struct CellId {
     /// The canonical word (shortlex minimum of the 4 orientations).
     word: Word,
@@ -25,7 +30,6 @@ impl CellId {
     }
 
     /// Create a CellId from an already-canonical word. No validation.
-    #[allow(dead_code)]
     pub fn from_canonical(word: Word) -> Self {
         Self { word }
     }
@@ -63,16 +67,79 @@ impl fmt::Display for CellId {
     }
 }
 
+/// Interned handle for a `CellId`: cheap to copy, hash and compare. Only
+/// meaningful to the `CellInterner` that issued it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CellKey(u32);
+
+/// Hands out `CellKey`s in first-seen order. Keys are never reused.
+#[derive(Default)]
+pub struct CellInterner {
+    cells: Vec<CellId>,
+    keys: HashMap<CellId, CellKey>,
+}
+
+impl CellInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The key for `cell`, issuing a new one if it hasn't been seen.
+    pub fn intern(&mut self, cell: &CellId) -> CellKey {
+        if let Some(&key) = self.keys.get(cell) {
+            return key;
+        }
+        let key = CellKey(self.cells.len() as u32);
+        self.cells.push(cell.clone());
+        self.keys.insert(cell.clone(), key);
+        key
+    }
+
+    /// The key for `cell`, if it has been interned.
+    pub fn key(&self, cell: &CellId) -> Option<CellKey> {
+        self.keys.get(cell).copied()
+    }
+
+    /// The cell a key was issued for.
+    pub fn cell(&self, key: CellKey) -> &CellId {
+        &self.cells[key.0 as usize]
+    }
+}
+
+/// Only the cells are stored; a key is its cell's position among them.
+impl Serialize for CellInterner {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.cells.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CellInterner {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut interner = Self::new();
+        for cell in Vec::<CellId>::deserialize(deserializer)? {
+            interner.intern(&cell);
+        }
+        Ok(interner)
+    }
+}
+
 /// A cell with a specific orientation (which edge the turtle faces).
 #[derive(Clone, Debug)]
 pub struct OrientedCell {
     pub id: CellId,
     /// 0–3: how many B (left turns) from the canonical orientation to reach
     /// the orientation of the word that produced this cell.
-    #[allow(dead_code)]
     pub orientation: u8,
 }
 
+impl OrientedCell {
+    /// Canonical edge the producing word's turtle faces: canonical = word·B^orientation,
+    /// so the turtle is the canonical one turned right `orientation` times.
+    pub fn facing(&self) -> u8 {
+        (4 - self.orientation) % 4
+    }
+}
+
 /// Reduce a word and canonicalize: try all 4 orientations, pick shortlex minimum.
 ///
 /// Returns the CellId and the orientation offset (how many B's were appended
@@ -81,16 +148,22 @@ pub fn canonicalize(word: &[u8], rules: &[RewriteRule]) -> OrientedCell {
     // Reduce the base word.
     let mut base = word.to_vec();
     rewrite::reduce(&mut base, rules);
+    canonicalize_reduced(base, rules)
+}
 
+/// Canonicalize an already-reduced word.
+pub fn canonicalize_reduced(base: Word, rules: &[RewriteRule]) -> OrientedCell {
     let mut best = base.clone();
     let mut best_rot: u8 = 0;
 
-    // Try appending 1, 2, 3 B's (left turns) and reducing.
+    // Try appending 1, 2, 3 B's (left turns) and reducing. Only the new
+    // suffix can start a rewrite.
+    let base_len = base.len();
     let mut rotated = base;
     for rot in 1..4u8 {
         rotated.push(B);
         let mut candidate = rotated.clone();
-        rewrite::reduce(&mut candidate, rules);
+        rewrite::reduce_from(&mut candidate, base_len, rules);
         if rewrite::shortlex_cmp(&candidate, &best) == std::cmp::Ordering::Less {
             best = candidate;
             best_rot = rot;
@@ -121,7 +194,8 @@ pub fn neighbor(cell: &CellId, edge: u8, rules: &[RewriteRule]) -> OrientedCell
     word.extend(std::iter::repeat_n(B, edge as usize));
     // Cross the edge.
     word.push(A);
-    canonicalize(&word, rules)
+    rewrite::reduce_from(&mut word, cell.word.len(), rules);
+    canonicalize_reduced(word, rules)
 }
 
 /// Compute all 4 neighbors of a cell (one per edge in canonical orientation).
@@ -135,6 +209,75 @@ pub fn all_neighbors(cell: &CellId, rules: &[RewriteRule]) -> [OrientedCell; 4]
     ]
 }
 
+/// `to` as seen from `from`: the canonical cell of from⁻¹·to, with the
+/// orientation relative to `from`'s canonical frame.
+pub fn relative(from: &CellId, to: &CellId, rules: &[RewriteRule]) -> OrientedCell {
+    canonicalize_reduced(rewrite::relative_word(&from.word, &to.word, rules), rules)
+}
+
+/// Cell hops from the origin. A canonical word crosses one edge per `a`
+/// and no cell path is shorter.
+pub fn distance_from_origin(cell: &CellId) -> usize {
+    cell.word.iter().filter(|&&c| c == A).count()
+}
+
+/// Fewest edge crossings between two cells.
+pub fn distance(from: &CellId, to: &CellId, rules: &[RewriteRule]) -> usize {
+    distance_from_origin(&relative(from, to, rules).id)
+}
+
+/// A shortest cell path from `from` to `to`, as the edge to cross out of
+/// each cell along the way (in that cell's canonical frame, as taken by
+/// [`neighbor`]). Empty when `from == to`.
+pub fn shortest_path(from: &CellId, to: &CellId, rules: &[RewriteRule]) -> Vec<u8> {
+    let mut path = Vec::new();
+    let mut cell = from.clone();
+    let mut edge: u8 = 0;
+    for &letter in relative(from, to, rules).id.word() {
+        match letter {
+            A => {
+                path.push(edge);
+                let next = neighbor(&cell, edge, rules);
+                edge = next.facing();
+                cell = next.id;
+            }
+            B => edge = (edge + 1) % 4,
+            B_INV => edge = (edge + 3) % 4,
+            _ => unreachable!(),
+        }
+    }
+    path
+}
+
+/// Hyperbolic distance between the centres of two cells.
+///
+/// Taken from the relative word only, as 2·acosh|a| of its SU(1,1) transform,
+/// which stays accurate far past where centres crowd the disk boundary.
+pub fn center_distance(from: &CellId, to: &CellId, cfg: &TilingConfig, rules: &[RewriteRule]) -> f64 {
+    let word = rewrite::relative_word(&from.word, &to.word, rules);
+    let xforms = neighbor_transforms(cfg);
+
+    // Multiply raw matrices: `Mobius::compose` renormalizes by |a|² − |b|²,
+    // which cancels catastrophically once |a| is large, while the plain
+    // product keeps |a| to full relative precision.
+    let (mut a, mut b) = (Complex::ONE, Complex::ZERO);
+    let (mut facing, mut parity) = (0usize, 0usize);
+    for &letter in &word {
+        match letter {
+            A => {
+                let x = xforms[parity][facing];
+                (a, b) = (a * x.a + b * x.b.conj(), a * x.b + b * x.a.conj());
+                parity ^= 1;
+                facing = (facing + 2) % 4;
+            }
+            B => facing = (facing + 1) % 4,
+            B_INV => facing = (facing + 3) % 4,
+            _ => unreachable!(),
+        }
+    }
+    2.0 * a.abs().max(1.0).acosh()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -397,4 +540,126 @@ mod tests {
         // They should indeed be different:
         assert_ne!(path1.id, path2.id, "east+north and north+east should be different cells in {{4,5}}");
     }
+
+    // --- Distance and paths ---
+
+    /// All cells within `radius` hops of `center`, with their BFS distance.
+    fn bfs(center: &CellId, radius: usize, r: &[RewriteRule]) -> Vec<(CellId, usize)> {
+        let mut seen: HashSet<CellId> = HashSet::from([center.clone()]);
+        let mut out = vec![(center.clone(), 0)];
+        let mut ring = vec![center.clone()];
+        for depth in 1..=radius {
+            let mut next = Vec::new();
+            for cell in &ring {
+                for n in all_neighbors(cell, r) {
+                    if seen.insert(n.id.clone()) {
+                        out.push((n.id.clone(), depth));
+                        next.push(n.id);
+                    }
+                }
+            }
+            ring = next;
+        }
+        out
+    }
+
+    /// Canonical cell `n` steps along a straight line (in and out through
+    /// opposite edges) starting from `start` heading out of edge 0.
+    fn straight(start: &CellId, n: usize, r: &[RewriteRule]) -> CellId {
+        let mut word = start.word().to_vec();
+        for i in 0..n {
+            if i > 0 {
+                word.extend([B, B]);
+            }
+            word.push(A);
+        }
+        canonicalize(&word, r).id
+    }
+
+    #[test]
+    fn test_distance_matches_bfs() {
+        for q in [5, 6] {
+            let r = rewrite::rules_for(q);
+            for (center, _) in bfs(&CellId::origin(), 2, &r) {
+                for (cell, hops) in bfs(&center, 3, &r) {
+                    assert_eq!(distance(&center, &cell, &r), hops, "q={q}: {center} → {cell}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_distance_symmetric_and_triangle() {
+        let r = rules();
+        let cfg = TilingConfig::new(4, 5);
+        let cells: Vec<CellId> = bfs(&CellId::origin(), 2, &r).into_iter().map(|(c, _)| c).collect();
+        let n = cells.len();
+        let mut hops = vec![vec![0; n]; n];
+        let mut dist = vec![vec![0.0; n]; n];
+        for i in 0..n {
+            for j in 0..n {
+                hops[i][j] = distance(&cells[i], &cells[j], &r);
+                dist[i][j] = center_distance(&cells[i], &cells[j], &cfg, &r);
+            }
+        }
+        for i in 0..n {
+            assert_eq!(hops[i][i], 0);
+            assert!(dist[i][i].abs() < 1e-6);
+            for j in 0..n {
+                assert_eq!(hops[i][j], hops[j][i], "{} ↔ {}", cells[i], cells[j]);
+                assert!((dist[i][j] - dist[j][i]).abs() < 1e-9);
+                for k in 0..n {
+                    assert!(hops[i][k] <= hops[i][j] + hops[j][k]);
+                    assert!(dist[i][k] <= dist[i][j] + dist[j][k] + 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shortest_path_reaches_target() {
+        let r = rules();
+        for (from, _) in bfs(&CellId::origin(), 1, &r) {
+            for (to, hops) in bfs(&from, 4, &r) {
+                let path = shortest_path(&from, &to, &r);
+                assert_eq!(path.len(), hops, "{from} → {to}");
+                let end = path.iter().fold(from.clone(), |cell, &e| neighbor(&cell, e, &r).id);
+                assert_eq!(end, to, "path {path:?} from {from} ends at {end}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_center_distance_neighbors_and_lines() {
+        let r = rules();
+        let cfg = TilingConfig::new(4, 5);
+        let step = super::super::poincare::center_to_center_distance(&cfg);
+        for n in all_neighbors(&CellId::origin(), &r) {
+            assert!((center_distance(&CellId::origin(), &n.id, &cfg, &r) - step).abs() < 1e-9);
+        }
+        // Centres on a straight line are collinear, so distances add up
+        // exactly, even 150 cells out where absolute coordinates are useless.
+        let deep = straight(&CellId::origin(), 150, &r);
+        assert_eq!(distance_from_origin(&deep), 150);
+        let far = straight(&deep, 40, &r);
+        assert_eq!(distance(&deep, &far, &r), 40);
+        assert_eq!(shortest_path(&deep, &far, &r).len(), 40);
+        let d = center_distance(&deep, &far, &cfg, &r);
+        assert!((d - 40.0 * step).abs() < 1e-6 * d, "got {d}, expected {}", 40.0 * step);
+    }
+
+    #[test]
+    fn test_interner_keys_are_stable() {
+        let r = rules();
+        let mut interner = CellInterner::new();
+        let origin = interner.intern(&CellId::origin());
+        let a = interner.intern(&canonicalize(&[A], &r).id);
+        assert_ne!(origin, a);
+        // Turning in place reaches the same cell, so it gets the same key
+        assert_eq!(interner.intern(&canonicalize(&[A, B, B_INV, B], &r).id), a);
+        assert_eq!(interner.intern(&CellId::origin()), origin);
+        assert_eq!(interner.key(&canonicalize(&[A], &r).id), Some(a));
+        assert_eq!(interner.key(&canonicalize(&[B, A], &r).id), None);
+        assert_eq!(interner.cell(a), &canonicalize(&[A], &r).id);
+    }
 }
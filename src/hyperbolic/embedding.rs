@@ -1,5 +1,103 @@
+use serde::{Deserialize, Serialize};
+
 use super::poincare::Complex;
 
+/// How the camera lays the Poincaré disk out in world space.
+///
+/// Every flat projection is scaled so the view centre keeps the disk's scale
+/// and orientation, except Klein, which keeps the unit disk as its boundary.
+/// Matches the shader's disk_to_world function; `shader_index` is the value
+/// it switches on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Projection {
+    /// Poincaré disk lifted into a gentle bowl.
+    #[default]
+    Bowl,
+    /// Klein disk: geodesics are straight lines.
+    Klein,
+    /// Upper half-plane, with the view centre at i and the ideal line at the top.
+    HalfPlane,
+    /// Band model: the strip |Im w| < π/4, with the disk's real axis along it.
+    Band,
+    /// Gans model: the hyperboloid seen orthographically from above.
+    Gans,
+}
+
+impl Projection {
+    pub const ALL: [Projection; 5] =
+        [Projection::Bowl, Projection::Klein, Projection::HalfPlane, Projection::Band, Projection::Gans];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Projection::Bowl => "Poincaré bowl",
+            Projection::Klein => "Klein",
+            Projection::HalfPlane => "Upper half-plane",
+            Projection::Band => "Band",
+            Projection::Gans => "Gans (hyperboloid)",
+        }
+    }
+
+    /// Value of `globals.projection` in the shaders.
+    pub fn shader_index(self) -> u32 {
+        self as u32
+    }
+
+    /// Map a point on the Poincaré disk to world coordinates [X, Y, Z], Y up.
+    /// Only the bowl leaves the Y = 0 plane.
+    pub fn project(self, z: Complex) -> [f32; 3] {
+        let w = match self {
+            Projection::Bowl => return disk_to_bowl(z),
+            Projection::Klein => z * (2.0 / (1.0 + z.norm_sq())),
+            Projection::HalfPlane => {
+                // Cayley map w = (i + z) / (1 + iz), then i moved to the origin
+                let i = Complex::new(0.0, 1.0);
+                ((i + z) / (Complex::new(1.0, 0.0) + i * z) - i) * 0.5
+            }
+            Projection::Band => {
+                // atanh z = ½ log((1 + z) / (1 − z))
+                let q = (Complex::new(1.0, 0.0) + z) / (Complex::new(1.0, 0.0) - z);
+                Complex::new(0.25 * q.norm_sq().ln(), 0.5 * q.im.atan2(q.re))
+            }
+            Projection::Gans => z * (1.0 / (1.0 - z.norm_sq())),
+        };
+        [w.re as f32, 0.0, w.im as f32]
+    }
+
+    /// Map a point on the projected surface, given by its world X and Z, back
+    /// to the Poincaré disk. None if it lies outside the model's image.
+    pub fn unproject(self, x: f64, z: f64) -> Option<Complex> {
+        let w = Complex::new(x, z);
+        let disk = match self {
+            Projection::Bowl => w,
+            Projection::Klein => {
+                let r2 = w.norm_sq();
+                if r2 >= 1.0 {
+                    return None;
+                }
+                w * (1.0 / (1.0 + (1.0 - r2).sqrt()))
+            }
+            Projection::HalfPlane => {
+                let i = Complex::new(0.0, 1.0);
+                let h = w * 2.0 + i;
+                if h.im <= 0.0 {
+                    return None;
+                }
+                (h - i) / (Complex::new(1.0, 0.0) - i * h)
+            }
+            Projection::Band => {
+                if w.im.abs() >= std::f64::consts::FRAC_PI_4 {
+                    return None;
+                }
+                // tanh w = sinh 2w / (cosh 2x + cos 2y) in components
+                let den = (2.0 * w.re).cosh() + (2.0 * w.im).cos();
+                Complex::new((2.0 * w.re).sinh() / den, (2.0 * w.im).sin() / den)
+            }
+            Projection::Gans => w * (2.0 / (1.0 + (1.0 + 4.0 * w.norm_sq()).sqrt())),
+        };
+        (disk.abs() < 1.0).then_some(disk)
+    }
+}
+
 /// Convert a point on the Poincare disk to gentle bowl coordinates (Y-up).
 /// Uses disk coordinates directly for X,Z with mild upward curvature.
 /// Matches the shader's disk_to_bowl function.
@@ -57,4 +155,78 @@ mod tests {
         assert!((x as f64 - z.re).abs() < 1e-6);
         assert!((zz as f64 - z.im).abs() < 1e-6);
     }
+
+    // --- Projections ---
+
+    fn samples() -> Vec<Complex> {
+        let mut points = vec![Complex::ZERO];
+        for r in [0.1, 0.4, 0.7, 0.9, 0.97] {
+            for k in 0..12 {
+                points.push(Complex::from_polar(r, k as f64 * std::f64::consts::PI / 6.0 + 0.1));
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn test_projections_round_trip() {
+        for projection in Projection::ALL {
+            for p in samples() {
+                let [x, _, z] = projection.project(p);
+                let back = projection.unproject(x as f64, z as f64).unwrap_or_else(|| {
+                    panic!("{projection:?}: {p:?} projected to ({x}, {z}) has no preimage")
+                });
+                assert!((back - p).abs() < 1e-4, "{projection:?}: {p:?} came back as {back:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_flat_projections_fix_view_centre() {
+        // Near the centre every flat model but Klein matches the disk itself,
+        // so the view looks the same there whichever is selected.
+        for projection in [Projection::HalfPlane, Projection::Band, Projection::Gans] {
+            let [x, y, z] = projection.project(Complex::ZERO);
+            assert!(x.abs() < 1e-6 && y == 0.0 && z.abs() < 1e-6, "{projection:?}");
+            for dir in [Complex::new(1e-4, 0.0), Complex::new(0.0, 1e-4), Complex::new(-7e-5, 7e-5)] {
+                let [x, _, z] = projection.project(dir);
+                let (dx, dz) = (x as f64 - dir.re, z as f64 - dir.im);
+                assert!(dx.abs() < 1e-7 && dz.abs() < 1e-7, "{projection:?} moves {dir:?} by ({dx}, {dz})");
+            }
+        }
+    }
+
+    #[test]
+    fn test_klein_geodesics_straight() {
+        // A translated diameter is a circular arc on the disk, but a straight
+        // chord in the Klein model.
+        let shift = crate::hyperbolic::poincare::Mobius {
+            a: Complex::new(0.6_f64.cosh(), 0.0),
+            b: Complex::from_polar(0.6_f64.sinh(), 1.9),
+        };
+        let chord: Vec<[f32; 3]> = (-9..=9)
+            .map(|t| Projection::Klein.project(shift.apply(Complex::new(t as f64 / 10.0, 0.0))))
+            .collect();
+        let (a, b) = (chord[0], chord[chord.len() - 1]);
+        for p in &chord {
+            let cross = (b[0] - a[0]) * (p[2] - a[2]) - (b[2] - a[2]) * (p[0] - a[0]);
+            assert!(cross.abs() < 1e-5, "Klein point {p:?} off the chord by {cross}");
+        }
+    }
+
+    #[test]
+    fn test_projection_images() {
+        for p in samples() {
+            let [x, _, z] = Projection::Klein.project(p);
+            assert!(x * x + z * z < 1.0);
+            // The half-plane's ideal line Im w = 0 sits at Z = −½
+            let [_, _, z] = Projection::HalfPlane.project(p);
+            assert!(z > -0.5);
+            let [_, _, z] = Projection::Band.project(p);
+            assert!((z as f64).abs() < std::f64::consts::FRAC_PI_4);
+        }
+        assert!(Projection::Klein.unproject(0.8, 0.7).is_none());
+        assert!(Projection::HalfPlane.unproject(0.0, -0.6).is_none());
+        assert!(Projection::Band.unproject(3.0, 0.8).is_none());
+    }
 }
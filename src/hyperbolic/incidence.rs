@@ -0,0 +1,215 @@
+//! Canonical vertex and edge identities.
+//!
+//! A vertex is where q cells meet; an edge is shared by 2 cells. Each is
+//! named from any incident cell by a local index in that cell's canonical
+//! frame, then reduced to the shortlex-least such name so every incident cell
+//! agrees on it.
+//!
+//! Corner `k` of a cell lies between its edges `k` and `k + 1`, matching
+//! vertex `k` of the canonical polygon. Edge `e` runs from corner `e - 1` to
+//! corner `e`.
+
+use std::cmp::Ordering;
+
+use super::cell_id::{self, CellId};
+use super::rewrite::{self, RewriteRule, A, B};
+
+/// A tiling vertex, as its shortlex-least incident (cell, corner).
+#[allow(dead_code)] // no gameplay code names vertices or edges yet
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VertexId {
+    pub cell: CellId,
+    pub corner: u8,
+}
+
+/// A tiling edge, as the shortlex-least of its two (cell, edge) sides.
+#[allow(dead_code)] // no gameplay code names vertices or edges yet
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EdgeId {
+    pub cell: CellId,
+    pub edge: u8,
+}
+
+fn side_cmp(x: &(CellId, u8), y: &(CellId, u8)) -> Ordering {
+    rewrite::shortlex_cmp(x.0.word(), y.0.word()).then(x.1.cmp(&y.1))
+}
+
+/// The q (cell, corner) pairs around corner `corner` of `cell`, in cyclic
+/// order starting with the given one.
+///
+/// A turtle facing edge f that repeats `aB` walks around the corner between
+/// edges f − 1 and f, so start facing `corner + 1`.
+pub fn corner_cells(cell: &CellId, corner: u8, rules: &[RewriteRule]) -> Vec<(CellId, u8)> {
+    let start = (cell.clone(), corner);
+    let mut word = cell.word().to_vec();
+    word.extend(std::iter::repeat_n(B, (corner as usize + 1) % 4));
+    rewrite::reduce_from(&mut word, cell.len(), rules);
+
+    let mut around = vec![start.clone()];
+    loop {
+        let len = word.len();
+        word.extend([A, B]);
+        rewrite::reduce_from(&mut word, len, rules);
+        let here = cell_id::canonicalize_reduced(word.clone(), rules);
+        let corner = (here.facing() + 3) % 4;
+        let next = (here.id, corner);
+        if next == start {
+            return around;
+        }
+        around.push(next);
+    }
+}
+
+#[allow(dead_code)] // no gameplay code names vertices or edges yet
+impl VertexId {
+    /// The vertex at corner `corner` of `cell`.
+    pub fn new(cell: &CellId, corner: u8, rules: &[RewriteRule]) -> Self {
+        let (cell, corner) = corner_cells(cell, corner, rules)
+            .into_iter()
+            .min_by(side_cmp)
+            .unwrap();
+        Self { cell, corner }
+    }
+
+    /// The q cells meeting at this vertex, each with its local corner index,
+    /// in cyclic order starting from the representative.
+    pub fn cells(&self, rules: &[RewriteRule]) -> Vec<(CellId, u8)> {
+        corner_cells(&self.cell, self.corner, rules)
+    }
+}
+
+#[allow(dead_code)] // no gameplay code names vertices or edges yet
+impl EdgeId {
+    /// The edge across `edge` of `cell`.
+    pub fn new(cell: &CellId, edge: u8, rules: &[RewriteRule]) -> Self {
+        let [a, b] = edge_sides(cell, edge, rules);
+        let (cell, edge) = if side_cmp(&a, &b) == Ordering::Greater { b } else { a };
+        Self { cell, edge }
+    }
+
+    /// Both sides of this edge: the representative first, then the cell
+    /// across it with the edge index that leads back.
+    pub fn sides(&self, rules: &[RewriteRule]) -> [(CellId, u8); 2] {
+        edge_sides(&self.cell, self.edge, rules)
+    }
+
+    /// The two vertices at the ends of this edge.
+    pub fn vertices(&self, rules: &[RewriteRule]) -> [VertexId; 2] {
+        [
+            VertexId::new(&self.cell, (self.edge + 3) % 4, rules),
+            VertexId::new(&self.cell, self.edge, rules),
+        ]
+    }
+}
+
+fn edge_sides(cell: &CellId, edge: u8, rules: &[RewriteRule]) -> [(CellId, u8); 2] {
+    let across = cell_id::neighbor(cell, edge, rules);
+    // After crossing, the turtle faces back over the same edge.
+    let back = across.facing();
+    [(cell.clone(), edge), (across.id, back)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperbolic::cell_graph::word_to_mobius_state;
+    use crate::hyperbolic::poincare::{canonical_polygon, neighbor_transforms, Complex, Mobius, TilingConfig};
+    use std::collections::HashSet;
+
+    fn ball(radius: usize, rules: &[RewriteRule]) -> Vec<CellId> {
+        let mut seen = HashSet::from([CellId::origin()]);
+        let mut ring = vec![CellId::origin()];
+        for _ in 0..radius {
+            ring = ring
+                .iter()
+                .flat_map(|c| cell_id::all_neighbors(c, rules))
+                .filter(|n| seen.insert(n.id.clone()))
+                .map(|n| n.id)
+                .collect();
+        }
+        seen.into_iter().collect()
+    }
+
+    /// Disk position of corner `corner` of `cell`, through the cell's
+    /// canonical frame M(w)·R(f(w)).
+    fn corner_point(cell: &CellId, corner: u8, cfg: &TilingConfig) -> Complex {
+        let (m, facing, _) = word_to_mobius_state(cell.word(), &neighbor_transforms(cfg));
+        let frame = m.compose(&Mobius::rotation(facing as f64 * cfg.vertex_angle_step()));
+        frame.apply(canonical_polygon(cfg)[corner as usize])
+    }
+
+    #[test]
+    fn corner_cycle_has_q_adjacent_cells() {
+        for q in [5, 6, 7] {
+            let rules = rewrite::rules_for(q);
+            for corner in 0..4 {
+                let around = corner_cells(&CellId::origin(), corner, &rules);
+                assert_eq!(around.len(), q as usize);
+                let distinct: HashSet<&CellId> = around.iter().map(|(c, _)| c).collect();
+                assert_eq!(distinct.len(), q as usize);
+                for i in 0..around.len() {
+                    let (c, _) = &around[i];
+                    let (d, _) = &around[(i + 1) % around.len()];
+                    assert!(
+                        cell_id::all_neighbors(c, &rules).iter().any(|n| n.id == *d),
+                        "q={q}: {c} and {d} should share an edge",
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn vertex_id_agrees_from_every_cell() {
+        let rules = rewrite::rules_45();
+        let cfg = TilingConfig::new(4, 5);
+        let mut vertices = HashSet::new();
+        for cell in ball(2, &rules) {
+            for corner in 0..4 {
+                let v = VertexId::new(&cell, corner, &rules);
+                let p = corner_point(&cell, corner, &cfg);
+                for (c, k) in v.cells(&rules) {
+                    assert_eq!(VertexId::new(&c, k, &rules), v);
+                    let d = (corner_point(&c, k, &cfg) - p).abs();
+                    assert!(d < 1e-9, "{c} corner {k} is {d} away from {cell} corner {corner}");
+                }
+                vertices.insert(v);
+            }
+        }
+        // Origin's 4 corners are distinct vertices.
+        let origin: HashSet<VertexId> = (0..4).map(|k| VertexId::new(&CellId::origin(), k, &rules)).collect();
+        assert_eq!(origin.len(), 4);
+        assert!(vertices.len() > 4);
+    }
+
+    #[test]
+    fn edge_id_agrees_from_both_sides() {
+        let rules = rewrite::rules_for(6);
+        let cfg = TilingConfig::new(4, 6);
+        for cell in ball(2, &rules) {
+            for edge in 0..4 {
+                let e = EdgeId::new(&cell, edge, &rules);
+                let [a, b] = e.sides(&rules);
+                assert_eq!(a, (e.cell.clone(), e.edge));
+                assert_eq!(EdgeId::new(&b.0, b.1, &rules), e);
+                assert!(a == (cell.clone(), edge) || b == (cell.clone(), edge));
+                assert_eq!(cell_id::neighbor(&b.0, b.1, &rules).id, a.0);
+
+                // Both sides see the same two endpoints.
+                let ends: HashSet<VertexId> = e.vertices(&rules).into_iter().collect();
+                let from_b: HashSet<VertexId> = [
+                    VertexId::new(&b.0, (b.1 + 3) % 4, &rules),
+                    VertexId::new(&b.0, b.1, &rules),
+                ]
+                .into_iter()
+                .collect();
+                assert_eq!(ends, from_b);
+                for v in &ends {
+                    let p = corner_point(&v.cell, v.corner, &cfg);
+                    let on_a = [(a.1 + 3) % 4, a.1].map(|k| (corner_point(&a.0, k, &cfg) - p).abs());
+                    assert!(on_a.iter().any(|&d| d < 1e-9));
+                }
+            }
+        }
+    }
+}
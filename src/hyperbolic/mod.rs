@@ -4,4 +4,6 @@ pub mod embedding;
 pub mod rewrite;
 pub mod cell_id;
 pub mod cell_graph;
+pub mod automaton;
+pub mod incidence;
 mod algebraic_tests;
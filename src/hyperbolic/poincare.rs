@@ -132,6 +132,14 @@ impl Mobius {
         }
     }
 
+    /// Rotation about the origin by `angle` radians.
+    pub fn rotation(angle: f64) -> Self {
+        Self {
+            a: Complex::from_polar(1.0, angle / 2.0),
+            b: Complex::ZERO,
+        }
+    }
+
     /// Apply this transform to a point on the Poincare disk.
     pub fn apply(&self, z: Complex) -> Complex {
         let num = self.a * z + self.b;
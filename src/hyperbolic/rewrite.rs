@@ -1,9 +1,12 @@
-//! Knuth-Bendix confluent rewrite engine for {4,5} hyperbolic tiling.
+//! Knuth-Bendix confluent rewrite engine for {4,q} hyperbolic tilings.
 //!
 //! Alphabet: a (move forward & flip), B (turn left), b (turn right).
-//! 10 confluent rewrite rules reduce any word to a unique canonical form.
-//! (The 11th rule A→a is handled at parse time by our byte encoding.)
+//! A finite confluent rule set reduces any word to a unique canonical form.
+//! For {4,5} the 10 rules are hard-coded (the 11th rule A→a is handled at
+//! parse time by our byte encoding); other q are completed on demand and
+//! cached on disk.
 
+use std::path::PathBuf;
 
 /// Byte encoding for the turtle alphabet.
 pub const A: u8 = 0; // move forward & flip
@@ -13,6 +16,13 @@ pub const B_INV: u8 = 2; // turn right (b = B^{-1})
 /// A word in the turtle alphabet.
 pub type Word = Vec<u8>;
 
+/// Whether every byte of `word` is a letter of the turtle alphabet. Words
+/// from outside (the network, save files) must pass this before reaching
+/// the rewrite engine, which treats other bytes as unreachable.
+pub fn is_valid_word(word: &[u8]) -> bool {
+    word.iter().all(|&l| l <= B_INV)
+}
+
 /// A rewrite rule: replace `lhs` with `rhs` wherever found.
 #[derive(Clone, Debug)]
 pub struct RewriteRule {
@@ -61,15 +71,231 @@ pub fn rules_45() -> Vec<RewriteRule> {
         .collect()
 }
 
+/// Upper bound on the rule count during completion. Every q ≥ 5 settles at
+/// 8 or 10 rules; blowing past this means the presentation is wrong.
+const MAX_COMPLETION_RULES: usize = 256;
+
+/// Return confluent rewrite rules for {4,q}, q ≥ 5.
+///
+/// {4,5} uses the hard-coded [`rules_45`]. Other q are loaded from the disk
+/// cache if present and still confluent, otherwise completed with
+/// [`complete_rules`] and written back to the cache.
+pub fn rules_for(q: u32) -> Vec<RewriteRule> {
+    assert!(q >= 5, "{{4,{q}}} is not hyperbolic");
+    if q == 5 {
+        return rules_45();
+    }
+
+    let path = cache_path(q);
+    if let Some(rules) = path.as_ref().and_then(|p| load_rules(p, q)) {
+        return rules;
+    }
+
+    let rules = complete_rules(q);
+    if let Some(path) = path {
+        save_rules(&path, &rules);
+    }
+    rules
+}
+
+/// Knuth-Bendix completion of a^2 = B^4 = (aB)^q = e, bB = Bb = e under
+/// shortlex order (b < B < a). Rules come back longest-LHS-first.
+pub fn complete_rules(q: u32) -> Vec<RewriteRule> {
+    let mut rules = Vec::new();
+    for lhs in defining_relations(q) {
+        push_oriented(&mut rules, lhs, vec![]);
+    }
+    interreduce(&mut rules);
+
+    loop {
+        let mut added = false;
+        for (left, right) in critical_pairs(&rules) {
+            let l = reduced(&left, &rules);
+            let r = reduced(&right, &rules);
+            added |= push_oriented(&mut rules, l, r);
+        }
+        if !added {
+            break;
+        }
+        interreduce(&mut rules);
+        assert!(
+            rules.len() <= MAX_COMPLETION_RULES,
+            "completion for {{4,{q}}} did not terminate"
+        );
+    }
+
+    sort_rules(&mut rules);
+    rules
+}
+
+/// Check that a rule set is confluent: every rule shrinks in shortlex order
+/// (so reduction terminates) and every critical pair joins.
+pub fn is_confluent(rules: &[RewriteRule]) -> bool {
+    rules
+        .iter()
+        .all(|r| shortlex_cmp(&r.rhs, &r.lhs) == std::cmp::Ordering::Less)
+        && critical_pairs(rules)
+            .into_iter()
+            .all(|(l, r)| reduced(&l, rules) == reduced(&r, rules))
+}
+
+/// The relators of the {4,q} turtle group as words equal to e.
+fn defining_relations(q: u32) -> Vec<Word> {
+    let vertex: Word = (0..q).flat_map(|_| [A, B]).collect();
+    vec![vec![A, A], vec![B, B_INV], vec![B_INV, B], vec![B; 4], vertex]
+}
+
+/// Add `l = r` as a rule oriented by shortlex. Returns false if trivial or
+/// already present.
+fn push_oriented(rules: &mut Vec<RewriteRule>, l: Word, r: Word) -> bool {
+    let (lhs, rhs) = match shortlex_cmp(&l, &r) {
+        std::cmp::Ordering::Equal => return false,
+        std::cmp::Ordering::Greater => (l, r),
+        std::cmp::Ordering::Less => (r, l),
+    };
+    if rules.iter().any(|rule| rule.lhs == lhs && rule.rhs == rhs) {
+        return false;
+    }
+    rules.push(RewriteRule { lhs, rhs });
+    true
+}
+
+/// Remove rules whose LHS another rule already rewrites (re-adding them as
+/// equations), then normalize every RHS. Repeats until stable.
+fn interreduce(rules: &mut Vec<RewriteRule>) {
+    'restart: loop {
+        for i in 0..rules.len() {
+            let reducible = (0..rules.len())
+                .any(|j| j != i && find(&rules[i].lhs, &rules[j].lhs).is_some());
+            if reducible {
+                let rule = rules.remove(i);
+                let l = reduced(&rule.lhs, rules);
+                let r = reduced(&rule.rhs, rules);
+                push_oriented(rules, l, r);
+                continue 'restart;
+            }
+        }
+        break;
+    }
+    for i in 0..rules.len() {
+        let rhs = reduced(&rules[i].rhs, rules);
+        rules[i].rhs = rhs;
+    }
+}
+
+/// All critical pairs: proper suffix/prefix overlaps between (possibly equal)
+/// LHSs, plus one LHS occurring inside another.
+fn critical_pairs(rules: &[RewriteRule]) -> Vec<(Word, Word)> {
+    let mut pairs = Vec::new();
+    for (i, r1) in rules.iter().enumerate() {
+        for (j, r2) in rules.iter().enumerate() {
+            let (l1, l2) = (&r1.lhs, &r2.lhs);
+            // Overlap: l1 = xu, l2 = uy, giving r1·y vs x·r2.
+            for k in 1..l1.len().min(l2.len()) {
+                if l1[l1.len() - k..] == l2[..k] {
+                    let left = [&r1.rhs[..], &l2[k..]].concat();
+                    let right = [&l1[..l1.len() - k], &r2.rhs[..]].concat();
+                    pairs.push((left, right));
+                }
+            }
+            // Inclusion: l1 = x·l2·y, giving r1 vs x·r2·y.
+            if i != j {
+                if let Some(pos) = find(l1, l2) {
+                    let right = [&l1[..pos], &r2.rhs[..], &l1[pos + l2.len()..]].concat();
+                    pairs.push((r1.rhs.clone(), right));
+                }
+            }
+        }
+    }
+    pairs
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return None;
+    }
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Longest LHS first, ties broken by shortlex, so `reduce` tries longer matches first.
+fn sort_rules(rules: &mut [RewriteRule]) {
+    rules.sort_by(|x, y| shortlex_cmp(&y.lhs, &x.lhs));
+}
+
+/// Where the rule set for {4,q} is cached. Test builds use a scratch
+/// directory, so running the tests never writes to the user's cache.
+fn cache_path(q: u32) -> Option<PathBuf> {
+    let file = format!("rules_4_{q}.txt");
+    if cfg!(test) {
+        return Some(std::env::temp_dir().join("octofact-test-cache").join(file));
+    }
+    directories::ProjectDirs::from("", "", "octofact").map(|dirs| dirs.cache_dir().join(file))
+}
+
+/// Load a cached rule set (one `lhs rhs` pair per line). Rejected unless it
+/// is confluent and still reduces every defining relator to e.
+fn load_rules(path: &std::path::Path, q: u32) -> Option<Vec<RewriteRule>> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let mut rules = Vec::new();
+    for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+        let mut parts = line.split_whitespace();
+        let (lhs, rhs) = (parts.next()?, parts.next()?);
+        if parts.next().is_some() || !is_word(lhs) || !is_word(rhs) {
+            return None;
+        }
+        rules.push(RewriteRule {
+            lhs: string_to_word(lhs),
+            rhs: string_to_word(rhs),
+        });
+    }
+    sort_rules(&mut rules);
+
+    let valid = is_confluent(&rules)
+        && defining_relations(q)
+            .iter()
+            .all(|w| reduced(w, &rules).is_empty());
+    if !valid {
+        log::warn!("Ignoring invalid rewrite cache {}", path.display());
+        return None;
+    }
+    Some(rules)
+}
+
+fn save_rules(path: &std::path::Path, rules: &[RewriteRule]) {
+    let contents: String = rules
+        .iter()
+        .map(|r| format!("{} {}\n", word_to_string(&r.lhs), word_to_string(&r.rhs)))
+        .collect();
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(path, contents));
+    if let Err(e) = result {
+        log::warn!("Failed to cache rewrite rules: {e}");
+    }
+}
+
+fn is_word(s: &str) -> bool {
+    s == "e" || s.bytes().all(|c| matches!(c, b'a' | b'B' | b'b'))
+}
+
 /// Reduce a word by repeatedly applying rewrite rules until no rule matches.
 ///
 /// Strategy: scan left-to-right for the first matching LHS. Replace it with
 /// the RHS, then back up the scan position to catch cascading rewrites.
 /// Repeat until a full scan finds no match.
 pub fn reduce(word: &mut Word, rules: &[RewriteRule]) {
+    reduce_from(word, 0, rules);
+}
+
+/// Like [`reduce`], for a word whose first `reduced_len` letters are already
+/// reduced: no LHS can lie entirely inside that prefix, so scanning starts
+/// just before the unreduced suffix. Makes appending a few letters to a deep
+/// cell's word cost O(suffix) instead of O(word).
+pub fn reduce_from(word: &mut Word, reduced_len: usize, rules: &[RewriteRule]) {
     let max_lhs = rules.iter().map(|r| r.lhs.len()).max().unwrap_or(0);
 
-    let mut pos = 0;
+    let mut pos = reduced_len.saturating_sub(max_lhs.saturating_sub(1));
     while pos < word.len() {
         let mut matched = false;
         // Try each rule at this position (longest LHS first due to ordering).
@@ -94,13 +320,35 @@ pub fn reduce(word: &mut Word, rules: &[RewriteRule]) {
 }
 
 /// Reduce a word, returning the result (non-mutating convenience wrapper).
-#[allow(dead_code)]
 pub fn reduced(word: &[u8], rules: &[RewriteRule]) -> Word {
     let mut w = word.to_vec();
     reduce(&mut w, rules);
     w
 }
 
+/// Inverse of a word: reversed, with a ↦ a and B ↔ b. Not reduced.
+pub fn inverse(word: &[u8]) -> Word {
+    word.iter()
+        .rev()
+        .map(|&c| match c {
+            A => A,
+            B => B_INV,
+            B_INV => B,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Reduced word for u⁻¹·v: `v` as seen from `u`'s frame. The shared prefix
+/// cancels up front (p·u')⁻¹·(p·v') = u'⁻¹·v', so nearby deep cells stay cheap.
+pub fn relative_word(u: &[u8], v: &[u8], rules: &[RewriteRule]) -> Word {
+    let common = u.iter().zip(v).take_while(|(x, y)| x == y).count();
+    let mut word = inverse(&u[common..]);
+    word.extend_from_slice(&v[common..]);
+    reduce(&mut word, rules);
+    word
+}
+
 /// Shortlex comparison for words.
 ///
 /// Ordering: shorter words first. For equal length, lexicographic with b < B < a.
@@ -150,7 +398,6 @@ pub fn word_to_string(word: &[u8]) -> String {
 }
 
 /// Parse a string into a word. 'e' or empty string → empty word.
-#[allow(dead_code)]
 pub fn string_to_word(s: &str) -> Word {
     if s.is_empty() || s == "e" {
         return vec![];
@@ -329,6 +576,91 @@ mod tests {
         }
     }
 
+    // --- Completion ---
+
+    fn rule_strings(rules: &[RewriteRule]) -> Vec<(String, String)> {
+        let mut v: Vec<_> = rules
+            .iter()
+            .map(|r| (word_to_string(&r.lhs), word_to_string(&r.rhs)))
+            .collect();
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn test_completion_reproduces_rules_45() {
+        let completed = complete_rules(5);
+        assert_eq!(rule_strings(&completed), rule_strings(&rules_45()));
+        assert!(is_confluent(&rules_45()));
+    }
+
+    #[test]
+    fn test_completion_confluent_for_higher_q() {
+        for q in 6..=9 {
+            let rules = complete_rules(q);
+            assert!(is_confluent(&rules), "{{4,{q}}} rules not confluent");
+            for relator in defining_relations(q) {
+                assert_eq!(reduced(&relator, &rules), vec![], "q={q}");
+            }
+            // (aB)^(q-1) is a nontrivial rotation about the vertex.
+            let short: Word = (0..q - 1).flat_map(|_| [A, B]).collect();
+            assert_ne!(reduced(&short, &rules), vec![], "q={q}");
+        }
+    }
+
+    #[test]
+    fn test_completion_46_rules() {
+        let rules = complete_rules(6);
+        let expected: Vec<(String, String)> = [
+            ("aBaBabb", "bababaB"),
+            ("ababab", "BaBaBa"),
+            ("aBaBaB", "bababa"),
+            ("bbb", "B"),
+            ("BB", "bb"),
+            ("bB", "e"),
+            ("Bb", "e"),
+            ("aa", "e"),
+        ]
+        .iter()
+        .map(|(l, r)| (l.to_string(), r.to_string()))
+        .collect();
+        let mut expected = expected;
+        expected.sort();
+        assert_eq!(rule_strings(&rules), expected);
+        // Longest LHS first.
+        assert_eq!(word_to_string(&rules[0].lhs), "aBaBabb");
+    }
+
+    #[test]
+    fn test_confluence_rejects_incomplete_rules() {
+        let mut rules = rules_45();
+        rules.retain(|r| r.lhs.len() < 9);
+        assert!(!is_confluent(&rules));
+    }
+
+    #[test]
+    fn test_cached_rules_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("octofact-rules-{}", std::process::id()));
+        let path = dir.join("rules_4_7.txt");
+        let rules = complete_rules(7);
+        save_rules(&path, &rules);
+        let loaded = load_rules(&path, 7).expect("cache should load");
+        assert_eq!(rule_strings(&loaded), rule_strings(&rules));
+        // A cache for the wrong q fails the relator check.
+        assert!(load_rules(&path, 8).is_none());
+        std::fs::write(&path, "aa e\n").unwrap();
+        assert!(load_rules(&path, 7).is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_cache_stays_out_of_user_dirs() {
+        let path = cache_path(6).unwrap();
+        assert!(path.starts_with(std::env::temp_dir()), "{}", path.display());
+        assert!(is_confluent(&rules_for(6)));
+        assert!(path.exists());
+    }
+
     #[test]
     fn test_empty_word() {
         assert_eq!(reduced(&[], &r()), vec![]);
@@ -342,6 +674,14 @@ mod tests {
         assert_eq!(reduced(&[B_INV], &rules), vec![B_INV]);
     }
 
+    #[test]
+    fn test_is_valid_word() {
+        assert!(is_valid_word(&[]));
+        assert!(is_valid_word(&[A, B, B_INV]));
+        assert!(!is_valid_word(&[A, 3]));
+        assert!(!is_valid_word(&[255]));
+    }
+
     // --- Shortlex ordering ---
 
     #[test]
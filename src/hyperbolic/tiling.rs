@@ -2,19 +2,25 @@ use std::collections::{HashMap, HashSet, VecDeque};
 
 use smallvec::SmallVec;
 
-use super::cell_graph::word_to_mobius;
+use super::cell_graph::word_to_mobius_state;
 use super::cell_id::{self, CellId};
 use super::poincare::{Complex, Mobius, TilingConfig, center_to_center_distance, neighbor_transforms, poincare_distance};
-use super::rewrite::{self, RewriteRule, A, B, B_INV};
+use super::rewrite::{self, RewriteRule, Word, A, B, B_INV};
 
-/// Legacy tile address type. Kept for backward compatibility with world.rs
-/// and power.rs which will be migrated to CellId in Phase 6.
+/// Raw word address, as sent over the wire. Simulation storage keys cells
+/// by `CellId` instead.
 pub type TileAddr = SmallVec<[u8; 12]>;
 
-/// Maximum number of tiles to keep. Prevents unbounded growth in hyperbolic space.
+/// Maximum number of unpinned tiles to keep. A backstop: eviction normally
+/// keeps the count far below this.
 const MAX_TILES: usize = 4096;
 
-/// A tile in the {4,5} tiling, identified by its canonical CellId.
+/// Tiles whose centre is more than this many cell steps from the view centre
+/// are evicted on recenter. The camera keeps 3 layers covered around itself,
+/// so the margin stops tiles at the edge of that churning on every crossing.
+const EVICTION_LAYERS: f64 = 5.0;
+
+/// A tile in the {4,q} tiling, identified by its canonical CellId.
 #[derive(Clone, Debug)]
 pub struct Tile {
     /// Canonical algebraic cell identity (shortlex-minimum reduced word).
@@ -24,12 +30,16 @@ pub struct Tile {
     /// Parity: false = even (same orientation as origin), true = odd (flipped).
     pub parity: bool,
     /// Physical edge index the turtle faces after walking the canonical word.
-    /// Used during construction to compute cached neighbor CellIds.
-    #[allow(dead_code)]
+    /// Used to compute cached neighbor CellIds and view-relative transforms.
     facing: u8,
     /// Cached CellIds of the 4 neighbors (by physical edge index).
     /// Computed once when the tile is created, avoiding repeated K-B reduction.
     pub neighbors: [CellId; 4],
+    /// Quarter turns between the frame reached by crossing each physical edge
+    /// and the neighbor's own frame: the neighbor's transform is
+    /// `transform · T_edge · R(turns · π/2)`. Grids only line up straight
+    /// across an edge when this is 0.
+    pub neighbor_turns: [u8; 4],
 }
 
 /// Spatial dedup key: discretize Poincare disk position to grid.
@@ -62,15 +72,30 @@ fn word_facing_parity(word: &[u8]) -> (u8, bool) {
     (facing, parity)
 }
 
-/// Compute all 4 neighbor CellIds for a tile, mapping physical edges to algebraic edges.
-fn compute_neighbors(id: &CellId, facing: u8, rules: &[RewriteRule]) -> [CellId; 4] {
-    [0u8, 1, 2, 3].map(|dir| {
-        let cell_edge = (dir + 4 - facing) % 4;
-        cell_id::neighbor(id, cell_edge, rules).id
-    })
+/// The neighbor across physical edge `dir` of the cell `id`, and the quarter
+/// turns between the frame reached by crossing and the neighbor's own frame.
+///
+/// Crossing leaves the turtle facing `dir + 2` in a frame translated straight
+/// across the edge. The neighbor's own frame is that of its canonical word,
+/// which faces `orientation` turns further round than the crossing word did,
+/// so the two frames differ by the mismatch in facings.
+pub fn edge_crossing(id: &CellId, dir: u8, rules: &[RewriteRule]) -> (CellId, u8) {
+    let (facing, _) = word_facing_parity(id.word());
+    let cell_edge = (dir + 4 - facing) % 4;
+    let neighbor = cell_id::neighbor(id, cell_edge, rules);
+    let (neighbor_facing, _) = word_facing_parity(neighbor.id.word());
+    let turns = (dir + 2 + neighbor.orientation + 4 - neighbor_facing) % 4;
+    (neighbor.id, turns)
+}
+
+/// Compute all 4 neighbor CellIds for a tile by physical edge, along with the
+/// quarter turns from each crossing to the neighbor's frame.
+fn compute_neighbors(id: &CellId, rules: &[RewriteRule]) -> ([CellId; 4], [u8; 4]) {
+    let crossings = [0u8, 1, 2, 3].map(|dir| edge_crossing(id, dir, rules));
+    (crossings.clone().map(|c| c.0), crossings.map(|c| c.1))
 }
 
-/// BFS tiling state for incremental expansion of a {4,5} tiling.
+/// BFS tiling state for incremental expansion of a {4,q} tiling.
 /// Uses algebraic CellId for exact cell identity (no floating-point drift).
 pub struct TilingState {
     pub cfg: TilingConfig,
@@ -82,29 +107,37 @@ pub struct TilingState {
     /// Secondary spatial index for click detection and `find_tile_near()`.
     spatial_to_tile: HashMap<(i64, i64), usize>,
     frontier: VecDeque<usize>,
+    /// Cells kept loaded however far away the view moves, such as those
+    /// holding structures. Only tiles near the view are indexed spatially or
+    /// expanded; pinned tiles beyond it are reachable by CellId alone.
+    pinned: HashSet<CellId>,
     /// `[0]` = transforms for even-parity tiles, `[1]` = for odd-parity tiles.
     pub neighbor_xforms: [Vec<Mobius>; 2],
-    /// Cached confluent rewrite rules for {4,5}.
+    /// Cached confluent rewrite rules for {4,q}.
     rules: Vec<RewriteRule>,
-    /// View offset: maps absolute (word_to_mobius) coordinates to view-relative coordinates.
-    /// Recomputed on each recenter to avoid holonomy accumulation.
-    view_offset: Mobius,
+    /// Canonical word of the tile the view is centered on. Transforms are
+    /// computed from words relative to it, never from absolute coordinates,
+    /// which lose all precision a few dozen cells from the origin.
+    view_word: Word,
+    /// Turtle facing at the end of `view_word`.
+    view_facing: u8,
 }
 
 impl TilingState {
     pub fn new(cfg: TilingConfig) -> Self {
         assert_eq!(cfg.p, 4, "CellId tiling only supports {{4,q}} (got p={})", cfg.p);
-        assert_eq!(cfg.q, 5, "CellId tiling only supports {{4,5}} (got q={})", cfg.q);
+        assert!(cfg.q >= 5, "CellId tiling needs a hyperbolic {{4,q}} (got q={})", cfg.q);
 
-        let rules = rewrite::rules_45();
+        let rules = rewrite::rules_for(cfg.q);
         let origin_id = CellId::origin();
-        let origin_neighbors = compute_neighbors(&origin_id, 0, &rules);
+        let (origin_neighbors, origin_turns) = compute_neighbors(&origin_id, &rules);
         let origin = Tile {
             id: origin_id.clone(),
             transform: Mobius::identity(),
             parity: false,
             facing: 0,
             neighbors: origin_neighbors,
+            neighbor_turns: origin_turns,
         };
 
         let key = spatial_key(Complex::ZERO);
@@ -125,15 +158,17 @@ impl TilingState {
             id_to_tile,
             spatial_to_tile,
             frontier,
+            pinned: HashSet::new(),
             neighbor_xforms: neighbor_transforms(&cfg),
             rules,
-            view_offset: Mobius::identity(),
+            view_word: Word::new(),
+            view_facing: 0,
         }
     }
 
     /// Expand only frontier tiles within `max_dist` hyperbolic distance of `target`.
     /// Uses algebraic CellId for dedup (exact, immune to floating-point drift).
-    /// Stops early if the tile count reaches MAX_TILES.
+    /// Stops early if the unpinned tile count reaches MAX_TILES.
     pub fn expand_near(&mut self, target: Complex, max_dist: f64) {
         let frontier_len = self.frontier.len();
         if frontier_len == 0 {
@@ -141,7 +176,7 @@ impl TilingState {
         }
         let mut deferred = VecDeque::new();
         for _ in 0..frontier_len {
-            if self.tiles.len() >= MAX_TILES {
+            if self.tiles.len() >= MAX_TILES + self.pinned.len() {
                 // Drain remaining frontier items into deferred.
                 while let Some(idx) = self.frontier.pop_front() {
                     deferred.push_back(idx);
@@ -161,26 +196,8 @@ impl TilingState {
                 if self.seen.contains(child_id) {
                     continue;
                 }
-                // Compute child transform from canonical word (via word_to_mobius),
-                // not incremental composition, to avoid holonomy accumulation.
-                let child_transform = self.view_offset
-                    .compose(&word_to_mobius(child_id.word(), &self.neighbor_xforms));
-                let (child_facing, child_parity) = word_facing_parity(child_id.word());
-                let child_neighbors = compute_neighbors(child_id, child_facing, &self.rules);
-                let child = Tile {
-                    id: child_id.clone(),
-                    transform: child_transform,
-                    parity: child_parity,
-                    facing: child_facing,
-                    neighbors: child_neighbors,
-                };
-                let child_idx = self.tiles.len();
-                self.seen.insert(child_id.clone());
-                self.id_to_tile.insert(child_id.clone(), child_idx);
-                let center = child_transform.apply(Complex::ZERO);
-                self.spatial_to_tile.insert(spatial_key(center), child_idx);
-                self.tiles.push(child);
-                self.frontier.push_back(child_idx);
+                let child = self.build_tile(child_id);
+                self.push_tile(child);
             }
         }
         while let Some(idx) = deferred.pop_back() {
@@ -188,6 +205,81 @@ impl TilingState {
         }
     }
 
+    /// Build the tile for a canonical CellId at its place in the current view.
+    fn build_tile(&self, id: &CellId) -> Tile {
+        let (facing, parity) = word_facing_parity(id.word());
+        let transform = self.view_transform(id.word(), facing);
+        let (neighbors, neighbor_turns) = compute_neighbors(id, &self.rules);
+        Tile {
+            id: id.clone(),
+            transform,
+            parity,
+            facing,
+            neighbors,
+            neighbor_turns,
+        }
+    }
+
+    /// Transform of the cell with canonical `word` (ending at `facing`) in the
+    /// current view, i.e. M(c)⁻¹·M(w) for view word c.
+    ///
+    /// With r = reduce(c⁻¹·w) and f the turtle facing, M(x)·R(f(x)) is a
+    /// homomorphism, so M(c)⁻¹·M(w) = R(f(c))·M(r)·R(f(r) − f(w)). Only the
+    /// short relative word is composed, so precision is independent of depth.
+    fn view_transform(&self, word: &[u8], facing: u8) -> Mobius {
+        let relative = rewrite::relative_word(&self.view_word, word, &self.rules);
+        let (transform, relative_facing, _) = word_to_mobius_state(&relative, &self.neighbor_xforms);
+        let step = self.cfg.vertex_angle_step();
+        let turn = (relative_facing + 4 - facing) % 4;
+        Mobius::rotation(self.view_facing as f64 * step)
+            .compose(&transform)
+            .compose(&Mobius::rotation(turn as f64 * step))
+    }
+
+    /// Index a new tile, adding it to the spatial index and the expansion
+    /// frontier if it lies within the eviction band.
+    fn push_tile(&mut self, tile: Tile) -> usize {
+        let idx = self.tiles.len();
+        self.seen.insert(tile.id.clone());
+        self.id_to_tile.insert(tile.id.clone(), idx);
+        let center = tile.transform.apply(Complex::ZERO);
+        self.tiles.push(tile);
+        if self.in_band(center) {
+            self.spatial_to_tile.insert(spatial_key(center), idx);
+            self.frontier.push_back(idx);
+        }
+        idx
+    }
+
+    /// Whether a tile centred at `center` (in view coordinates) is close
+    /// enough to the view to keep. False for centres that have lost all
+    /// precision, which land on or past the unit circle.
+    fn in_band(&self, center: Complex) -> bool {
+        let limit = EVICTION_LAYERS * center_to_center_distance(&self.cfg);
+        center.abs() < 1.0 && poincare_distance(Complex::ZERO, center) <= limit
+    }
+
+    /// Keep `id` loaded across recenters until it is unpinned.
+    pub fn pin(&mut self, id: &CellId) {
+        self.pinned.insert(id.clone());
+    }
+
+    /// Let `id` be evicted again once the view moves away from it.
+    pub fn unpin(&mut self, id: &CellId) {
+        self.pinned.remove(id);
+    }
+
+    /// Index of the tile with the given CellId, creating it if it hasn't
+    /// been expanded yet (or was evicted). Lets the simulation act on cells
+    /// far from the camera, such as edits made by another player.
+    pub fn ensure_tile(&mut self, id: &CellId) -> usize {
+        if let Some(idx) = self.find_tile(id) {
+            return idx;
+        }
+        let tile = self.build_tile(id);
+        self.push_tile(tile)
+    }
+
     /// Expand BFS until every frontier tile is at least `min_layers` hops away from `target`.
     pub fn ensure_coverage(&mut self, target: Complex, min_layers: usize) {
         let d = center_to_center_distance(&self.cfg);
@@ -232,8 +324,27 @@ impl TilingState {
         best.map(|(idx, _)| idx)
     }
 
+    /// The canonical CellId of any word over the turtle alphabet.
+    pub fn canonical_cell(&self, word: &[u8]) -> CellId {
+        cell_id::canonicalize(word, &self.rules).id
+    }
+
+    /// Hyperbolic distance between two cell centres (see
+    /// [`cell_id::center_distance`]).
+    pub fn center_distance(&self, from: &CellId, to: &CellId) -> f64 {
+        cell_id::center_distance(from, to, &self.cfg, &self.rules)
+    }
+
+    /// The physical edge of tile `idx` (as indexed in `Tile::neighbors`) that
+    /// starts a shortest cell path to `to`. `None` when the tile is `to`.
+    pub fn first_edge_toward(&self, idx: usize, to: &CellId) -> Option<usize> {
+        let tile = &self.tiles[idx];
+        let edge = *cell_id::shortest_path(&tile.id, to, &self.rules).first()?;
+        let next = cell_id::neighbor(&tile.id, edge, &self.rules).id;
+        tile.neighbors.iter().position(|n| *n == next)
+    }
+
     /// Look up a tile by its CellId.
-    #[allow(dead_code)]
     pub fn find_tile(&self, id: &CellId) -> Option<usize> {
         self.id_to_tile.get(id).copied()
     }
@@ -241,6 +352,7 @@ impl TilingState {
     /// Find the CellId of the tile adjacent to `tile_idx` across physical edge `edge` (0..3).
     /// Returns None if the neighbor tile hasn't been expanded yet.
     /// Uses cached neighbor CellIds — no K-B reduction needed.
+    #[allow(dead_code)]
     pub fn neighbor_tile_id(&self, tile_idx: usize, edge: u8) -> Option<CellId> {
         let neighbor_id = &self.tiles[tile_idx].neighbors[edge as usize];
         if self.seen.contains(neighbor_id) {
@@ -252,41 +364,34 @@ impl TilingState {
 
     /// Recenter the tiling so that `center_idx` becomes the origin.
     /// CellIds are absolute — they don't change on recenter. Only Mobius transforms
-    /// are updated (recomputed from word_to_mobius to avoid holonomy drift).
-    /// Evicts tiles beyond EVICTION_RADIUS to prevent unbounded growth.
+    /// are updated, recomputed from words relative to the new center.
+    /// Evicts unpinned tiles beyond EVICTION_LAYERS to bound memory; the
+    /// frontier is rebuilt so evicted cells are expanded again on re-entry.
     /// Returns the new index of the center tile after compaction.
     pub fn recenter_on(&mut self, center_idx: usize) -> usize {
-        const EVICTION_RADIUS: f64 = 0.99;
-        let threshold_sq = EVICTION_RADIUS * EVICTION_RADIUS;
-
-        // Recompute view_offset from the center tile's canonical word.
-        // This avoids accumulating floating-point error across recenters.
-        let center_absolute = word_to_mobius(
-            self.tiles[center_idx].id.word(),
-            &self.neighbor_xforms,
-        );
-        self.view_offset = center_absolute.inverse();
+        self.view_word = self.tiles[center_idx].id.word().to_vec();
+        self.view_facing = self.tiles[center_idx].facing;
 
-        // Recompute all tile transforms from canonical words + view_offset.
+        // Recompute all tile transforms relative to the new center.
         // Then evict, compact, and rebuild indices in a single pass.
         self.id_to_tile.clear();
         self.spatial_to_tile.clear();
+        let mut near = Vec::with_capacity(self.tiles.len());
         let mut write = 0usize;
         let mut new_center_idx = 0usize;
         let mut best_dist_sq = f64::MAX;
-        let mut any_evicted = false;
 
         for read in 0..self.tiles.len() {
-            let absolute = word_to_mobius(self.tiles[read].id.word(), &self.neighbor_xforms);
-            self.tiles[read].transform = self.view_offset.compose(&absolute);
+            let tile = &self.tiles[read];
+            self.tiles[read].transform = self.view_transform(tile.id.word(), tile.facing);
             let center = self.tiles[read].transform.apply(Complex::ZERO);
-            let dist_sq = center.norm_sq();
+            let in_band = self.in_band(center);
 
-            if dist_sq > threshold_sq {
-                any_evicted = true;
+            if !in_band && !self.pinned.contains(&self.tiles[read].id) {
                 continue;
             }
 
+            let dist_sq = center.norm_sq();
             if dist_sq < best_dist_sq {
                 best_dist_sq = dist_sq;
                 new_center_idx = write;
@@ -294,7 +399,10 @@ impl TilingState {
 
             // Rebuild indices
             self.id_to_tile.insert(self.tiles[read].id.clone(), write);
-            self.spatial_to_tile.insert(spatial_key(center), write);
+            if in_band {
+                self.spatial_to_tile.insert(spatial_key(center), write);
+            }
+            near.push(in_band);
 
             if write != read {
                 self.tiles.swap(write, read);
@@ -303,20 +411,18 @@ impl TilingState {
         }
         self.tiles.truncate(write);
 
-        // Rebuild seen and frontier when tiles were actually evicted.
-        if any_evicted {
-            self.seen.clear();
-            for tile in &self.tiles {
-                self.seen.insert(tile.id.clone());
-            }
-
-            self.frontier.clear();
-            for (idx, tile) in self.tiles.iter().enumerate() {
-                // Use cached neighbor CellIds — no K-B reduction needed.
-                let is_boundary = tile.neighbors.iter().any(|n| !self.seen.contains(n));
-                if is_boundary {
-                    self.frontier.push_back(idx);
-                }
+        // Rebuild seen and frontier: evicted cells drop out of both, and
+        // pinned tiles coming back into range rejoin the frontier.
+        self.seen.clear();
+        for tile in &self.tiles {
+            self.seen.insert(tile.id.clone());
+        }
+        self.frontier.clear();
+        for (idx, tile) in self.tiles.iter().enumerate() {
+            // Use cached neighbor CellIds — no K-B reduction needed.
+            let is_boundary = tile.neighbors.iter().any(|n| !self.seen.contains(n));
+            if near[idx] && is_boundary {
+                self.frontier.push_back(idx);
             }
         }
 
@@ -359,6 +465,12 @@ mod tests {
         TilingConfig::new(4, 5)
     }
 
+    /// Absolute-coordinate view offset; only accurate near the origin.
+    fn view_offset(state: &TilingState) -> Mobius {
+        use super::super::cell_graph::word_to_mobius;
+        word_to_mobius(&state.view_word, &state.neighbor_xforms).inverse()
+    }
+
     #[test]
     fn test_origin_tile() {
         let state = TilingState::new(cfg45());
@@ -448,7 +560,7 @@ mod tests {
         let cfg = cfg45();
         let xforms = neighbor_transforms(&cfg);
         for v in 0..4u8 {
-            let dirs: Vec<usize> = (0..5).map(|i| ((v as usize + i) % 4) as usize).collect();
+            let dirs: Vec<usize> = (0..5).map(|i| (v as usize + i) % 4).collect();
             let mut product = Mobius::identity();
             for &d in &dirs {
                 product = product.compose(&xforms[0][d]);
@@ -613,12 +725,12 @@ mod tests {
         state.ensure_coverage(Complex::ZERO, 3);
         state.recenter_on(1);
 
-        // After recenter, stored = view_offset ∘ word_to_mobius(tile).
+        // After recenter, stored = M(center)⁻¹ ∘ word_to_mobius(tile).
         // Check via the test-point method.
         let test_pt = Complex::new(0.1, 0.05);
         let mut bad_count = 0;
         for tile in &state.tiles {
-            let expected = state.view_offset
+            let expected = view_offset(&state)
                 .compose(&word_to_mobius(tile.id.word(), &state.neighbor_xforms));
             let dist = (expected.apply(test_pt) - tile.transform.apply(test_pt)).abs();
             if dist > 1e-6 {
@@ -644,11 +756,11 @@ mod tests {
         state.recenter_on(idx);
         state.ensure_coverage(Complex::ZERO, 3);
 
-        // All tiles should match view_offset ∘ word_to_mobius.
+        // All tiles should match M(center)⁻¹ ∘ word_to_mobius.
         let test_pt = Complex::new(0.1, 0.05);
         let mut bad_count = 0;
         for tile in &state.tiles {
-            let expected = state.view_offset
+            let expected = view_offset(&state)
                 .compose(&word_to_mobius(tile.id.word(), &state.neighbor_xforms));
             let dist = (expected.apply(test_pt) - tile.transform.apply(test_pt)).abs();
             if dist > 1e-6 {
@@ -841,6 +953,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_first_edge_toward_origin() {
+        let mut state = TilingState::new(cfg45());
+        state.ensure_coverage(Complex::ZERO, 3);
+        let origin = CellId::origin();
+        for idx in 0..state.tiles.len() {
+            let depth = cell_id::distance_from_origin(&state.tiles[idx].id);
+            let Some(edge) = state.first_edge_toward(idx, &origin) else {
+                assert_eq!(depth, 0);
+                continue;
+            };
+            let next = &state.tiles[idx].neighbors[edge];
+            assert_eq!(cell_id::distance_from_origin(next), depth - 1, "{:?}", state.tiles[idx].id);
+        }
+    }
+
     #[test]
     fn test_neighbor_tile_id() {
         let mut state = TilingState::new(cfg45());
@@ -855,6 +983,198 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_neighbor_turns_match_transforms() {
+        for q in [5, 6] {
+            let cfg = TilingConfig::new(4, q);
+            let mut state = TilingState::new(cfg);
+            state.ensure_coverage(Complex::ZERO, 4);
+            let step = cfg.vertex_angle_step();
+            let probe = Complex::new(0.05, 0.02);
+            let mut turned = 0;
+            for tile in &state.tiles {
+                for edge in 0..4 {
+                    let Some(idx) = state.find_tile(&tile.neighbors[edge]) else { continue };
+                    let turns = tile.neighbor_turns[edge];
+                    let expected = tile.transform
+                        .compose(&state.neighbor_xforms[tile.parity as usize][edge])
+                        .compose(&Mobius::rotation(turns as f64 * step));
+                    let dist = (expected.apply(probe) - state.tiles[idx].transform.apply(probe)).abs();
+                    assert!(dist < 1e-9, "{{4,{q}}} {} edge {edge}: off by {dist}", tile.id);
+                    // The neighbor's edge back must undo the turn
+                    let back = (edge as u8 + 2 + 4 - turns) % 4;
+                    assert_eq!(state.tiles[idx].neighbors[back as usize], tile.id);
+                    assert_eq!((turns + state.tiles[idx].neighbor_turns[back as usize]) % 4, 0);
+                    turned += (turns != 0) as usize;
+                }
+            }
+            assert!(turned > 0, "{{4,{q}}}: expected some rotated neighbors");
+        }
+    }
+
+    #[test]
+    fn ensure_tile_matches_expanded_tile() {
+        let mut expanded = TilingState::new(cfg45());
+        expanded.ensure_coverage(Complex::ZERO, 3);
+        let far = expanded.tiles.iter().max_by_key(|t| t.id.len()).unwrap().clone();
+
+        let mut fresh = TilingState::new(cfg45());
+        let idx = fresh.ensure_tile(&far.id);
+        assert_eq!(fresh.ensure_tile(&far.id), idx, "second call should find the same tile");
+        let tile = &fresh.tiles[idx];
+        assert_eq!(tile.neighbors, far.neighbors);
+        assert_eq!(tile.parity, far.parity);
+        let (a, b) = (tile.transform.apply(Complex::ZERO), far.transform.apply(Complex::ZERO));
+        assert!((a.re - b.re).abs() < 1e-9 && (a.im - b.im).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_deep_walk_stays_exact_and_bounded() {
+        let mut state = TilingState::new(cfg45());
+        state.ensure_coverage(Complex::ZERO, 3);
+        let baseline = state.tiles.len();
+
+        // Walk in a straight line: always leave through the edge opposite the
+        // one we entered by.
+        let mut center = 0;
+        let mut edge = 0u8;
+        for step in 0..200 {
+            let next_id = state.tiles[center].neighbors[edge as usize].clone();
+            let from_id = state.tiles[center].id.clone();
+            let next_idx = state.find_tile(&next_id).expect("neighbor should be expanded");
+            center = state.recenter_on(next_idx);
+            state.ensure_coverage(Complex::ZERO, 3);
+            assert_eq!(state.tiles[center].id, next_id, "step {step}: lost the center tile");
+
+            let back = state.tiles[center].neighbors.iter().position(|n| *n == from_id).unwrap();
+            edge = (back as u8 + 2) % 4;
+
+            assert!(
+                state.tiles.len() <= 2 * baseline,
+                "step {step}: {} tiles (started with {baseline})",
+                state.tiles.len(),
+            );
+            let c = state.tiles[center].transform.apply(Complex::ZERO);
+            assert!(c.abs() < 1e-9, "step {step}: center drifted to |z| = {}", c.abs());
+        }
+
+        assert!(state.tiles[center].id.len() >= 100, "walk should end deep: {}", state.tiles[center].id);
+        // Neighbors still sit exactly one edge away.
+        let step = center_to_center_distance(&state.cfg);
+        for n_id in &state.tiles[center].neighbors {
+            let n = &state.tiles[state.find_tile(n_id).unwrap()];
+            let d = poincare_distance(Complex::ZERO, n.transform.apply(Complex::ZERO));
+            assert!((d - step).abs() < 1e-9, "neighbor at distance {d}, expected {step}");
+        }
+    }
+
+    /// Fly `steps` cells in a straight line from the view centre, leaving
+    /// each cell through the edge opposite the one we entered by. Returns the
+    /// final centre and the edge that leads back the way we came.
+    fn fly_straight(state: &mut TilingState, mut center: usize, mut edge: u8, steps: usize) -> (usize, u8) {
+        for _ in 0..steps {
+            let from_id = state.tiles[center].id.clone();
+            let next_id = state.tiles[center].neighbors[edge as usize].clone();
+            center = state.recenter_on(state.find_tile(&next_id).unwrap());
+            state.ensure_coverage(Complex::ZERO, 3);
+            let back = state.tiles[center].neighbors.iter().position(|n| *n == from_id).unwrap();
+            edge = (back as u8 + 2) % 4;
+            assert_indices_consistent(state);
+        }
+        (center, (edge + 2) % 4)
+    }
+
+    fn assert_indices_consistent(state: &TilingState) {
+        assert_eq!(state.id_to_tile.len(), state.tiles.len());
+        assert_eq!(state.seen.len(), state.tiles.len());
+        for (idx, tile) in state.tiles.iter().enumerate() {
+            assert_eq!(state.find_tile(&tile.id), Some(idx));
+        }
+        for (&key, &idx) in &state.spatial_to_tile {
+            assert_eq!(spatial_key(state.tiles[idx].transform.apply(Complex::ZERO)), key);
+        }
+        let frontier: HashSet<usize> = state.frontier.iter().copied().collect();
+        assert_eq!(frontier.len(), state.frontier.len(), "duplicate frontier entries");
+        assert!(frontier.iter().all(|&idx| idx < state.tiles.len()));
+    }
+
+    #[test]
+    fn test_long_flight_evicts_and_reenters() {
+        for q in 5..=8 {
+            let mut state = TilingState::new(TilingConfig::new(4, q));
+            state.ensure_coverage(Complex::ZERO, 3);
+            let home: Vec<(CellId, Complex)> =
+                state.tiles.iter().map(|t| (t.id.clone(), t.transform.apply(Complex::ZERO))).collect();
+
+            let (center, back_edge) = fly_straight(&mut state, 0, 0, 40);
+            assert!(state.tiles.len() < MAX_TILES / 2, "{{4,{q}}}: {} tiles", state.tiles.len());
+            assert!(state.find_tile(&CellId::origin()).is_none(), "{{4,{q}}}: origin never evicted");
+
+            // Turn round and fly home: the cells around the origin are rebuilt
+            fly_straight(&mut state, center, back_edge, 40);
+            let origin = state.find_tile(&CellId::origin()).expect("origin rebuilt on re-entry");
+            let origin_center = state.tiles[origin].transform.apply(Complex::ZERO);
+            assert!(origin_center.abs() < 1e-9, "{{4,{q}}}: back at |z| = {}", origin_center.abs());
+            let inv = state.tiles[origin].transform.inverse();
+            for (id, c) in &home {
+                let idx = state.find_tile(id).unwrap_or_else(|| panic!("{{4,{q}}}: {id} not rebuilt"));
+                let z = inv.compose(&state.tiles[idx].transform).apply(Complex::ZERO);
+                assert!((z - *c).abs() < 1e-9, "{{4,{q}}}: {id} rebuilt off by {}", (z - *c).abs());
+            }
+        }
+    }
+
+    #[test]
+    fn test_pinned_cell_survives_eviction() {
+        let mut state = TilingState::new(cfg45());
+        state.ensure_coverage(Complex::ZERO, 3);
+        let pinned = state.tiles[0].neighbors[2].clone();
+        state.pin(&pinned);
+
+        let (center, _) = fly_straight(&mut state, 0, 0, 30);
+        let idx = state.find_tile(&pinned).expect("pinned cell evicted");
+        assert!(!state.spatial_to_tile.values().any(|&i| i == idx), "far pinned tile indexed spatially");
+        assert!(!state.frontier.contains(&idx), "far pinned tile left on the frontier");
+        assert!(state.find_tile(&CellId::origin()).is_none());
+
+        state.unpin(&pinned);
+        let center_id = state.tiles[center].id.clone();
+        let center = state.recenter_on(center);
+        assert!(state.find_tile(&pinned).is_none(), "unpinned cell kept");
+        assert_eq!(state.tiles[center].id, center_id);
+    }
+
+    #[test]
+    fn test_higher_q_tilings() {
+        for q in [6, 7] {
+            let cfg = TilingConfig::new(4, q);
+            let step = center_to_center_distance(&cfg);
+            let mut state = TilingState::new(cfg);
+            state.ensure_coverage(Complex::ZERO, 3);
+            assert!(state.tiles.len() > 20, "{{4,{q}}}: too few tiles");
+
+            let mut ids = HashSet::new();
+            let mut keys = HashSet::new();
+            for tile in &state.tiles {
+                let c = tile.transform.apply(Complex::ZERO);
+                assert!(ids.insert(tile.id.clone()), "{{4,{q}}}: duplicate {:?}", tile.id);
+                assert!(keys.insert(spatial_key(c)), "{{4,{q}}}: two tiles at {c:?}");
+            }
+
+            // Every cached neighbor that exists links back and sits one edge away.
+            for tile in &state.tiles {
+                let c = tile.transform.apply(Complex::ZERO);
+                for n_id in &tile.neighbors {
+                    let Some(n_idx) = state.find_tile(n_id) else { continue };
+                    let n = &state.tiles[n_idx];
+                    assert!(n.neighbors.contains(&tile.id), "{{4,{q}}}: asymmetric neighbors");
+                    let d = poincare_distance(c, n.transform.apply(Complex::ZERO));
+                    assert!((d - step).abs() < 1e-6, "{{4,{q}}}: neighbor at distance {d}");
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_format_address() {
         assert_eq!(format_address(&[]), "O");
@@ -1,28 +1,46 @@
 mod app;
 mod game;
 mod hyperbolic;
+mod net;
 mod render;
 mod sim;
 mod ui;
 
 use app::App;
+use game::config::GameConfig;
 use hyperbolic::poincare::TilingConfig;
+use net::NetMode;
 use winit::event_loop::EventLoop;
 
 fn main() {
     env_logger::init();
 
-    let args: Vec<String> = std::env::args().collect();
-    let (p, q) = if args.len() >= 3 {
+    // octofact [p q] [--host <port> | --join <addr>]
+    let mut positional = Vec::new();
+    let mut net = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--host" => {
+                let port = args.next().expect("--host needs a port");
+                net = Some(NetMode::Host { port: port.parse().expect("port must be a number") });
+            }
+            "--join" => {
+                net = Some(NetMode::Join { addr: args.next().expect("--join needs an address") });
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let (p, q) = if positional.len() >= 2 {
         (
-            args[1].parse().expect("p must be a positive integer"),
-            args[2].parse().expect("q must be a positive integer"),
+            positional[0].parse().expect("p must be a positive integer"),
+            positional[1].parse().expect("q must be a positive integer"),
         )
     } else {
-        (4, 5)
+        (4, GameConfig::load().gameplay.tiling_n.max(5))
     };
 
     let event_loop = EventLoop::new().expect("failed to create event loop");
-    let mut app = App::new(TilingConfig::new(p, q));
+    let mut app = App::new(TilingConfig::new(p, q), net);
     event_loop.run_app(&mut app).expect("event loop error");
 }
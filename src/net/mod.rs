@@ -0,0 +1,21 @@
+//! Lockstep multiplayer: peers exchange player actions, never simulation
+//! state, and apply them on the same tick.
+//!
+//! Every edit that touches the simulation travels as an action: building,
+//! wiring, and the settings made in the inspection panels (recipes, port
+//! layouts, filters, loader and station modes, schedules). Only view state
+//! such as the camera and open panels stays local.
+//!
+//! To try it, run `octofact --host 7777` and `octofact --join 127.0.0.1:7777`.
+
+pub mod protocol;
+pub mod session;
+pub mod snapshot;
+
+/// How this instance takes part in a session, from the command line.
+pub enum NetMode {
+    /// `--host <port>`: own the session and listen for players.
+    Host { port: u16 },
+    /// `--join <addr>`: connect to a host, e.g. `127.0.0.1:7777`.
+    Join { addr: String },
+}
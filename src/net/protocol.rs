@@ -0,0 +1,857 @@
+//! Wire format for lockstep multiplayer.
+//!
+//! Every message is a little-endian body behind a `u32` length prefix. The
+//! encoding is hand-rolled rather than derived so it stays stable and
+//! compact: a frame with no commands, sent every tick, is fourteen bytes.
+
+use slotmap::{Key, KeyData};
+
+use crate::game::items::ItemId;
+use crate::game::world::{Direction, EntityId};
+use crate::hyperbolic::rewrite;
+use crate::hyperbolic::tiling::TileAddr;
+use crate::sim::circuit::{ArithOp, CombinatorConfig, Comparator, Condition, Operand, Terminal, WireEnd};
+use crate::sim::loader::LoaderMode;
+use crate::sim::rail::{StationMode, TrainId};
+use crate::sim::storage::StorageOutputMode;
+
+/// Peer index within a session. The host is always player 0.
+pub type PlayerId = u8;
+
+pub const HOST_PLAYER: PlayerId = 0;
+
+/// Bumped whenever the encoding below changes.
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// Largest body accepted from a peer. A welcome carries a snapshot of the
+/// whole simulation, so this is generous.
+pub const MAX_MESSAGE_LEN: usize = 64 << 20;
+
+/// An edit to the shared world. Positions are tile addresses and grid cells
+/// rather than tile indices, which depend on each peer's camera. Entity keys
+/// agree on every peer because every peer applies the same placements in the
+/// same order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlayerAction {
+    Place {
+        address: TileAddr,
+        grid_xy: (i32, i32),
+        item: ItemId,
+        direction: Direction,
+    },
+    Remove {
+        address: TileAddr,
+        grid_xy: (i32, i32),
+    },
+    Rotate {
+        address: TileAddr,
+        grid_xy: (i32, i32),
+    },
+    SetRecipe {
+        machine: EntityId,
+        recipe: Option<usize>,
+    },
+    /// Run a signal wire between two terminals.
+    Wire {
+        from: WireEnd,
+        to: WireEnd,
+    },
+    /// Set or clear the enable condition on a machine, splitter or belt.
+    SetCondition {
+        entity: EntityId,
+        condition: Option<Condition>,
+    },
+    SetCombinator {
+        combinator: EntityId,
+        config: CombinatorConfig,
+    },
+    SetSplitterFilter {
+        splitter: EntityId,
+        belt: EntityId,
+        filter: Option<ItemId>,
+    },
+    SetSplitterPriority {
+        splitter: EntityId,
+        belt: EntityId,
+        priority: bool,
+    },
+    SetWhitelist {
+        storage: EntityId,
+        items: Vec<ItemId>,
+    },
+    SetSlotLock {
+        storage: EntityId,
+        slot: usize,
+        lock: Option<ItemId>,
+    },
+    SetOutputMode {
+        storage: EntityId,
+        port: usize,
+        mode: StorageOutputMode,
+    },
+    SetSinkFilter {
+        sink: EntityId,
+        filter: Option<ItemId>,
+    },
+    ResetSinkCounts {
+        sink: EntityId,
+    },
+    /// Carries the mode to switch to rather than a toggle, so two players
+    /// flipping the same loader in one frame agree on the outcome.
+    SetLoaderMode {
+        loader: EntityId,
+        mode: LoaderMode,
+    },
+    SetLoaderFilter {
+        loader: EntityId,
+        filter: Option<ItemId>,
+    },
+    /// Move one of a machine's ports (by index into its layout) to a new
+    /// canonical cell and side.
+    MovePort {
+        machine: EntityId,
+        port: usize,
+        cell_offset: (i32, i32),
+        side: Direction,
+    },
+    ResetPorts {
+        machine: EntityId,
+    },
+    SetStationMode {
+        station: EntityId,
+        mode: StationMode,
+    },
+    AddStop {
+        train: TrainId,
+        station: EntityId,
+    },
+    RemoveStop {
+        train: TrainId,
+        index: usize,
+    },
+    SetTrainRunning {
+        train: TrainId,
+        running: bool,
+    },
+    SetSensor {
+        belt: EntityId,
+        on: bool,
+    },
+}
+
+/// An action and the player who made it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Command {
+    pub player: PlayerId,
+    pub action: PlayerAction,
+}
+
+/// The commands every peer applies before simulating `tick`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    pub tick: u64,
+    pub commands: Vec<Command>,
+}
+
+/// The grid cell a player is pointing at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cursor {
+    pub address: TileAddr,
+    pub grid_xy: (i32, i32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    /// Client → host, first message on a connection.
+    Hello { version: u32 },
+    /// Host → client. `q` is the host's tiling ({4,q}); cell addresses
+    /// only mean the same thing to peers that agree on it. `snapshot` is
+    /// the host's simulation after `tick` (see `net::snapshot`); frames
+    /// from the next tick on follow.
+    Welcome {
+        player: PlayerId,
+        q: u32,
+        free_placement: bool,
+        tick: u64,
+        snapshot: Vec<u8>,
+    },
+    /// Client → host: schedule an action in the next frame.
+    Submit(PlayerAction),
+    /// Host → clients, once per tick.
+    Frame(Frame),
+    /// Client → host: state hash after simulating `tick`.
+    Checksum { tick: u64, hash: u64 },
+    /// Host → clients: `player` disagreed with the host at `tick`.
+    Desync { tick: u64, player: PlayerId },
+    /// Any direction; the host relays clients' cursors to everyone else.
+    Cursor { player: PlayerId, cursor: Cursor },
+    /// Host → clients: `player` disconnected.
+    Left { player: PlayerId },
+}
+
+impl Message {
+    /// Encode with the length prefix.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = Writer { buf: vec![0; 4] };
+        match self {
+            Message::Hello { version } => {
+                w.u8(0);
+                w.u32(*version);
+            }
+            Message::Welcome { player, q, free_placement, tick, snapshot } => {
+                w.u8(1);
+                w.u8(*player);
+                w.u32(*q);
+                w.u8(*free_placement as u8);
+                w.u64(*tick);
+                w.u32(snapshot.len() as u32);
+                w.buf.extend_from_slice(snapshot);
+            }
+            Message::Submit(action) => {
+                w.u8(2);
+                w.action(action);
+            }
+            Message::Frame(frame) => {
+                w.u8(3);
+                w.frame(frame);
+            }
+            Message::Checksum { tick, hash } => {
+                w.u8(4);
+                w.u64(*tick);
+                w.u64(*hash);
+            }
+            Message::Desync { tick, player } => {
+                w.u8(5);
+                w.u64(*tick);
+                w.u8(*player);
+            }
+            Message::Cursor { player, cursor } => {
+                w.u8(6);
+                w.u8(*player);
+                w.address(&cursor.address);
+                w.grid(cursor.grid_xy);
+            }
+            Message::Left { player } => {
+                w.u8(7);
+                w.u8(*player);
+            }
+        }
+        let len = (w.buf.len() - 4) as u32;
+        w.buf[..4].copy_from_slice(&len.to_le_bytes());
+        w.buf
+    }
+
+    /// Decode one body (without its length prefix). None if it is malformed
+    /// or has trailing bytes.
+    pub fn decode(body: &[u8]) -> Option<Message> {
+        let mut r = Reader { buf: body };
+        let msg = match r.u8()? {
+            0 => Message::Hello { version: r.u32()? },
+            1 => {
+                let player = r.u8()?;
+                let q = r.u32()?;
+                let free_placement = r.bool()?;
+                let tick = r.u64()?;
+                let len = r.u32()? as usize;
+                if r.buf.len() < len {
+                    return None;
+                }
+                let (snapshot, rest) = r.buf.split_at(len);
+                r.buf = rest;
+                Message::Welcome { player, q, free_placement, tick, snapshot: snapshot.to_vec() }
+            }
+            2 => Message::Submit(r.action()?),
+            3 => Message::Frame(r.frame()?),
+            4 => Message::Checksum { tick: r.u64()?, hash: r.u64()? },
+            5 => Message::Desync { tick: r.u64()?, player: r.u8()? },
+            6 => {
+                let player = r.u8()?;
+                let address = r.address()?;
+                let grid_xy = r.grid()?;
+                Message::Cursor { player, cursor: Cursor { address, grid_xy } }
+            }
+            7 => Message::Left { player: r.u8()? },
+            _ => return None,
+        };
+        r.buf.is_empty().then_some(msg)
+    }
+}
+
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn entity(&mut self, entity: EntityId) {
+        self.u64(entity.data().as_ffi());
+    }
+
+    /// Item ids fit in a byte; u8::MAX stands for "none".
+    fn option_item(&mut self, item: Option<ItemId>) {
+        self.u8(item.map_or(u8::MAX, |i| i as u8));
+    }
+
+    fn wire_end(&mut self, end: WireEnd) {
+        self.entity(end.entity);
+        self.u8(end.terminal as u8);
+    }
+
+    fn operand(&mut self, operand: Operand) {
+        match operand {
+            Operand::Constant(n) => {
+                self.u8(0);
+                self.buf.extend_from_slice(&n.to_le_bytes());
+            }
+            Operand::Signal(item) => {
+                self.u8(1);
+                self.u8(item as u8);
+            }
+        }
+    }
+
+    fn condition(&mut self, condition: Condition) {
+        self.u8(condition.left as u8);
+        self.u8(condition.cmp as u8);
+        self.operand(condition.right);
+    }
+
+    fn grid(&mut self, (gx, gy): (i32, i32)) {
+        self.buf.extend_from_slice(&gx.to_le_bytes());
+        self.buf.extend_from_slice(&gy.to_le_bytes());
+    }
+
+    fn address(&mut self, address: &[u8]) {
+        self.u8(address.len() as u8);
+        self.buf.extend_from_slice(address);
+    }
+
+    fn action(&mut self, action: &PlayerAction) {
+        match action {
+            PlayerAction::Place { address, grid_xy, item, direction } => {
+                self.u8(0);
+                self.address(address);
+                self.grid(*grid_xy);
+                self.u8(*item as u8);
+                self.u8(*direction as u8);
+            }
+            PlayerAction::Remove { address, grid_xy } => {
+                self.u8(1);
+                self.address(address);
+                self.grid(*grid_xy);
+            }
+            PlayerAction::Rotate { address, grid_xy } => {
+                self.u8(2);
+                self.address(address);
+                self.grid(*grid_xy);
+            }
+            PlayerAction::SetRecipe { machine, recipe } => {
+                self.u8(3);
+                self.entity(*machine);
+                // Recipe indices are small; u32::MAX stands for "none"
+                self.u32(recipe.map_or(u32::MAX, |r| r as u32));
+            }
+            PlayerAction::Wire { from, to } => {
+                self.u8(4);
+                self.wire_end(*from);
+                self.wire_end(*to);
+            }
+            PlayerAction::SetCondition { entity, condition } => {
+                self.u8(5);
+                self.entity(*entity);
+                match condition {
+                    Some(condition) => {
+                        self.u8(1);
+                        self.condition(*condition);
+                    }
+                    None => self.u8(0),
+                }
+            }
+            PlayerAction::SetCombinator { combinator, config } => {
+                self.u8(6);
+                self.entity(*combinator);
+                match *config {
+                    CombinatorConfig::Arithmetic { left, op, right, output } => {
+                        self.u8(0);
+                        self.u8(left as u8);
+                        self.u8(op as u8);
+                        self.operand(right);
+                        self.u8(output as u8);
+                    }
+                    CombinatorConfig::Decider { condition, output, copy_input } => {
+                        self.u8(1);
+                        self.condition(condition);
+                        self.u8(output as u8);
+                        self.u8(copy_input as u8);
+                    }
+                }
+            }
+            PlayerAction::SetSplitterFilter { splitter, belt, filter } => {
+                self.u8(7);
+                self.entity(*splitter);
+                self.entity(*belt);
+                self.option_item(*filter);
+            }
+            PlayerAction::SetSplitterPriority { splitter, belt, priority } => {
+                self.u8(8);
+                self.entity(*splitter);
+                self.entity(*belt);
+                self.u8(*priority as u8);
+            }
+            PlayerAction::SetWhitelist { storage, items } => {
+                self.u8(9);
+                self.entity(*storage);
+                self.u32(items.len() as u32);
+                for &item in items {
+                    self.u8(item as u8);
+                }
+            }
+            PlayerAction::SetSlotLock { storage, slot, lock } => {
+                self.u8(10);
+                self.entity(*storage);
+                self.u32(*slot as u32);
+                self.option_item(*lock);
+            }
+            PlayerAction::SetOutputMode { storage, port, mode } => {
+                self.u8(11);
+                self.entity(*storage);
+                self.u32(*port as u32);
+                self.option_item(mode.filter);
+                self.u16(mode.keep);
+            }
+            PlayerAction::SetSinkFilter { sink, filter } => {
+                self.u8(12);
+                self.entity(*sink);
+                self.option_item(*filter);
+            }
+            PlayerAction::ResetSinkCounts { sink } => {
+                self.u8(13);
+                self.entity(*sink);
+            }
+            PlayerAction::SetLoaderMode { loader, mode } => {
+                self.u8(14);
+                self.entity(*loader);
+                self.u8(*mode as u8);
+            }
+            PlayerAction::SetLoaderFilter { loader, filter } => {
+                self.u8(15);
+                self.entity(*loader);
+                self.option_item(*filter);
+            }
+            PlayerAction::MovePort { machine, port, cell_offset, side } => {
+                self.u8(16);
+                self.entity(*machine);
+                self.u32(*port as u32);
+                self.grid(*cell_offset);
+                self.u8(*side as u8);
+            }
+            PlayerAction::ResetPorts { machine } => {
+                self.u8(17);
+                self.entity(*machine);
+            }
+            PlayerAction::SetStationMode { station, mode } => {
+                self.u8(18);
+                self.entity(*station);
+                self.u8(*mode as u8);
+            }
+            PlayerAction::AddStop { train, station } => {
+                self.u8(19);
+                self.u32(train.0);
+                self.entity(*station);
+            }
+            PlayerAction::RemoveStop { train, index } => {
+                self.u8(20);
+                self.u32(train.0);
+                self.u32(*index as u32);
+            }
+            PlayerAction::SetTrainRunning { train, running } => {
+                self.u8(21);
+                self.u32(train.0);
+                self.u8(*running as u8);
+            }
+            PlayerAction::SetSensor { belt, on } => {
+                self.u8(22);
+                self.entity(*belt);
+                self.u8(*on as u8);
+            }
+        }
+    }
+
+    fn frame(&mut self, frame: &Frame) {
+        self.u64(frame.tick);
+        self.u8(frame.commands.len() as u8);
+        for command in &frame.commands {
+            self.u8(command.player);
+            self.action(&command.action);
+        }
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, rest) = self.buf.split_first_chunk::<N>()?;
+        self.buf = rest;
+        Some(*head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|[b]| b)
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        match self.u8()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    fn entity(&mut self) -> Option<EntityId> {
+        Some(KeyData::from_ffi(self.u64()?).into())
+    }
+
+    fn item(&mut self) -> Option<ItemId> {
+        ItemId::all().get(self.u8()? as usize).copied()
+    }
+
+    fn option_item(&mut self) -> Option<Option<ItemId>> {
+        match self.u8()? {
+            u8::MAX => Some(None),
+            i => Some(Some(*ItemId::all().get(i as usize)?)),
+        }
+    }
+
+    fn direction(&mut self) -> Option<Direction> {
+        [Direction::North, Direction::East, Direction::South, Direction::West]
+            .get(self.u8()? as usize)
+            .copied()
+    }
+
+    fn wire_end(&mut self) -> Option<WireEnd> {
+        let entity = self.entity()?;
+        let terminal = match self.u8()? {
+            0 => Terminal::Main,
+            1 => Terminal::Output,
+            _ => return None,
+        };
+        Some(WireEnd { entity, terminal })
+    }
+
+    fn operand(&mut self) -> Option<Operand> {
+        Some(match self.u8()? {
+            0 => Operand::Constant(i32::from_le_bytes(self.take()?)),
+            1 => Operand::Signal(self.item()?),
+            _ => return None,
+        })
+    }
+
+    fn condition(&mut self) -> Option<Condition> {
+        Some(Condition {
+            left: self.item()?,
+            cmp: *Comparator::ALL.get(self.u8()? as usize)?,
+            right: self.operand()?,
+        })
+    }
+
+    fn grid(&mut self) -> Option<(i32, i32)> {
+        Some((i32::from_le_bytes(self.take()?), i32::from_le_bytes(self.take()?)))
+    }
+
+    /// A tile address; None unless every byte is a turtle letter, since
+    /// anything else would trip up the rewrite engine.
+    fn address(&mut self) -> Option<TileAddr> {
+        let len = self.u8()? as usize;
+        if self.buf.len() < len {
+            return None;
+        }
+        let (head, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        rewrite::is_valid_word(head).then(|| TileAddr::from_slice(head))
+    }
+
+    fn action(&mut self) -> Option<PlayerAction> {
+        Some(match self.u8()? {
+            0 => PlayerAction::Place {
+                address: self.address()?,
+                grid_xy: self.grid()?,
+                item: self.item()?,
+                direction: self.direction()?,
+            },
+            1 => PlayerAction::Remove { address: self.address()?, grid_xy: self.grid()? },
+            2 => PlayerAction::Rotate { address: self.address()?, grid_xy: self.grid()? },
+            3 => PlayerAction::SetRecipe {
+                machine: self.entity()?,
+                recipe: match self.u32()? {
+                    u32::MAX => None,
+                    r => Some(r as usize),
+                },
+            },
+            4 => PlayerAction::Wire { from: self.wire_end()?, to: self.wire_end()? },
+            5 => PlayerAction::SetCondition {
+                entity: self.entity()?,
+                condition: match self.u8()? {
+                    0 => None,
+                    1 => Some(self.condition()?),
+                    _ => return None,
+                },
+            },
+            6 => PlayerAction::SetCombinator {
+                combinator: self.entity()?,
+                config: match self.u8()? {
+                    0 => CombinatorConfig::Arithmetic {
+                        left: self.item()?,
+                        op: *ArithOp::ALL.get(self.u8()? as usize)?,
+                        right: self.operand()?,
+                        output: self.item()?,
+                    },
+                    1 => CombinatorConfig::Decider {
+                        condition: self.condition()?,
+                        output: self.item()?,
+                        copy_input: self.bool()?,
+                    },
+                    _ => return None,
+                },
+            },
+            7 => PlayerAction::SetSplitterFilter {
+                splitter: self.entity()?,
+                belt: self.entity()?,
+                filter: self.option_item()?,
+            },
+            8 => PlayerAction::SetSplitterPriority {
+                splitter: self.entity()?,
+                belt: self.entity()?,
+                priority: self.bool()?,
+            },
+            9 => {
+                let storage = self.entity()?;
+                let count = self.u32()? as usize;
+                let mut items = Vec::with_capacity(count.min(self.buf.len()));
+                for _ in 0..count {
+                    items.push(self.item()?);
+                }
+                PlayerAction::SetWhitelist { storage, items }
+            }
+            10 => PlayerAction::SetSlotLock {
+                storage: self.entity()?,
+                slot: self.u32()? as usize,
+                lock: self.option_item()?,
+            },
+            11 => PlayerAction::SetOutputMode {
+                storage: self.entity()?,
+                port: self.u32()? as usize,
+                mode: StorageOutputMode { filter: self.option_item()?, keep: self.u16()? },
+            },
+            12 => PlayerAction::SetSinkFilter { sink: self.entity()?, filter: self.option_item()? },
+            13 => PlayerAction::ResetSinkCounts { sink: self.entity()? },
+            14 => PlayerAction::SetLoaderMode {
+                loader: self.entity()?,
+                mode: match self.u8()? {
+                    0 => LoaderMode::Load,
+                    1 => LoaderMode::Unload,
+                    _ => return None,
+                },
+            },
+            15 => PlayerAction::SetLoaderFilter { loader: self.entity()?, filter: self.option_item()? },
+            16 => PlayerAction::MovePort {
+                machine: self.entity()?,
+                port: self.u32()? as usize,
+                cell_offset: self.grid()?,
+                side: self.direction()?,
+            },
+            17 => PlayerAction::ResetPorts { machine: self.entity()? },
+            18 => PlayerAction::SetStationMode {
+                station: self.entity()?,
+                mode: match self.u8()? {
+                    0 => StationMode::Load,
+                    1 => StationMode::Unload,
+                    _ => return None,
+                },
+            },
+            19 => PlayerAction::AddStop { train: TrainId(self.u32()?), station: self.entity()? },
+            20 => PlayerAction::RemoveStop { train: TrainId(self.u32()?), index: self.u32()? as usize },
+            21 => PlayerAction::SetTrainRunning { train: TrainId(self.u32()?), running: self.bool()? },
+            22 => PlayerAction::SetSensor { belt: self.entity()?, on: self.bool()? },
+            _ => return None,
+        })
+    }
+
+    fn frame(&mut self) -> Option<Frame> {
+        let tick = self.u64()?;
+        let count = self.u8()? as usize;
+        let mut commands = Vec::with_capacity(count);
+        for _ in 0..count {
+            let player = self.u8()?;
+            commands.push(Command { player, action: self.action()? });
+        }
+        Some(Frame { tick, commands })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slotmap::SlotMap;
+
+    fn round_trip(msg: Message) {
+        let bytes = msg.encode();
+        let len = u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize;
+        assert_eq!(len, bytes.len() - 4);
+        assert_eq!(Message::decode(&bytes[4..]), Some(msg));
+    }
+
+    fn sample_actions() -> Vec<PlayerAction> {
+        let mut sm: SlotMap<EntityId, ()> = SlotMap::with_key();
+        let machine = sm.insert(());
+        let other = sm.insert(());
+        let condition = Condition { left: ItemId::Point, cmp: Comparator::GreaterEq, right: Operand::Constant(-500) };
+        vec![
+            PlayerAction::Place {
+                address: TileAddr::from_slice(&[0, 2, 1]),
+                grid_xy: (-32, 17),
+                item: ItemId::DeciderCombinator,
+                direction: Direction::West,
+            },
+            PlayerAction::Remove { address: TileAddr::new(), grid_xy: (0, 0) },
+            PlayerAction::Rotate { address: TileAddr::from_slice(&[2]), grid_xy: (5, -5) },
+            PlayerAction::SetRecipe { machine, recipe: Some(7) },
+            PlayerAction::SetRecipe { machine, recipe: None },
+            PlayerAction::Wire { from: WireEnd::output(machine), to: WireEnd::main(other) },
+            PlayerAction::SetCondition { entity: machine, condition: Some(condition) },
+            PlayerAction::SetCondition { entity: machine, condition: None },
+            PlayerAction::SetCombinator {
+                combinator: other,
+                config: CombinatorConfig::Arithmetic {
+                    left: ItemId::Wavelet,
+                    op: ArithOp::Rem,
+                    right: Operand::Signal(ItemId::Point),
+                    output: ItemId::NullSet,
+                },
+            },
+            PlayerAction::SetCombinator {
+                combinator: other,
+                config: CombinatorConfig::Decider { condition, output: ItemId::Preimage, copy_input: true },
+            },
+            PlayerAction::SetSplitterFilter { splitter: machine, belt: other, filter: Some(ItemId::Point) },
+            PlayerAction::SetSplitterFilter { splitter: machine, belt: other, filter: None },
+            PlayerAction::SetSplitterPriority { splitter: machine, belt: other, priority: true },
+            PlayerAction::SetWhitelist { storage: other, items: vec![ItemId::Point, ItemId::Wavelet] },
+            PlayerAction::SetSlotLock { storage: other, slot: 11, lock: Some(ItemId::LineSegment) },
+            PlayerAction::SetOutputMode {
+                storage: other,
+                port: 1,
+                mode: StorageOutputMode { filter: Some(ItemId::Point), keep: 300 },
+            },
+            PlayerAction::SetSinkFilter { sink: other, filter: None },
+            PlayerAction::ResetSinkCounts { sink: other },
+            PlayerAction::SetLoaderMode { loader: other, mode: LoaderMode::Unload },
+            PlayerAction::SetLoaderFilter { loader: other, filter: Some(ItemId::ExactSequence) },
+            PlayerAction::MovePort { machine, port: 2, cell_offset: (2, 1), side: Direction::South },
+            PlayerAction::ResetPorts { machine },
+            PlayerAction::SetStationMode { station: other, mode: StationMode::Unload },
+            PlayerAction::AddStop { train: TrainId(4), station: other },
+            PlayerAction::RemoveStop { train: TrainId(4), index: 3 },
+            PlayerAction::SetTrainRunning { train: TrainId(9), running: true },
+            PlayerAction::SetSensor { belt: machine, on: false },
+        ]
+    }
+
+    #[test]
+    fn every_message_round_trips() {
+        let commands: Vec<Command> = sample_actions()
+            .into_iter()
+            .enumerate()
+            .map(|(i, action)| Command { player: i as PlayerId, action })
+            .collect();
+        let frame = Frame { tick: 1234, commands };
+        round_trip(Message::Hello { version: PROTOCOL_VERSION });
+        round_trip(Message::Welcome {
+            player: 3,
+            q: 5,
+            free_placement: true,
+            tick: 99_999,
+            snapshot: vec![0, 1, 2, 255],
+        });
+        for action in sample_actions() {
+            round_trip(Message::Submit(action));
+        }
+        round_trip(Message::Frame(frame));
+        round_trip(Message::Checksum { tick: 600, hash: 0xdead_beef_0123_4567 });
+        round_trip(Message::Desync { tick: 1200, player: 2 });
+        round_trip(Message::Cursor {
+            player: 1,
+            cursor: Cursor { address: TileAddr::from_slice(&[1, 1]), grid_xy: (3, -9) },
+        });
+        round_trip(Message::Left { player: 4 });
+    }
+
+    #[test]
+    fn empty_frame_is_small() {
+        let bytes = Message::Frame(Frame { tick: 1, commands: Vec::new() }).encode();
+        assert_eq!(bytes.len(), 14);
+    }
+
+    #[test]
+    fn malformed_bodies_are_rejected() {
+        let bytes = Message::Frame(Frame {
+            tick: 9,
+            commands: vec![Command { player: 0, action: sample_actions().remove(0) }],
+        })
+        .encode();
+        let body = &bytes[4..];
+        for cut in 0..body.len() {
+            assert_eq!(Message::decode(&body[..cut]), None, "truncated at {cut}");
+        }
+        let mut trailing = body.to_vec();
+        trailing.push(0);
+        assert_eq!(Message::decode(&trailing), None);
+        assert_eq!(Message::decode(&[200]), None);
+    }
+
+    #[test]
+    fn addresses_with_bad_letters_are_rejected() {
+        let cursor = |address: &[u8]| Message::Cursor {
+            player: 1,
+            cursor: Cursor { address: TileAddr::from_slice(address), grid_xy: (0, 0) },
+        };
+        round_trip(cursor(&[0, 1, 2]));
+        for bad in [&[3][..], &[0, 1, 7], &[255, 0]] {
+            let bytes = cursor(bad).encode();
+            assert_eq!(Message::decode(&bytes[4..]), None, "{bad:?}");
+        }
+        let mut place = Message::Submit(sample_actions().remove(0)).encode();
+        // Body: tag, action tag, address length, then the letters
+        place[4 + 3] = b'a';
+        assert_eq!(Message::decode(&place[4..]), None);
+    }
+}
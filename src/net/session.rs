@@ -0,0 +1,605 @@
+//! Host and client ends of a lockstep session over TCP.
+//!
+//! The host owns the clock. Each tick it gathers the actions submitted
+//! since the last one — its own and those its clients sent — into a
+//! `Frame`, applies it, and broadcasts it. Clients apply the same frames in
+//! the same order and may only simulate a tick once its frame has arrived,
+//! so every peer runs the identical command stream and, the simulation
+//! being deterministic, reaches the identical state. Clients report a state
+//! hash every `STATE_HASH_INTERVAL` ticks and the host flags any mismatch.
+//!
+//! A client that joins late is sent a snapshot of the host's simulation
+//! (see `net::snapshot`) and follows live frames from the tick after it.
+//! The session only carries the snapshot; the game takes it when
+//! `wants_snapshot` says a client is waiting.
+//!
+//! Sockets are non-blocking and polled once per rendered frame.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+use super::NetMode;
+use super::protocol::{
+    Command, Cursor, Frame, Message, PlayerAction, PlayerId, HOST_PLAYER, MAX_MESSAGE_LEN,
+    PROTOCOL_VERSION,
+};
+
+/// Host state hashes kept for comparing against late client checksums.
+const HASH_HISTORY: usize = 64;
+
+/// A frame carries at most this many commands; the rest wait a tick.
+const MAX_FRAME_COMMANDS: usize = u8::MAX as usize;
+
+/// A length-prefixed message stream over a non-blocking socket.
+struct Connection {
+    stream: TcpStream,
+    inbox: Vec<u8>,
+    outbox: Vec<u8>,
+    closed: bool,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            inbox: Vec::new(),
+            outbox: Vec::new(),
+            closed: false,
+        })
+    }
+
+    fn send(&mut self, msg: &Message) {
+        self.outbox.extend_from_slice(&msg.encode());
+    }
+
+    /// Write as much of the outbox as the socket takes.
+    fn flush(&mut self) {
+        while !self.closed && !self.outbox.is_empty() {
+            match self.stream.write(&self.outbox) {
+                Ok(0) => self.closed = true,
+                Ok(n) => {
+                    self.outbox.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => self.closed = true,
+            }
+        }
+    }
+
+    /// Read everything available and return the complete messages. A
+    /// malformed message closes the connection.
+    fn receive(&mut self) -> Vec<Message> {
+        let mut buf = [0u8; 16 * 1024];
+        while !self.closed {
+            match self.stream.read(&mut buf) {
+                Ok(0) => self.closed = true,
+                Ok(n) => self.inbox.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => self.closed = true,
+            }
+        }
+
+        let mut messages = Vec::new();
+        let mut start = 0;
+        while let Some(prefix) = self.inbox[start..].first_chunk::<4>() {
+            let len = u32::from_le_bytes(*prefix) as usize;
+            if len > MAX_MESSAGE_LEN {
+                self.closed = true;
+                break;
+            }
+            let Some(body) = self.inbox.get(start + 4..start + 4 + len) else {
+                break;
+            };
+            match Message::decode(body) {
+                Some(msg) => messages.push(msg),
+                None => {
+                    self.closed = true;
+                    break;
+                }
+            }
+            start += 4 + len;
+        }
+        self.inbox.drain(..start);
+        messages
+    }
+}
+
+/// Where a client is in joining, as seen by the host.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Joining {
+    /// Connected; hello not yet received.
+    Connected,
+    /// Said hello; waiting on the game for a snapshot.
+    Waiting,
+    /// Sent the snapshot. Only welcomed peers receive frames.
+    Welcomed,
+}
+
+/// A client connection as seen by the host.
+struct Peer {
+    player: PlayerId,
+    conn: Connection,
+    joining: Joining,
+}
+
+struct Host {
+    listener: TcpListener,
+    peers: Vec<Peer>,
+    next_player: PlayerId,
+    q: u32,
+    free_placement: bool,
+    /// Last tick a frame was issued for.
+    tick: u64,
+    pending: Vec<Command>,
+    /// Recent (tick, hash) pairs from the host's own simulation.
+    hashes: VecDeque<(u64, u64)>,
+}
+
+struct Client {
+    conn: Connection,
+    player: Option<PlayerId>,
+    welcome: Option<Welcome>,
+    frames: VecDeque<Frame>,
+}
+
+enum Role {
+    Host(Host),
+    Client(Client),
+}
+
+/// What a joining client needs to rebuild the host's world.
+pub struct Welcome {
+    /// The host's tiling, {4,q}. A client on another tiling can't take part.
+    pub q: u32,
+    pub free_placement: bool,
+    /// Tick the host had simulated when it took the snapshot.
+    pub tick: u64,
+    pub snapshot: Vec<u8>,
+}
+
+/// One peer's end of a lockstep session.
+pub struct NetSession {
+    role: Role,
+    /// Other players' cursors.
+    cursors: HashMap<PlayerId, Cursor>,
+    /// First disagreement seen: (tick, player).
+    desync: Option<(u64, PlayerId)>,
+}
+
+impl NetSession {
+    /// Host or join as the command line asked.
+    pub fn start(mode: &NetMode, q: u32, free_placement: bool) -> io::Result<Self> {
+        match mode {
+            NetMode::Host { port } => Self::host(*port, q, free_placement),
+            NetMode::Join { addr } => Self::join(addr.as_str()),
+        }
+    }
+
+    /// Listen for clients on `port` (0 picks a free one), playing on the
+    /// {4,q} tiling.
+    pub fn host(port: u16, q: u32, free_placement: bool) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self::new(Role::Host(Host {
+            listener,
+            peers: Vec::new(),
+            next_player: HOST_PLAYER + 1,
+            q,
+            free_placement,
+            tick: 0,
+            pending: Vec::new(),
+            hashes: VecDeque::with_capacity(HASH_HISTORY),
+        })))
+    }
+
+    /// Connect to a host and ask to join.
+    pub fn join(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let mut conn = Connection::new(TcpStream::connect(addr)?)?;
+        conn.send(&Message::Hello { version: PROTOCOL_VERSION });
+        conn.flush();
+        Ok(Self::new(Role::Client(Client {
+            conn,
+            player: None,
+            welcome: None,
+            frames: VecDeque::new(),
+        })))
+    }
+
+    fn new(role: Role) -> Self {
+        Self {
+            role,
+            cursors: HashMap::new(),
+            desync: None,
+        }
+    }
+
+    /// The host's listening address.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match &self.role {
+            Role::Host(host) => host.listener.local_addr().ok(),
+            Role::Client(_) => None,
+        }
+    }
+
+    pub fn is_host(&self) -> bool {
+        matches!(self.role, Role::Host(_))
+    }
+
+    /// This peer's player number; None for a client not yet welcomed.
+    pub fn player(&self) -> Option<PlayerId> {
+        match &self.role {
+            Role::Host(_) => Some(HOST_PLAYER),
+            Role::Client(client) => client.player,
+        }
+    }
+
+    /// Whether a client has lost its host.
+    pub fn is_closed(&self) -> bool {
+        match &self.role {
+            Role::Host(_) => false,
+            Role::Client(client) => client.conn.closed,
+        }
+    }
+
+    /// Frames received but not yet simulated. Always 0 on the host, which
+    /// issues frames as it simulates them.
+    pub fn backlog(&self) -> usize {
+        match &self.role {
+            Role::Host(_) => 0,
+            Role::Client(client) => client.frames.len(),
+        }
+    }
+
+    /// Connected clients, for the host.
+    pub fn peer_count(&self) -> usize {
+        match &self.role {
+            Role::Host(host) => host.peers.len(),
+            Role::Client(_) => 0,
+        }
+    }
+
+    pub fn desync(&self) -> Option<(u64, PlayerId)> {
+        self.desync
+    }
+
+    pub fn cursors(&self) -> impl Iterator<Item = (PlayerId, &Cursor)> {
+        self.cursors.iter().map(|(&p, c)| (p, c))
+    }
+
+    /// Whether a client is waiting for a snapshot, for the host.
+    pub fn wants_snapshot(&self) -> bool {
+        match &self.role {
+            Role::Host(host) => host.peers.iter().any(|p| p.joining == Joining::Waiting),
+            Role::Client(_) => false,
+        }
+    }
+
+    /// Welcome every waiting client with `snapshot`, taken after simulating
+    /// the last frame issued.
+    pub fn send_snapshot(&mut self, snapshot: Vec<u8>) {
+        let Role::Host(host) = &mut self.role else {
+            return;
+        };
+        for peer in host.peers.iter_mut().filter(|p| p.joining == Joining::Waiting) {
+            peer.conn.send(&Message::Welcome {
+                player: peer.player,
+                q: host.q,
+                free_placement: host.free_placement,
+                tick: host.tick,
+                snapshot: snapshot.clone(),
+            });
+            for (&player, cursor) in &self.cursors {
+                peer.conn.send(&Message::Cursor { player, cursor: cursor.clone() });
+            }
+            peer.conn.flush();
+            peer.joining = Joining::Welcomed;
+            log::info!("player {} joined at tick {}", peer.player, host.tick);
+        }
+    }
+
+    /// The join handshake's result, once, when it arrives.
+    pub fn take_welcome(&mut self) -> Option<Welcome> {
+        match &mut self.role {
+            Role::Host(_) => None,
+            Role::Client(client) => client.welcome.take(),
+        }
+    }
+
+    /// Accept new clients, handle everything received, and send what is
+    /// queued.
+    pub fn poll(&mut self) {
+        match &mut self.role {
+            Role::Host(host) => {
+                host.accept();
+                let mut relay = Vec::new();
+                for peer in &mut host.peers {
+                    for msg in peer.conn.receive() {
+                        match msg {
+                            Message::Hello { version } if peer.joining == Joining::Connected => {
+                                if version != PROTOCOL_VERSION {
+                                    log::warn!("player {} speaks protocol {version}, dropping", peer.player);
+                                    peer.conn.closed = true;
+                                    continue;
+                                }
+                                peer.joining = Joining::Waiting;
+                            }
+                            Message::Submit(action) if peer.joining == Joining::Welcomed => {
+                                host.pending.push(Command { player: peer.player, action });
+                            }
+                            Message::Checksum { tick, hash } if peer.joining == Joining::Welcomed => {
+                                let ours = host.hashes.iter().find(|&&(t, _)| t == tick);
+                                if ours.is_some_and(|&(_, h)| h != hash) && self.desync.is_none() {
+                                    log::error!("desync: player {} disagrees at tick {tick}", peer.player);
+                                    self.desync = Some((tick, peer.player));
+                                    relay.push(Message::Desync { tick, player: peer.player });
+                                }
+                            }
+                            Message::Cursor { cursor, .. } if peer.joining == Joining::Welcomed => {
+                                self.cursors.insert(peer.player, cursor.clone());
+                                relay.push(Message::Cursor { player: peer.player, cursor });
+                            }
+                            other => {
+                                log::warn!("unexpected message from player {}: {other:?}", peer.player);
+                                peer.conn.closed = true;
+                            }
+                        }
+                    }
+                }
+                host.peers.retain(|peer| {
+                    if peer.conn.closed {
+                        log::info!("player {} left", peer.player);
+                        self.cursors.remove(&peer.player);
+                        relay.push(Message::Left { player: peer.player });
+                    }
+                    !peer.conn.closed
+                });
+                for msg in &relay {
+                    host.broadcast(msg);
+                }
+                for peer in &mut host.peers {
+                    peer.conn.flush();
+                }
+            }
+            Role::Client(client) => {
+                for msg in client.conn.receive() {
+                    match msg {
+                        Message::Welcome { player, q, free_placement, tick, snapshot } if client.player.is_none() => {
+                            client.player = Some(player);
+                            client.welcome = Some(Welcome { q, free_placement, tick, snapshot });
+                        }
+                        Message::Frame(frame) if client.player.is_some() => client.frames.push_back(frame),
+                        Message::Desync { tick, player } => {
+                            log::error!("desync: player {player} disagrees with the host at tick {tick}");
+                            self.desync.get_or_insert((tick, player));
+                        }
+                        Message::Cursor { player, cursor } => {
+                            if Some(player) != client.player {
+                                self.cursors.insert(player, cursor);
+                            }
+                        }
+                        Message::Left { player } => {
+                            self.cursors.remove(&player);
+                        }
+                        other => {
+                            log::warn!("unexpected message from host: {other:?}");
+                            client.conn.closed = true;
+                        }
+                    }
+                }
+                client.conn.flush();
+            }
+        }
+    }
+
+    /// Queue a local action. It takes effect when it comes back in a frame.
+    pub fn submit(&mut self, action: PlayerAction) {
+        match &mut self.role {
+            Role::Host(host) => host.pending.push(Command { player: HOST_PLAYER, action }),
+            Role::Client(client) => {
+                client.conn.send(&Message::Submit(action));
+                client.conn.flush();
+            }
+        }
+    }
+
+    /// The frame to apply before simulating `tick`, or None if a client has
+    /// not received it yet. The host issues it on the spot.
+    pub fn next_frame(&mut self, tick: u64) -> Option<Frame> {
+        match &mut self.role {
+            Role::Host(host) => {
+                let take = host.pending.len().min(MAX_FRAME_COMMANDS);
+                let frame = Frame { tick, commands: host.pending.drain(..take).collect() };
+                host.tick = tick;
+                host.broadcast(&Message::Frame(frame.clone()));
+                Some(frame)
+            }
+            Role::Client(client) => {
+                let front = client.frames.front()?;
+                if front.tick != tick {
+                    log::error!("expected frame for tick {tick}, host sent {}", front.tick);
+                    client.conn.closed = true;
+                    return None;
+                }
+                client.frames.pop_front()
+            }
+        }
+    }
+
+    /// Record this peer's state hash after simulating `tick`. The host keeps
+    /// it to check clients against; a client reports it to the host.
+    pub fn record_hash(&mut self, tick: u64, hash: u64) {
+        match &mut self.role {
+            Role::Host(host) => {
+                if host.hashes.len() == HASH_HISTORY {
+                    host.hashes.pop_front();
+                }
+                host.hashes.push_back((tick, hash));
+            }
+            Role::Client(client) => client.conn.send(&Message::Checksum { tick, hash }),
+        }
+    }
+
+    /// Tell the other players where this one is pointing.
+    pub fn send_cursor(&mut self, cursor: Cursor) {
+        match &mut self.role {
+            Role::Host(host) => host.broadcast(&Message::Cursor { player: HOST_PLAYER, cursor }),
+            Role::Client(client) => {
+                if let Some(player) = client.player {
+                    client.conn.send(&Message::Cursor { player, cursor });
+                }
+            }
+        }
+    }
+}
+
+impl Host {
+    fn accept(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, addr)) => match Connection::new(stream) {
+                    Ok(conn) => {
+                        let player = self.next_player;
+                        self.next_player = self.next_player.wrapping_add(1).max(HOST_PLAYER + 1);
+                        log::info!("connection from {addr} as player {player}");
+                        self.peers.push(Peer { player, conn, joining: Joining::Connected });
+                    }
+                    Err(e) => log::warn!("rejecting {addr}: {e}"),
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    log::warn!("accept failed: {e}");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Queue a message for every welcomed peer.
+    fn broadcast(&mut self, msg: &Message) {
+        let bytes = msg.encode();
+        for peer in self.peers.iter_mut().filter(|p| p.joining == Joining::Welcomed) {
+            peer.conn.outbox.extend_from_slice(&bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::items::ItemId;
+    use crate::game::world::Direction;
+    use crate::hyperbolic::tiling::TileAddr;
+    use std::time::{Duration, Instant};
+
+    fn place(gx: i32) -> PlayerAction {
+        PlayerAction::Place {
+            address: TileAddr::from_slice(&[0]),
+            grid_xy: (gx, 0),
+            item: ItemId::Belt,
+            direction: Direction::East,
+        }
+    }
+
+    /// Poll every session until `done` holds, failing after a few seconds.
+    /// Hosts answer joins with an empty snapshot.
+    fn pump(sessions: &mut [&mut NetSession], mut done: impl FnMut(&mut [&mut NetSession]) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            for s in sessions.iter_mut() {
+                s.poll();
+                if s.wants_snapshot() {
+                    s.send_snapshot(Vec::new());
+                }
+            }
+            if done(sessions) {
+                return;
+            }
+            assert!(Instant::now() < deadline, "timed out waiting on the network");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn connect(host: &NetSession) -> NetSession {
+        let port = host.local_addr().unwrap().port();
+        NetSession::join(("127.0.0.1", port)).unwrap()
+    }
+
+    #[test]
+    fn late_joiner_gets_snapshot_then_live_frames() {
+        let mut host = NetSession::host(0, 5, true).unwrap();
+        host.submit(place(1));
+        assert_eq!(host.next_frame(1).unwrap().commands.len(), 1);
+        assert!(host.next_frame(2).unwrap().commands.is_empty());
+
+        let mut client = connect(&host);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !host.wants_snapshot() {
+            host.poll();
+            client.poll();
+            assert!(Instant::now() < deadline, "timed out waiting on the network");
+        }
+        host.send_snapshot(vec![7, 8, 9]);
+        assert!(!host.wants_snapshot());
+        let mut welcome = None;
+        pump(&mut [&mut host, &mut client], |s| {
+            welcome = s[1].take_welcome();
+            welcome.is_some()
+        });
+        let welcome = welcome.unwrap();
+        assert_eq!(client.player(), Some(1));
+        assert_eq!(welcome.q, 5);
+        assert!(welcome.free_placement);
+        assert_eq!(welcome.tick, 2);
+        assert_eq!(welcome.snapshot, vec![7, 8, 9]);
+
+        // The client's action comes back in the host's next frame, on both ends
+        client.submit(place(2));
+        pump(&mut [&mut host, &mut client], |s| matches!(&s[0].role, Role::Host(h) if !h.pending.is_empty()));
+        let frame = host.next_frame(3).unwrap();
+        assert_eq!(frame.commands, vec![Command { player: 1, action: place(2) }]);
+        assert_eq!(client.next_frame(3), None, "nothing received yet");
+        pump(&mut [&mut host, &mut client], |s| s[1].backlog() == 1);
+        assert_eq!(client.next_frame(3), Some(frame));
+    }
+
+    #[test]
+    fn checksum_mismatch_flags_desync_everywhere() {
+        let mut host = NetSession::host(0, 5, false).unwrap();
+        let mut client = connect(&host);
+        pump(&mut [&mut host, &mut client], |s| s[1].take_welcome().is_some());
+
+        host.record_hash(600, 0xaaaa);
+        client.record_hash(600, 0xaaaa);
+        host.record_hash(1200, 0xbbbb);
+        client.record_hash(1200, 0xcccc);
+        pump(&mut [&mut host, &mut client], |s| s[1].desync().is_some());
+        assert_eq!(host.desync(), Some((1200, 1)));
+        assert_eq!(client.desync(), Some((1200, 1)));
+    }
+
+    #[test]
+    fn cursors_are_relayed_and_cleared_on_leave() {
+        let mut host = NetSession::host(0, 5, false).unwrap();
+        let mut a = connect(&host);
+        let mut b = connect(&host);
+        pump(&mut [&mut host, &mut a, &mut b], |s| s[1].player().is_some() && s[2].player().is_some());
+
+        let cursor = Cursor { address: TileAddr::from_slice(&[2]), grid_xy: (4, 4) };
+        let a_id = a.player().unwrap();
+        a.send_cursor(cursor.clone());
+        host.send_cursor(Cursor { address: TileAddr::new(), grid_xy: (0, 0) });
+        pump(&mut [&mut host, &mut a, &mut b], |s| s[2].cursors().count() == 2);
+        assert!(b.cursors().any(|(p, c)| p == a_id && *c == cursor));
+        assert!(host.cursors().any(|(p, _)| p == a_id));
+        assert!(a.cursors().all(|(p, _)| p != a_id), "own cursor is not echoed");
+
+        drop(a);
+        pump(&mut [&mut host, &mut b], |s| s[1].cursors().count() == 1);
+        assert_eq!(host.peer_count(), 1);
+    }
+}
@@ -0,0 +1,142 @@
+//! The whole simulation as bytes, sent to a player joining a session.
+//!
+//! Slot maps rebuild their free lists in a fixed order when loaded, not the
+//! order the host's removals left them in. The host therefore loads its own
+//! snapshot back as it sends it, so that both ends hand out the same entity
+//! keys for whatever is built next.
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::inventory::Inventory;
+use crate::game::world::WorldState;
+use crate::sim::belt::BeltNetwork;
+use crate::sim::circuit::CircuitNetwork;
+use crate::sim::fluid::FluidNetwork;
+use crate::sim::loader::LoaderPool;
+use crate::sim::machine::MachinePool;
+use crate::sim::power::PowerNetwork;
+use crate::sim::rail::RailNetwork;
+use crate::sim::sink::SinkPool;
+use crate::sim::sleep::CellSleep;
+use crate::sim::splitter::SplitterPool;
+use crate::sim::storage::StoragePool;
+
+/// Everything a tick reads or writes. The power and rail networks come back
+/// without their rewrite rules, which the loader supplies again.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub world: WorldState,
+    pub inventory: Inventory,
+    pub belts: BeltNetwork,
+    pub machines: MachinePool,
+    pub splitters: SplitterPool,
+    pub storage: StoragePool,
+    pub sinks: SinkPool,
+    pub loaders: LoaderPool,
+    pub fluids: FluidNetwork,
+    pub rails: RailNetwork,
+    pub circuits: CircuitNetwork,
+    pub power: PowerNetwork,
+    pub sleep: CellSleep,
+}
+
+impl Snapshot {
+    pub fn encode(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("simulation state always serializes")
+    }
+
+    /// None if the bytes aren't a snapshot.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        bincode::deserialize(bytes).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::items::{ItemId, MachineType};
+    use crate::game::recipes::RecipeIndex;
+    use crate::game::world::Direction;
+    use crate::hyperbolic::cell_id::CellId;
+    use crate::sim::state_hash::state_hash;
+
+    fn hash(s: &Snapshot) -> u64 {
+        state_hash(
+            &s.belts,
+            &s.machines,
+            &s.storage,
+            &s.splitters,
+            &s.sinks,
+            &s.power,
+            &s.fluids,
+            &s.rails,
+            &s.circuits,
+            &s.loaders,
+        )
+    }
+
+    fn place_belt(s: &mut Snapshot, addr: &CellId, gx: i32) -> slotmap::KeyData {
+        let e = s.world.place(addr, (gx, 0), ItemId::Belt, Direction::East).unwrap();
+        s.belts.on_belt_placed(e, addr, gx, 0, Direction::East, &s.world);
+        slotmap::Key::data(&e)
+    }
+
+    #[test]
+    fn host_and_joiner_agree_after_loading_the_same_bytes() {
+        let addr = &CellId::from_canonical(vec![0]);
+        let recipes = RecipeIndex::new();
+        let mut original = Snapshot {
+            world: WorldState::new(),
+            inventory: Inventory::starting_inventory(),
+            belts: BeltNetwork::new(),
+            machines: MachinePool::new(),
+            splitters: SplitterPool::new(),
+            storage: StoragePool::new(),
+            sinks: SinkPool::new(),
+            loaders: LoaderPool::new(),
+            fluids: FluidNetwork::new(),
+            rails: RailNetwork::new(),
+            circuits: CircuitNetwork::new(),
+            power: PowerNetwork::new(),
+            sleep: CellSleep::new(),
+        };
+        let source = original.world.place(addr, (0, 10), ItemId::SourceMachine, Direction::North).unwrap();
+        original.machines.add(source, MachineType::Source);
+        original.machines.set_recipe(source, Some(recipes.recipes_for_machine(MachineType::Source)[0].0));
+        let feed = original.world.place(addr, (0, 0), ItemId::Belt, Direction::East).unwrap();
+        original.belts.on_belt_placed(feed, addr, 0, 0, Direction::East, &original.world);
+        original.belts.connect_machine_output_to_belt(feed, source, 0);
+        for gx in 1..6 {
+            place_belt(&mut original, addr, gx);
+        }
+        // Leave holes in the entity free list
+        for gx in [2, 4] {
+            let e = original.world.tile_entities(addr).unwrap()[&(gx, 0)];
+            original.world.remove(addr, (gx, 0));
+            original.belts.on_belt_removed(e);
+        }
+        for _ in 0..120 {
+            original.machines.tick(&recipes);
+            original.belts.tick();
+            original.belts.tick_port_transfers(&mut original.machines, &mut original.storage, &mut original.sinks);
+        }
+
+        let bytes = original.encode();
+        let mut host = Snapshot::decode(&bytes).unwrap();
+        let mut joiner = Snapshot::decode(&bytes).unwrap();
+        assert_eq!(hash(&host), hash(&original));
+        assert_eq!(hash(&joiner), hash(&original));
+
+        assert_eq!(place_belt(&mut host, addr, 4), place_belt(&mut joiner, addr, 4));
+        assert_eq!(place_belt(&mut host, addr, 2), place_belt(&mut joiner, addr, 2));
+        for s in [&mut host, &mut joiner] {
+            for _ in 0..120 {
+                s.machines.tick(&recipes);
+                s.belts.tick();
+                s.belts.tick_port_transfers(&mut s.machines, &mut s.storage, &mut s.sinks);
+            }
+        }
+        assert_eq!(hash(&host), hash(&joiner));
+        assert_eq!(Snapshot::decode(&bytes[..bytes.len() / 2]).map(|_| ()), None);
+    }
+}
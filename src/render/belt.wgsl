@@ -10,6 +10,8 @@ struct Globals {
     view_proj: mat4x4<f32>,
     grid_params: vec4<f32>,  // (enabled, divisions, line_width, klein_half_side)
     color_cycle: f32,
+    corner_exclusion: f32,
+    projection: u32,         // Projection::shader_index, read by disk_to_world
 };
 
 @group(0) @binding(0)
@@ -49,10 +51,10 @@ fn vs_belt(vert: VertexInput, inst: InstanceInput) -> VertexOutput {
     let inset = 0.92;
     let klein = vert.local_pos * cell_size * inset + inst.grid_pos / divisions * 2.0 * khs;
 
-    // Klein -> Poincare -> Mobius -> bowl
+    // Klein -> Poincare -> Mobius -> world
     let poincare = klein_to_poincare(klein);
     let disk = apply_mobius(poincare, inst.mobius_a, inst.mobius_b);
-    var world = disk_to_bowl(disk);
+    var world = disk_to_world(disk);
 
     var normal: vec3<f32>;
 
@@ -75,8 +77,8 @@ fn vs_belt(vert: VertexInput, inst: InstanceInput) -> VertexOutput {
         let p_dy = klein_to_poincare(k_dy);
         let d_dx = apply_mobius(p_dx, inst.mobius_a, inst.mobius_b);
         let d_dy = apply_mobius(p_dy, inst.mobius_a, inst.mobius_b);
-        let w_dx = disk_to_bowl(d_dx);
-        let w_dy = disk_to_bowl(d_dy);
+        let w_dx = disk_to_world(d_dx);
+        let w_dy = disk_to_world(d_dy);
         normal = normalize(cross(w_dx - world, w_dy - world));
     }
 
@@ -1,4 +1,5 @@
 use crate::game::input::{GameAction, InputState};
+use crate::hyperbolic::embedding::Projection;
 use crate::hyperbolic::poincare::{Complex, Mobius};
 use crate::hyperbolic::tiling::TilingState;
 
@@ -14,6 +15,7 @@ pub struct Camera {
     pub heading: f64,
     pub height: f32,
     pub mode: CameraMode,
+    pub projection: Projection,
 }
 
 /// Snapshot for interpolation between sim ticks.
@@ -34,6 +36,7 @@ impl Camera {
             heading: 0.0,
             height: 2.0,
             mode: CameraMode::TopDown,
+            projection: Projection::default(),
         }
     }
 
@@ -214,9 +217,11 @@ impl Camera {
             return None;
         }
 
-        // Iteratively intersect ray with bowl surface y = 0.4*r^2/(1+r^2)
+        // Iteratively intersect ray with bowl surface y = 0.4*r^2/(1+r^2);
+        // the other projections lie flat in y = 0
+        let iterations = if self.projection == Projection::Bowl { 5 } else { 0 };
         let mut target_y = 0.0_f32;
-        for _ in 0..5 {
+        for _ in 0..iterations {
             let t = (target_y - near.y) / dir.y;
             if t < 0.0 {
                 return None;
@@ -230,7 +235,7 @@ impl Camera {
             return None;
         }
         let hit = near + dir * t;
-        Some(Complex::new(hit.x as f64, hit.z as f64))
+        self.projection.unproject(hit.x as f64, hit.z as f64)
     }
 }
 
@@ -45,6 +45,36 @@ fn disk_to_bowl(z: vec2<f32>) -> vec3<f32> {
     return vec3<f32>(z.x, y, z.y);
 }
 
+// Poincare disk -> world under the camera's projection (Y-up). Every shader's
+// Globals must carry `projection`; cases match hyperbolic::embedding::Projection.
+fn disk_to_world(z: vec2<f32>) -> vec3<f32> {
+    switch globals.projection {
+        // Klein
+        case 1u: {
+            let k = poincare_to_klein(z);
+            return vec3<f32>(k.x, 0.0, k.y);
+        }
+        // Upper half-plane: w = (i + z) / (1 + iz), with i moved to the origin
+        case 2u: {
+            let w = cdiv(vec2<f32>(z.x, z.y + 1.0), vec2<f32>(1.0 - z.y, z.x));
+            return vec3<f32>(0.5 * w.x, 0.0, 0.5 * (w.y - 1.0));
+        }
+        // Band: atanh z = 0.5 * log((1 + z) / (1 - z))
+        case 3u: {
+            let q = cdiv(vec2<f32>(1.0 + z.x, z.y), vec2<f32>(1.0 - z.x, -z.y));
+            return vec3<f32>(0.25 * log(dot(q, q)), 0.0, 0.5 * atan2(q.y, q.x));
+        }
+        // Gans: hyperboloid seen orthographically, halved to match the disk at the centre
+        case 4u: {
+            let g = z / max(1.0 - dot(z, z), 1e-6);
+            return vec3<f32>(g.x, 0.0, g.y);
+        }
+        default: {
+            return disk_to_bowl(z);
+        }
+    }
+}
+
 // Poincare disk -> Klein disk: K = 2P / (1 + |P|^2)
 fn poincare_to_klein(p: vec2<f32>) -> vec2<f32> {
     let r2 = dot(p, p);
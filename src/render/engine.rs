@@ -1,10 +1,11 @@
 use std::sync::Arc;
 use winit::window::Window;
 
+use crate::hyperbolic::embedding::Projection;
 use crate::hyperbolic::poincare::{Complex, Mobius};
 use crate::hyperbolic::tiling::TilingState;
-use crate::render::instances::{BeltInstance, InstanceBuffer, ItemInstance, MachineInstance, TileInstance};
-use crate::render::pipeline::{BeltPipeline, Globals, ItemPipeline, MachinePipeline, RenderState, TilePipeline, MAX_TILES};
+use crate::render::instances::{BeltInstance, InstanceBuffer, ItemInstance, MachineInstance, PipeInstance, TileInstance};
+use crate::render::pipeline::{BeltPipeline, Globals, ItemPipeline, MachinePipeline, PipePipeline, RenderState, TilePipeline, MAX_TILES};
 use crate::ui::icons::IconAtlas;
 use crate::ui::integration::EguiIntegration;
 use crate::ui::style::apply_octofact_style;
@@ -91,6 +92,8 @@ pub struct RenderEngine {
     pub render: RenderState,
     pub tile_pipeline: TilePipeline,
     pub tile_instances: InstanceBuffer<TileInstance>,
+    pub pipe_pipeline: PipePipeline,
+    pub pipe_instances: InstanceBuffer<PipeInstance>,
     pub belt_pipeline: BeltPipeline,
     pub belt_instances: InstanceBuffer<BeltInstance>,
     pub machine_pipeline: MachinePipeline,
@@ -127,6 +130,9 @@ impl RenderEngine {
         let tile_instances = InstanceBuffer::new(&gpu.device, "tile instances", 256);
 
         let globals_layout = tile_pipeline.pipeline.get_bind_group_layout(0);
+        let pipe_pipeline = PipePipeline::new(&gpu.device, gpu.config.format, &globals_layout);
+        let pipe_instances = InstanceBuffer::new(&gpu.device, "pipe instances", 256);
+
         let belt_pipeline = BeltPipeline::new(&gpu.device, gpu.config.format, &globals_layout);
         let belt_instances = InstanceBuffer::new(&gpu.device, "belt instances", 256);
 
@@ -145,6 +151,8 @@ impl RenderEngine {
             render,
             tile_pipeline,
             tile_instances,
+            pipe_pipeline,
+            pipe_instances,
             belt_pipeline,
             belt_instances,
             machine_pipeline,
@@ -184,6 +192,8 @@ impl RenderEngine {
         view_proj: &glam::Mat4,
         grid_enabled: bool,
         klein_half_side: f32,
+        corner_exclusion: f32,
+        projection: Projection,
     ) {
         // Build instance data
         self.tile_instances.clear();
@@ -209,7 +219,9 @@ impl RenderEngine {
                 klein_half_side,
             ],
             color_cycle: 13.0,
-            _pad: [0.0; 3],
+            corner_exclusion,
+            projection: projection.shader_index(),
+            _pad: 0.0,
         };
         self.tile_pipeline.upload_globals(&self.gpu.queue, &globals);
     }
@@ -288,6 +300,20 @@ impl RenderEngine {
                 pass.draw_indexed(0..self.render.num_indices, 0, 0..tile_count);
             }
 
+            // Draw pipes (instanced) before belts so they sit underneath
+            let pipe_count = self.pipe_instances.count();
+            if pipe_count > 0 {
+                pass.set_pipeline(&self.pipe_pipeline.pipeline);
+                pass.set_bind_group(0, &self.tile_pipeline.globals_bind_group, &[]);
+                pass.set_vertex_buffer(0, self.pipe_pipeline.vertex_buffer.slice(..));
+                pass.set_vertex_buffer(1, self.pipe_instances.slice());
+                pass.set_index_buffer(
+                    self.pipe_pipeline.index_buffer.slice(..),
+                    wgpu::IndexFormat::Uint16,
+                );
+                pass.draw_indexed(0..self.pipe_pipeline.num_indices, 0, 0..pipe_count);
+            }
+
             // Draw belt segments (instanced)
             let belt_count = self.belt_instances.count();
             if belt_count > 0 {
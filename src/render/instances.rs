@@ -188,10 +188,79 @@ impl BeltInstance {
     }
 }
 
+/// Per-pipe-segment instance data. Drawn below belts so pipes can run
+/// underneath them, with arms toward each connected side and the core
+/// tinted by the fluid held.
+///
+/// 44 bytes (10 floats + connection mask). Shader locations 5–10.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PipeInstance {
+    /// Mobius numerator coefficient for the parent tile.
+    pub mobius_a: [f32; 2],
+    /// Mobius denominator coefficient for the parent tile.
+    pub mobius_b: [f32; 2],
+    /// Grid cell position within the tile.
+    pub grid_pos: [f32; 2],
+    /// Volume fraction 0.0–1.0.
+    pub fill: f32,
+    /// Colour of the fluid held (ignored when `fill` is 0).
+    pub fluid_color: [f32; 3],
+    /// Connected sides: bit 0=North, 1=East, 2=South, 3=West.
+    pub connections: u32,
+}
+
+impl PipeInstance {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<PipeInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                // mobius_a
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // mobius_b
+                wgpu::VertexAttribute {
+                    offset: 8,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // grid_pos
+                wgpu::VertexAttribute {
+                    offset: 16,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // fill
+                wgpu::VertexAttribute {
+                    offset: 24,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                // fluid_color
+                wgpu::VertexAttribute {
+                    offset: 28,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                // connections
+                wgpu::VertexAttribute {
+                    offset: 40,
+                    shader_location: 10,
+                    format: wgpu::VertexFormat::Uint32,
+                },
+            ],
+        }
+    }
+}
+
 /// Per-machine instance data. Positions a machine visual on the tile
 /// surface at a grid cell, with type and crafting state for the shader.
 ///
-/// 40 bytes (10 floats). Shader locations 5–12.
+/// 44 bytes (10 floats + packed ports). Shader locations 5–12.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct MachineInstance {
@@ -202,7 +271,9 @@ pub struct MachineInstance {
     /// Grid cell position within the tile (0..63, 0..63).
     pub grid_pos: [f32; 2],
     /// Machine type: 0=Composer, 1=Inverter, 2=Embedder, 3=Quotient,
-    /// 4=Transformer, 5=Source, 6=Quadrupole, 7=Dynamo.
+    /// 4=Transformer, 5=Source, 6=Quadrupole, 7=Dynamo, 8=Splitter,
+    /// 9=Storage, 10=Sink, 11=Loader, 12=Pump, 13=Rail, 14=Train Station,
+    /// 15=Locomotive, 16=Cargo Wagon.
     pub machine_type: f32,
     /// Crafting progress 0.0–1.0, or negative for special states
     /// (-1.0 = idle, -2.0 = no power).
@@ -211,9 +282,32 @@ pub struct MachineInstance {
     pub power_sat: f32,
     /// Facing direction: 0=North, 1=East, 2=South, 3=West.
     pub facing: f32,
+    /// Canonical port layout packed by `encode_ports` (machines only, 0 otherwise).
+    pub ports: u32,
 }
 
 impl MachineInstance {
+    /// Pack up to four canonical ports into one u32, one byte per port:
+    /// bits 0–1 side, bit 2 kind (1 = output), bits 3–4 cell x, bits 5–6 cell y,
+    /// bit 7 set when the port is present.
+    pub fn encode_ports(ports: &[crate::sim::inserter::PortDef]) -> u32 {
+        ports.iter().take(4).enumerate().fold(0, |acc, (i, p)| {
+            let side = match p.side {
+                crate::game::world::Direction::North => 0,
+                crate::game::world::Direction::East => 1,
+                crate::game::world::Direction::South => 2,
+                crate::game::world::Direction::West => 3,
+            };
+            let kind = (p.kind == crate::sim::inserter::PortKind::Output) as u32;
+            let byte = 0x80
+                | side
+                | (kind << 2)
+                | ((p.cell_offset.0 as u32 & 3) << 3)
+                | ((p.cell_offset.1 as u32 & 3) << 5);
+            acc | (byte << (8 * i))
+        })
+    }
+
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<MachineInstance>() as wgpu::BufferAddress,
@@ -261,6 +355,12 @@ impl MachineInstance {
                     shader_location: 11,
                     format: wgpu::VertexFormat::Float32,
                 },
+                // ports
+                wgpu::VertexAttribute {
+                    offset: 40,
+                    shader_location: 12,
+                    format: wgpu::VertexFormat::Uint32,
+                },
             ],
         }
     }
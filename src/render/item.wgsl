@@ -5,6 +5,8 @@ struct Globals {
     view_proj: mat4x4<f32>,
     grid_params: vec4<f32>,  // (enabled, divisions, line_width, klein_half_side)
     color_cycle: f32,
+    corner_exclusion: f32,
+    projection: u32,         // Projection::shader_index, read by disk_to_world
 };
 
 @group(0) @binding(0)
@@ -41,10 +43,10 @@ fn vs_item(vert: VertexInput, inst: InstanceInput) -> VertexOutput {
     let item_size = cell_size * 0.4;
     let klein = vert.local_pos * item_size + inst.klein_pos;
 
-    // Klein -> Poincare -> Mobius -> bowl -> clip
+    // Klein -> Poincare -> Mobius -> world -> clip
     let poincare = klein_to_poincare(klein);
     let disk = apply_mobius(poincare, inst.mobius_a, inst.mobius_b);
-    var world = disk_to_bowl(disk);
+    var world = disk_to_world(disk);
     world.y += 0.006;  // lift above belt surface (BELT_HEIGHT = 0.005)
 
     out.clip_position = globals.view_proj * vec4<f32>(world, 1.0);
@@ -10,6 +10,8 @@ struct Globals {
     view_proj: mat4x4<f32>,
     grid_params: vec4<f32>,  // (enabled, divisions, line_width, klein_half_side)
     color_cycle: f32,
+    corner_exclusion: f32,
+    projection: u32,         // Projection::shader_index, read by disk_to_world
 };
 
 @group(0) @binding(0)
@@ -28,6 +30,7 @@ struct InstanceInput {
     @location(9) progress: f32,         // 0.0-1.0 working, -1.0 idle, -2.0 no power
     @location(10) power_sat: f32,       // 0.0-1.0 satisfaction, -1.0 not connected
     @location(11) facing: f32,          // 0=North, 1=East, 2=South, 3=West
+    @location(12) ports: u32,           // packed canonical port layout (machines)
 };
 
 struct VertexOutput {
@@ -39,6 +42,7 @@ struct VertexOutput {
     @location(4) world_normal: vec3<f32>,
     @location(5) power_sat: f32,
     @location(6) facing: f32,
+    @location(7) @interpolate(flat) ports: u32,
 };
 
 // Machine footprint in grid cells: (width, height), canonical (facing North).
@@ -47,9 +51,18 @@ fn machine_size_canonical(mt: u32) -> vec2<f32> {
         case 5u: { return vec2<f32>(1.0, 1.0); }  // Source
         case 6u: { return vec2<f32>(1.0, 1.0); }  // Quadrupole
         case 8u: { return vec2<f32>(1.0, 1.0); }  // Splitter
+        case 10u: { return vec2<f32>(1.0, 1.0); } // Sink
+        case 11u: { return vec2<f32>(1.0, 1.0); } // Loader
+        case 13u: { return vec2<f32>(1.0, 1.0); } // Rail
+        case 15u: { return vec2<f32>(1.0, 1.0); } // Locomotive
+        case 16u: { return vec2<f32>(1.0, 1.0); } // Cargo wagon
         case 0u: { return vec2<f32>(2.0, 2.0); }  // Composer
         case 7u: { return vec2<f32>(2.0, 2.0); }  // Dynamo
         case 9u: { return vec2<f32>(2.0, 2.0); }  // Storage
+        case 12u: { return vec2<f32>(2.0, 2.0); } // Pump
+        case 17u: { return vec2<f32>(1.0, 2.0); } // Arithmetic combinator
+        case 18u: { return vec2<f32>(1.0, 2.0); } // Decider combinator
+        case 14u: { return vec2<f32>(8.0, 4.0); } // Train station
         default: { return vec2<f32>(3.0, 3.0); }   // Inverter, Embedder, Quotient, Transformer
     }
 }
@@ -77,6 +90,15 @@ fn machine_color(mt: u32) -> vec3<f32> {
         case 7u: { return vec3<f32>(1.0, 0.9, 0.3); }   // Dynamo: bright gold
         case 8u: { return vec3<f32>(0.3, 0.8, 0.7); }   // Splitter: teal
         case 9u: { return vec3<f32>(0.8, 0.6, 0.3); }   // Storage: amber
+        case 10u: { return vec3<f32>(0.3, 0.2, 0.4); }  // Sink: dark violet
+        case 11u: { return vec3<f32>(0.5, 0.7, 0.9); }  // Loader: sky blue
+        case 12u: { return vec3<f32>(0.3, 0.75, 0.6); } // Pump: sea green
+        case 13u: { return vec3<f32>(0.4, 0.3, 0.2); }  // Rail: timber ballast
+        case 14u: { return vec3<f32>(0.7, 0.45, 0.35); } // Train station: brick
+        case 15u: { return vec3<f32>(0.85, 0.25, 0.2); } // Locomotive: red
+        case 16u: { return vec3<f32>(0.6, 0.5, 0.35); } // Cargo wagon: tan
+        case 17u: { return vec3<f32>(0.9, 0.6, 0.2); }  // Arithmetic combinator: orange
+        case 18u: { return vec3<f32>(0.3, 0.7, 0.9); }  // Decider combinator: cyan
         default: { return vec3<f32>(0.5, 0.5, 0.5); }
     }
 }
@@ -87,6 +109,14 @@ fn machine_height(mt: u32) -> f32 {
         case 6u: { return 0.005; }  // Quadrupole: short relay
         case 5u: { return 0.008; }  // Source: medium
         case 8u: { return 0.008; }  // Splitter: medium
+        case 10u: { return 0.005; } // Sink: low basin
+        case 11u: { return 0.005; } // Loader: low coupler
+        case 12u: { return 0.008; } // Pump: medium
+        case 13u: { return 0.002; } // Rail: flush with the ground
+        case 14u: { return 0.004; } // Train station: low platform
+        case 15u: { return 0.012; } // Locomotive: tallest
+        case 16u: { return 0.010; } // Cargo wagon
+        case 17u, 18u: { return 0.006; } // Combinators: low boxes
         default: { return 0.010; }  // All production machines: tall
     }
 }
@@ -194,44 +224,87 @@ fn splitter_port_indicators(uv: vec2<f32>, bitmask: u32) -> vec4<f32> {
     return best;
 }
 
-// Get port indicator overlay for a given machine type.
+// Loader port indicators: the attached building is North (canonical), the belt South.
+// Mode comes from the progress field: 0 = load (belt in, building out), 1 = unload.
+fn loader_port_indicators(uv: vec2<f32>, facing: u32, unload: bool) -> vec4<f32> {
+    let size = vec2<f32>(1.0, 1.0);
+    let cell = vec2<f32>(0.0, 0.0);
+    var belt_kind = 0u;
+    var building_kind = 1u;
+    if unload {
+        belt_kind = 1u;
+        building_kind = 0u;
+    }
+    var best = check_port(uv, size, cell, 2u, facing, belt_kind);
+    best = max(best, check_port(uv, size, cell, 0u, facing, building_kind));
+    return best;
+}
+
+// Rail track: two steel rails per connected side, joined at the centre.
+// Connection bitmask from the progress field: bit 0=N, 1=E, 2=S, 3=W.
+fn rail_track(uv: vec2<f32>, mask: u32) -> vec4<f32> {
+    let c = uv - 0.5;  // +y = South
+    let gauge = 0.2;
+    let w = 0.035;
+    let steel = vec3<f32>(0.75, 0.78, 0.82);
+    let on_ns = abs(abs(c.x) - gauge) < w;
+    let on_ew = abs(abs(c.y) - gauge) < w;
+    let arm_n = (mask & 1u) != 0u && on_ns && c.y < gauge;
+    let arm_e = (mask & 2u) != 0u && on_ew && c.x > -gauge;
+    let arm_s = (mask & 4u) != 0u && on_ns && c.y > -gauge;
+    let arm_w = (mask & 8u) != 0u && on_ew && c.x < gauge;
+    // A lone rail shows a short N-S stub so it is visible before linking
+    let stub = mask == 0u && on_ns && abs(c.y) < gauge;
+    if arm_n || arm_e || arm_s || arm_w || stub {
+        return vec4<f32>(steel, 1.0);
+    }
+    return vec4<f32>(0.0);
+}
+
+// Machine port indicators: decode the per-entity layout packed by
+// MachineInstance::encode_ports (one byte per port, bit 7 = present).
+fn packed_port_indicators(uv: vec2<f32>, mt: u32, facing: u32, ports: u32) -> vec4<f32> {
+    let canon_size = machine_size_canonical(mt);
+    var best = vec4<f32>(0.0);
+    for (var i = 0u; i < 4u; i = i + 1u) {
+        let byte = (ports >> (8u * i)) & 0xFFu;
+        if (byte & 0x80u) == 0u {
+            continue;
+        }
+        let side = byte & 3u;
+        let kind = (byte >> 2u) & 1u;
+        let cell = vec2<f32>(f32((byte >> 3u) & 3u), f32((byte >> 5u) & 3u));
+        best = max(best, check_port(uv, canon_size, cell, side, facing, kind));
+    }
+    return best;
+}
+
+// Get port indicator overlay for a non-machine structure with fixed ports.
 // Returns vec4(color.rgb, alpha) — alpha > 0 means a port indicator is here.
 fn port_indicators(uv: vec2<f32>, mt: u32, facing: u32) -> vec4<f32> {
     let canon_size = machine_size_canonical(mt);
     var best = vec4<f32>(0.0);
 
     switch mt {
-        case 0u: { // Composer (2×2): input South@(0,1), output North@(0,0)
+        case 9u: { // Storage (2×2): input0 South@(0,1), input1 South@(1,1), output0 North@(0,0), output1 North@(1,0)
             best = max(best, check_port(uv, canon_size, vec2<f32>(0.0, 1.0), 2u, facing, 0u));
+            best = max(best, check_port(uv, canon_size, vec2<f32>(1.0, 1.0), 2u, facing, 0u));
             best = max(best, check_port(uv, canon_size, vec2<f32>(0.0, 0.0), 0u, facing, 1u));
-        }
-        case 1u: { // Inverter (3×3): input South@(1,2), output North@(1,0)
-            best = max(best, check_port(uv, canon_size, vec2<f32>(1.0, 2.0), 2u, facing, 0u));
-            best = max(best, check_port(uv, canon_size, vec2<f32>(1.0, 0.0), 0u, facing, 1u));
-        }
-        case 2u: { // Embedder (3×3): input0 South@(1,2), input1 West@(0,1), output North@(1,0)
-            best = max(best, check_port(uv, canon_size, vec2<f32>(1.0, 2.0), 2u, facing, 0u));
-            best = max(best, check_port(uv, canon_size, vec2<f32>(0.0, 1.0), 3u, facing, 0u));
             best = max(best, check_port(uv, canon_size, vec2<f32>(1.0, 0.0), 0u, facing, 1u));
         }
-        case 3u: { // Quotient (3×3): input South@(1,2), output0 North@(1,0), output1 East@(2,1)
-            best = max(best, check_port(uv, canon_size, vec2<f32>(1.0, 2.0), 2u, facing, 0u));
-            best = max(best, check_port(uv, canon_size, vec2<f32>(1.0, 0.0), 0u, facing, 1u));
-            best = max(best, check_port(uv, canon_size, vec2<f32>(2.0, 1.0), 1u, facing, 1u));
+        case 10u: { // Sink (1×1): input South@(0,0)
+            best = max(best, check_port(uv, canon_size, vec2<f32>(0.0, 0.0), 2u, facing, 0u));
         }
-        case 4u: { // Transformer (3×3): input0 South@(1,2), input1 West@(0,1), output North@(1,0)
-            best = max(best, check_port(uv, canon_size, vec2<f32>(1.0, 2.0), 2u, facing, 0u));
-            best = max(best, check_port(uv, canon_size, vec2<f32>(0.0, 1.0), 3u, facing, 0u));
-            best = max(best, check_port(uv, canon_size, vec2<f32>(1.0, 0.0), 0u, facing, 1u));
+        case 12u: { // Pump (2×2): fluid input South@(0,1), fluid output North@(0,0)
+            best = max(best, check_port(uv, canon_size, vec2<f32>(0.0, 1.0), 2u, facing, 0u));
+            best = max(best, check_port(uv, canon_size, vec2<f32>(0.0, 0.0), 0u, facing, 1u));
         }
-        case 5u: { // Source (1×1): output North@(0,0)
+        case 15u: { // Locomotive: arrow off the front shows direction of travel
             best = max(best, check_port(uv, canon_size, vec2<f32>(0.0, 0.0), 0u, facing, 1u));
         }
-        case 9u: { // Storage (2×2): input0 South@(0,1), input1 South@(1,1), output0 North@(0,0), output1 North@(1,0)
+        case 17u, 18u: { // Combinators (1×2): input South@(0,1), output North@(0,0)
             best = max(best, check_port(uv, canon_size, vec2<f32>(0.0, 1.0), 2u, facing, 0u));
-            best = max(best, check_port(uv, canon_size, vec2<f32>(1.0, 1.0), 2u, facing, 0u));
             best = max(best, check_port(uv, canon_size, vec2<f32>(0.0, 0.0), 0u, facing, 1u));
-            best = max(best, check_port(uv, canon_size, vec2<f32>(1.0, 0.0), 0u, facing, 1u));
         }
         default: { } // Quadrupole, Dynamo: no ports
     }
@@ -259,10 +332,10 @@ fn vs_machine(vert: VertexInput, inst: InstanceInput) -> VertexOutput {
     let center = (inst.grid_pos + footprint_offset) / divisions * 2.0 * khs;
     let klein = scaled + center;
 
-    // Klein -> Poincare -> Mobius -> bowl
+    // Klein -> Poincare -> Mobius -> world
     let poincare = klein_to_poincare(klein);
     let disk = apply_mobius(poincare, inst.mobius_a, inst.mobius_b);
-    var world = disk_to_bowl(disk);
+    var world = disk_to_world(disk);
 
     var normal: vec3<f32>;
 
@@ -285,8 +358,8 @@ fn vs_machine(vert: VertexInput, inst: InstanceInput) -> VertexOutput {
         let p_dy = klein_to_poincare(k_dy);
         let d_dx = apply_mobius(p_dx, inst.mobius_a, inst.mobius_b);
         let d_dy = apply_mobius(p_dy, inst.mobius_a, inst.mobius_b);
-        let w_dx = disk_to_bowl(d_dx);
-        let w_dy = disk_to_bowl(d_dy);
+        let w_dx = disk_to_world(d_dx);
+        let w_dy = disk_to_world(d_dy);
         normal = normalize(cross(w_dx - world, w_dy - world));
     }
 
@@ -298,6 +371,7 @@ fn vs_machine(vert: VertexInput, inst: InstanceInput) -> VertexOutput {
     out.world_normal = normal;
     out.power_sat = inst.power_sat;
     out.facing = inst.facing;
+    out.ports = inst.ports;
 
     return out;
 }
@@ -325,13 +399,13 @@ fn fs_machine(in: VertexOutput) -> @location(0) vec4<f32> {
         let grad = 1.0 - wall_v * 0.4;
         var side_color = side_lit * grad;
 
-        // State dimming for side walls too (skip for splitters)
-        if mt == 9u {
+        // State dimming for side walls too (production machines and power only)
+        if mt == 9u || mt == 16u {
             // Storage: fill-level brightness on side walls too
             let fill = clamp(in.progress, 0.0, 1.0);
             let brightness = 0.5 + 0.5 * fill;
             side_color *= brightness;
-        } else if mt != 8u {
+        } else if mt <= 7u {
             if in.progress >= 0.0 {
                 let pulse = 0.8 + 0.2 * sin(in.progress * 6.2832);
                 side_color *= pulse;
@@ -367,13 +441,13 @@ fn fs_machine(in: VertexOutput) -> @location(0) vec4<f32> {
     // Apply lighting
     color *= lighting;
 
-    // State-based pulsing glow (skip for splitters and storage — they don't craft)
-    if mt == 9u {
-        // Storage: fill-level brightness. progress = 0.0 (empty) to 1.0 (full).
+    // State-based pulsing glow (production machines and power only — logistics don't craft)
+    if mt == 9u || mt == 16u {
+        // Storage and cargo wagons: fill-level brightness. progress = 0.0 (empty) to 1.0 (full).
         let fill = clamp(in.progress, 0.0, 1.0);
         let brightness = 0.5 + 0.5 * fill;
         color *= brightness;
-    } else if mt != 8u {
+    } else if mt <= 7u {
         if in.progress >= 0.0 {
             let pulse = 0.8 + 0.2 * sin(in.progress * 6.2832);
             color *= pulse;
@@ -392,6 +466,12 @@ fn fs_machine(in: VertexOutput) -> @location(0) vec4<f32> {
         // Splitter: decode dynamic connection bitmask from progress field
         let bitmask = u32(max(in.progress, 0.0) + 0.5);
         port = splitter_port_indicators(in.uv, bitmask);
+    } else if mt == 11u {
+        port = loader_port_indicators(in.uv, facing_u, in.progress > 0.5);
+    } else if mt == 13u {
+        port = rail_track(in.uv, u32(max(in.progress, 0.0) + 0.5));
+    } else if mt <= 5u {
+        port = packed_port_indicators(in.uv, mt, facing_u, in.ports);
     } else {
         port = port_indicators(in.uv, mt, facing_u);
     }
@@ -127,7 +127,7 @@ pub fn build_polygon_mesh(vertices: &[Complex]) -> (Vec<Vertex>, Vec<u16>) {
 
     let wall_bot_start = verts.len() as u16;
 
-    // Side wall bottom vertices (same positions, shader places at bowl height without elevation)
+    // Side wall bottom vertices (same positions, shader places on the projected surface without elevation)
     for i in 0..verts_per_ring {
         let src = &verts[(outer_ring_start + i) as usize];
         verts.push(Vertex {
@@ -216,7 +216,7 @@ pub fn build_box_mesh() -> (Vec<QuadVertex>, Vec<u16>) {
 
 /// Build a subdivided box mesh for multi-cell machines.
 /// The top face is an `n × n` grid so intermediate vertices get properly
-/// transformed through Klein → Poincaré → Möbius → bowl in the shader,
+/// transformed through Klein → Poincaré → Möbius → projection in the shader,
 /// preventing large flat-quad distortion on the curved surface.
 /// Side walls are subdivided `n` times along each edge.
 pub fn build_subdivided_box_mesh(n: u32) -> (Vec<QuadVertex>, Vec<u16>) {
@@ -0,0 +1,101 @@
+// Instanced pipe shader: draws a flat pipe junction per pipe segment.
+// Prepended by common.wgsl at load time.
+//
+// Pipes sit below belt height, so a belt on the same cell covers them and
+// only the arms reaching past the belt show. The box mesh top face is cut
+// down to a hub plus one arm per connected side; side walls are discarded.
+
+struct Globals {
+    view_proj: mat4x4<f32>,
+    grid_params: vec4<f32>,  // (enabled, divisions, line_width, klein_half_side)
+    color_cycle: f32,
+    corner_exclusion: f32,
+    projection: u32,         // Projection::shader_index, read by disk_to_world
+};
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+
+struct VertexInput {
+    @location(0) local_pos: vec2<f32>,  // unit quad: -0.5 to 0.5
+    @location(1) uv: vec2<f32>,         // 0-1 for top face, 2-3 for side walls
+};
+
+struct InstanceInput {
+    @location(5) mobius_a: vec2<f32>,
+    @location(6) mobius_b: vec2<f32>,
+    @location(7) grid_pos: vec2<f32>,
+    @location(8) fill: f32,               // 0..1 volume fraction
+    @location(9) fluid_color: vec3<f32>,
+    @location(10) connections: u32,       // bit 0=N, 1=E, 2=S, 3=W
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) fill: f32,
+    @location(2) fluid_color: vec3<f32>,
+    @location(3) @interpolate(flat) connections: u32,
+    @location(4) disk_r: f32,
+};
+
+const PIPE_HEIGHT: f32 = 0.0025;
+const PIPE_HALF_WIDTH: f32 = 0.16;
+
+@vertex
+fn vs_pipe(vert: VertexInput, inst: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    let divisions = globals.grid_params.y;
+    let khs = globals.grid_params.w;
+    let cell_size = 2.0 * khs / divisions;
+
+    // Full cell (no inset) so arms of neighbouring pipes meet at the edge
+    let klein = vert.local_pos * cell_size + inst.grid_pos / divisions * 2.0 * khs;
+
+    let poincare = klein_to_poincare(klein);
+    let disk = apply_mobius(poincare, inst.mobius_a, inst.mobius_b);
+    var world = disk_to_world(disk);
+    if vert.uv.y < 2.5 {
+        world.y += PIPE_HEIGHT;
+    }
+
+    out.clip_position = globals.view_proj * vec4<f32>(world, 1.0);
+    out.uv = vert.uv;
+    out.fill = inst.fill;
+    out.fluid_color = inst.fluid_color;
+    out.connections = inst.connections;
+    out.disk_r = length(disk);
+    return out;
+}
+
+@fragment
+fn fs_pipe(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Flat pipes: no side walls
+    if in.uv.y > 1.5 { discard; }
+
+    let fade = 1.0 - smoothstep(0.85, 0.95, in.disk_r);
+    if fade < 0.01 { discard; }
+
+    let c = in.uv - 0.5;  // centered: -0.5 to 0.5, +y = South
+    let w = PIPE_HALF_WIDTH;
+    let hub = abs(c.x) < w * 1.4 && abs(c.y) < w * 1.4;
+    let arm_n = (in.connections & 1u) != 0u && abs(c.x) < w && c.y < 0.0;
+    let arm_e = (in.connections & 2u) != 0u && abs(c.y) < w && c.x > 0.0;
+    let arm_s = (in.connections & 4u) != 0u && abs(c.x) < w && c.y > 0.0;
+    let arm_w = (in.connections & 8u) != 0u && abs(c.y) < w && c.x < 0.0;
+    if !(hub || arm_n || arm_e || arm_s || arm_w) { discard; }
+
+    // Distance to the pipe's centreline for the wall/core split
+    var core_d = max(abs(c.x), abs(c.y)) / (w * 1.4);
+    if !hub {
+        if arm_n || arm_s { core_d = abs(c.x) / w; } else { core_d = abs(c.y) / w; }
+    }
+
+    let wall = vec3<f32>(0.30, 0.38, 0.36);
+    let empty = vec3<f32>(0.10, 0.12, 0.12);
+    let core = mix(empty, in.fluid_color, clamp(in.fill, 0.0, 1.0));
+    let color = mix(core, wall, smoothstep(0.55, 0.7, core_d));
+
+    return vec4<f32>(color * fade, 1.0);
+}
@@ -1,4 +1,4 @@
-use super::instances::{BeltInstance, ItemInstance, MachineInstance, TileInstance};
+use super::instances::{BeltInstance, ItemInstance, MachineInstance, PipeInstance, TileInstance};
 use super::mesh::{QuadVertex, Vertex};
 
 /// Global uniforms shared across all instanced draw calls.
@@ -8,14 +8,18 @@ use super::mesh::{QuadVertex, Vertex};
 ///   view_proj: mat4x4<f32>  (64)
 ///   grid_params: vec4<f32>  (16) — enabled, divisions, line_width, klein_half_side
 ///   color_cycle: f32        (4)
-///   _pad: 12 bytes          (align to 16)
+///   corner_exclusion: f32   (4)  — no-build radius around cell corners, in squares
+///   projection: u32         (4)  — `Projection::shader_index`
+///   _pad: 4 bytes           (align to 16)
 #[repr(C)]
 #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Globals {
     pub view_proj: [[f32; 4]; 4],
     pub grid_params: [f32; 4],
     pub color_cycle: f32,
-    pub _pad: [f32; 3],
+    pub corner_exclusion: f32,
+    pub projection: u32,
+    pub _pad: f32,
 }
 
 /// Max tiles we can draw per frame.
@@ -299,6 +303,109 @@ impl BeltPipeline {
     }
 }
 
+/// Instanced pipe pipeline: one draw call for all visible pipe segments,
+/// issued before belts so pipes read as running underneath them.
+/// Uses the same Globals uniform bind group as TilePipeline.
+pub struct PipePipeline {
+    pub pipeline: wgpu::RenderPipeline,
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub num_indices: u32,
+}
+
+impl PipePipeline {
+    /// Create the pipe pipeline. `globals_layout` should come from
+    /// `tile_pipeline.pipeline.get_bind_group_layout(0)` to share the same bind group.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        globals_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        use wgpu::util::DeviceExt;
+
+        let common_src = include_str!("common.wgsl");
+        let pipe_src = include_str!("pipe.wgsl");
+        let full_src = format!("{}\n{}", common_src, pipe_src);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("pipe instanced shader"),
+            source: wgpu::ShaderSource::Wgsl(full_src.into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("pipe pipeline layout"),
+            bind_group_layouts: &[globals_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("pipe instanced pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_pipe"),
+                buffers: &[QuadVertex::desc(), PipeInstance::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_pipe"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        // Same box mesh as belts; the shader cuts the top face to the pipe shape
+        let (quad_verts, quad_indices) = crate::render::mesh::build_box_mesh();
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("pipe vertex buffer"),
+            contents: bytemuck::cast_slice(&quad_verts),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("pipe index buffer"),
+            contents: bytemuck::cast_slice(&quad_indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            num_indices: quad_indices.len() as u32,
+        }
+    }
+}
+
 /// Instanced machine pipeline: one draw call for all visible machines.
 /// Uses the same Globals uniform bind group as TilePipeline.
 pub struct MachinePipeline {
@@ -5,6 +5,8 @@ struct Globals {
     view_proj: mat4x4<f32>,
     grid_params: vec4<f32>,  // (enabled, divisions, line_width, klein_half_side)
     color_cycle: f32,
+    corner_exclusion: f32,   // no-build radius around cell corners, in squares
+    projection: u32,         // Projection::shader_index, read by disk_to_world
 };
 
 @group(0) @binding(0)
@@ -53,27 +55,27 @@ fn vs_tile(
     var normal: vec3<f32>;
 
     if vert_type > 0.5 {
-        // Side wall top: bowl + elevation
-        world = disk_to_bowl(w);
+        // Side wall top: surface + elevation
+        world = disk_to_world(w);
         world.y += inst.elevation;
         let outward = normalize(w);
         normal = normalize(vec3<f32>(outward.x, 0.0, outward.y));
     } else if vert_type < -0.5 {
-        // Side wall bottom: bowl only (no elevation)
-        world = disk_to_bowl(w);
+        // Side wall bottom: surface only (no elevation)
+        world = disk_to_world(w);
         let outward = normalize(w);
         normal = normalize(vec3<f32>(outward.x, 0.0, outward.y));
     } else {
-        // Top face: bowl + elevation, normal via finite differences
-        world = disk_to_bowl(w);
+        // Top face: surface + elevation, normal via finite differences
+        world = disk_to_world(w);
         world.y += inst.elevation;
 
         let eps = 0.001;
         let w_dx = apply_mobius(z + vec2<f32>(eps, 0.0), inst.mobius_a, inst.mobius_b);
         let w_dy = apply_mobius(z + vec2<f32>(0.0, eps), inst.mobius_a, inst.mobius_b);
-        var world_dx = disk_to_bowl(w_dx);
+        var world_dx = disk_to_world(w_dx);
         world_dx.y += inst.elevation;
-        var world_dy = disk_to_bowl(w_dy);
+        var world_dy = disk_to_world(w_dy);
         world_dy.y += inst.elevation;
         normal = normalize(cross(world_dx - world, world_dy - world));
     }
@@ -138,6 +140,18 @@ fn fs_tile(in: VertexOutput) -> @location(0) vec4<f32> {
         let lw = globals.grid_params.z;
         let t = smoothstep(0.0, lw, nearest);
         final_color = mix(vec3<f32>(0.06, 0.06, 0.10), final_color, t);
+
+        // Hatch the squares near each corner, where q cells meet and
+        // building is not allowed
+        let half = globals.grid_params.y * 0.5;
+        let square = floor(gp);
+        let r = globals.corner_exclusion;
+        if half - abs(square.x) < r && half - abs(square.y) < r {
+            let stripe = fract((gp.x + gp.y) * 0.5);
+            if stripe < 0.5 {
+                final_color = mix(final_color, vec3<f32>(0.35, 0.08, 0.10), 0.6);
+            }
+        }
     }
 
     return vec4<f32>(final_color, 1.0);
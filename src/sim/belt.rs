@@ -1,8 +1,12 @@
 use slotmap::{new_key_type, SlotMap, SecondaryMap};
+use serde::{Deserialize, Serialize};
 
 use crate::game::items::ItemId;
 use crate::game::world::{Direction, EntityId, StructureKind, WorldState};
+use crate::hyperbolic::cell_id::CellId;
 use crate::sim::machine::MachinePool;
+use crate::sim::parallel;
+use crate::sim::state_hash::StateHasher;
 
 new_key_type! {
     /// Identifies a transport line in the belt network.
@@ -20,8 +24,19 @@ pub const DEFAULT_BELT_SPEED: u16 = 4;
 /// 64 = 1/4 grid square → max 4 items per grid square.
 pub const MIN_ITEM_GAP: u32 = 64;
 
+/// Window over which a belt sensor averages its throughput: one minute at 60 UPS.
+pub const SENSOR_WINDOW_TICKS: u64 = 3600;
+
+/// Ticks per sensor bucket (one second at 60 UPS).
+const SENSOR_BUCKET_TICKS: u64 = 60;
+
+const SENSOR_BUCKETS: usize = (SENSOR_WINDOW_TICKS / SENSOR_BUCKET_TICKS) as usize;
+
+/// Smoothing factor for a line's measured output flow (about two seconds).
+const FLOW_SMOOTHING: f32 = 1.0 / 120.0;
+
 /// What's connected at one end of a transport line.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BeltEnd {
     /// Nothing connected — items stop here.
     Open,
@@ -44,10 +59,16 @@ pub enum BeltEnd {
     StorageInput { entity: EntityId, slot: usize },
     /// Storage output port feeds into belt input.
     StorageOutput { entity: EntityId, slot: usize },
+    /// Belt output feeds into a sink, which destroys the items.
+    SinkInput { entity: EntityId },
+    /// Belt endpoint connected to a loader.
+    /// When on output_end: belt feeds the loader (loader in Load mode).
+    /// When on input_end: loader feeds the belt (loader in Unload mode).
+    Loader { entity: EntityId },
 }
 
 /// An item riding on a transport line.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BeltItem {
     pub item: ItemId,
     /// Fixed-point distance from the output end.
@@ -57,6 +78,7 @@ pub struct BeltItem {
 
 /// A single transport line — possibly spanning multiple consecutive belt segments.
 /// Items flow from input_end (pos = length) toward output_end (pos = 0).
+#[derive(Serialize, Deserialize)]
 pub struct TransportLine {
     /// Items on the line, ordered front (output) to back (input).
     pub items: Vec<BeltItem>,
@@ -68,6 +90,21 @@ pub struct TransportLine {
     pub input_end: BeltEnd,
     /// Where items exit this line.
     pub output_end: BeltEnd,
+    /// Set by the circuit network: a halted line neither moves nor hands
+    /// items off at its output end.
+    pub halted: bool,
+    /// Network tick at which this line's cell went to sleep, or the tick it
+    /// was last caught up to. `None` while awake.
+    pub asleep_since: Option<u64>,
+    /// Smoothed items per tick handed to the next line. Frozen while either
+    /// end of the link is asleep, which pins the flow across the boundary.
+    pub flow: f32,
+    /// Accumulated pinned flow; one item crosses a sleep boundary per unit.
+    boundary_credit: f32,
+    /// Off the network's active list: nothing on the line can move until an
+    /// event (an insert, a take, the next line moving, a connection change)
+    /// wakes it.
+    idle: bool,
 }
 
 impl TransportLine {
@@ -78,7 +115,52 @@ impl TransportLine {
             length,
             input_end: BeltEnd::Open,
             output_end: BeltEnd::Open,
+            halted: false,
+            asleep_since: None,
+            flow: 0.0,
+            boundary_credit: 0.0,
+            idle: false,
+        }
+    }
+
+    /// The front item, if it has reached the output end and the line is
+    /// neither halted nor asleep.
+    pub fn ready_item(&self) -> Option<ItemId> {
+        match self.items.first() {
+            Some(front) if front.pos == 0 && !self.halted && self.asleep_since.is_none() => Some(front.item),
+            _ => None,
+        }
+    }
+
+    pub fn is_asleep(&self) -> bool {
+        self.asleep_since.is_some()
+    }
+
+    /// Bring an asleep line's items up to `now` in closed form: every item
+    /// moves `speed × elapsed` toward the output, compacting behind the one
+    /// ahead. Exact for a line that hands nothing off in the meantime.
+    fn catch_up(&mut self, now: u64) {
+        let Some(since) = self.asleep_since else {
+            return;
+        };
+        if !self.halted {
+            let distance = (now - since).saturating_mul(self.speed as u64);
+            self.advance_by(distance.min(u32::MAX as u64) as u32, &mut []);
         }
+        self.asleep_since = Some(now);
+    }
+
+    /// Whether advancing would leave every item where it is: the line is
+    /// empty or fully compressed against its output end.
+    fn is_settled(&self) -> bool {
+        let mut min = 0;
+        for item in &self.items {
+            if item.pos != min {
+                return false;
+            }
+            min = item.pos.saturating_add(MIN_ITEM_GAP);
+        }
+        true
     }
 
     /// Check whether the input end has room for another item.
@@ -121,24 +203,111 @@ impl TransportLine {
         self.items.insert(idx, BeltItem { item, pos: offset });
     }
 
-    /// Advance all items toward the output by `speed` units.
-    /// Items compress against each other (min gap enforced).
-    fn advance(&mut self) {
-        let speed = self.speed as u32;
-        for i in 0..self.items.len() {
-            let new_pos = self.items[i].pos.saturating_sub(speed);
-            let min = if i == 0 {
-                0
-            } else {
-                self.items[i - 1].pos.saturating_add(MIN_ITEM_GAP)
-            };
-            self.items[i].pos = new_pos.max(min);
+    /// Move items `distance` units toward the output end, counting each item
+    /// that crosses a probe position. Returns whether any item moved.
+    fn advance_by(&mut self, distance: u32, probes: &mut [LineProbe]) -> bool {
+        advance_items(&mut self.items, distance, probes)
+    }
+}
+
+/// Move items `distance` units toward the output end, counting each item
+/// that crosses a probe position. Items compress against each other (min gap
+/// enforced). Returns whether any item moved.
+fn advance_items(items: &mut [BeltItem], distance: u32, probes: &mut [LineProbe]) -> bool {
+    let mut moved = false;
+    for i in 0..items.len() {
+        let old_pos = items[i].pos;
+        let new_pos = old_pos.saturating_sub(distance);
+        let min = if i == 0 {
+            0
+        } else {
+            items[i - 1].pos.saturating_add(MIN_ITEM_GAP)
+        };
+        items[i].pos = new_pos.max(min);
+        moved |= items[i].pos != old_pos;
+        for probe in probes.iter_mut() {
+            if old_pos >= probe.pos && items[i].pos < probe.pos {
+                probe.crossings += 1;
+            }
+        }
+    }
+    moved
+}
+
+/// One line's items lifted out of the network for a parallel advance.
+struct LineAdvance {
+    line: TransportLineId,
+    items: Vec<BeltItem>,
+    distance: u32,
+    probes: Vec<LineProbe>,
+    moved: bool,
+}
+
+/// A sensor's position on a line for one advance: the centre of its segment.
+struct LineProbe {
+    entity: EntityId,
+    pos: u32,
+    crossings: u32,
+}
+
+/// Throughput counter on a belt segment. Counts items passing the segment's
+/// centre, bucketed per second over a one-minute window.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BeltSensor {
+    /// Items passed since the sensor was switched on.
+    pub total: u64,
+    /// Ticks since the sensor was switched on.
+    ticks: u64,
+    #[serde(with = "sensor_buckets")]
+    buckets: [u32; SENSOR_BUCKETS],
+}
+
+/// Serde for the bucket array, which is longer than serde handles itself.
+mod sensor_buckets {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::SENSOR_BUCKETS;
+
+    pub fn serialize<S: Serializer>(buckets: &[u32; SENSOR_BUCKETS], serializer: S) -> Result<S::Ok, S::Error> {
+        buckets.as_slice().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u32; SENSOR_BUCKETS], D::Error> {
+        Vec::<u32>::deserialize(deserializer)?
+            .try_into()
+            .map_err(|_| de::Error::custom("wrong number of sensor buckets"))
+    }
+}
+
+impl BeltSensor {
+    fn new() -> Self {
+        Self { total: 0, ticks: 0, buckets: [0; SENSOR_BUCKETS] }
+    }
+
+    fn record(&mut self, crossings: u32) {
+        let bucket = ((self.ticks / SENSOR_BUCKET_TICKS) as usize) % self.buckets.len();
+        if self.ticks.is_multiple_of(SENSOR_BUCKET_TICKS) {
+            self.buckets[bucket] = 0;
+        }
+        self.buckets[bucket] += crossings;
+        self.total += crossings as u64;
+        self.ticks += 1;
+    }
+
+    /// Items per minute over the last minute, extrapolated while the sensor
+    /// has been on for less than that.
+    pub fn items_per_minute(&self) -> f32 {
+        if self.ticks == 0 {
+            return 0.0;
         }
+        let passed: u32 = self.buckets.iter().sum();
+        let window = self.ticks.min(SENSOR_WINDOW_TICKS);
+        passed as f32 * SENSOR_WINDOW_TICKS as f32 / window as f32
     }
 }
 
 /// Tracks a belt entity's position within a (possibly merged) transport line.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct BeltSegment {
     /// Which transport line this entity belongs to.
     pub line: TransportLineId,
@@ -148,9 +317,19 @@ pub struct BeltSegment {
 }
 
 /// The belt simulation network — manages all transport lines.
+#[derive(Serialize, Deserialize)]
 pub struct BeltNetwork {
     lines: SlotMap<TransportLineId, TransportLine>,
     segments: SecondaryMap<EntityId, BeltSegment>,
+    /// Belt segments with a throughput sensor switched on.
+    sensors: SecondaryMap<EntityId, BeltSensor>,
+    /// Ticks run so far; asleep lines record when they stopped against it.
+    tick_count: u64,
+    /// Lines that are not idle, in the order they were woken. Only these are
+    /// visited each tick.
+    active: Vec<TransportLineId>,
+    /// Lines halted by the circuit network, in the order they were halted.
+    halted: Vec<TransportLineId>,
 }
 
 impl BeltNetwork {
@@ -158,15 +337,150 @@ impl BeltNetwork {
         Self {
             lines: SlotMap::with_key(),
             segments: SecondaryMap::new(),
+            sensors: SecondaryMap::new(),
+            tick_count: 0,
+            active: Vec::new(),
+            halted: Vec::new(),
         }
     }
 
+    /// Feed the network's state into a state hash. Lines are identified by
+    /// the belt at their output end, so the hash doesn't depend on line ids.
+    pub fn hash_state(&self, h: &mut StateHasher) {
+        let mut heads: SecondaryMap<TransportLineId, EntityId> = SecondaryMap::new();
+        for (entity, seg) in &self.segments {
+            if seg.offset == 0 {
+                heads.insert(seg.line, entity);
+            }
+        }
+        let hash_end = |h: &mut StateHasher, end: BeltEnd| match end {
+            BeltEnd::Open => h.u8(0),
+            BeltEnd::Belt(line) => {
+                h.u8(1);
+                if let Some(&head) = heads.get(line) {
+                    h.entity(head);
+                }
+            }
+            BeltEnd::MachineInput { entity, slot }
+            | BeltEnd::MachineOutput { entity, slot }
+            | BeltEnd::StorageInput { entity, slot }
+            | BeltEnd::StorageOutput { entity, slot } => {
+                h.u8(2);
+                h.entity(entity);
+                h.u32(slot as u32);
+            }
+            BeltEnd::SideInject { entity }
+            | BeltEnd::Splitter { entity }
+            | BeltEnd::SinkInput { entity }
+            | BeltEnd::Loader { entity } => {
+                h.u8(3);
+                h.entity(entity);
+            }
+        };
+
+        let mut segments: Vec<(EntityId, BeltSegment)> = self.segments.iter().map(|(e, s)| (e, *s)).collect();
+        segments.sort_by_key(|&(entity, _)| entity);
+        h.u32(segments.len() as u32);
+        for (entity, seg) in segments {
+            h.entity(entity);
+            h.u32(seg.offset);
+            if seg.offset != 0 {
+                continue;
+            }
+            let line = &self.lines[seg.line];
+            h.u32(line.length);
+            h.u16(line.speed);
+            h.bool(line.halted);
+            h.u64(line.asleep_since.map_or(u64::MAX, |t| t));
+            h.f32(line.flow);
+            h.f32(line.boundary_credit);
+            hash_end(h, line.input_end);
+            hash_end(h, line.output_end);
+            h.u32(line.items.len() as u32);
+            for item in &line.items {
+                h.item(item.item);
+                h.u32(item.pos);
+            }
+        }
+
+        let mut sensors: Vec<(EntityId, &BeltSensor)> = self.sensors.iter().collect();
+        sensors.sort_by_key(|&(entity, _)| entity);
+        for (entity, sensor) in sensors {
+            h.entity(entity);
+            h.u64(sensor.total);
+            h.u64(sensor.ticks);
+            for &bucket in &sensor.buckets {
+                h.u32(bucket);
+            }
+        }
+        h.u64(self.tick_count);
+    }
+
+    /// Add a transport line to the network, awake.
+    fn insert_line(&mut self, line: TransportLine) -> TransportLineId {
+        let id = self.lines.insert(TransportLine { idle: false, ..line });
+        self.active.push(id);
+        id
+    }
+
+    /// Put an idle line back on the active list.
+    fn wake_line(&mut self, id: TransportLineId) {
+        if let Some(line) = self.lines.get_mut(id) {
+            if line.idle {
+                line.idle = false;
+                self.active.push(id);
+            }
+        }
+    }
+
+    /// Whether a line can leave the active list: nothing on it will move next
+    /// tick, and whatever could change that wakes it. Lines polled by machine,
+    /// storage, and sink ports, lines side-injecting, and links across a
+    /// sleep boundary have no such event and stay active while they have
+    /// work pending.
+    fn can_idle(&self, line: &TransportLine) -> bool {
+        if matches!(line.input_end, BeltEnd::MachineOutput { .. } | BeltEnd::StorageOutput { .. }) {
+            return false;
+        }
+        let target = match line.output_end {
+            BeltEnd::Belt(target_id) => self.lines.get(target_id),
+            _ => None,
+        };
+        if target.is_some_and(|t| t.is_asleep() != line.is_asleep()) {
+            return false;
+        }
+        if line.is_asleep() {
+            return true;
+        }
+        if !line.halted && !line.is_settled() {
+            return false;
+        }
+        if line.ready_item().is_none() {
+            return true;
+        }
+        match line.output_end {
+            BeltEnd::Open | BeltEnd::Splitter { .. } | BeltEnd::Loader { .. } => true,
+            BeltEnd::Belt(_) => target.is_none_or(|t| !t.can_accept_at_input()),
+            _ => false,
+        }
+    }
+
+    /// Number of lines visited each tick.
+    pub fn active_line_count(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Number of transport lines in the network.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
     /// Called after a belt entity is placed in the world.
     /// Merges consecutive same-direction segments within a tile into one line.
     pub fn on_belt_placed(
         &mut self,
         entity: EntityId,
-        tile: &[u8],
+        tile: &CellId,
         gx: i32,
         gy: i32,
         direction: Direction,
@@ -195,7 +509,7 @@ impl BeltNetwork {
         match (upstream_seg, downstream_seg) {
             (None, None) => {
                 // No neighbors — create a new single-segment line.
-                let line_id = self.lines.insert(TransportLine::new(FP_SCALE));
+                let line_id = self.insert_line(TransportLine::new(FP_SCALE));
                 self.segments.insert(entity, BeltSegment { line: line_id, offset: 0 });
             }
             (Some(up), None) => {
@@ -225,7 +539,7 @@ impl BeltNetwork {
             (Some(up), Some(down)) if up.line == down.line => {
                 // Both on the same line already (filling a gap) — shouldn't normally happen.
                 // Create a standalone line as a fallback.
-                let line_id = self.lines.insert(TransportLine::new(FP_SCALE));
+                let line_id = self.insert_line(TransportLine::new(FP_SCALE));
                 self.segments.insert(entity, BeltSegment { line: line_id, offset: 0 });
             }
             (Some(up), Some(down)) => {
@@ -263,6 +577,7 @@ impl BeltNetwork {
                     if let Some(feeder) = self.lines.get_mut(feeder_id) {
                         feeder.output_end = BeltEnd::Belt(down_line_id);
                     }
+                    self.wake_line(feeder_id);
                 }
 
                 // Reassign all upstream segments to the downstream line.
@@ -283,6 +598,7 @@ impl BeltNetwork {
 
         // --- Perpendicular side-inject detection ---
         let new_seg = *self.segments.get(entity).unwrap();
+        self.wake_line(new_seg.line);
 
         // Forward: if this belt is at the output end and faces a perpendicular belt ahead
         if new_seg.offset == 0 {
@@ -318,6 +634,7 @@ impl BeltNetwork {
                         if other_output_open {
                             self.lines.get_mut(other_seg.line).unwrap().output_end =
                                 BeltEnd::SideInject { entity };
+                            self.wake_line(other_seg.line);
                         }
                     }
                 }
@@ -325,43 +642,85 @@ impl BeltNetwork {
         }
     }
 
-    /// Run one simulation tick for all transport lines.
+    /// The belt component of each advancing line: lines joined output to
+    /// input share one, named by the index of a member in `advances`.
+    fn components(&self, advances: &[LineAdvance]) -> Vec<usize> {
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        let mut index: SecondaryMap<TransportLineId, usize> = SecondaryMap::new();
+        for (i, adv) in advances.iter().enumerate() {
+            index.insert(adv.line, i);
+        }
+        let mut parent: Vec<usize> = (0..advances.len()).collect();
+        for (i, adv) in advances.iter().enumerate() {
+            let target = match self.lines[adv.line].output_end {
+                BeltEnd::Belt(target) => Some(target),
+                BeltEnd::SideInject { entity } => self.segments.get(entity).map(|seg| seg.line),
+                _ => None,
+            };
+            if let Some(&j) = target.and_then(|t| index.get(t)) {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+        (0..advances.len()).map(|i| root(&mut parent, i)).collect()
+    }
+
+    /// Run one simulation tick for the active transport lines. Idle lines
+    /// are skipped; they cannot move until something wakes them.
     pub fn tick(&mut self) {
-        let line_ids: Vec<TransportLineId> = self.lines.keys().collect();
+        self.tick_with(parallel::MIN_PART);
+    }
+
+    /// `tick`, packing the line advance into parts of at least `min_part`
+    /// lines.
+    fn tick_with(&mut self, min_part: usize) {
+        let mut line_ids = std::mem::take(&mut self.active);
 
         // Phase 1: Transfer items at output ends to connected inputs.
         let mut transfers: Vec<(TransportLineId, TransportLineId)> = Vec::new();
         let mut side_injects: Vec<(TransportLineId, TransportLineId, u32)> = Vec::new();
+        let mut boundary: Vec<(TransportLineId, TransportLineId)> = Vec::new();
+        let mut flow_samples: Vec<(TransportLineId, bool)> = Vec::new();
 
         for &line_id in &line_ids {
             let line = match self.lines.get(line_id) {
                 Some(l) => l,
                 None => continue,
             };
-            if line.items.is_empty() {
+            // Belt-to-belt links measure their flow while both ends are awake
+            // and run at that pinned rate while either end sleeps
+            if let BeltEnd::Belt(target_id) = line.output_end {
+                let Some(target) = self.lines.get(target_id) else {
+                    continue;
+                };
+                if line.is_asleep() || target.is_asleep() {
+                    if !(line.is_asleep() && target.is_asleep()) {
+                        boundary.push((line_id, target_id));
+                    }
+                    continue;
+                }
+                let moved = line.ready_item().is_some() && target.can_accept_at_input();
+                if moved {
+                    transfers.push((line_id, target_id));
+                }
+                flow_samples.push((line_id, moved));
                 continue;
             }
-            // Front item sitting at the output end?
-            if line.items[0].pos == 0 {
-                match line.output_end {
-                    BeltEnd::Belt(target_id) => {
-                        if let Some(target) = self.lines.get(target_id) {
-                            if target.can_accept_at_input() {
-                                transfers.push((line_id, target_id));
-                            }
-                        }
-                    }
-                    BeltEnd::SideInject { entity: target_entity } => {
-                        if let Some(target_seg) = self.segments.get(target_entity).copied() {
-                            let inject_offset = target_seg.offset + FP_SCALE / 2;
-                            if let Some(target_line) = self.lines.get(target_seg.line) {
-                                if target_line.can_accept_at_offset(inject_offset) {
-                                    side_injects.push((line_id, target_seg.line, inject_offset));
-                                }
-                            }
+            // Front item sitting at the output end, side-injecting into another belt?
+            if let (Some(_), BeltEnd::SideInject { entity: target_entity }) = (line.ready_item(), line.output_end) {
+                if let Some(target_seg) = self.segments.get(target_entity).copied() {
+                    let inject_offset = target_seg.offset + FP_SCALE / 2;
+                    if let Some(target_line) = self.lines.get(target_seg.line) {
+                        if target_line.can_accept_at_offset(inject_offset) {
+                            side_injects.push((line_id, target_seg.line, inject_offset));
                         }
                     }
-                    _ => {}
                 }
             }
         }
@@ -373,6 +732,7 @@ impl BeltNetwork {
             };
             let target = self.lines.get_mut(target_id).unwrap();
             target.insert_at_input(item);
+            self.wake_line(target_id);
         }
 
         for (source_id, target_id, offset) in side_injects {
@@ -382,14 +742,178 @@ impl BeltNetwork {
             };
             let target = self.lines.get_mut(target_id).unwrap();
             target.insert_at_offset(item, offset);
+            self.wake_line(target_id);
+        }
+
+        for (line_id, moved) in flow_samples {
+            let line = self.lines.get_mut(line_id).unwrap();
+            let sample = if moved { 1.0 } else { 0.0 };
+            line.flow += (sample - line.flow) * FLOW_SMOOTHING;
+        }
+
+        for (source_id, target_id) in boundary {
+            self.pinned_transfer(source_id, target_id);
         }
 
-        // Phase 2: Advance all items toward output.
+        // Lines woken by a handoff advance this tick too
+        line_ids.append(&mut self.active);
+
+        // Phase 2: Advance all items toward output, counting sensor crossings.
+        // Lines advance independently, so their items are lifted out and
+        // moved in parallel parts, then put back in order. Each part holds
+        // whole belt components, so a chain of linked lines stays together.
+        let mut probes: SecondaryMap<TransportLineId, Vec<LineProbe>> = SecondaryMap::new();
+        for (entity, _) in &self.sensors {
+            if let Some(seg) = self.segments.get(entity) {
+                if !probes.contains_key(seg.line) {
+                    probes.insert(seg.line, Vec::new());
+                }
+                probes[seg.line].push(LineProbe { entity, pos: seg.offset + FP_SCALE / 2, crossings: 0 });
+            }
+        }
+        let mut advances: Vec<LineAdvance> = Vec::new();
         for &line_id in &line_ids {
             if let Some(line) = self.lines.get_mut(line_id) {
-                line.advance();
+                if !line.halted && !line.is_asleep() {
+                    advances.push(LineAdvance {
+                        line: line_id,
+                        items: std::mem::take(&mut line.items),
+                        distance: line.speed as u32,
+                        probes: probes.remove(line_id).unwrap_or_default(),
+                        moved: false,
+                    });
+                }
             }
         }
+        let (part_of, part_count) = parallel::pack_groups(&self.components(&advances), min_part);
+        let mut parts: Vec<Vec<&mut LineAdvance>> = (0..part_count).map(|_| Vec::new()).collect();
+        for (adv, &part) in advances.iter_mut().zip(&part_of) {
+            parts[part].push(adv);
+        }
+        parallel::run(parts, |part| {
+            for adv in part {
+                adv.moved = advance_items(&mut adv.items, adv.distance, &mut adv.probes);
+            }
+        });
+        let mut crossings: SecondaryMap<EntityId, u32> = SecondaryMap::new();
+        for adv in advances {
+            let line = &mut self.lines[adv.line];
+            line.items = adv.items;
+            for probe in adv.probes {
+                crossings.insert(probe.entity, probe.crossings);
+            }
+            // Movement may open room at the input end for the line feeding this one
+            if let (true, BeltEnd::Belt(feeder_id)) = (adv.moved, line.input_end) {
+                self.wake_line(feeder_id);
+            }
+        }
+        for (entity, sensor) in self.sensors.iter_mut() {
+            sensor.record(crossings.get(entity).copied().unwrap_or(0));
+        }
+
+        // Phase 3: Lines with nothing left to do drop off the active list.
+        for line_id in line_ids {
+            let Some(line) = self.lines.get(line_id) else {
+                continue;
+            };
+            if self.can_idle(line) {
+                self.lines[line_id].idle = true;
+            } else {
+                self.active.push(line_id);
+            }
+        }
+
+        self.tick_count += 1;
+    }
+
+    /// Move one item across a belt link with one end asleep, at the link's
+    /// pinned flow. The asleep end is caught up first so its front (or back)
+    /// is where it would be had it kept ticking.
+    fn pinned_transfer(&mut self, source_id: TransportLineId, target_id: TransportLineId) {
+        let now = self.tick_count;
+        let Some(source) = self.lines.get_mut(source_id) else {
+            return;
+        };
+        source.boundary_credit = (source.boundary_credit + source.flow).min(1.0);
+        if source.boundary_credit < 1.0 {
+            return;
+        }
+        source.catch_up(now);
+        if source.halted || source.items.first().is_none_or(|front| front.pos != 0) {
+            return;
+        }
+        let Some(target) = self.lines.get_mut(target_id) else {
+            return;
+        };
+        target.catch_up(now);
+        if !target.can_accept_at_input() {
+            return;
+        }
+        let source = self.lines.get_mut(source_id).unwrap();
+        let item = source.items.remove(0).item;
+        source.boundary_credit -= 1.0;
+        self.lines.get_mut(target_id).unwrap().insert_at_input(item);
+        self.wake_line(target_id);
+    }
+
+    /// Freeze the transport lines of these belts (their cell went to sleep).
+    /// Frozen lines neither move nor hand items off, except across a link to
+    /// an awake line, which keeps its pinned flow.
+    pub fn sleep_belts(&mut self, belts: impl IntoIterator<Item = EntityId>) {
+        for entity in belts {
+            let Some(seg) = self.segments.get(entity).copied() else {
+                continue;
+            };
+            if let Some(line) = self.lines.get_mut(seg.line) {
+                line.asleep_since.get_or_insert(self.tick_count);
+                let feeder = line.input_end;
+                self.wake_line_and_feeder(seg.line, feeder);
+            }
+        }
+    }
+
+    /// Wake the transport lines of these belts, catching each up in closed
+    /// form for the ticks it slept.
+    pub fn wake_belts(&mut self, belts: impl IntoIterator<Item = EntityId>) {
+        for entity in belts {
+            let Some(seg) = self.segments.get(entity).copied() else {
+                continue;
+            };
+            if let Some(line) = self.lines.get_mut(seg.line) {
+                line.catch_up(self.tick_count);
+                line.asleep_since = None;
+                let feeder = line.input_end;
+                self.wake_line_and_feeder(seg.line, feeder);
+            }
+        }
+    }
+
+    /// Wake a line whose sleep state changed, and the line feeding it: a
+    /// link across a sleep boundary has to stay active on both sides.
+    fn wake_line_and_feeder(&mut self, line_id: TransportLineId, input_end: BeltEnd) {
+        self.wake_line(line_id);
+        if let BeltEnd::Belt(feeder_id) = input_end {
+            self.wake_line(feeder_id);
+        }
+    }
+
+    /// Switch the throughput sensor on a belt segment on or off. Switching
+    /// on resets its counts. Returns false if the entity is not a belt.
+    pub fn set_sensor(&mut self, entity: EntityId, on: bool) -> bool {
+        if !self.segments.contains_key(entity) {
+            return false;
+        }
+        if on {
+            self.sensors.insert(entity, BeltSensor::new());
+        } else {
+            self.sensors.remove(entity);
+        }
+        true
+    }
+
+    /// Throughput sensor on a belt segment, if switched on.
+    pub fn sensor(&self, entity: EntityId) -> Option<&BeltSensor> {
+        self.sensors.get(entity)
     }
 
     /// Debug: spawn an item at the center of the belt entity's segment.
@@ -399,6 +923,7 @@ impl BeltNetwork {
                 let pos = seg.offset + FP_SCALE / 2;
                 let idx = line.items.partition_point(|i| i.pos < pos);
                 line.items.insert(idx, BeltItem { item, pos });
+                self.wake_line(seg.line);
             }
         }
     }
@@ -414,6 +939,39 @@ impl BeltNetwork {
         Some((&line.items[start..end], seg.offset))
     }
 
+    /// Halt exactly the transport lines these belt entities belong to and
+    /// release the rest. Halting any belt on a line halts the whole line.
+    /// Only lines whose halt state changes are woken, so a line held halted
+    /// tick after tick stays idle.
+    pub fn set_halted_entities(&mut self, entities: impl IntoIterator<Item = EntityId>) {
+        let mut halted = Vec::new();
+        let mut halted_set = std::collections::HashSet::new();
+        for entity in entities {
+            if let Some(seg) = self.segments.get(entity) {
+                if self.lines.contains_key(seg.line) && halted_set.insert(seg.line) {
+                    halted.push(seg.line);
+                }
+            }
+        }
+        for line_id in std::mem::take(&mut self.halted) {
+            if halted_set.contains(&line_id) {
+                continue;
+            }
+            if let Some(line) = self.lines.get_mut(line_id) {
+                line.halted = false;
+                self.wake_line(line_id);
+            }
+        }
+        for &line_id in &halted {
+            let line = &mut self.lines[line_id];
+            if !line.halted {
+                line.halted = true;
+                self.wake_line(line_id);
+            }
+        }
+        self.halted = halted;
+    }
+
     /// Link the output end of `source`'s transport line to the input end of
     /// `target`'s transport line. Used for cross-tile belt connections where
     /// items should transfer across tile boundaries.
@@ -435,6 +993,7 @@ impl BeltNetwork {
         if let Some(line) = self.lines.get_mut(target_line_id) {
             line.input_end = BeltEnd::Belt(source_line_id);
         }
+        self.wake_line(source_line_id);
     }
 
     /// Connect a belt's transport line output to a machine input port.
@@ -459,6 +1018,7 @@ impl BeltNetwork {
                 slot,
             };
         }
+        self.wake_line(seg.line);
     }
 
     /// Connect a belt's transport line input to a machine output port.
@@ -487,6 +1047,7 @@ impl BeltNetwork {
                 slot,
             };
         }
+        self.wake_line(seg.line);
     }
 
     /// Disconnect all belt connections to/from a machine entity.
@@ -531,6 +1092,7 @@ impl BeltNetwork {
             return false;
         }
         line.output_end = BeltEnd::Splitter { entity: splitter_entity };
+        self.wake_line(seg.line);
         true
     }
 
@@ -558,6 +1120,7 @@ impl BeltNetwork {
             return false;
         }
         line.input_end = BeltEnd::Splitter { entity: splitter_entity };
+        self.wake_line(seg.line);
         true
     }
 
@@ -599,6 +1162,7 @@ impl BeltNetwork {
                 slot,
             };
         }
+        self.wake_line(seg.line);
     }
 
     /// Connect a storage output port to a belt's transport line input.
@@ -626,6 +1190,7 @@ impl BeltNetwork {
                 slot,
             };
         }
+        self.wake_line(seg.line);
     }
 
     /// Disconnect all belt connections to/from a storage entity.
@@ -667,40 +1232,125 @@ impl BeltNetwork {
         (inputs, outputs)
     }
 
+    /// Connect a belt's transport line output to a sink's input port.
+    /// Only succeeds if the belt entity is at the output end of its line.
+    pub fn connect_belt_to_sink_input(&mut self, belt_entity: EntityId, sink_entity: EntityId) {
+        let seg = match self.segments.get(belt_entity) {
+            Some(s) => *s,
+            None => return,
+        };
+        if seg.offset != 0 {
+            return;
+        }
+        if let Some(line) = self.lines.get_mut(seg.line) {
+            line.output_end = BeltEnd::SinkInput { entity: sink_entity };
+        }
+        self.wake_line(seg.line);
+    }
+
+    /// Disconnect all belts feeding a sink entity, setting their output ends back to Open.
+    pub fn disconnect_sink_ports(&mut self, sink_entity: EntityId) {
+        for (_id, line) in self.lines.iter_mut() {
+            if line.output_end == (BeltEnd::SinkInput { entity: sink_entity }) {
+                line.output_end = BeltEnd::Open;
+            }
+        }
+    }
+
+    /// Connect a belt's output end to a loader (belt feeds into loader).
+    /// Only succeeds if the belt entity is at the output end of its line
+    /// and the output end is currently Open.
+    pub fn connect_belt_to_loader(&mut self, belt_entity: EntityId, loader_entity: EntityId) -> bool {
+        let seg = match self.segments.get(belt_entity) {
+            Some(s) => *s,
+            None => return false,
+        };
+        if seg.offset != 0 {
+            return false;
+        }
+        let line = match self.lines.get_mut(seg.line) {
+            Some(l) => l,
+            None => return false,
+        };
+        if line.output_end != BeltEnd::Open {
+            return false;
+        }
+        line.output_end = BeltEnd::Loader { entity: loader_entity };
+        self.wake_line(seg.line);
+        true
+    }
+
+    /// Connect a loader to a belt's input end (loader feeds belt).
+    /// Only succeeds if the belt entity is at the input end of its line
+    /// and the input end is currently Open.
+    pub fn connect_loader_to_belt(&mut self, belt_entity: EntityId, loader_entity: EntityId) -> bool {
+        let seg = match self.segments.get(belt_entity) {
+            Some(s) => *s,
+            None => return false,
+        };
+        let line = match self.lines.get_mut(seg.line) {
+            Some(l) => l,
+            None => return false,
+        };
+        if seg.offset != line.length - FP_SCALE || line.input_end != BeltEnd::Open {
+            return false;
+        }
+        line.input_end = BeltEnd::Loader { entity: loader_entity };
+        self.wake_line(seg.line);
+        true
+    }
+
+    /// Disconnect all belt connections to/from a loader entity.
+    /// Sets any BeltEnd::Loader referencing this loader back to Open.
+    pub fn disconnect_loader_ports(&mut self, loader_entity: EntityId) {
+        let end = BeltEnd::Loader { entity: loader_entity };
+        for (_id, line) in self.lines.iter_mut() {
+            if line.output_end == end {
+                line.output_end = BeltEnd::Open;
+            }
+            if line.input_end == end {
+                line.input_end = BeltEnd::Open;
+            }
+        }
+    }
+
     /// Check if a belt entity's line has a front item at pos=0 ready to take.
     pub fn peek_front_item(&self, belt_entity: EntityId) -> Option<ItemId> {
         let seg = self.segments.get(belt_entity)?;
-        let line = self.lines.get(seg.line)?;
-        if !line.items.is_empty() && line.items[0].pos == 0 {
-            Some(line.items[0].item)
-        } else {
-            None
-        }
+        self.lines.get(seg.line)?.ready_item()
     }
 
     /// Take the front item from a belt entity's transport line (pos=0).
     pub fn take_front_item(&mut self, belt_entity: EntityId) -> Option<ItemId> {
         let seg = self.segments.get(belt_entity)?;
-        let line = self.lines.get_mut(seg.line)?;
-        if !line.items.is_empty() && line.items[0].pos == 0 {
-            Some(line.items.remove(0).item)
-        } else {
-            None
-        }
+        let line_id = seg.line;
+        let line = self.lines.get_mut(line_id)?;
+        line.ready_item()?;
+        let item = line.items.remove(0).item;
+        self.wake_line(line_id);
+        Some(item)
     }
 
     /// Check if a belt entity's line can accept an item at its input end.
-    pub fn can_accept_at_entity_input(&self, belt_entity: EntityId) -> bool {
+    /// An asleep line is caught up first, so the check sees where its items
+    /// are now rather than where they stopped.
+    pub fn can_accept_at_entity_input(&mut self, belt_entity: EntityId) -> bool {
         let seg = match self.segments.get(belt_entity) {
             Some(s) => s,
             None => return false,
         };
-        self.lines.get(seg.line)
-            .map(|l| l.can_accept_at_input())
+        let now = self.tick_count;
+        self.lines.get_mut(seg.line)
+            .map(|l| {
+                l.catch_up(now);
+                l.can_accept_at_input()
+            })
             .unwrap_or(false)
     }
 
-    /// Push an item to the input end of a belt entity's transport line.
+    /// Push an item to the input end of a belt entity's transport line. An
+    /// asleep line is caught up first, so the item lands behind the others
+    /// where they are now.
     pub fn push_to_entity_input(&mut self, belt_entity: EntityId, item: ItemId) -> bool {
         let seg = match self.segments.get(belt_entity) {
             Some(s) => *s,
@@ -710,8 +1360,10 @@ impl BeltNetwork {
             Some(l) => l,
             None => return false,
         };
+        line.catch_up(self.tick_count);
         if line.can_accept_at_input() {
             line.insert_at_input(item);
+            self.wake_line(seg.line);
             true
         } else {
             false
@@ -740,14 +1392,16 @@ impl BeltNetwork {
         (output_splitter, input_splitter)
     }
 
-    /// Run port transfers: move items between belt endpoints and machine/storage ports.
-    /// Call this each tick after belt advance and machine tick.
+    /// Run port transfers: move items between belt endpoints and machine/storage/sink ports.
+    /// Call this each tick after belt advance and machine tick. Lines with a
+    /// port transfer pending never go idle, so only active lines are checked.
     pub fn tick_port_transfers(
         &mut self,
         machine_pool: &mut MachinePool,
         storage_pool: &mut crate::sim::storage::StoragePool,
+        sink_pool: &mut crate::sim::sink::SinkPool,
     ) {
-        let line_ids: Vec<TransportLineId> = self.lines.keys().collect();
+        let line_ids = self.active.clone();
 
         // Phase 1: Belt → Machine (input ports)
         // Items at a belt's output end (pos=0) transfer into machine input slots.
@@ -758,8 +1412,8 @@ impl BeltNetwork {
                 None => continue,
             };
             if let BeltEnd::MachineInput { entity, slot } = line.output_end {
-                if !line.items.is_empty() && line.items[0].pos == 0 {
-                    belt_to_machine.push((line_id, line.items[0].item, entity, slot));
+                if let Some(item) = line.ready_item() {
+                    belt_to_machine.push((line_id, item, entity, slot));
                 }
             }
         }
@@ -802,8 +1456,8 @@ impl BeltNetwork {
                 None => continue,
             };
             if let BeltEnd::StorageInput { entity, .. } = line.output_end {
-                if !line.items.is_empty() && line.items[0].pos == 0 {
-                    belt_to_storage.push((line_id, line.items[0].item, entity));
+                if let Some(item) = line.ready_item() {
+                    belt_to_storage.push((line_id, item, entity));
                 }
             }
         }
@@ -817,31 +1471,46 @@ impl BeltNetwork {
 
         // Phase 4: Storage → Belt (output ports)
         // Storage provides items to a belt's input end.
-        let mut storage_to_belt: Vec<(TransportLineId, EntityId)> = Vec::new();
+        let mut storage_to_belt: Vec<(TransportLineId, EntityId, usize)> = Vec::new();
         for &line_id in &line_ids {
             let line = match self.lines.get(line_id) {
                 Some(l) => l,
                 None => continue,
             };
-            if let BeltEnd::StorageOutput { entity, .. } = line.input_end {
+            if let BeltEnd::StorageOutput { entity, slot } = line.input_end {
                 if line.can_accept_at_input() {
-                    storage_to_belt.push((line_id, entity));
+                    storage_to_belt.push((line_id, entity, slot));
                 }
             }
         }
-        for (line_id, entity) in storage_to_belt {
-            if let Some(item) = storage_pool.provide_output(entity) {
+        for (line_id, entity, slot) in storage_to_belt {
+            if let Some(item) = storage_pool.provide_output(entity, slot) {
                 if let Some(line) = self.lines.get_mut(line_id) {
                     line.insert_at_input(item);
                 }
             }
         }
+
+        // Phase 5: Belt → Sink (input ports)
+        // Items at a belt's output end (pos=0) are destroyed by the sink.
+        for &line_id in &line_ids {
+            let Some(line) = self.lines.get_mut(line_id) else {
+                continue;
+            };
+            if let BeltEnd::SinkInput { entity } = line.output_end {
+                if line.ready_item().is_some_and(|item| sink_pool.accept_input(entity, item)) {
+                    line.items.remove(0);
+                }
+            }
+        }
     }
 
     /// Remove a belt entity from the network. This handles splitting or
     /// shrinking the transport line as needed. Items on the removed segment
     /// are lost (dropped).
     pub fn on_belt_removed(&mut self, entity: EntityId) {
+        self.sensors.remove(entity);
+
         // Clean up any SideInject links that target this entity.
         for (_, line) in self.lines.iter_mut() {
             if let BeltEnd::SideInject { entity: target } = line.output_end {
@@ -850,6 +1519,19 @@ impl BeltNetwork {
                 }
             }
         }
+        // Removal can free room or open an end anywhere along the line and at
+        // both its neighbours; let them all re-check
+        if let Some(seg) = self.segments.get(entity).copied() {
+            if let Some(line) = self.lines.get(seg.line) {
+                let ends = [line.input_end, line.output_end];
+                self.wake_line(seg.line);
+                for end in ends {
+                    if let BeltEnd::Belt(other_id) = end {
+                        self.wake_line(other_id);
+                    }
+                }
+            }
+        }
 
         let seg = match self.segments.remove(entity) {
             Some(s) => s,
@@ -891,7 +1573,10 @@ impl BeltNetwork {
             let line = self.lines.get_mut(seg.line).unwrap();
             // Clear SideInject — it was specific to this segment's position,
             // and the new output-end segment may not be adjacent to the target.
-            if matches!(line.output_end, BeltEnd::SideInject { .. } | BeltEnd::Splitter { .. }) {
+            if matches!(
+                line.output_end,
+                BeltEnd::SideInject { .. } | BeltEnd::Splitter { .. } | BeltEnd::Loader { .. }
+            ) {
                 line.output_end = BeltEnd::Open;
             }
             // Remove items in the removed segment's range [0, FP_SCALE)
@@ -909,8 +1594,8 @@ impl BeltNetwork {
         } else if seg.offset == line_len - FP_SCALE {
             // Removing the input-end segment. Shrink the line.
             let line = self.lines.get_mut(seg.line).unwrap();
-            // Clear Splitter — the new input-end segment may not be adjacent to the splitter.
-            if matches!(line.input_end, BeltEnd::Splitter { .. }) {
+            // Clear Splitter/Loader — the new input-end segment may not be adjacent to it.
+            if matches!(line.input_end, BeltEnd::Splitter { .. } | BeltEnd::Loader { .. }) {
                 line.input_end = BeltEnd::Open;
             }
             // Remove items in the removed segment's range
@@ -950,12 +1635,10 @@ impl BeltNetwork {
             line.input_end = BeltEnd::Open;
 
             // Create the new input-half line
-            let new_line_id = self.lines.insert(TransportLine {
+            let new_line_id = self.insert_line(TransportLine {
                 items: input_items,
-                speed: DEFAULT_BELT_SPEED,
-                length: input_half_len,
                 input_end: old_input_end,
-                output_end: BeltEnd::Open,
+                ..TransportLine::new(input_half_len)
             });
 
             // Update external line that fed into the old input end
@@ -976,14 +1659,6 @@ impl BeltNetwork {
             }
         }
     }
-
-    /// Advance N ticks (for chunk fast-forward).
-    #[allow(dead_code)]
-    pub fn fast_forward(&mut self, ticks: u32) {
-        for _ in 0..ticks {
-            self.tick();
-        }
-    }
 }
 
 /// Check if a grid position is within a tile's bounds (-32..=32 on each axis).
@@ -993,7 +1668,7 @@ pub fn is_within_tile(gx: i32, gy: i32) -> bool {
 
 /// Find a belt entity at the given tile+grid position with the given direction.
 fn find_belt_at(
-    tile: &[u8],
+    tile: &CellId,
     grid_xy: (i32, i32),
     direction: Direction,
     world: &WorldState,
@@ -1011,7 +1686,7 @@ fn find_belt_at(
 
 /// Find any belt entity at the given tile+grid position, regardless of direction.
 fn find_any_belt_at(
-    tile: &[u8],
+    tile: &CellId,
     grid_xy: (i32, i32),
     world: &WorldState,
 ) -> Option<(EntityId, Direction)> {
@@ -1034,7 +1709,11 @@ fn is_perpendicular(a: Direction, b: Direction) -> bool {
 mod tests {
     use super::*;
 
-    fn place_belt(world: &mut WorldState, net: &mut BeltNetwork, addr: &[u8], gx: i32, gy: i32, dir: Direction) -> EntityId {
+    fn cell(word: &[u8]) -> CellId {
+        CellId::from_canonical(word.to_vec())
+    }
+
+    fn place_belt(world: &mut WorldState, net: &mut BeltNetwork, addr: &CellId, gx: i32, gy: i32, dir: Direction) -> EntityId {
         let entity = world.place(addr, (gx, gy), ItemId::Belt, dir).unwrap();
         net.on_belt_placed(entity, addr, gx, gy, dir, world);
         entity
@@ -1052,7 +1731,7 @@ mod tests {
     fn single_item_moves_toward_output() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let e = place_belt(&mut world, &mut net, &[0], 0, 0, Direction::East);
+        let e = place_belt(&mut world, &mut net, &cell(&[0]), 0, 0, Direction::East);
 
         net.spawn_item_on_entity(e, ItemId::NullSet);
         let items = local_items(&net, e);
@@ -1069,7 +1748,7 @@ mod tests {
     fn item_stops_at_open_end() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let e = place_belt(&mut world, &mut net, &[0], 0, 0, Direction::East);
+        let e = place_belt(&mut world, &mut net, &cell(&[0]), 0, 0, Direction::East);
 
         net.spawn_item_on_entity(e, ItemId::NullSet);
         for _ in 0..1000 {
@@ -1085,7 +1764,7 @@ mod tests {
     fn items_compress_behind_blocked_front() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let e = place_belt(&mut world, &mut net, &[0], 0, 0, Direction::East);
+        let e = place_belt(&mut world, &mut net, &cell(&[0]), 0, 0, Direction::East);
 
         // Manually insert two items on the line
         let seg = *net.segments.get(e).unwrap();
@@ -1107,7 +1786,7 @@ mod tests {
     fn consecutive_belts_merge_into_one_line() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
         let e1 = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
         let e2 = place_belt(&mut world, &mut net, addr, 1, 0, Direction::East);
 
@@ -1132,7 +1811,7 @@ mod tests {
     fn item_flows_through_merged_line() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
         let e1 = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
         let e2 = place_belt(&mut world, &mut net, addr, 1, 0, Direction::East);
 
@@ -1157,27 +1836,12 @@ mod tests {
         assert_eq!(items2[0].0, ItemId::NullSet);
     }
 
-    #[test]
-    fn fast_forward_advances_correctly() {
-        let mut world = WorldState::new();
-        let mut net = BeltNetwork::new();
-        let e = place_belt(&mut world, &mut net, &[0], 0, 0, Direction::East);
-
-        net.spawn_item_on_entity(e, ItemId::NullSet);
-        let start = local_items(&net, e)[0].1;
-
-        net.fast_forward(10);
-
-        let after = local_items(&net, e)[0].1;
-        assert_eq!(after, start - 10 * DEFAULT_BELT_SPEED as u32);
-    }
-
     #[test]
     fn placement_order_does_not_matter() {
         // Place downstream belt first, then upstream — should still merge.
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         let e2 = place_belt(&mut world, &mut net, addr, 1, 0, Direction::East);
         let e1 = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
@@ -1194,7 +1858,7 @@ mod tests {
     fn different_directions_do_not_merge() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         let e1 = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
         let e2 = place_belt(&mut world, &mut net, addr, 1, 0, Direction::North);
@@ -1208,7 +1872,7 @@ mod tests {
     fn three_belts_merge_into_one_line() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         let e1 = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
         let e2 = place_belt(&mut world, &mut net, addr, 1, 0, Direction::East);
@@ -1234,7 +1898,7 @@ mod tests {
         // Place e1 and e3 first (two separate lines), then e2 bridges them.
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         let e1 = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
         let e3 = place_belt(&mut world, &mut net, addr, 2, 0, Direction::East);
@@ -1262,7 +1926,7 @@ mod tests {
     fn perpendicular_belt_side_injects_items() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // Two belts: e1 East, e2 North — perpendicular, connected via SideInject.
         let e1 = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
@@ -1288,9 +1952,9 @@ mod tests {
         let mut net = BeltNetwork::new();
 
         // Tile A: belt at (32, 0) East — at the eastern edge
-        let e1 = place_belt(&mut world, &mut net, &[0], 32, 0, Direction::East);
+        let e1 = place_belt(&mut world, &mut net, &cell(&[0]), 32, 0, Direction::East);
         // Tile B: belt at (-32, 0) East — at the western edge
-        let e2 = place_belt(&mut world, &mut net, &[0, 0], -32, 0, Direction::East);
+        let e2 = place_belt(&mut world, &mut net, &cell(&[0, 0]), -32, 0, Direction::East);
 
         // Link: e1's output → e2's input (cross-tile)
         net.link_output_to_input(e1, e2);
@@ -1317,10 +1981,10 @@ mod tests {
         let mut net = BeltNetwork::new();
 
         // Tile A: belts at (31, 0) and (32, 0) East — merged line
-        let e1 = place_belt(&mut world, &mut net, &[0], 31, 0, Direction::East);
-        let e2 = place_belt(&mut world, &mut net, &[0], 32, 0, Direction::East);
+        let e1 = place_belt(&mut world, &mut net, &cell(&[0]), 31, 0, Direction::East);
+        let e2 = place_belt(&mut world, &mut net, &cell(&[0]), 32, 0, Direction::East);
         // Tile B: belt at (-32, 0) East
-        let e3 = place_belt(&mut world, &mut net, &[0, 0], -32, 0, Direction::East);
+        let e3 = place_belt(&mut world, &mut net, &cell(&[0, 0]), -32, 0, Direction::East);
 
         // e1 and e2 are on the same line
         let seg1 = *net.segments.get(e1).unwrap();
@@ -1350,12 +2014,12 @@ mod tests {
 
         // Two belts flowing in opposite directions across the same tile boundary
         // Tile A: belt at (32, 0) East, belt at (32, 1) West (separate lines)
-        let e1 = place_belt(&mut world, &mut net, &[0], 32, 0, Direction::East);
-        let e2 = place_belt(&mut world, &mut net, &[0], -32, 1, Direction::West);
+        let e1 = place_belt(&mut world, &mut net, &cell(&[0]), 32, 0, Direction::East);
+        let e2 = place_belt(&mut world, &mut net, &cell(&[0]), -32, 1, Direction::West);
 
         // Tile B: corresponding belts
-        let e3 = place_belt(&mut world, &mut net, &[0, 0], -32, 0, Direction::East);
-        let e4 = place_belt(&mut world, &mut net, &[0, 0], 32, 1, Direction::West);
+        let e3 = place_belt(&mut world, &mut net, &cell(&[0, 0]), -32, 0, Direction::East);
+        let e4 = place_belt(&mut world, &mut net, &cell(&[0, 0]), 32, 1, Direction::West);
 
         // Link e1→e3 (East flow) and e4→e2 (West flow)
         net.link_output_to_input(e1, e3);
@@ -1381,6 +2045,7 @@ mod tests {
     use crate::game::items::MachineType;
     use crate::game::recipes::RecipeIndex;
     use crate::sim::machine::{MachinePool, DEFAULT_CRAFT_TICKS};
+    use crate::sim::sink::SinkPool;
     use crate::sim::storage::StoragePool;
 
     #[test]
@@ -1388,7 +2053,7 @@ mod tests {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
         let mut machines = MachinePool::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // Belt at (0,0) flowing East, machine at (1,0)
         let belt = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
@@ -1411,7 +2076,8 @@ mod tests {
 
         // Now run port transfers — item should move into machine
         let mut storages = StoragePool::new();
-        net.tick_port_transfers(&mut machines, &mut storages);
+        let mut sinks = SinkPool::new();
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
         let items = local_items(&net, belt);
         assert_eq!(items.len(), 0); // item left the belt
         let slots = machines.input_slots(machine_entity).unwrap();
@@ -1424,7 +2090,7 @@ mod tests {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
         let mut machines = MachinePool::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // Machine at (0,0) (Composer is 2x2, occupies 0..1 x 0..1), belt at (2,0) flowing East
         let machine_entity = world.place(addr, (0, 0), ItemId::Composer, Direction::East).unwrap();
@@ -1443,7 +2109,8 @@ mod tests {
 
         // Run port transfers — item should appear on belt
         let mut storages = StoragePool::new();
-        net.tick_port_transfers(&mut machines, &mut storages);
+        let mut sinks = SinkPool::new();
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
         let seg = *net.segments.get(belt).unwrap();
         let line = net.lines.get(seg.line).unwrap();
         assert_eq!(line.items.len(), 1);
@@ -1457,7 +2124,7 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut machines = MachinePool::new();
         let recipes = RecipeIndex::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // Input belt → machine → output belt
         // Composer is 2x2 at (0,0): occupies (0,0)-(1,1)
@@ -1485,9 +2152,10 @@ mod tests {
 
         // Run the full cycle: belt tick + port transfer + machine tick
         let mut storages = StoragePool::new();
+        let mut sinks = SinkPool::new();
         for _ in 0..(500 + DEFAULT_CRAFT_TICKS as u32 + 100) {
             net.tick();
-            net.tick_port_transfers(&mut machines, &mut storages);
+            net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
             machines.tick(&recipes);
         }
 
@@ -1508,7 +2176,7 @@ mod tests {
     fn connection_only_at_line_endpoint() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // Three merged belts: (0,0), (1,0), (2,0) East
         let _e1 = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
@@ -1531,7 +2199,7 @@ mod tests {
     fn t_junction_east_into_north_creates_side_inject() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // North belt at (1, 0)
         let north = place_belt(&mut world, &mut net, addr, 1, 0, Direction::North);
@@ -1553,7 +2221,7 @@ mod tests {
         // Place the dead-ending belt first, then the perpendicular belt
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // East belt at (0, 0) first — output faces (1, 0), nothing there yet
         let east = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
@@ -1573,7 +2241,7 @@ mod tests {
     fn t_junction_items_transfer_to_perpendicular_belt() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // North belt at (1, 0)
         let north = place_belt(&mut world, &mut net, addr, 1, 0, Direction::North);
@@ -1600,7 +2268,7 @@ mod tests {
     fn side_inject_respects_min_gap() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // North belt at (1, 0), East belt at (0, 0)
         let north = place_belt(&mut world, &mut net, addr, 1, 0, Direction::North);
@@ -1638,7 +2306,7 @@ mod tests {
     fn side_inject_blocks_when_target_full() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // North belt at (1, 0), East belt at (0, 0)
         let north = place_belt(&mut world, &mut net, addr, 1, 0, Direction::North);
@@ -1671,7 +2339,7 @@ mod tests {
     fn removing_target_belt_clears_side_inject() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         let north = place_belt(&mut world, &mut net, addr, 1, 0, Direction::North);
         let east = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
@@ -1697,7 +2365,7 @@ mod tests {
     fn removing_source_belt_cleans_up() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         let _north = place_belt(&mut world, &mut net, addr, 1, 0, Direction::North);
         let east = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
@@ -1715,7 +2383,7 @@ mod tests {
     fn opposite_direction_does_not_side_inject() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // West belt at (1, 0) — facing opposite to East
         let _west = place_belt(&mut world, &mut net, addr, 1, 0, Direction::West);
@@ -1731,7 +2399,7 @@ mod tests {
     fn same_direction_merges_not_side_injects() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // East belt at (1, 0) — same direction
         let e2 = place_belt(&mut world, &mut net, addr, 1, 0, Direction::East);
@@ -1748,7 +2416,7 @@ mod tests {
         // Test a different orientation: South belt feeds onto East belt
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // East belt at (0, 1)
         let east = place_belt(&mut world, &mut net, addr, 0, 1, Direction::East);
@@ -1775,7 +2443,7 @@ mod tests {
     fn output_end_removal_clears_side_inject() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
 
         // North belt at (2, 0)
         let north = place_belt(&mut world, &mut net, addr, 2, 0, Direction::North);
@@ -1823,7 +2491,7 @@ mod tests {
 
     // --- Splitter-belt connection tests ---
 
-    fn place_splitter(world: &mut WorldState, pool: &mut crate::sim::splitter::SplitterPool, addr: &[u8], gx: i32, gy: i32) -> EntityId {
+    fn place_splitter(world: &mut WorldState, pool: &mut crate::sim::splitter::SplitterPool, addr: &CellId, gx: i32, gy: i32) -> EntityId {
         let entity = world.place(addr, (gx, gy), ItemId::Splitter, Direction::North).unwrap();
         pool.add(entity);
         entity
@@ -1836,8 +2504,8 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut pool = crate::sim::splitter::SplitterPool::new();
 
-        let splitter = place_splitter(&mut world, &mut pool, &[0], 5, 5);
-        let belt = place_belt(&mut world, &mut net, &[0], 4, 5, Direction::East);
+        let splitter = place_splitter(&mut world, &mut pool, &cell(&[0]), 5, 5);
+        let belt = place_belt(&mut world, &mut net, &cell(&[0]), 4, 5, Direction::East);
 
         // Belt's output end faces the splitter
         assert!(net.connect_belt_to_splitter(belt, splitter));
@@ -1860,8 +2528,8 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut pool = crate::sim::splitter::SplitterPool::new();
 
-        let splitter = place_splitter(&mut world, &mut pool, &[0], 5, 5);
-        let belt = place_belt(&mut world, &mut net, &[0], 6, 5, Direction::East);
+        let splitter = place_splitter(&mut world, &mut pool, &cell(&[0]), 5, 5);
+        let belt = place_belt(&mut world, &mut net, &cell(&[0]), 6, 5, Direction::East);
 
         // Belt's input end faces the splitter
         assert!(net.connect_splitter_to_belt(belt, splitter));
@@ -1883,14 +2551,14 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut pool = crate::sim::splitter::SplitterPool::new();
 
-        let splitter = place_splitter(&mut world, &mut pool, &[0], 5, 5);
+        let splitter = place_splitter(&mut world, &mut pool, &cell(&[0]), 5, 5);
 
         // Two belts feeding in from West and South
-        let belt_w = place_belt(&mut world, &mut net, &[0], 4, 5, Direction::East);
-        let belt_s = place_belt(&mut world, &mut net, &[0], 5, 6, Direction::North);
+        let belt_w = place_belt(&mut world, &mut net, &cell(&[0]), 4, 5, Direction::East);
+        let belt_s = place_belt(&mut world, &mut net, &cell(&[0]), 5, 6, Direction::North);
 
         // One belt taking out to the East
-        let belt_e = place_belt(&mut world, &mut net, &[0], 6, 5, Direction::East);
+        let belt_e = place_belt(&mut world, &mut net, &cell(&[0]), 6, 5, Direction::East);
 
         assert!(net.connect_belt_to_splitter(belt_w, splitter));
         pool.add_input(splitter, belt_w);
@@ -1911,13 +2579,13 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut pool = crate::sim::splitter::SplitterPool::new();
 
-        let splitter = place_splitter(&mut world, &mut pool, &[0], 5, 5);
+        let splitter = place_splitter(&mut world, &mut pool, &cell(&[0]), 5, 5);
 
         // One belt feeding in from West
-        let belt_in = place_belt(&mut world, &mut net, &[0], 4, 5, Direction::East);
+        let belt_in = place_belt(&mut world, &mut net, &cell(&[0]), 4, 5, Direction::East);
         // Two belts taking out to East and South
-        let belt_out1 = place_belt(&mut world, &mut net, &[0], 6, 5, Direction::East);
-        let belt_out2 = place_belt(&mut world, &mut net, &[0], 5, 6, Direction::South);
+        let belt_out1 = place_belt(&mut world, &mut net, &cell(&[0]), 6, 5, Direction::East);
+        let belt_out2 = place_belt(&mut world, &mut net, &cell(&[0]), 5, 6, Direction::South);
 
         assert!(net.connect_belt_to_splitter(belt_in, splitter));
         pool.add_input(splitter, belt_in);
@@ -1936,12 +2604,12 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut pool = crate::sim::splitter::SplitterPool::new();
 
-        let splitter = place_splitter(&mut world, &mut pool, &[0], 5, 5);
+        let splitter = place_splitter(&mut world, &mut pool, &cell(&[0]), 5, 5);
 
-        let belt_in1 = place_belt(&mut world, &mut net, &[0], 4, 5, Direction::East);
-        let belt_in2 = place_belt(&mut world, &mut net, &[0], 5, 4, Direction::South);
-        let belt_out1 = place_belt(&mut world, &mut net, &[0], 6, 5, Direction::East);
-        let belt_out2 = place_belt(&mut world, &mut net, &[0], 5, 6, Direction::South);
+        let belt_in1 = place_belt(&mut world, &mut net, &cell(&[0]), 4, 5, Direction::East);
+        let belt_in2 = place_belt(&mut world, &mut net, &cell(&[0]), 5, 4, Direction::South);
+        let belt_out1 = place_belt(&mut world, &mut net, &cell(&[0]), 6, 5, Direction::East);
+        let belt_out2 = place_belt(&mut world, &mut net, &cell(&[0]), 5, 6, Direction::South);
 
         assert!(net.connect_belt_to_splitter(belt_in1, splitter));
         pool.add_input(splitter, belt_in1);
@@ -1963,12 +2631,12 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut pool = crate::sim::splitter::SplitterPool::new();
 
-        let splitter = place_splitter(&mut world, &mut pool, &[0], 7, 5);
+        let splitter = place_splitter(&mut world, &mut pool, &cell(&[0]), 7, 5);
 
         // Place 3 belts going East: (4,5) (5,5) (6,5) — they merge into one line
-        let _belt1 = place_belt(&mut world, &mut net, &[0], 4, 5, Direction::East);
-        let belt2 = place_belt(&mut world, &mut net, &[0], 5, 5, Direction::East);
-        let belt3 = place_belt(&mut world, &mut net, &[0], 6, 5, Direction::East);
+        let _belt1 = place_belt(&mut world, &mut net, &cell(&[0]), 4, 5, Direction::East);
+        let belt2 = place_belt(&mut world, &mut net, &cell(&[0]), 5, 5, Direction::East);
+        let belt3 = place_belt(&mut world, &mut net, &cell(&[0]), 6, 5, Direction::East);
 
         // belt3 is at offset 0 (output end) — should connect
         assert!(net.connect_belt_to_splitter(belt3, splitter));
@@ -1983,11 +2651,11 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut pool = crate::sim::splitter::SplitterPool::new();
 
-        let splitter = place_splitter(&mut world, &mut pool, &[0], 7, 5);
+        let splitter = place_splitter(&mut world, &mut pool, &cell(&[0]), 7, 5);
 
         // Place 2 belts going East: (5,5) then (6,5) — output is Belt connection
-        let _belt1 = place_belt(&mut world, &mut net, &[0], 6, 5, Direction::East);
-        let belt2 = place_belt(&mut world, &mut net, &[0], 5, 5, Direction::East);
+        let _belt1 = place_belt(&mut world, &mut net, &cell(&[0]), 6, 5, Direction::East);
+        let belt2 = place_belt(&mut world, &mut net, &cell(&[0]), 5, 5, Direction::East);
 
         // belt2 is NOT at offset 0 (it was merged as upstream), so connect should fail
         // Actually after merge, belt1 is at offset 0 and belt2 is at the input end
@@ -2002,9 +2670,9 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut pool = crate::sim::splitter::SplitterPool::new();
 
-        let splitter = place_splitter(&mut world, &mut pool, &[0], 5, 5);
-        let belt_in = place_belt(&mut world, &mut net, &[0], 4, 5, Direction::East);
-        let belt_out = place_belt(&mut world, &mut net, &[0], 6, 5, Direction::East);
+        let splitter = place_splitter(&mut world, &mut pool, &cell(&[0]), 5, 5);
+        let belt_in = place_belt(&mut world, &mut net, &cell(&[0]), 4, 5, Direction::East);
+        let belt_out = place_belt(&mut world, &mut net, &cell(&[0]), 6, 5, Direction::East);
 
         assert!(net.connect_belt_to_splitter(belt_in, splitter));
         assert!(net.connect_splitter_to_belt(belt_out, splitter));
@@ -2030,11 +2698,11 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut pool = crate::sim::splitter::SplitterPool::new();
 
-        let splitter = place_splitter(&mut world, &mut pool, &[0], 5, 5);
+        let splitter = place_splitter(&mut world, &mut pool, &cell(&[0]), 5, 5);
 
         // Place 2 belts going East: (3,5) then (4,5)
-        let belt1 = place_belt(&mut world, &mut net, &[0], 3, 5, Direction::East);
-        let belt2 = place_belt(&mut world, &mut net, &[0], 4, 5, Direction::East);
+        let belt1 = place_belt(&mut world, &mut net, &cell(&[0]), 3, 5, Direction::East);
+        let belt2 = place_belt(&mut world, &mut net, &cell(&[0]), 4, 5, Direction::East);
         // They merge: belt2 at offset 0 (output end), belt1 at offset FP_SCALE
 
         assert!(net.connect_belt_to_splitter(belt2, splitter));
@@ -2055,11 +2723,11 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut pool = crate::sim::splitter::SplitterPool::new();
 
-        let splitter = place_splitter(&mut world, &mut pool, &[0], 2, 5);
+        let splitter = place_splitter(&mut world, &mut pool, &cell(&[0]), 2, 5);
 
         // Place 2 belts going East: (4,5) then (3,5) — they merge
-        let belt1 = place_belt(&mut world, &mut net, &[0], 4, 5, Direction::East);
-        let belt2 = place_belt(&mut world, &mut net, &[0], 3, 5, Direction::East);
+        let belt1 = place_belt(&mut world, &mut net, &cell(&[0]), 4, 5, Direction::East);
+        let belt2 = place_belt(&mut world, &mut net, &cell(&[0]), 3, 5, Direction::East);
         // belt1 at offset 0, belt2 at offset FP_SCALE (input end)
 
         assert!(net.connect_splitter_to_belt(belt2, splitter));
@@ -2084,11 +2752,11 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut pool = crate::sim::splitter::SplitterPool::new();
 
-        let splitter_a = place_splitter(&mut world, &mut pool, &[0], 3, 5);
-        let splitter_b = place_splitter(&mut world, &mut pool, &[0], 6, 5);
+        let splitter_a = place_splitter(&mut world, &mut pool, &cell(&[0]), 3, 5);
+        let splitter_b = place_splitter(&mut world, &mut pool, &cell(&[0]), 6, 5);
 
         // Place belt at (4,5) going East, connect to both splitters
-        let belt = place_belt(&mut world, &mut net, &[0], 4, 5, Direction::East);
+        let belt = place_belt(&mut world, &mut net, &cell(&[0]), 4, 5, Direction::East);
 
         // Output end faces East toward (5,5) — but splitter_b is at (6,5), not (5,5)
         // So let me adjust: single belt, splitter behind and splitter ahead
@@ -2114,7 +2782,7 @@ mod tests {
         net: &mut BeltNetwork,
         storage_pool: &mut StoragePool,
     ) -> (EntityId, EntityId) {
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
         // Belt at (0,0) flowing East
         let belt = place_belt(world, net, addr, 0, 0, Direction::East);
         // Storage at (1,0) — 2x2 footprint
@@ -2131,7 +2799,7 @@ mod tests {
         net: &mut BeltNetwork,
         storage_pool: &mut StoragePool,
     ) -> (EntityId, EntityId) {
-        let addr: &[u8] = &[0];
+        let addr = &cell(&[0]);
         // Storage at (0,0) — 2x2 footprint
         let storage_entity = world.place(addr, (0, 0), ItemId::Storage, Direction::North).unwrap();
         storage_pool.add(storage_entity);
@@ -2148,6 +2816,7 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut machines = MachinePool::new();
         let mut storages = StoragePool::new();
+        let mut sinks = SinkPool::new();
 
         let (belt, storage_entity) = setup_belt_to_storage(&mut world, &mut net, &mut storages);
 
@@ -2163,7 +2832,7 @@ mod tests {
         assert_eq!(items[0].1, 0); // at output end
 
         // Port transfer should move item into storage
-        net.tick_port_transfers(&mut machines, &mut storages);
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
         let items = local_items(&net, belt);
         assert_eq!(items.len(), 0, "item should have left the belt");
         let state = storages.get(storage_entity).unwrap();
@@ -2177,6 +2846,7 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut machines = MachinePool::new();
         let mut storages = StoragePool::new();
+        let mut sinks = SinkPool::new();
 
         let (belt, storage_entity) = setup_storage_to_belt(&mut world, &mut net, &mut storages);
 
@@ -2184,7 +2854,7 @@ mod tests {
         storages.accept_input(storage_entity, ItemId::LineSegment, 1);
 
         // Port transfer should push item onto belt input end
-        net.tick_port_transfers(&mut machines, &mut storages);
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
         let seg = *net.segments.get(belt).unwrap();
         let line = net.lines.get(seg.line).unwrap();
         assert_eq!(line.items.len(), 1);
@@ -2201,6 +2871,7 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut machines = MachinePool::new();
         let mut storages = StoragePool::new();
+        let mut sinks = SinkPool::new();
 
         let (belt, storage_entity) = setup_belt_to_storage(&mut world, &mut net, &mut storages);
 
@@ -2220,7 +2891,7 @@ mod tests {
         }
 
         // Port transfer should NOT move item — storage is full
-        net.tick_port_transfers(&mut machines, &mut storages);
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
         let items = local_items(&net, belt);
         assert_eq!(items.len(), 1, "item should remain on belt when storage is full");
         assert_eq!(items[0].1, 0);
@@ -2232,11 +2903,12 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut machines = MachinePool::new();
         let mut storages = StoragePool::new();
+        let mut sinks = SinkPool::new();
 
         let (belt, _storage_entity) = setup_storage_to_belt(&mut world, &mut net, &mut storages);
 
         // Storage is empty — port transfer should not produce anything
-        net.tick_port_transfers(&mut machines, &mut storages);
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
         let seg = *net.segments.get(belt).unwrap();
         let line = net.lines.get(seg.line).unwrap();
         assert_eq!(line.items.len(), 0, "empty storage should not produce items");
@@ -2248,6 +2920,7 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut machines = MachinePool::new();
         let mut storages = StoragePool::new();
+        let mut sinks = SinkPool::new();
 
         let (input_belt, storage_entity) = setup_belt_to_storage(&mut world, &mut net, &mut storages);
 
@@ -2256,13 +2929,13 @@ mod tests {
         for _ in 0..500 {
             net.tick();
         }
-        net.tick_port_transfers(&mut machines, &mut storages);
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
 
         net.spawn_item_on_entity(input_belt, ItemId::LineSegment);
         for _ in 0..500 {
             net.tick();
         }
-        net.tick_port_transfers(&mut machines, &mut storages);
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
 
         // Check storage has both items in separate slots
         let state = storages.get(storage_entity).unwrap();
@@ -2278,7 +2951,8 @@ mod tests {
         let mut net = BeltNetwork::new();
         let mut machines = MachinePool::new();
         let mut storages = StoragePool::new();
-        let addr: &[u8] = &[0];
+        let mut sinks = SinkPool::new();
+        let addr = &cell(&[0]);
 
         // Storage at (5,5)
         let storage_entity = world.place(addr, (5, 5), ItemId::Storage, Direction::North).unwrap();
@@ -2303,7 +2977,7 @@ mod tests {
         // Single tick_port_transfers handles the full round-trip:
         // Phase 3 (Belt→Storage): item enters storage
         // Phase 4 (Storage→Belt): item immediately exits to output belt
-        net.tick_port_transfers(&mut machines, &mut storages);
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
 
         // Item should have left the input belt
         assert_eq!(local_items(&net, input_belt).len(), 0, "item should have left input belt");
@@ -2318,4 +2992,354 @@ mod tests {
         let total: u16 = storages.get(storage_entity).unwrap().slots.iter().map(|s| s.count).sum();
         assert_eq!(total, 0, "storage should be empty after pass-through");
     }
+
+    #[test]
+    fn storage_output_port_respects_mode() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let mut machines = MachinePool::new();
+        let mut storages = StoragePool::new();
+        let mut sinks = SinkPool::new();
+
+        let (belt, storage_entity) = setup_storage_to_belt(&mut world, &mut net, &mut storages);
+        storages.accept_input(storage_entity, ItemId::Point, 1);
+        storages.accept_input(storage_entity, ItemId::LineSegment, 1);
+        storages.set_output_mode(
+            storage_entity,
+            0,
+            crate::sim::storage::StorageOutputMode { filter: Some(ItemId::LineSegment), keep: 0 },
+        );
+
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
+        let seg = *net.segments.get(belt).unwrap();
+        let line = net.lines.get(seg.line).unwrap();
+        assert_eq!(line.items.len(), 1);
+        assert_eq!(line.items[0].item, ItemId::LineSegment);
+        assert_eq!(storages.get(storage_entity).unwrap().count_of(ItemId::Point), 1);
+    }
+
+    #[test]
+    fn belt_to_sink_destroys_items() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let mut machines = MachinePool::new();
+        let mut storages = StoragePool::new();
+        let mut sinks = SinkPool::new();
+
+        let addr = &cell(&[0]);
+        let belt = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
+        let sink = world.place(addr, (1, 0), ItemId::Void, Direction::West).unwrap();
+        sinks.add(sink);
+        sinks.set_filter(sink, Some(ItemId::Point));
+        net.connect_belt_to_sink_input(belt, sink);
+
+        let seg = *net.segments.get(belt).unwrap();
+        net.lines.get_mut(seg.line).unwrap().items.push(BeltItem { item: ItemId::Point, pos: 0 });
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
+        assert!(net.lines.get(seg.line).unwrap().items.is_empty());
+        assert_eq!(sinks.get(sink).unwrap().consumed[&ItemId::Point], 1);
+
+        // Filtered-out items stay on the belt
+        net.lines.get_mut(seg.line).unwrap().items.push(BeltItem { item: ItemId::Square, pos: 0 });
+        net.tick_port_transfers(&mut machines, &mut storages, &mut sinks);
+        assert_eq!(net.lines.get(seg.line).unwrap().items.len(), 1);
+
+        net.disconnect_sink_ports(sink);
+        assert_eq!(net.lines.get(seg.line).unwrap().output_end, BeltEnd::Open);
+    }
+
+    #[test]
+    fn sensor_counts_items_passing_segment_centre() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let addr = &cell(&[0]);
+        let back = place_belt(&mut world, &mut net, addr, 0, 0, Direction::East);
+        let middle = place_belt(&mut world, &mut net, addr, 1, 0, Direction::East);
+        let front = place_belt(&mut world, &mut net, addr, 2, 0, Direction::East);
+        assert!(net.set_sensor(middle, true));
+        assert!(net.set_sensor(front, true));
+
+        let seg = *net.segments.get(back).unwrap();
+        let line = net.lines.get_mut(seg.line).unwrap();
+        for pos in [572, 636, 700] {
+            line.items.push(BeltItem { item: ItemId::Point, pos });
+        }
+        for _ in 0..200 {
+            net.tick();
+        }
+
+        // All three pass the middle; the last one queues at the front segment's centre
+        assert_eq!(net.sensor(middle).unwrap().total, 3);
+        assert_eq!(net.sensor(front).unwrap().total, 2);
+        // 3 items in 200 ticks, extrapolated to a minute
+        assert!((net.sensor(middle).unwrap().items_per_minute() - 54.0).abs() < 1e-3);
+
+        net.on_belt_removed(middle);
+        assert!(net.sensor(middle).is_none());
+        assert!(!net.set_sensor(middle, true));
+    }
+
+    #[test]
+    fn asleep_line_catches_up_on_wake() {
+        let build = || {
+            let mut world = WorldState::new();
+            let mut net = BeltNetwork::new();
+            let addr = &cell(&[0]);
+            let belts: Vec<EntityId> =
+                (0..3).map(|gx| place_belt(&mut world, &mut net, addr, gx, 0, Direction::East)).collect();
+            let seg = *net.segments.get(belts[0]).unwrap();
+            let line = net.lines.get_mut(seg.line).unwrap();
+            for pos in [100, 140, 500, 700, 1000] {
+                line.items.push(BeltItem { item: ItemId::Point, pos });
+            }
+            (world, net, belts)
+        };
+        let (_w1, mut ticked, belts) = build();
+        let (_w2, mut slept, _) = build();
+
+        slept.sleep_belts(belts.iter().copied());
+        for _ in 0..150 {
+            ticked.tick();
+            slept.tick();
+        }
+        // Asleep: nothing moved
+        let line = slept.segments.get(belts[0]).unwrap().line;
+        let positions = |net: &BeltNetwork| net.lines[line].items.iter().map(|i| i.pos).collect::<Vec<_>>();
+        assert_eq!(positions(&slept), vec![100, 140, 500, 700, 1000]);
+
+        slept.wake_belts(belts.iter().copied());
+        assert_eq!(positions(&slept), positions(&ticked));
+        assert_eq!(positions(&slept)[0], 0);
+    }
+
+    #[test]
+    fn insert_into_asleep_line_lands_behind_caught_up_items() {
+        let build = || {
+            let mut world = WorldState::new();
+            let mut net = BeltNetwork::new();
+            let addr = &cell(&[0]);
+            let belts: Vec<EntityId> =
+                (0..3).map(|gx| place_belt(&mut world, &mut net, addr, gx, 0, Direction::East)).collect();
+            assert!(net.push_to_entity_input(belts[0], ItemId::Point));
+            (world, net, belts)
+        };
+        let (_w1, mut ticked, belts) = build();
+        let (_w2, mut slept, _) = build();
+
+        slept.sleep_belts(belts.iter().copied());
+        for _ in 0..30 {
+            ticked.tick();
+            slept.tick();
+        }
+        // The first item has moved clear of the input end while asleep
+        assert!(slept.can_accept_at_entity_input(belts[0]));
+        assert!(ticked.push_to_entity_input(belts[0], ItemId::Square));
+        assert!(slept.push_to_entity_input(belts[0], ItemId::Square));
+        for _ in 0..30 {
+            ticked.tick();
+            slept.tick();
+        }
+
+        slept.wake_belts(belts.iter().copied());
+        let line = slept.segments.get(belts[0]).unwrap().line;
+        let items = |net: &BeltNetwork| net.lines[line].items.iter().map(|i| (i.item, i.pos)).collect::<Vec<_>>();
+        let length = 3 * FP_SCALE;
+        let speed = DEFAULT_BELT_SPEED as u32;
+        assert_eq!(items(&slept), vec![(ItemId::Point, length - 60 * speed), (ItemId::Square, length - 30 * speed)]);
+        assert_eq!(items(&slept), items(&ticked));
+    }
+
+    #[test]
+    fn asleep_line_feeds_awake_neighbour_at_pinned_flow() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let e1 = place_belt(&mut world, &mut net, &cell(&[0]), 32, 0, Direction::East);
+        let e2 = place_belt(&mut world, &mut net, &cell(&[0, 0]), -32, 0, Direction::East);
+        net.link_output_to_input(e1, e2);
+        net.spawn_item_on_entity(e1, ItemId::NullSet);
+
+        // No flow measured before sleeping: the link stays shut
+        let line = net.segments.get(e1).unwrap().line;
+        net.lines[line].flow = 0.0;
+        net.sleep_belts([e1]);
+        for _ in 0..1000 {
+            net.tick();
+        }
+        assert_eq!(local_items(&net, e1).len(), 1);
+        assert!(local_items(&net, e2).is_empty());
+
+        // With a pinned flow the sleeping end still hands its item over
+        net.lines[line].flow = 0.1;
+        for _ in 0..20 {
+            net.tick();
+        }
+        assert!(local_items(&net, e1).is_empty());
+        assert_eq!(local_items(&net, e2).len(), 1);
+    }
+
+    #[test]
+    fn settled_line_goes_idle_and_wakes_on_insert() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let e1 = place_belt(&mut world, &mut net, &cell(&[0]), 0, 0, Direction::East);
+        net.spawn_item_on_entity(e1, ItemId::Point);
+        assert_eq!(net.active_line_count(), 1);
+
+        for _ in 0..100 {
+            net.tick();
+        }
+        assert_eq!(local_items(&net, e1), vec![(ItemId::Point, 0)]);
+        assert_eq!(net.active_line_count(), 0);
+
+        // An insert wakes the line, which sleeps again once the new item settles
+        assert!(net.push_to_entity_input(e1, ItemId::Square));
+        assert_eq!(net.active_line_count(), 1);
+        for _ in 0..100 {
+            net.tick();
+        }
+        assert_eq!(local_items(&net, e1), vec![(ItemId::Point, 0), (ItemId::Square, MIN_ITEM_GAP)]);
+        assert_eq!(net.active_line_count(), 0);
+    }
+
+    #[test]
+    fn blocked_feeder_wakes_when_downstream_moves() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let e1 = place_belt(&mut world, &mut net, &cell(&[0]), 32, 0, Direction::East);
+        let e2 = place_belt(&mut world, &mut net, &cell(&[0, 0]), -32, 0, Direction::East);
+        net.link_output_to_input(e1, e2);
+        for _ in 0..6 {
+            net.spawn_item_on_entity(e1, ItemId::Point);
+            for _ in 0..100 {
+                net.tick();
+            }
+        }
+        let on_line = |net: &BeltNetwork, e: EntityId| net.lines[net.segments[e].line].items.len();
+        // e2 jammed full, e1 holding the sixth item at its output: nothing can move
+        assert_eq!(on_line(&net, e2), 5);
+        assert_eq!(on_line(&net, e1), 1);
+        assert_eq!(net.active_line_count(), 0);
+
+        // Taking from e2 lets its items compress, which wakes e1 to hand over
+        assert_eq!(net.take_front_item(e2), Some(ItemId::Point));
+        for _ in 0..100 {
+            net.tick();
+        }
+        assert_eq!(on_line(&net, e1), 0);
+        assert_eq!(on_line(&net, e2), 5);
+        assert_eq!(net.active_line_count(), 0);
+    }
+
+    #[test]
+    fn halt_release_wakes_line() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let e1 = place_belt(&mut world, &mut net, &cell(&[0]), 0, 0, Direction::East);
+        net.spawn_item_on_entity(e1, ItemId::Point);
+        net.set_halted_entities([e1]);
+        net.tick();
+        assert_eq!(net.active_line_count(), 0);
+
+        // Holding the halt doesn't wake the line
+        net.set_halted_entities([e1]);
+        assert_eq!(net.active_line_count(), 0);
+
+        net.set_halted_entities([]);
+        net.tick();
+        assert_eq!(local_items(&net, e1), vec![(ItemId::Point, FP_SCALE / 2 - DEFAULT_BELT_SPEED as u32)]);
+        assert_eq!(net.active_line_count(), 1);
+    }
+
+    /// 10k jammed lines plus a handful still moving. Once the jammed lines
+    /// go idle a tick visits only the active list, which holds just the
+    /// moving ones.
+    #[test]
+    fn ten_thousand_idle_lines_tick_in_proportion_to_active() {
+        const JAMMED: usize = 10_000;
+        const MOVING: usize = 10;
+        let mut net = BeltNetwork::new();
+        for _ in 0..JAMMED {
+            let mut line = TransportLine::new(4 * FP_SCALE);
+            for i in 0..16 {
+                line.items.push(BeltItem { item: ItemId::Point, pos: i * MIN_ITEM_GAP });
+            }
+            net.insert_line(line);
+        }
+        for _ in 0..MOVING {
+            let mut line = TransportLine::new(1000 * FP_SCALE);
+            line.items.push(BeltItem { item: ItemId::Point, pos: 1000 * FP_SCALE });
+            net.insert_line(line);
+        }
+        assert_eq!(net.active_line_count(), JAMMED + MOVING);
+
+        net.tick();
+        for _ in 0..100 {
+            assert_eq!(net.active_line_count(), MOVING);
+            net.tick();
+        }
+        assert_eq!(net.active_line_count(), MOVING);
+    }
+
+    #[test]
+    fn parallel_tick_matches_sequential() {
+        let build = || {
+            let mut world = WorldState::new();
+            let mut net = BeltNetwork::new();
+            // Chains of three cross-tile links so handoffs mix with parallel advances
+            for row in -20..=20 {
+                let mut prev: Option<EntityId> = None;
+                for (k, tile) in [cell(&[0]), cell(&[0, 0]), cell(&[0, 0, 0])].iter().enumerate() {
+                    let e = place_belt(&mut world, &mut net, tile, 30, row, Direction::East);
+                    place_belt(&mut world, &mut net, tile, 31, row, Direction::East);
+                    let last = place_belt(&mut world, &mut net, tile, 32, row, Direction::East);
+                    if let Some(p) = prev {
+                        net.link_output_to_input(p, e);
+                    }
+                    prev = Some(last);
+                    if (row + k as i32) % 3 != 0 {
+                        net.spawn_item_on_entity(e, ItemId::Point);
+                    }
+                }
+                net.set_sensor(prev.unwrap(), true);
+            }
+            (world, net)
+        };
+        let (_w1, mut parallel) = build();
+        let (_w2, mut sequential) = build();
+        for t in 0..600 {
+            if t % 40 == 0 {
+                for net in [&mut parallel, &mut sequential] {
+                    let ids: Vec<EntityId> = net.segments.keys().step_by(7).collect();
+                    for e in ids {
+                        net.push_to_entity_input(e, ItemId::Square);
+                    }
+                }
+            }
+            parallel.tick_with(5);
+            sequential.tick_with(usize::MAX);
+        }
+        for (id, line) in &parallel.lines {
+            let other = &sequential.lines[id];
+            let items = |l: &TransportLine| l.items.iter().map(|i| (i.item, i.pos)).collect::<Vec<_>>();
+            assert_eq!(items(line), items(other));
+        }
+        assert_eq!(parallel.active, sequential.active);
+        for (entity, sensor) in &parallel.sensors {
+            assert_eq!(sensor.total, sequential.sensors[entity].total);
+        }
+    }
+
+    #[test]
+    fn sensor_rate_forgets_old_buckets() {
+        let mut sensor = BeltSensor::new();
+        for _ in 0..SENSOR_WINDOW_TICKS {
+            sensor.record(1);
+        }
+        assert!((sensor.items_per_minute() - 3600.0).abs() < 1e-3);
+        for _ in 0..SENSOR_WINDOW_TICKS {
+            sensor.record(0);
+        }
+        assert_eq!(sensor.items_per_minute(), 0.0);
+        assert_eq!(sensor.total, 3600);
+    }
 }
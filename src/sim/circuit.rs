@@ -0,0 +1,733 @@
+//! Circuit network: wires carrying integer signals keyed by item.
+//!
+//! Wires join entity terminals; each connected set of terminals is one
+//! network. Every tick, in order:
+//!
+//! 1. each network's signals are summed from its readers — storage
+//!    contents, items on wired belt segments — plus the outputs combinators
+//!    computed last tick;
+//! 2. enable conditions on machines, splitters, and belts are tested
+//!    against the network their main terminal is wired to;
+//! 3. combinators compute new outputs from their input network.
+//!
+//! Combinator outputs therefore reach the network one tick later, which
+//! keeps feedback loops well defined. Signal maps are ordered and all
+//! arithmetic wraps, so a tick is deterministic for a given wiring.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::items::ItemId;
+use crate::game::world::EntityId;
+use crate::sim::belt::BeltNetwork;
+use crate::sim::machine::MachinePool;
+use crate::sim::splitter::SplitterPool;
+use crate::sim::state_hash::StateHasher;
+use crate::sim::storage::StoragePool;
+
+/// Signal values on one network, keyed by item. Zero values are omitted.
+pub type Signals = BTreeMap<ItemId, i32>;
+
+/// Which side of an entity a wire attaches to. Only combinators have an
+/// output terminal; everything else (including a combinator's input) is `Main`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Terminal {
+    Main,
+    Output,
+}
+
+/// One end of a wire.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WireEnd {
+    pub entity: EntityId,
+    pub terminal: Terminal,
+}
+
+impl WireEnd {
+    pub fn main(entity: EntityId) -> Self {
+        Self { entity, terminal: Terminal::Main }
+    }
+
+    pub fn output(entity: EntityId) -> Self {
+        Self { entity, terminal: Terminal::Output }
+    }
+}
+
+/// Comparison used by enable conditions and decider combinators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparator {
+    Less,
+    LessEq,
+    Equal,
+    NotEqual,
+    GreaterEq,
+    Greater,
+}
+
+impl Comparator {
+    pub const ALL: [Comparator; 6] = [
+        Self::Less,
+        Self::LessEq,
+        Self::Equal,
+        Self::NotEqual,
+        Self::GreaterEq,
+        Self::Greater,
+    ];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Less => "<",
+            Self::LessEq => "≤",
+            Self::Equal => "=",
+            Self::NotEqual => "≠",
+            Self::GreaterEq => "≥",
+            Self::Greater => ">",
+        }
+    }
+
+    pub fn test(&self, a: i32, b: i32) -> bool {
+        match self {
+            Self::Less => a < b,
+            Self::LessEq => a <= b,
+            Self::Equal => a == b,
+            Self::NotEqual => a != b,
+            Self::GreaterEq => a >= b,
+            Self::Greater => a > b,
+        }
+    }
+}
+
+/// Right-hand side of a condition or arithmetic operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Operand {
+    Constant(i32),
+    Signal(ItemId),
+}
+
+impl Operand {
+    pub fn value(&self, signals: &Signals) -> i32 {
+        match *self {
+            Self::Constant(c) => c,
+            Self::Signal(item) => signal(signals, item),
+        }
+    }
+}
+
+/// Value of one signal (zero if absent).
+pub fn signal(signals: &Signals, item: ItemId) -> i32 {
+    signals.get(&item).copied().unwrap_or(0)
+}
+
+/// `left cmp right`, e.g. "Cube ≥ 500".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Condition {
+    pub left: ItemId,
+    pub cmp: Comparator,
+    pub right: Operand,
+}
+
+impl Default for Condition {
+    fn default() -> Self {
+        Self { left: ItemId::NullSet, cmp: Comparator::Greater, right: Operand::Constant(0) }
+    }
+}
+
+impl Condition {
+    pub fn holds(&self, signals: &Signals) -> bool {
+        self.cmp.test(signal(signals, self.left), self.right.value(signals))
+    }
+}
+
+/// Operation of an arithmetic combinator. All operations wrap; division
+/// and remainder by zero yield zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl ArithOp {
+    pub const ALL: [ArithOp; 5] = [Self::Add, Self::Sub, Self::Mul, Self::Div, Self::Rem];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "−",
+            Self::Mul => "×",
+            Self::Div => "÷",
+            Self::Rem => "%",
+        }
+    }
+
+    pub fn apply(&self, a: i32, b: i32) -> i32 {
+        match self {
+            Self::Add => a.wrapping_add(b),
+            Self::Sub => a.wrapping_sub(b),
+            Self::Mul => a.wrapping_mul(b),
+            Self::Div => a.checked_div(b).unwrap_or(0),
+            Self::Rem => a.checked_rem(b).unwrap_or(0),
+        }
+    }
+}
+
+/// What a combinator computes from its input network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CombinatorConfig {
+    /// Output `left op right` on the `output` signal.
+    Arithmetic { left: ItemId, op: ArithOp, right: Operand, output: ItemId },
+    /// While `condition` holds, output 1 on `output` — or, with
+    /// `copy_input`, the input network's value of `output`.
+    Decider { condition: Condition, output: ItemId, copy_input: bool },
+}
+
+impl CombinatorConfig {
+    pub fn default_arithmetic() -> Self {
+        Self::Arithmetic {
+            left: ItemId::NullSet,
+            op: ArithOp::Add,
+            right: Operand::Constant(0),
+            output: ItemId::NullSet,
+        }
+    }
+
+    pub fn default_decider() -> Self {
+        Self::Decider { condition: Condition::default(), output: ItemId::NullSet, copy_input: false }
+    }
+
+    /// Compute the output signals for one input signal set.
+    pub fn evaluate(&self, input: &Signals) -> Signals {
+        let (output, value) = match *self {
+            Self::Arithmetic { left, op, right, output } => {
+                (output, op.apply(signal(input, left), right.value(input)))
+            }
+            Self::Decider { condition, output, copy_input } => {
+                if !condition.holds(input) {
+                    return Signals::new();
+                }
+                (output, if copy_input { signal(input, output) } else { 1 })
+            }
+        };
+        let mut signals = Signals::new();
+        if value != 0 {
+            signals.insert(output, value);
+        }
+        signals
+    }
+}
+
+/// Per-combinator state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CombinatorState {
+    pub entity: EntityId,
+    pub config: CombinatorConfig,
+    /// Signals placed on the output network, computed last tick.
+    pub output: Signals,
+}
+
+fn hash_operand(h: &mut StateHasher, operand: &Operand) {
+    match *operand {
+        Operand::Constant(n) => {
+            h.u8(0);
+            h.i32(n);
+        }
+        Operand::Signal(item) => {
+            h.u8(1);
+            h.item(item);
+        }
+    }
+}
+
+fn hash_condition(h: &mut StateHasher, condition: &Condition) {
+    h.item(condition.left);
+    h.u8(condition.cmp as u8);
+    hash_operand(h, &condition.right);
+}
+
+/// All wires, enable conditions, and combinators.
+#[derive(Serialize, Deserialize)]
+pub struct CircuitNetwork {
+    wires: Vec<(WireEnd, WireEnd)>,
+    conditions: Vec<(EntityId, Condition)>,
+    combinators: Vec<CombinatorState>,
+    combinator_to_idx: HashMap<EntityId, usize>,
+    /// Entities whose condition was dropped; re-enabled on the next tick.
+    released: Vec<EntityId>,
+    /// Set on any wiring change; networks are rebuilt lazily.
+    dirty: bool,
+    /// Network index of every wired terminal.
+    net_of: HashMap<WireEnd, usize>,
+    /// Terminals in each network, in discovery order.
+    members: Vec<Vec<WireEnd>>,
+    /// Signals on each network as of the last tick.
+    signals: Vec<Signals>,
+}
+
+impl CircuitNetwork {
+    pub fn new() -> Self {
+        Self {
+            wires: Vec::new(),
+            conditions: Vec::new(),
+            combinators: Vec::new(),
+            combinator_to_idx: HashMap::new(),
+            released: Vec::new(),
+            dirty: false,
+            net_of: HashMap::new(),
+            members: Vec::new(),
+            signals: Vec::new(),
+        }
+    }
+
+    /// Feed the wiring, conditions, and combinator settings and outputs
+    /// into a state hash, in entity order. Network signals are summed afresh
+    /// each tick from these, so they aren't hashed.
+    pub fn hash_state(&self, h: &mut StateHasher) {
+        let end_key = |end: WireEnd| (end.entity, end.terminal as u8);
+        let hash_end = |h: &mut StateHasher, end: WireEnd| {
+            h.entity(end.entity);
+            h.u8(end.terminal as u8);
+        };
+        let mut wires: Vec<(WireEnd, WireEnd)> = self
+            .wires
+            .iter()
+            .map(|&(a, b)| if end_key(a) <= end_key(b) { (a, b) } else { (b, a) })
+            .collect();
+        wires.sort_by_key(|&(a, b)| (end_key(a), end_key(b)));
+        h.u32(wires.len() as u32);
+        for (a, b) in wires {
+            hash_end(h, a);
+            hash_end(h, b);
+        }
+
+        let mut conditions = self.conditions.clone();
+        conditions.sort_by_key(|&(entity, _)| entity);
+        h.u32(conditions.len() as u32);
+        for (entity, condition) in conditions {
+            h.entity(entity);
+            hash_condition(h, &condition);
+        }
+
+        let mut combinators: Vec<&CombinatorState> = self.combinators.iter().collect();
+        combinators.sort_by_key(|c| c.entity);
+        h.u32(combinators.len() as u32);
+        for c in combinators {
+            h.entity(c.entity);
+            match c.config {
+                CombinatorConfig::Arithmetic { left, op, right, output } => {
+                    h.u8(0);
+                    h.item(left);
+                    h.u8(op as u8);
+                    hash_operand(h, &right);
+                    h.item(output);
+                }
+                CombinatorConfig::Decider { condition, output, copy_input } => {
+                    h.u8(1);
+                    hash_condition(h, &condition);
+                    h.item(output);
+                    h.bool(copy_input);
+                }
+            }
+            h.u32(c.output.len() as u32);
+            for (&item, &n) in &c.output {
+                h.item(item);
+                h.i32(n);
+            }
+        }
+
+        let mut released = self.released.clone();
+        released.sort();
+        h.u32(released.len() as u32);
+        for entity in released {
+            h.entity(entity);
+        }
+    }
+
+    // --- Wiring ---
+
+    /// Run a wire between two terminals. Returns false for a wire from a
+    /// terminal to itself or one that already exists.
+    pub fn connect(&mut self, a: WireEnd, b: WireEnd) -> bool {
+        if a == b || self.wires.iter().any(|&(x, y)| (x, y) == (a, b) || (x, y) == (b, a)) {
+            return false;
+        }
+        self.wires.push((a, b));
+        self.dirty = true;
+        true
+    }
+
+    /// Number of wires attached to any terminal of an entity.
+    pub fn wire_count(&self, entity: EntityId) -> usize {
+        self.wires
+            .iter()
+            .filter(|(a, b)| a.entity == entity || b.entity == entity)
+            .count()
+    }
+
+    /// Drop everything attached to an entity (on removal): wires, its
+    /// condition, and combinator state. Returns the number of wires cut.
+    pub fn remove_entity(&mut self, entity: EntityId) -> usize {
+        let before = self.wires.len();
+        self.wires.retain(|(a, b)| a.entity != entity && b.entity != entity);
+        let cut = before - self.wires.len();
+        if cut > 0 {
+            self.dirty = true;
+        }
+        self.conditions.retain(|&(e, _)| e != entity);
+        if let Some(idx) = self.combinator_to_idx.remove(&entity) {
+            let last = self.combinators.len() - 1;
+            if idx != last {
+                self.combinators.swap(idx, last);
+                let swapped_entity = self.combinators[idx].entity;
+                self.combinator_to_idx.insert(swapped_entity, idx);
+            }
+            self.combinators.pop();
+        }
+        cut
+    }
+
+    /// Signals on the network a terminal is wired to, as of the last tick.
+    pub fn network_signals(&self, end: WireEnd) -> Option<&Signals> {
+        self.net_of.get(&end).and_then(|&n| self.signals.get(n))
+    }
+
+    /// Number of terminals on the network a terminal is wired to.
+    pub fn network_size(&self, end: WireEnd) -> usize {
+        self.net_of.get(&end).map(|&n| self.members[n].len()).unwrap_or(0)
+    }
+
+    // --- Conditions ---
+
+    /// Set or clear the enable condition on a machine, splitter, or belt.
+    pub fn set_condition(&mut self, entity: EntityId, condition: Option<Condition>) {
+        let existing = self.conditions.iter().position(|&(e, _)| e == entity);
+        match (existing, condition) {
+            (Some(i), Some(c)) => self.conditions[i].1 = c,
+            (None, Some(c)) => self.conditions.push((entity, c)),
+            (Some(i), None) => {
+                self.conditions.remove(i);
+                self.released.push(entity);
+            }
+            (None, None) => {}
+        }
+    }
+
+    pub fn condition(&self, entity: EntityId) -> Option<&Condition> {
+        self.conditions.iter().find(|(e, _)| *e == entity).map(|(_, c)| c)
+    }
+
+    // --- Combinators ---
+
+    /// Register a newly placed combinator.
+    pub fn add_combinator(&mut self, entity: EntityId, config: CombinatorConfig) {
+        let idx = self.combinators.len();
+        self.combinators.push(CombinatorState { entity, config, output: Signals::new() });
+        self.combinator_to_idx.insert(entity, idx);
+    }
+
+    pub fn combinator(&self, entity: EntityId) -> Option<&CombinatorState> {
+        self.combinator_to_idx.get(&entity).map(|&i| &self.combinators[i])
+    }
+
+    /// Replace a combinator's configuration.
+    pub fn set_combinator_config(&mut self, entity: EntityId, config: CombinatorConfig) {
+        if let Some(&i) = self.combinator_to_idx.get(&entity) {
+            self.combinators[i].config = config;
+        }
+    }
+
+    // --- Simulation ---
+
+    /// Group wired terminals into networks (connected components), numbered
+    /// in the order their first wire was laid.
+    fn rebuild(&mut self) {
+        let mut adjacency: HashMap<WireEnd, Vec<WireEnd>> = HashMap::new();
+        let mut order: Vec<WireEnd> = Vec::new();
+        for &(a, b) in &self.wires {
+            for (from, to) in [(a, b), (b, a)] {
+                let list = adjacency.entry(from).or_default();
+                if list.is_empty() {
+                    order.push(from);
+                }
+                list.push(to);
+            }
+        }
+
+        self.net_of.clear();
+        self.members.clear();
+        for start in order {
+            if self.net_of.contains_key(&start) {
+                continue;
+            }
+            let net = self.members.len();
+            let mut members = Vec::new();
+            let mut queue = VecDeque::from([start]);
+            self.net_of.insert(start, net);
+            while let Some(end) = queue.pop_front() {
+                members.push(end);
+                for &next in &adjacency[&end] {
+                    if let std::collections::hash_map::Entry::Vacant(slot) = self.net_of.entry(next) {
+                        slot.insert(net);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            self.members.push(members);
+        }
+        self.signals = vec![Signals::new(); self.members.len()];
+        self.dirty = false;
+    }
+
+    /// Run one circuit tick: read signals, apply enable conditions, and
+    /// evaluate combinators.
+    pub fn tick(
+        &mut self,
+        storage: &StoragePool,
+        belts: &mut BeltNetwork,
+        machines: &mut MachinePool,
+        splitters: &mut SplitterPool,
+    ) {
+        if self.dirty {
+            self.rebuild();
+        }
+
+        // Phase 1: read every network
+        let mut signals = vec![Signals::new(); self.members.len()];
+        for (net, members) in self.members.iter().enumerate() {
+            let sum = &mut signals[net];
+            let mut add = |item: ItemId, n: i32| {
+                let v = sum.entry(item).or_insert(0);
+                *v = v.wrapping_add(n);
+            };
+            for end in members {
+                match end.terminal {
+                    Terminal::Main => {
+                        if let Some(state) = storage.get(end.entity) {
+                            for slot in state.slots.iter().filter(|s| s.count > 0) {
+                                add(slot.item, slot.count as i32);
+                            }
+                        } else if let Some((items, _)) = belts.entity_items(end.entity) {
+                            for bi in items {
+                                add(bi.item, 1);
+                            }
+                        }
+                    }
+                    Terminal::Output => {
+                        if let Some(c) = self.combinator(end.entity) {
+                            for (&item, &n) in &c.output {
+                                add(item, n);
+                            }
+                        }
+                    }
+                }
+            }
+            sum.retain(|_, v| *v != 0);
+        }
+
+        // Phase 2: enable conditions. Belts halt their whole transport line,
+        // so the disabled belts are gathered and their lines halted together.
+        for entity in self.released.drain(..) {
+            machines.set_enabled(entity, true);
+            splitters.set_enabled(entity, true);
+        }
+        let mut halted = Vec::new();
+        for &(entity, condition) in &self.conditions {
+            let enabled = match self.net_of.get(&WireEnd::main(entity)) {
+                Some(&net) => condition.holds(&signals[net]),
+                None => true, // unwired: nothing to test against
+            };
+            if !machines.set_enabled(entity, enabled) && !splitters.set_enabled(entity, enabled) && !enabled {
+                halted.push(entity);
+            }
+        }
+        belts.set_halted_entities(halted);
+
+        // Phase 3: combinators, seen by the network next tick
+        let empty = Signals::new();
+        for c in &mut self.combinators {
+            let input = match self.net_of.get(&WireEnd::main(c.entity)) {
+                Some(&net) => &signals[net],
+                None => &empty,
+            };
+            c.output = c.config.evaluate(input);
+        }
+
+        self.signals = signals;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperbolic::cell_id::CellId;
+    use crate::game::items::MachineType;
+    use crate::game::world::{Direction, WorldState};
+    use slotmap::SlotMap;
+
+    fn make_entities(n: usize) -> Vec<EntityId> {
+        let mut sm: SlotMap<EntityId, ()> = SlotMap::with_key();
+        (0..n).map(|_| sm.insert(())).collect()
+    }
+
+    struct Pools {
+        storage: StoragePool,
+        belts: BeltNetwork,
+        machines: MachinePool,
+        splitters: SplitterPool,
+    }
+
+    impl Pools {
+        fn new() -> Self {
+            Self {
+                storage: StoragePool::new(),
+                belts: BeltNetwork::new(),
+                machines: MachinePool::new(),
+                splitters: SplitterPool::new(),
+            }
+        }
+
+        fn tick(&mut self, circuit: &mut CircuitNetwork) {
+            circuit.tick(&self.storage, &mut self.belts, &mut self.machines, &mut self.splitters);
+        }
+    }
+
+    fn cubes_at_least(n: i32) -> Condition {
+        Condition { left: ItemId::Cube, cmp: Comparator::GreaterEq, right: Operand::Constant(n) }
+    }
+
+    #[test]
+    fn arithmetic_wraps_and_guards_division() {
+        assert_eq!(ArithOp::Add.apply(i32::MAX, 1), i32::MIN);
+        assert_eq!(ArithOp::Div.apply(7, 0), 0);
+        assert_eq!(ArithOp::Rem.apply(7, 0), 0);
+        assert_eq!(ArithOp::Div.apply(i32::MIN, -1), 0);
+        assert_eq!(ArithOp::Rem.apply(7, 3), 1);
+    }
+
+    #[test]
+    fn storage_contents_disable_machine() {
+        let e = make_entities(2);
+        let (store, machine) = (e[0], e[1]);
+        let mut pools = Pools::new();
+        pools.storage.add(store);
+        pools.machines.add(machine, MachineType::Composer);
+
+        let mut circuit = CircuitNetwork::new();
+        circuit.connect(WireEnd::main(store), WireEnd::main(machine));
+        // "Stop the Composer when storage has 100 Cubes"
+        circuit.set_condition(
+            machine,
+            Some(Condition { left: ItemId::Cube, cmp: Comparator::Less, right: Operand::Constant(100) }),
+        );
+
+        pools.tick(&mut circuit);
+        assert_eq!(pools.machines.is_enabled(machine), Some(true));
+
+        pools.storage.accept_input(store, ItemId::Cube, 50);
+        pools.storage.accept_input(store, ItemId::Cube, 50);
+        pools.tick(&mut circuit);
+        assert_eq!(pools.machines.is_enabled(machine), Some(false));
+        assert_eq!(circuit.network_signals(WireEnd::main(store)).unwrap()[&ItemId::Cube], 100);
+
+        // Dropping the condition re-enables the machine
+        circuit.set_condition(machine, None);
+        pools.tick(&mut circuit);
+        assert_eq!(pools.machines.is_enabled(machine), Some(true));
+    }
+
+    #[test]
+    fn combinator_output_arrives_next_tick() {
+        let e = make_entities(3);
+        let (store, comb, splitter) = (e[0], e[1], e[2]);
+        let mut pools = Pools::new();
+        pools.storage.add(store);
+        pools.splitters.add(splitter);
+        pools.storage.accept_input(store, ItemId::Square, 3);
+
+        let mut circuit = CircuitNetwork::new();
+        circuit.add_combinator(
+            comb,
+            CombinatorConfig::Arithmetic {
+                left: ItemId::Square,
+                op: ArithOp::Mul,
+                right: Operand::Constant(4),
+                output: ItemId::Cube,
+            },
+        );
+        circuit.connect(WireEnd::main(store), WireEnd::main(comb));
+        circuit.connect(WireEnd::output(comb), WireEnd::main(splitter));
+        circuit.set_condition(splitter, Some(cubes_at_least(12)));
+
+        // Tick 1: the combinator computes 12 Cubes, but the splitter's network
+        // has not seen it yet
+        pools.tick(&mut circuit);
+        assert!(!pools.splitters.get(splitter).unwrap().enabled);
+        assert_eq!(circuit.combinator(comb).unwrap().output[&ItemId::Cube], 12);
+
+        pools.tick(&mut circuit);
+        assert!(pools.splitters.get(splitter).unwrap().enabled);
+        assert_eq!(circuit.network_size(WireEnd::main(splitter)), 2);
+    }
+
+    #[test]
+    fn decider_copies_or_flags() {
+        let mut input = Signals::new();
+        input.insert(ItemId::Cube, 7);
+        let flag = CombinatorConfig::Decider { condition: cubes_at_least(5), output: ItemId::Point, copy_input: false };
+        assert_eq!(flag.evaluate(&input), Signals::from([(ItemId::Point, 1)]));
+        let copy = CombinatorConfig::Decider { condition: cubes_at_least(5), output: ItemId::Cube, copy_input: true };
+        assert_eq!(copy.evaluate(&input), Signals::from([(ItemId::Cube, 7)]));
+        let fail = CombinatorConfig::Decider { condition: cubes_at_least(8), output: ItemId::Cube, copy_input: true };
+        assert!(fail.evaluate(&input).is_empty());
+    }
+
+    #[test]
+    fn belt_reader_and_halt() {
+        let mut world = WorldState::new();
+        let mut pools = Pools::new();
+        let addr = &CellId::from_canonical(vec![0]);
+        let belt = world.place(addr, (0, 0), ItemId::Belt, Direction::East).unwrap();
+        pools.belts.on_belt_placed(belt, addr, 0, 0, Direction::East, &world);
+        pools.belts.spawn_item_on_entity(belt, ItemId::Cube);
+        // Keys from a fresh SlotMap collide with the world's first entity
+        let store = make_entities(2)[1];
+        pools.storage.add(store);
+
+        let mut circuit = CircuitNetwork::new();
+        circuit.connect(WireEnd::main(belt), WireEnd::main(store));
+        circuit.set_condition(belt, Some(cubes_at_least(2)));
+        pools.tick(&mut circuit);
+        assert_eq!(circuit.network_signals(WireEnd::main(store)).unwrap()[&ItemId::Cube], 1);
+
+        // Halted: the item stays put
+        let before = pools.belts.entity_items(belt).unwrap().0[0].pos;
+        pools.belts.tick();
+        assert_eq!(pools.belts.entity_items(belt).unwrap().0[0].pos, before);
+
+        // A second Cube in storage satisfies the condition and releases the line
+        pools.storage.accept_input(store, ItemId::Cube, 1);
+        pools.tick(&mut circuit);
+        pools.belts.tick();
+        assert!(pools.belts.entity_items(belt).unwrap().0[0].pos < before);
+    }
+
+    #[test]
+    fn remove_entity_cuts_wires_and_splits_network() {
+        let e = make_entities(3);
+        let mut pools = Pools::new();
+        let mut circuit = CircuitNetwork::new();
+        circuit.connect(WireEnd::main(e[0]), WireEnd::main(e[1]));
+        circuit.connect(WireEnd::main(e[1]), WireEnd::main(e[2]));
+        assert!(!circuit.connect(WireEnd::main(e[1]), WireEnd::main(e[0])));
+        pools.tick(&mut circuit);
+        assert_eq!(circuit.network_size(WireEnd::main(e[0])), 3);
+
+        assert_eq!(circuit.remove_entity(e[1]), 2);
+        assert_eq!(circuit.wire_count(e[0]), 0);
+        pools.tick(&mut circuit);
+        assert_eq!(circuit.network_size(WireEnd::main(e[0])), 0);
+        assert!(circuit.network_signals(WireEnd::main(e[2])).is_none());
+    }
+}
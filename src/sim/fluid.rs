@@ -0,0 +1,570 @@
+//! Fluid network: pipes, pumps, and machine fluid ports.
+//!
+//! Every pipe is one segment holding a single fluid at an integer volume.
+//! Pipes join their neighbours across cell edges (including tile edges).
+//! Pumps pressurize the pipe on their output side and draw on the pipe on
+//! their input side; the head falls off by one per pipe hop, so a run needs
+//! a relay pump roughly every [`PUMP_HEAD`] squares. Fluid only moves
+//! between segments down a pressure gradient, which makes an unpumped pipe
+//! run inert.
+//!
+//! All transfers run in dense-index order with integer volumes, so a tick is
+//! fully deterministic for a given placement history.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::items::FluidId;
+use crate::game::world::{Direction, EntityId};
+use crate::sim::machine::MachinePool;
+use crate::sim::state_hash::StateHasher;
+
+/// Volume a single pipe segment can hold.
+pub const PIPE_CAPACITY: u32 = 100;
+
+/// Maximum volume moved across one connection per tick.
+pub const PIPE_FLOW_RATE: u32 = 10;
+
+/// Pressure a pump applies at its output pipe. Drops by one per pipe hop.
+pub const PUMP_HEAD: i32 = 40;
+
+/// Volume a pump moves from its input pipe to its output pipe per tick.
+pub const PUMP_RATE: u32 = 20;
+
+/// A single-fluid container. Used for pipe segments and machine tanks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FluidTank {
+    /// The fluid held, if any. Cleared when the tank drains to zero.
+    pub fluid: Option<FluidId>,
+    pub amount: u32,
+}
+
+impl FluidTank {
+    /// Whether this tank can take `fluid` without mixing.
+    pub fn accepts(&self, fluid: FluidId) -> bool {
+        self.amount == 0 || self.fluid == Some(fluid)
+    }
+
+    /// Add up to `amount` of `fluid`, limited by `capacity`. Returns the
+    /// volume actually added (zero if the tank holds a different fluid).
+    pub fn fill(&mut self, fluid: FluidId, amount: u32, capacity: u32) -> u32 {
+        if !self.accepts(fluid) {
+            return 0;
+        }
+        let added = amount.min(capacity.saturating_sub(self.amount));
+        if added > 0 {
+            self.fluid = Some(fluid);
+            self.amount += added;
+        }
+        added
+    }
+
+    /// Remove up to `max` volume. Returns the fluid and volume removed.
+    pub fn drain(&mut self, max: u32) -> Option<(FluidId, u32)> {
+        let fluid = self.fluid?;
+        let taken = max.min(self.amount);
+        if taken == 0 {
+            return None;
+        }
+        self.amount -= taken;
+        if self.amount == 0 {
+            self.fluid = None;
+        }
+        Some((fluid, taken))
+    }
+}
+
+/// What a pipe is attached to, besides other pipes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FluidEnd {
+    /// The pipe feeds a machine's fluid input tank.
+    MachineInput,
+    /// A machine's fluid output tank empties into the pipe.
+    MachineOutput,
+    /// A pump draws from the pipe.
+    PumpInput,
+    /// A pump pushes into (and pressurizes) the pipe.
+    PumpOutput,
+}
+
+/// A pipe's attachment to a machine or pump port.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Attachment {
+    /// The machine or pump entity.
+    pub owner: EntityId,
+    /// The pipe entity.
+    pub pipe: EntityId,
+    /// Direction from the pipe toward the owner (for rendering stubs).
+    pub side: Direction,
+    pub end: FluidEnd,
+}
+
+/// Per-pipe state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PipeSegment {
+    pub entity: EntityId,
+    /// Fluid and volume held by this segment.
+    pub contents: FluidTank,
+    /// Pump-derived pressure: positive downstream of a pump, negative on its
+    /// suction side, zero when no pump reaches this segment.
+    pub pressure: i32,
+    /// Neighbouring pipe in each direction (N, E, S, W), in this pipe's frame.
+    pub links: [Option<EntityId>; 4],
+}
+
+/// Index of a direction into `PipeSegment::links` and connection masks.
+fn dir_index(d: Direction) -> usize {
+    d.rotations_from_north() as usize
+}
+
+/// Pool of all pipes and their attachments. Dense storage indexed by EntityId.
+#[derive(Serialize, Deserialize)]
+pub struct FluidNetwork {
+    segments: Vec<PipeSegment>,
+    entity_to_idx: HashMap<EntityId, usize>,
+    attachments: Vec<Attachment>,
+    /// Set on any topology change; pressure and the edge list are rebuilt lazily.
+    dirty: bool,
+    /// Cached pipe↔pipe connections as dense index pairs, each listed once.
+    edges: Vec<(usize, usize)>,
+}
+
+impl FluidNetwork {
+    pub fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+            entity_to_idx: HashMap::new(),
+            attachments: Vec::new(),
+            dirty: false,
+            edges: Vec::new(),
+        }
+    }
+
+    /// Feed every pipe's contents and links, and every attachment, into a
+    /// state hash, in entity order. Pressure is rebuilt from the topology,
+    /// so it isn't hashed.
+    pub fn hash_state(&self, h: &mut StateHasher) {
+        let mut order: Vec<&PipeSegment> = self.segments.iter().collect();
+        order.sort_by_key(|s| s.entity);
+        h.u32(order.len() as u32);
+        for pipe in order {
+            h.entity(pipe.entity);
+            h.tank(&pipe.contents);
+            for &link in &pipe.links {
+                h.option_entity(link);
+            }
+        }
+        let mut attachments: Vec<&Attachment> = self.attachments.iter().collect();
+        attachments.sort_by_key(|a| (a.pipe, a.owner, a.end as u8));
+        h.u32(attachments.len() as u32);
+        for a in attachments {
+            h.entity(a.pipe);
+            h.entity(a.owner);
+            h.u8(a.end as u8);
+            h.u8(a.side.rotations_from_north());
+        }
+    }
+
+    /// Register a newly placed pipe. Links are added with `link_pipes`.
+    pub fn add_pipe(&mut self, entity: EntityId) {
+        let idx = self.segments.len();
+        self.segments.push(PipeSegment {
+            entity,
+            contents: FluidTank::default(),
+            pressure: 0,
+            links: [None; 4],
+        });
+        self.entity_to_idx.insert(entity, idx);
+        self.dirty = true;
+    }
+
+    /// Remove a pipe by EntityId, dropping its links and attachments.
+    /// Any fluid it held is lost. Swap-removes with the last element.
+    pub fn remove_pipe(&mut self, entity: EntityId) -> bool {
+        let Some(idx) = self.entity_to_idx.remove(&entity) else {
+            return false;
+        };
+        for link in self.segments[idx].links.into_iter().flatten() {
+            if let Some(&n) = self.entity_to_idx.get(&link) {
+                for slot in &mut self.segments[n].links {
+                    if *slot == Some(entity) {
+                        *slot = None;
+                    }
+                }
+            }
+        }
+        self.attachments.retain(|a| a.pipe != entity);
+
+        let last = self.segments.len() - 1;
+        if idx != last {
+            self.segments.swap(idx, last);
+            let swapped_entity = self.segments[idx].entity;
+            self.entity_to_idx.insert(swapped_entity, idx);
+        }
+        self.segments.pop();
+        self.dirty = true;
+        true
+    }
+
+    /// Join pipe `a` to pipe `b`, where `b` lies in `direction` from `a` and
+    /// `a` lies in `back` from `b`. Within a cell `back` is the opposite of
+    /// `direction`; across an edge it is in `b`'s grid (see `EdgeTransfer`).
+    /// Returns false if either pipe is unknown.
+    pub fn link_pipes(&mut self, a: EntityId, b: EntityId, direction: Direction, back: Direction) -> bool {
+        let (Some(&ia), Some(&ib)) = (self.entity_to_idx.get(&a), self.entity_to_idx.get(&b)) else {
+            return false;
+        };
+        self.segments[ia].links[dir_index(direction)] = Some(b);
+        self.segments[ib].links[dir_index(back)] = Some(a);
+        self.dirty = true;
+        true
+    }
+
+    /// Attach a pipe to a machine or pump port. `side` points from the pipe
+    /// toward the owner. Returns false if the pipe is unknown.
+    pub fn attach(&mut self, owner: EntityId, pipe: EntityId, side: Direction, end: FluidEnd) -> bool {
+        if !self.entity_to_idx.contains_key(&pipe) {
+            return false;
+        }
+        let exists = self
+            .attachments
+            .iter()
+            .any(|a| a.owner == owner && a.pipe == pipe && a.end == end);
+        if !exists {
+            self.attachments.push(Attachment { owner, pipe, side, end });
+            self.dirty = true;
+        }
+        true
+    }
+
+    /// Drop every attachment owned by a machine or pump (on removal or rotation).
+    pub fn detach_owner(&mut self, owner: EntityId) {
+        let before = self.attachments.len();
+        self.attachments.retain(|a| a.owner != owner);
+        if self.attachments.len() != before {
+            self.dirty = true;
+        }
+    }
+
+    /// Get a reference to the segment state for a pipe.
+    pub fn segment(&self, entity: EntityId) -> Option<&PipeSegment> {
+        self.entity_to_idx.get(&entity).map(|&i| &self.segments[i])
+    }
+
+    /// Bitmask (bit 0 = N, 1 = E, 2 = S, 3 = W) of the directions in which a
+    /// pipe connects to another pipe, machine, or pump. Used by the renderer.
+    pub fn connection_mask(&self, entity: EntityId) -> u32 {
+        let Some(seg) = self.segment(entity) else {
+            return 0;
+        };
+        let mut mask = 0;
+        for (i, link) in seg.links.iter().enumerate() {
+            if link.is_some() {
+                mask |= 1 << i;
+            }
+        }
+        for a in self.attachments.iter().filter(|a| a.pipe == entity) {
+            mask |= 1 << dir_index(a.side);
+        }
+        mask
+    }
+
+    /// Rebuild the edge list and recompute pressure after a topology change.
+    fn rebuild(&mut self) {
+        self.edges.clear();
+        for (i, seg) in self.segments.iter().enumerate() {
+            for link in seg.links.iter().flatten() {
+                if let Some(&j) = self.entity_to_idx.get(link) {
+                    if i < j {
+                        self.edges.push((i, j));
+                    }
+                }
+            }
+        }
+
+        let n = self.segments.len();
+        let mut push = vec![0i32; n];
+        let mut suction = vec![0i32; n];
+        for a in &self.attachments {
+            let Some(&start) = self.entity_to_idx.get(&a.pipe) else {
+                continue;
+            };
+            let field = match a.end {
+                FluidEnd::PumpOutput => &mut push,
+                FluidEnd::PumpInput => &mut suction,
+                _ => continue,
+            };
+            Self::spread_head(&self.segments, &self.entity_to_idx, start, field);
+        }
+        for (i, seg) in self.segments.iter_mut().enumerate() {
+            seg.pressure = push[i] - suction[i];
+        }
+        self.dirty = false;
+    }
+
+    /// Breadth-first spread of a pump head from `start`, keeping the highest
+    /// head seen at each segment.
+    fn spread_head(
+        segments: &[PipeSegment],
+        entity_to_idx: &HashMap<EntityId, usize>,
+        start: usize,
+        field: &mut [i32],
+    ) {
+        let mut dist = vec![u32::MAX; segments.len()];
+        let mut queue = VecDeque::new();
+        dist[start] = 0;
+        queue.push_back(start);
+        while let Some(i) = queue.pop_front() {
+            let head = PUMP_HEAD - dist[i] as i32;
+            if head <= 0 {
+                continue;
+            }
+            field[i] = field[i].max(head);
+            for link in segments[i].links.iter().flatten() {
+                if let Some(&j) = entity_to_idx.get(link) {
+                    if dist[j] == u32::MAX {
+                        dist[j] = dist[i] + 1;
+                        queue.push_back(j);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run one simulation tick:
+    /// 1. Machine fluid outputs empty into their pipes.
+    /// 2. Pumps move fluid from their input pipe to their output pipe.
+    /// 3. Segments flow down the pressure gradient.
+    /// 4. Machine fluid inputs draw from their pipes.
+    pub fn tick(&mut self, machines: &mut MachinePool) {
+        if self.dirty {
+            self.rebuild();
+        }
+
+        // Phase 1: machine outputs → pipe
+        for a in &self.attachments {
+            if a.end != FluidEnd::MachineOutput {
+                continue;
+            }
+            let Some(&p) = self.entity_to_idx.get(&a.pipe) else {
+                continue;
+            };
+            let Some(tank) = machines.fluid_output_tank(a.owner) else {
+                continue;
+            };
+            let Some(fluid) = tank.fluid else {
+                continue;
+            };
+            let moved = self.segments[p].contents.fill(fluid, tank.amount.min(PIPE_FLOW_RATE), PIPE_CAPACITY);
+            if moved > 0 {
+                machines.drain_fluid_output(a.owner, moved);
+            }
+        }
+
+        // Phase 2: pumps, pairing each pump's input attachment with its output
+        for out in &self.attachments {
+            if out.end != FluidEnd::PumpOutput {
+                continue;
+            }
+            let Some(input) = self
+                .attachments
+                .iter()
+                .find(|a| a.owner == out.owner && a.end == FluidEnd::PumpInput)
+            else {
+                continue;
+            };
+            let (Some(&src), Some(&dst)) = (
+                self.entity_to_idx.get(&input.pipe),
+                self.entity_to_idx.get(&out.pipe),
+            ) else {
+                continue;
+            };
+            Self::transfer(&mut self.segments, src, dst, PUMP_RATE);
+        }
+
+        // Phase 3: pressure-driven flow between linked segments
+        for &(a, b) in &self.edges {
+            let (pa, pb) = (self.segments[a].pressure, self.segments[b].pressure);
+            if pa > pb {
+                Self::transfer(&mut self.segments, a, b, PIPE_FLOW_RATE);
+            } else if pb > pa {
+                Self::transfer(&mut self.segments, b, a, PIPE_FLOW_RATE);
+            }
+        }
+
+        // Phase 4: pipe → machine inputs
+        for a in &self.attachments {
+            if a.end != FluidEnd::MachineInput {
+                continue;
+            }
+            let Some(&p) = self.entity_to_idx.get(&a.pipe) else {
+                continue;
+            };
+            let contents = self.segments[p].contents;
+            let Some(fluid) = contents.fluid else {
+                continue;
+            };
+            let moved = machines.fill_fluid_input(a.owner, fluid, contents.amount.min(PIPE_FLOW_RATE));
+            self.segments[p].contents.drain(moved);
+        }
+    }
+
+    /// Move up to `max` volume from segment `src` to `dst`, respecting
+    /// capacity and refusing to mix fluids.
+    fn transfer(segments: &mut [PipeSegment], src: usize, dst: usize, max: u32) {
+        let from = segments[src].contents;
+        let Some(fluid) = from.fluid else {
+            return;
+        };
+        let moved = segments[dst].contents.fill(fluid, from.amount.min(max), PIPE_CAPACITY);
+        segments[src].contents.drain(moved);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::items::MachineType;
+    use slotmap::SlotMap;
+
+    fn make_entities(n: usize) -> Vec<EntityId> {
+        let mut sm: SlotMap<EntityId, ()> = SlotMap::with_key();
+        (0..n).map(|_| sm.insert(())).collect()
+    }
+
+    /// A straight east-running pipe of `n` segments.
+    fn pipe_run(net: &mut FluidNetwork, pipes: &[EntityId]) {
+        for &p in pipes {
+            net.add_pipe(p);
+        }
+        for w in pipes.windows(2) {
+            net.link_pipes(w[0], w[1], Direction::East, Direction::West);
+        }
+    }
+
+    #[test]
+    fn tank_refuses_to_mix() {
+        let mut tank = FluidTank::default();
+        assert_eq!(tank.fill(FluidId::MetricFoam, 30, 50), 30);
+        assert_eq!(tank.fill(FluidId::Coolant, 10, 50), 0);
+        assert_eq!(tank.fill(FluidId::MetricFoam, 30, 50), 20);
+        assert_eq!(tank.drain(100), Some((FluidId::MetricFoam, 50)));
+        assert!(tank.fluid.is_none());
+        assert_eq!(tank.fill(FluidId::Coolant, 10, 50), 10);
+    }
+
+    #[test]
+    fn contents_alone_alter_hash() {
+        let e = make_entities(2);
+        let mut net = FluidNetwork::new();
+        pipe_run(&mut net, &e);
+        let hash = |net: &FluidNetwork| {
+            let mut h = StateHasher::new();
+            net.hash_state(&mut h);
+            h.finish()
+        };
+        let before = hash(&net);
+        net.segments[1].contents.fill(FluidId::Coolant, 5, PIPE_CAPACITY);
+        assert_ne!(hash(&net), before);
+    }
+
+    #[test]
+    fn pressure_falls_off_from_pump() {
+        let e = make_entities(4);
+        let (pump, pipes) = (e[0], &e[1..]);
+        let mut net = FluidNetwork::new();
+        pipe_run(&mut net, pipes);
+        net.attach(pump, pipes[0], Direction::West, FluidEnd::PumpOutput);
+
+        let mut machines = MachinePool::new();
+        net.tick(&mut machines);
+        assert_eq!(net.segment(pipes[0]).unwrap().pressure, PUMP_HEAD);
+        assert_eq!(net.segment(pipes[1]).unwrap().pressure, PUMP_HEAD - 1);
+        assert_eq!(net.segment(pipes[2]).unwrap().pressure, PUMP_HEAD - 2);
+    }
+
+    #[test]
+    fn head_runs_out_past_pump_range() {
+        let e = make_entities(PUMP_HEAD as usize + 3);
+        let (pump, pipes) = (e[0], &e[1..]);
+        let mut net = FluidNetwork::new();
+        pipe_run(&mut net, pipes);
+        net.attach(pump, pipes[0], Direction::West, FluidEnd::PumpOutput);
+
+        let mut machines = MachinePool::new();
+        net.tick(&mut machines);
+        assert_eq!(net.segment(pipes[PUMP_HEAD as usize - 1]).unwrap().pressure, 1);
+        assert_eq!(net.segment(pipes[PUMP_HEAD as usize]).unwrap().pressure, 0);
+        assert_eq!(net.segment(pipes[PUMP_HEAD as usize + 1]).unwrap().pressure, 0);
+    }
+
+    #[test]
+    fn unpumped_pipes_do_not_flow() {
+        let pipes = make_entities(2);
+        let mut net = FluidNetwork::new();
+        pipe_run(&mut net, &pipes);
+        net.segments[0].contents.fill(FluidId::MetricFoam, 50, PIPE_CAPACITY);
+
+        let mut machines = MachinePool::new();
+        net.tick(&mut machines);
+        assert_eq!(net.segment(pipes[0]).unwrap().contents.amount, 50);
+        assert_eq!(net.segment(pipes[1]).unwrap().contents.amount, 0);
+    }
+
+    #[test]
+    fn source_to_machine_through_pump() {
+        // Source → pipe a → pump → pipe b → pipe c → Transformer
+        let e = make_entities(6);
+        let (source, a, pump, b, c, transformer) = (e[0], e[1], e[2], e[3], e[4], e[5]);
+        let mut machines = MachinePool::new();
+        machines.add(source, MachineType::Source);
+        machines.add(transformer, MachineType::Transformer);
+        machines.fill_fluid_output(source, FluidId::MetricFoam, 100);
+
+        let mut net = FluidNetwork::new();
+        net.add_pipe(a);
+        pipe_run(&mut net, &[b, c]);
+        net.attach(source, a, Direction::West, FluidEnd::MachineOutput);
+        net.attach(pump, a, Direction::East, FluidEnd::PumpInput);
+        net.attach(pump, b, Direction::West, FluidEnd::PumpOutput);
+        net.attach(transformer, c, Direction::East, FluidEnd::MachineInput);
+
+        for _ in 0..20 {
+            net.tick(&mut machines);
+        }
+        let tank = machines.fluid_input_tank(transformer).unwrap();
+        assert_eq!(tank.fluid, Some(FluidId::MetricFoam));
+        assert_eq!(tank.amount, 100);
+        assert_eq!(machines.fluid_output_tank(source).unwrap().amount, 0);
+    }
+
+    #[test]
+    fn flow_does_not_mix_fluids() {
+        let e = make_entities(3);
+        let (pump, pipes) = (e[0], &e[1..]);
+        let mut net = FluidNetwork::new();
+        pipe_run(&mut net, pipes);
+        net.attach(pump, pipes[0], Direction::West, FluidEnd::PumpOutput);
+        net.segments[0].contents.fill(FluidId::MetricFoam, 50, PIPE_CAPACITY);
+        net.segments[1].contents.fill(FluidId::Coolant, 5, PIPE_CAPACITY);
+
+        let mut machines = MachinePool::new();
+        net.tick(&mut machines);
+        assert_eq!(net.segment(pipes[0]).unwrap().contents.amount, 50);
+        assert_eq!(net.segment(pipes[1]).unwrap().contents.fluid, Some(FluidId::Coolant));
+    }
+
+    #[test]
+    fn remove_pipe_unlinks_neighbours() {
+        let pipes = make_entities(3);
+        let mut net = FluidNetwork::new();
+        pipe_run(&mut net, &pipes);
+
+        assert!(net.remove_pipe(pipes[1]));
+        assert!(net.segment(pipes[1]).is_none());
+        assert_eq!(net.connection_mask(pipes[0]), 0);
+        assert_eq!(net.connection_mask(pipes[2]), 0);
+        assert!(!net.remove_pipe(pipes[1]));
+    }
+}
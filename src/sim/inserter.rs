@@ -4,12 +4,13 @@
 //! ports. Belts connect directly to ports. Items transfer between belt endpoints
 //! and machine input/output slots during the simulation tick.
 
+use serde::{Deserialize, Serialize};
 use crate::game::items::MachineType;
 use crate::game::world::{Direction, StructureKind};
 
 /// Whether a port accepts or produces items.
 #[allow(dead_code)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PortKind {
     Input,
     Output,
@@ -17,7 +18,7 @@ pub enum PortKind {
 
 /// A port definition in canonical orientation (machine facing North).
 #[allow(dead_code)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct PortDef {
     /// Which side of the machine cell this port is on (canonical, facing North).
     pub side: Direction,
@@ -98,12 +99,71 @@ pub fn storage_port_layout() -> &'static [PortDef] {
     ]
 }
 
+/// Get the canonical port layout for a Sink building (defined facing North).
+///
+/// Sink is 1×1 with a single input on its South edge.
+pub fn sink_port_layout() -> &'static [PortDef] {
+    &[PortDef { side: Direction::South, kind: PortKind::Input, slot: 0, cell_offset: (0, 0) }]
+}
+
+/// Get the canonical fluid port layout for a machine type (defined facing North).
+///
+/// Fluid ports attach to a pipe in the adjacent cell. They sit on the East
+/// edge so they never collide with the default item ports. `slot` is unused:
+/// each machine has a single fluid input tank and a single fluid output tank.
+pub fn fluid_port_layout(machine_type: MachineType) -> &'static [PortDef] {
+    use Direction::*;
+    use PortKind::*;
+    match machine_type {
+        // Transformer (3×3): fluid input east-south, fluid output east-north
+        MachineType::Transformer => &[
+            PortDef { side: East, kind: Input, slot: 0, cell_offset: (2, 2) },
+            PortDef { side: East, kind: Output, slot: 0, cell_offset: (2, 0) },
+        ],
+        // Embedder (3×3): fluid input east-center
+        MachineType::Embedder => &[
+            PortDef { side: East, kind: Input, slot: 0, cell_offset: (2, 1) },
+        ],
+        // Source (1×1): fluid output east
+        MachineType::Source => &[
+            PortDef { side: East, kind: Output, slot: 0, cell_offset: (0, 0) },
+        ],
+        MachineType::Composer | MachineType::Inverter | MachineType::Quotient => &[],
+    }
+}
+
+/// Get the canonical fluid port layout for a Pump (defined facing North).
+///
+/// Pump is 2×2: draws from the pipe behind it and pushes out the front.
+/// ```text
+///   (0,0) (1,0)   ← North edge: Output
+///   (0,1) (1,1)   ← South edge: Input
+/// ```
+pub fn pump_port_layout() -> &'static [PortDef] {
+    use Direction::*;
+    use PortKind::*;
+    &[
+        PortDef { side: South, kind: Input, slot: 0, cell_offset: (0, 1) },
+        PortDef { side: North, kind: Output, slot: 0, cell_offset: (0, 0) },
+    ]
+}
+
+/// Get the canonical fluid port layout for any structure kind with fluid ports.
+pub fn structure_fluid_port_layout(kind: StructureKind) -> &'static [PortDef] {
+    match kind {
+        StructureKind::Machine(mt) => fluid_port_layout(mt),
+        StructureKind::Pump => pump_port_layout(),
+        _ => &[],
+    }
+}
+
 /// Get the canonical port layout for any structure kind that has ports.
 /// Returns `None` for structure types without ports (Belt, PowerNode, etc.).
 pub fn structure_port_layout(kind: StructureKind) -> Option<&'static [PortDef]> {
     match kind {
         StructureKind::Machine(mt) => Some(port_layout(mt)),
         StructureKind::Storage => Some(storage_port_layout()),
+        StructureKind::Sink => Some(sink_port_layout()),
         _ => None,
     }
 }
@@ -127,10 +187,15 @@ pub struct RotatedPort {
 /// Rotates both port side directions and cell offsets within the footprint.
 #[allow(dead_code)]
 pub fn rotated_ports(machine_type: MachineType, facing: Direction) -> Vec<RotatedPort> {
+    rotate_port_defs(port_layout(machine_type), machine_type.footprint(), facing)
+}
+
+/// Rotate an arbitrary canonical port layout (e.g. a machine's per-entity
+/// layout) for a structure with canonical `footprint` at the given facing.
+pub fn rotate_port_defs(defs: &[PortDef], footprint: (i32, i32), facing: Direction) -> Vec<RotatedPort> {
     let n = facing.rotations_from_north();
-    let (w, h) = machine_type.footprint();
-    port_layout(machine_type)
-        .iter()
+    let (w, h) = footprint;
+    defs.iter()
         .map(|def| RotatedPort {
             side: def.side.rotate_n_cw(n),
             kind: def.kind,
@@ -140,24 +205,46 @@ pub fn rotated_ports(machine_type: MachineType, facing: Direction) -> Vec<Rotate
         .collect()
 }
 
+/// Whether a port at `cell_offset` on `side` sits on the outer edge of a
+/// `(w, h)` footprint, i.e. faces out of the structure rather than into it.
+pub fn port_position_valid(footprint: (i32, i32), cell_offset: (i32, i32), side: Direction) -> bool {
+    let (w, h) = footprint;
+    let (cx, cy) = cell_offset;
+    if cx < 0 || cy < 0 || cx >= w || cy >= h {
+        return false;
+    }
+    match side {
+        Direction::North => cy == 0,
+        Direction::South => cy == h - 1,
+        Direction::West => cx == 0,
+        Direction::East => cx == w - 1,
+    }
+}
+
+/// Every position a port may occupy on a `(w, h)` footprint, in canonical
+/// orientation: each edge cell paired with each outward side it has.
+pub fn port_positions(footprint: (i32, i32)) -> Vec<((i32, i32), Direction)> {
+    let (w, h) = footprint;
+    let mut out = Vec::new();
+    for side in [Direction::North, Direction::East, Direction::South, Direction::West] {
+        for cy in 0..h {
+            for cx in 0..w {
+                if port_position_valid(footprint, (cx, cy), side) {
+                    out.push(((cx, cy), side));
+                }
+            }
+        }
+    }
+    out
+}
+
 /// Get the rotated port layout for any structure kind at the given facing direction.
 /// Returns empty vec for structure types without ports.
 pub fn rotated_structure_ports(kind: StructureKind, facing: Direction) -> Vec<RotatedPort> {
-    let ports = match structure_port_layout(kind) {
-        Some(p) => p,
-        None => return Vec::new(),
-    };
-    let n = facing.rotations_from_north();
-    let (w, h) = kind.footprint();
-    ports
-        .iter()
-        .map(|def| RotatedPort {
-            side: def.side.rotate_n_cw(n),
-            kind: def.kind,
-            slot: def.slot,
-            cell_offset: facing.rotate_cell(def.cell_offset.0, def.cell_offset.1, w, h),
-        })
-        .collect()
+    match structure_port_layout(kind) {
+        Some(ports) => rotate_port_defs(ports, kind.footprint(), facing),
+        None => Vec::new(),
+    }
 }
 
 /// Find a port at a specific cell offset and facing side for any structure kind.
@@ -842,4 +929,71 @@ mod tests {
         assert!(structure_port_layout(StructureKind::Storage).is_some());
         assert!(structure_port_layout(StructureKind::Machine(MachineType::Composer)).is_some());
     }
+
+    #[test]
+    fn sink_rotated_input_faces_away_from_facing() {
+        // Sink facing East: its single input sits on the West side.
+        let port = structure_port_at_cell_on_side(
+            StructureKind::Sink, Direction::East, (0, 0), Direction::West,
+        ).unwrap();
+        assert_eq!(port.kind, PortKind::Input);
+        assert!(belt_compatible_with_port(&port, Direction::East));
+        assert!(structure_port_at_cell_on_side(
+            StructureKind::Sink, Direction::East, (0, 0), Direction::East,
+        ).is_none());
+    }
+
+    #[test]
+    fn port_position_valid_only_on_outer_edges() {
+        // 3×3: centre cell has no outward side, edge cells face out
+        assert!(!port_position_valid((3, 3), (1, 1), Direction::North));
+        assert!(port_position_valid((3, 3), (1, 0), Direction::North));
+        assert!(!port_position_valid((3, 3), (1, 0), Direction::South));
+        assert!(port_position_valid((3, 3), (2, 2), Direction::East));
+        assert!(port_position_valid((3, 3), (2, 2), Direction::South));
+        assert!(!port_position_valid((3, 3), (3, 0), Direction::East));
+    }
+
+    #[test]
+    fn port_positions_cover_perimeter() {
+        // Each side of a w×h footprint has w (or h) positions
+        assert_eq!(port_positions((3, 3)).len(), 12);
+        assert_eq!(port_positions((2, 2)).len(), 8);
+        assert_eq!(port_positions((1, 1)).len(), 4);
+        for (cell, side) in port_positions((3, 2)) {
+            assert!(port_position_valid((3, 2), cell, side));
+        }
+    }
+
+    #[test]
+    fn rotate_port_defs_matches_builtin_layouts() {
+        for &mt in &[MachineType::Composer, MachineType::Embedder, MachineType::Quotient] {
+            for dir in [Direction::North, Direction::East, Direction::South, Direction::West] {
+                let a = rotated_ports(mt, dir);
+                let b = rotate_port_defs(port_layout(mt), mt.footprint(), dir);
+                for (x, y) in a.iter().zip(&b) {
+                    assert_eq!((x.side, x.cell_offset, x.slot), (y.side, y.cell_offset, y.slot));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fluid_ports_sit_on_edges_clear_of_item_ports() {
+        for &mt in &[
+            MachineType::Composer, MachineType::Inverter, MachineType::Embedder,
+            MachineType::Quotient, MachineType::Transformer, MachineType::Source,
+        ] {
+            for fp in fluid_port_layout(mt) {
+                assert!(port_position_valid(mt.footprint(), fp.cell_offset, fp.side), "{:?}", mt);
+                let clash = port_layout(mt)
+                    .iter()
+                    .any(|p| p.cell_offset == fp.cell_offset && p.side == fp.side);
+                assert!(!clash, "{:?} fluid port overlaps an item port", mt);
+            }
+        }
+        for p in pump_port_layout() {
+            assert!(port_position_valid((2, 2), p.cell_offset, p.side));
+        }
+    }
 }
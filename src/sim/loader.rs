@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::items::ItemId;
+use crate::game::world::EntityId;
+use crate::sim::belt::BeltNetwork;
+use crate::sim::machine::MachinePool;
+use crate::sim::state_hash::StateHasher;
+use crate::sim::storage::StoragePool;
+
+/// Which way a loader moves items.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoaderMode {
+    /// Belt → target: fill the attached building from the belt behind the loader.
+    #[default]
+    Load,
+    /// Target → belt: empty the attached building onto the belt behind the loader.
+    Unload,
+}
+
+impl LoaderMode {
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Load => Self::Unload,
+            Self::Unload => Self::Load,
+        }
+    }
+}
+
+/// The building a loader is attached to (the cell the loader faces).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoaderTarget {
+    Storage(EntityId),
+    Machine(EntityId),
+}
+
+impl LoaderTarget {
+    pub fn entity(self) -> EntityId {
+        match self {
+            Self::Storage(e) | Self::Machine(e) => e,
+        }
+    }
+}
+
+/// Per-loader state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoaderState {
+    pub entity: EntityId,
+    pub mode: LoaderMode,
+    /// Only move this item. `None` moves anything.
+    pub filter: Option<ItemId>,
+    /// Building in front of the loader, if any.
+    pub target: Option<LoaderTarget>,
+    /// Belt entity behind the loader. In `Load` mode its line's output end is
+    /// `BeltEnd::Loader`; in `Unload` mode its input end is.
+    pub belt: Option<EntityId>,
+}
+
+impl LoaderState {
+    fn passes(&self, item: ItemId) -> bool {
+        self.filter.is_none_or(|f| f == item)
+    }
+}
+
+/// Pool of all placed loaders. Dense storage indexed by EntityId.
+///
+/// A loader bridges a belt and any face of a storage building or machine,
+/// bypassing the fixed port layouts. It moves at most one item per tick,
+/// which keeps up with a fully compressed belt.
+#[derive(Serialize, Deserialize)]
+pub struct LoaderPool {
+    loaders: Vec<LoaderState>,
+    entity_to_idx: HashMap<EntityId, usize>,
+}
+
+impl LoaderPool {
+    pub fn new() -> Self {
+        Self {
+            loaders: Vec::new(),
+            entity_to_idx: HashMap::new(),
+        }
+    }
+
+    /// Feed every loader's settings and attachments into a state hash, in
+    /// entity order.
+    pub fn hash_state(&self, h: &mut StateHasher) {
+        let mut order: Vec<&LoaderState> = self.loaders.iter().collect();
+        order.sort_by_key(|l| l.entity);
+        h.u32(order.len() as u32);
+        for loader in order {
+            h.entity(loader.entity);
+            h.u8(loader.mode as u8);
+            h.option_item(loader.filter);
+            match loader.target {
+                None => h.u8(0),
+                Some(LoaderTarget::Storage(e)) => {
+                    h.u8(1);
+                    h.entity(e);
+                }
+                Some(LoaderTarget::Machine(e)) => {
+                    h.u8(2);
+                    h.entity(e);
+                }
+            }
+            h.option_entity(loader.belt);
+        }
+    }
+
+    /// Register a newly placed loader.
+    pub fn add(&mut self, entity: EntityId) {
+        let idx = self.loaders.len();
+        self.loaders.push(LoaderState {
+            entity,
+            mode: LoaderMode::default(),
+            filter: None,
+            target: None,
+            belt: None,
+        });
+        self.entity_to_idx.insert(entity, idx);
+    }
+
+    /// Remove a loader by EntityId. Swap-removes with the last element.
+    pub fn remove(&mut self, entity: EntityId) -> bool {
+        let Some(idx) = self.entity_to_idx.remove(&entity) else {
+            return false;
+        };
+        let last = self.loaders.len() - 1;
+
+        if idx != last {
+            self.loaders.swap(idx, last);
+            let swapped_entity = self.loaders[idx].entity;
+            self.entity_to_idx.insert(swapped_entity, idx);
+        }
+
+        self.loaders.pop();
+        true
+    }
+
+    /// Get a reference to the loader state for an entity.
+    pub fn get(&self, entity: EntityId) -> Option<&LoaderState> {
+        self.entity_to_idx.get(&entity)
+            .map(|&i| &self.loaders[i])
+    }
+
+    /// Get a mutable reference to the loader state for an entity.
+    pub fn get_mut(&mut self, entity: EntityId) -> Option<&mut LoaderState> {
+        let &i = self.entity_to_idx.get(&entity)?;
+        Some(&mut self.loaders[i])
+    }
+
+    /// Set the attached building.
+    pub fn set_target(&mut self, entity: EntityId, target: Option<LoaderTarget>) {
+        if let Some(state) = self.get_mut(entity) {
+            state.target = target;
+        }
+    }
+
+    /// Set the belt behind the loader.
+    pub fn set_belt(&mut self, entity: EntityId, belt: Option<EntityId>) {
+        if let Some(state) = self.get_mut(entity) {
+            state.belt = belt;
+        }
+    }
+
+    /// Set the transfer direction. The caller is responsible for rewiring the belt.
+    pub fn set_mode(&mut self, entity: EntityId, mode: LoaderMode) {
+        if let Some(state) = self.get_mut(entity) {
+            state.mode = mode;
+        }
+    }
+
+    /// Set the item filter, or clear it with `None`.
+    pub fn set_filter(&mut self, entity: EntityId, filter: Option<ItemId>) {
+        if let Some(state) = self.get_mut(entity) {
+            state.filter = filter;
+        }
+    }
+
+    /// Detach every loader attached to a removed building.
+    pub fn clear_target(&mut self, target_entity: EntityId) {
+        for state in &mut self.loaders {
+            if state.target.is_some_and(|t| t.entity() == target_entity) {
+                state.target = None;
+            }
+        }
+    }
+
+    /// Detach every loader fed by (or feeding) a removed belt.
+    pub fn disconnect_belt(&mut self, belt: EntityId) {
+        for state in &mut self.loaders {
+            if state.belt == Some(belt) {
+                state.belt = None;
+            }
+        }
+    }
+
+    /// Run one tick: each loader with both a belt and a target moves one item.
+    pub fn tick(
+        &mut self,
+        belt_network: &mut BeltNetwork,
+        machine_pool: &mut MachinePool,
+        storage_pool: &mut StoragePool,
+    ) {
+        for state in &self.loaders {
+            let (Some(belt), Some(target)) = (state.belt, state.target) else {
+                continue;
+            };
+            match state.mode {
+                LoaderMode::Load => {
+                    let Some(item) = belt_network.peek_front_item(belt) else {
+                        continue;
+                    };
+                    if !state.passes(item) {
+                        continue;
+                    }
+                    let accepted = match target {
+                        LoaderTarget::Storage(e) => storage_pool.accept_input(e, item, 1),
+                        LoaderTarget::Machine(e) => machine_pool.insert_input(e, item, 1),
+                    };
+                    if accepted {
+                        belt_network.take_front_item(belt);
+                    }
+                }
+                LoaderMode::Unload => {
+                    if !belt_network.can_accept_at_entity_input(belt) {
+                        continue;
+                    }
+                    let taken = match target {
+                        LoaderTarget::Storage(e) => storage_pool.take_item(e, state.filter),
+                        LoaderTarget::Machine(e) => {
+                            let slot = machine_pool.output_slots(e).and_then(|slots| {
+                                slots.iter().position(|s| s.count > 0 && state.passes(s.item))
+                            });
+                            slot.and_then(|s| machine_pool.take_output_from_slot(e, s))
+                        }
+                    };
+                    if let Some(item) = taken {
+                        belt_network.push_to_entity_input(belt, item);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperbolic::cell_id::CellId;
+    use crate::game::items::MachineType;
+    use crate::game::world::{Direction, WorldState};
+
+    fn place_belt(world: &mut WorldState, net: &mut BeltNetwork, addr: &CellId, gx: i32, gy: i32, dir: Direction) -> EntityId {
+        let entity = world.place(addr, (gx, gy), ItemId::Belt, dir).unwrap();
+        net.on_belt_placed(entity, addr, gx, gy, dir, world);
+        entity
+    }
+
+    /// Belt (0,0)→East feeds loader at (1,0) facing East into storage at (2,0).
+    fn setup_load_into_storage(
+        world: &mut WorldState,
+        net: &mut BeltNetwork,
+        storages: &mut StoragePool,
+    ) -> (LoaderPool, EntityId, EntityId, EntityId) {
+        let addr = &CellId::from_canonical(vec![0]);
+        let belt = place_belt(world, net, addr, 0, 0, Direction::East);
+        let loader = world.place(addr, (1, 0), ItemId::Loader, Direction::East).unwrap();
+        let storage = world.place(addr, (2, 0), ItemId::Storage, Direction::North).unwrap();
+        storages.add(storage);
+
+        let mut pool = LoaderPool::new();
+        pool.add(loader);
+        pool.set_target(loader, Some(LoaderTarget::Storage(storage)));
+        assert!(net.connect_belt_to_loader(belt, loader));
+        pool.set_belt(loader, Some(belt));
+        (pool, belt, loader, storage)
+    }
+
+    #[test]
+    fn load_moves_belt_items_into_storage() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let mut machines = MachinePool::new();
+        let mut storages = StoragePool::new();
+        let (mut pool, belt, _, storage) = setup_load_into_storage(&mut world, &mut net, &mut storages);
+
+        net.spawn_item_on_entity(belt, ItemId::Point);
+        for _ in 0..200 {
+            net.tick();
+            pool.tick(&mut net, &mut machines, &mut storages);
+        }
+        assert_eq!(storages.get(storage).unwrap().count_of(ItemId::Point), 1);
+        assert!(net.entity_items(belt).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn load_filter_blocks_other_items() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let mut machines = MachinePool::new();
+        let mut storages = StoragePool::new();
+        let (mut pool, belt, loader, storage) = setup_load_into_storage(&mut world, &mut net, &mut storages);
+        pool.set_filter(loader, Some(ItemId::Wavelet));
+
+        net.spawn_item_on_entity(belt, ItemId::Point);
+        for _ in 0..200 {
+            net.tick();
+            pool.tick(&mut net, &mut machines, &mut storages);
+        }
+        assert_eq!(storages.get(storage).unwrap().count_of(ItemId::Point), 0);
+        assert_eq!(net.peek_front_item(belt), Some(ItemId::Point));
+    }
+
+    #[test]
+    fn unload_storage_keeps_up_with_belt() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let mut machines = MachinePool::new();
+        let mut storages = StoragePool::new();
+        let addr = &CellId::from_canonical(vec![0]);
+
+        // Storage at (0,0)-(1,1), loader at (2,0) facing West into it,
+        // belt at (3,0) flowing East away from the loader.
+        let storage = world.place(addr, (0, 0), ItemId::Storage, Direction::North).unwrap();
+        storages.add(storage);
+        for _ in 0..40 {
+            storages.accept_input(storage, ItemId::Point, 1);
+            storages.accept_input(storage, ItemId::Square, 1);
+        }
+        let loader = world.place(addr, (2, 0), ItemId::Loader, Direction::West).unwrap();
+        let belt = place_belt(&mut world, &mut net, addr, 3, 0, Direction::East);
+
+        let mut pool = LoaderPool::new();
+        pool.add(loader);
+        pool.set_mode(loader, LoaderMode::Unload);
+        pool.set_filter(loader, Some(ItemId::Square));
+        pool.set_target(loader, Some(LoaderTarget::Storage(storage)));
+        assert!(net.connect_loader_to_belt(belt, loader));
+        pool.set_belt(loader, Some(belt));
+
+        pool.tick(&mut net, &mut machines, &mut storages);
+        assert!(!net.can_accept_at_entity_input(belt));
+
+        // The loader refills the belt as soon as there is room: the belt stays saturated.
+        for _ in 0..200 {
+            net.tick();
+            pool.tick(&mut net, &mut machines, &mut storages);
+        }
+        assert!(!net.can_accept_at_entity_input(belt));
+        assert_eq!(net.peek_front_item(belt), Some(ItemId::Square));
+        let state = storages.get(storage).unwrap();
+        assert_eq!(state.count_of(ItemId::Point), 40);
+        assert!(state.count_of(ItemId::Square) < 40);
+    }
+
+    #[test]
+    fn load_into_machine_from_any_side() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let mut machines = MachinePool::new();
+        let mut storages = StoragePool::new();
+        let addr = &CellId::from_canonical(vec![0]);
+
+        // Composer (2×2) at (0,0); its ports are north/south, but the loader
+        // feeds it from the east face.
+        let machine = world.place(addr, (0, 0), ItemId::Composer, Direction::North).unwrap();
+        machines.add(machine, MachineType::Composer);
+        let loader = world.place(addr, (2, 1), ItemId::Loader, Direction::West).unwrap();
+        let belt = place_belt(&mut world, &mut net, addr, 3, 1, Direction::West);
+
+        let mut pool = LoaderPool::new();
+        pool.add(loader);
+        pool.set_target(loader, Some(LoaderTarget::Machine(machine)));
+        assert!(net.connect_belt_to_loader(belt, loader));
+        pool.set_belt(loader, Some(belt));
+
+        net.spawn_item_on_entity(belt, ItemId::LineSegment);
+        for _ in 0..200 {
+            net.tick();
+            pool.tick(&mut net, &mut machines, &mut storages);
+        }
+        let slots = machines.input_slots(machine).unwrap();
+        assert!(slots.iter().any(|s| s.item == ItemId::LineSegment && s.count == 1));
+    }
+
+    #[test]
+    fn detach_on_removal() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let mut storages = StoragePool::new();
+        let (mut pool, belt, loader, storage) = setup_load_into_storage(&mut world, &mut net, &mut storages);
+
+        pool.clear_target(storage);
+        assert_eq!(pool.get(loader).unwrap().target, None);
+        pool.disconnect_belt(belt);
+        assert_eq!(pool.get(loader).unwrap().belt, None);
+        assert!(pool.remove(loader));
+        assert!(pool.get(loader).is_none());
+    }
+}
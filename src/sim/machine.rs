@@ -1,8 +1,14 @@
 use std::collections::HashMap;
 
-use crate::game::items::{ItemId, MachineType};
+use serde::{Deserialize, Serialize};
+
+use crate::game::items::{FluidId, ItemId, MachineType, Recipe};
 use crate::game::recipes::RecipeIndex;
-use crate::game::world::EntityId;
+use crate::game::world::{Direction, EntityId};
+use crate::sim::fluid::FluidTank;
+use crate::sim::inserter::{port_layout, port_position_valid, rotate_port_defs, PortDef, RotatedPort};
+use crate::sim::parallel;
+use crate::sim::state_hash::StateHasher;
 
 /// Default crafting duration in ticks (60 UPS = 2 seconds).
 pub const DEFAULT_CRAFT_TICKS: u16 = 120;
@@ -11,14 +17,14 @@ pub const DEFAULT_CRAFT_TICKS: u16 = 120;
 pub const SOURCE_CRAFT_TICKS: u16 = 30;
 
 /// An item type + count, used for machine input/output slots.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub struct ItemStack {
     pub item: ItemId,
     pub count: u16,
 }
 
 /// Machine processing state.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MachineState {
     /// No recipe set, or recipe complete and waiting for new inputs.
     Idle,
@@ -35,7 +41,11 @@ pub enum MachineState {
 /// Maximum number of input/output slots per machine.
 pub const MAX_SLOTS: usize = 4;
 
+/// Capacity of each machine fluid tank (input and output).
+pub const MACHINE_TANK_CAPACITY: u32 = 200;
+
 /// Hot data — touched every simulation tick. Kept contiguous for cache performance.
+#[derive(Serialize, Deserialize)]
 pub struct MachineHotData {
     /// Crafting progress [0.0 .. 1.0].
     pub progress: Vec<f32>,
@@ -47,9 +57,17 @@ pub struct MachineHotData {
     pub power_draw: Vec<f32>,
     /// Current state.
     pub state: Vec<MachineState>,
+    /// Cleared by a circuit condition; a disabled machine freezes in place.
+    pub enabled: Vec<bool>,
+    /// Set while the machine's cell sleeps; caught up in bulk on waking.
+    pub asleep: Vec<bool>,
+    /// Off the active list: blocked until an input, output, recipe, enable,
+    /// or power change re-queues it.
+    pub idle: Vec<bool>,
 }
 
 /// Cold data — touched on interaction (UI, inserter delivery, recipe selection).
+#[derive(Serialize, Deserialize)]
 pub struct MachineColdData {
     /// Which entity in the world this machine corresponds to.
     pub entity_id: Vec<EntityId>,
@@ -61,10 +79,18 @@ pub struct MachineColdData {
     pub input_slots: Vec<[ItemStack; MAX_SLOTS]>,
     /// Output item slots.
     pub output_slots: Vec<[ItemStack; MAX_SLOTS]>,
+    /// Per-entity port layout in canonical orientation (machine facing North).
+    /// Starts as the type's `port_layout()`; players may move individual ports.
+    pub ports: Vec<Vec<PortDef>>,
+    /// Fluid input tank, filled from an attached pipe.
+    pub fluid_in: Vec<FluidTank>,
+    /// Fluid output tank, emptied into an attached pipe.
+    pub fluid_out: Vec<FluidTank>,
 }
 
 /// SoA machine pool. Hot and cold vecs are indexed by the same dense index.
 /// Use `entity_to_idx` for EntityId -> index lookup.
+#[derive(Serialize, Deserialize)]
 pub struct MachinePool {
     pub hot: MachineHotData,
     pub cold: MachineColdData,
@@ -72,6 +98,9 @@ pub struct MachinePool {
     pub count: usize,
     /// EntityId -> dense index mapping.
     entity_to_idx: HashMap<EntityId, usize>,
+    /// Machines that are not idle, in the order they were queued. Only these
+    /// are visited each tick.
+    active: Vec<EntityId>,
 }
 
 impl MachinePool {
@@ -83,6 +112,9 @@ impl MachinePool {
                 recipe_total_ticks: Vec::new(),
                 power_draw: Vec::new(),
                 state: Vec::new(),
+                enabled: Vec::new(),
+                asleep: Vec::new(),
+                idle: Vec::new(),
             },
             cold: MachineColdData {
                 entity_id: Vec::new(),
@@ -90,9 +122,36 @@ impl MachinePool {
                 recipe: Vec::new(),
                 input_slots: Vec::new(),
                 output_slots: Vec::new(),
+                ports: Vec::new(),
+                fluid_in: Vec::new(),
+                fluid_out: Vec::new(),
             },
             count: 0,
             entity_to_idx: HashMap::new(),
+            active: Vec::new(),
+        }
+    }
+
+    /// Feed every machine's state into a state hash, in entity order.
+    pub fn hash_state(&self, h: &mut StateHasher) {
+        let mut order: Vec<usize> = (0..self.count).collect();
+        order.sort_by_key(|&i| self.cold.entity_id[i]);
+        h.u32(order.len() as u32);
+        for i in order {
+            h.entity(self.cold.entity_id[i]);
+            h.u64(self.cold.recipe[i].map_or(u64::MAX, |r| r as u64));
+            h.u8(self.hot.state[i] as u8);
+            h.f32(self.hot.progress[i]);
+            h.u16(self.hot.recipe_ticks[i]);
+            h.u16(self.hot.recipe_total_ticks[i]);
+            h.f32(self.hot.power_draw[i]);
+            h.bool(self.hot.enabled[i]);
+            h.bool(self.hot.asleep[i]);
+            for stack in self.cold.input_slots[i].iter().chain(&self.cold.output_slots[i]) {
+                h.stack(stack);
+            }
+            h.tank(&self.cold.fluid_in[i]);
+            h.tank(&self.cold.fluid_out[i]);
         }
     }
 
@@ -106,6 +165,9 @@ impl MachinePool {
         self.hot.recipe_total_ticks.push(0);
         self.hot.power_draw.push(1.0); // full power until power network says otherwise
         self.hot.state.push(MachineState::Idle);
+        self.hot.enabled.push(true);
+        self.hot.asleep.push(false);
+        self.hot.idle.push(false);
 
         // Cold data
         self.cold.entity_id.push(entity);
@@ -113,8 +175,12 @@ impl MachinePool {
         self.cold.recipe.push(None);
         self.cold.input_slots.push([ItemStack::default(); MAX_SLOTS]);
         self.cold.output_slots.push([ItemStack::default(); MAX_SLOTS]);
+        self.cold.ports.push(port_layout(machine_type).to_vec());
+        self.cold.fluid_in.push(FluidTank::default());
+        self.cold.fluid_out.push(FluidTank::default());
 
         self.entity_to_idx.insert(entity, idx);
+        self.active.push(entity);
         self.count += 1;
         idx
     }
@@ -133,6 +199,9 @@ impl MachinePool {
             self.hot.recipe_total_ticks.swap(idx, last);
             self.hot.power_draw.swap(idx, last);
             self.hot.state.swap(idx, last);
+            self.hot.enabled.swap(idx, last);
+            self.hot.asleep.swap(idx, last);
+            self.hot.idle.swap(idx, last);
 
             // Swap cold data
             self.cold.entity_id.swap(idx, last);
@@ -140,6 +209,9 @@ impl MachinePool {
             self.cold.recipe.swap(idx, last);
             self.cold.input_slots.swap(idx, last);
             self.cold.output_slots.swap(idx, last);
+            self.cold.ports.swap(idx, last);
+            self.cold.fluid_in.swap(idx, last);
+            self.cold.fluid_out.swap(idx, last);
 
             // Update the swapped entity's index
             let swapped_entity = self.cold.entity_id[idx];
@@ -152,16 +224,46 @@ impl MachinePool {
         self.hot.recipe_total_ticks.pop();
         self.hot.power_draw.pop();
         self.hot.state.pop();
+        self.hot.enabled.pop();
+        self.hot.asleep.pop();
+        self.hot.idle.pop();
         self.cold.entity_id.pop();
         self.cold.machine_type.pop();
         self.cold.recipe.pop();
         self.cold.input_slots.pop();
         self.cold.output_slots.pop();
+        self.cold.ports.pop();
+        self.cold.fluid_in.pop();
+        self.cold.fluid_out.pop();
 
+        self.active.retain(|&e| e != entity);
         self.count -= 1;
         true
     }
 
+    /// Put an idle machine back on the active list.
+    fn requeue(&mut self, i: usize) {
+        if self.hot.idle[i] {
+            self.hot.idle[i] = false;
+            self.active.push(self.cold.entity_id[i]);
+        }
+    }
+
+    /// Number of machines visited each tick.
+    pub fn active_count(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Set a machine's power satisfaction, re-queuing it if that changed.
+    pub fn set_power_satisfaction(&mut self, entity: EntityId, satisfaction: f32) {
+        if let Some(i) = self.index_of(entity) {
+            if self.hot.power_draw[i] != satisfaction {
+                self.hot.power_draw[i] = satisfaction;
+                self.requeue(i);
+            }
+        }
+    }
+
     /// Look up the dense index for an EntityId.
     pub fn index_of(&self, entity: EntityId) -> Option<usize> {
         self.entity_to_idx.get(&entity).copied()
@@ -172,6 +274,48 @@ impl MachinePool {
         self.index_of(entity).map(|i| self.hot.state[i])
     }
 
+    /// Whether the machine is enabled (not held off by a circuit condition).
+    pub fn is_enabled(&self, entity: EntityId) -> Option<bool> {
+        self.index_of(entity).map(|i| self.hot.enabled[i])
+    }
+
+    /// Enable or disable a machine. Returns false if the entity is not a machine.
+    pub fn set_enabled(&mut self, entity: EntityId, enabled: bool) -> bool {
+        match self.index_of(entity) {
+            Some(i) => {
+                if self.hot.enabled[i] != enabled {
+                    self.hot.enabled[i] = enabled;
+                    self.requeue(i);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop ticking a machine (its cell went to sleep).
+    pub fn sleep(&mut self, entity: EntityId) {
+        if let Some(i) = self.index_of(entity) {
+            self.hot.asleep[i] = true;
+        }
+    }
+
+    /// Resume ticking a machine, first catching it up in closed form for the
+    /// `ticks` it slept.
+    pub fn wake(&mut self, entity: EntityId, ticks: u64, recipes: &RecipeIndex) {
+        let Some(i) = self.index_of(entity) else {
+            return;
+        };
+        if !self.hot.asleep[i] {
+            return;
+        }
+        self.hot.asleep[i] = false;
+        if let (true, Some(r)) = (self.hot.enabled[i], self.cold.recipe[i]) {
+            self.catch_up(i, ticks, &recipes.all[r]);
+        }
+        self.requeue(i);
+    }
+
     /// Get the machine type for an entity.
     pub fn machine_type(&self, entity: EntityId) -> Option<MachineType> {
         self.index_of(entity).map(|i| self.cold.machine_type[i])
@@ -190,6 +334,7 @@ impl MachinePool {
             self.hot.recipe_ticks[i] = 0;
             self.hot.recipe_total_ticks[i] = 0;
             self.hot.state[i] = MachineState::Idle;
+            self.requeue(i);
         }
     }
 
@@ -203,6 +348,99 @@ impl MachinePool {
         self.index_of(entity).map(|i| &self.cold.output_slots[i])
     }
 
+    /// Canonical port layout for an entity.
+    pub fn ports(&self, entity: EntityId) -> Option<&[PortDef]> {
+        self.index_of(entity).map(|i| self.cold.ports[i].as_slice())
+    }
+
+    /// Port layout for an entity rotated to its facing direction.
+    pub fn rotated_ports(&self, entity: EntityId, facing: Direction) -> Vec<RotatedPort> {
+        let Some(i) = self.index_of(entity) else {
+            return Vec::new();
+        };
+        rotate_port_defs(&self.cold.ports[i], self.cold.machine_type[i].footprint(), facing)
+    }
+
+    /// Move one port (by index into the layout) to a new canonical cell and side.
+    /// Rejects positions that don't face out of the footprint or that another
+    /// port already occupies. Returns true if the layout changed.
+    pub fn move_port(
+        &mut self,
+        entity: EntityId,
+        port: usize,
+        cell_offset: (i32, i32),
+        side: Direction,
+    ) -> bool {
+        let Some(i) = self.index_of(entity) else {
+            return false;
+        };
+        let footprint = self.cold.machine_type[i].footprint();
+        let ports = &mut self.cold.ports[i];
+        if port >= ports.len() || !port_position_valid(footprint, cell_offset, side) {
+            return false;
+        }
+        let taken = ports
+            .iter()
+            .enumerate()
+            .any(|(j, p)| j != port && p.cell_offset == cell_offset && p.side == side);
+        if taken {
+            return false;
+        }
+        let def = &mut ports[port];
+        if def.cell_offset == cell_offset && def.side == side {
+            return false;
+        }
+        def.cell_offset = cell_offset;
+        def.side = side;
+        true
+    }
+
+    /// Restore the machine type's default port layout.
+    pub fn reset_ports(&mut self, entity: EntityId) {
+        if let Some(i) = self.index_of(entity) {
+            self.cold.ports[i] = port_layout(self.cold.machine_type[i]).to_vec();
+        }
+    }
+
+    /// The machine's fluid input tank.
+    pub fn fluid_input_tank(&self, entity: EntityId) -> Option<FluidTank> {
+        self.index_of(entity).map(|i| self.cold.fluid_in[i])
+    }
+
+    /// The machine's fluid output tank.
+    pub fn fluid_output_tank(&self, entity: EntityId) -> Option<FluidTank> {
+        self.index_of(entity).map(|i| self.cold.fluid_out[i])
+    }
+
+    /// Pour fluid into the input tank. Returns the volume accepted.
+    pub fn fill_fluid_input(&mut self, entity: EntityId, fluid: FluidId, amount: u32) -> u32 {
+        let Some(i) = self.index_of(entity) else {
+            return 0;
+        };
+        let accepted = self.cold.fluid_in[i].fill(fluid, amount, MACHINE_TANK_CAPACITY);
+        if accepted > 0 {
+            self.requeue(i);
+        }
+        accepted
+    }
+
+    /// Pour fluid into the output tank. Returns the volume accepted.
+    #[allow(dead_code)] // used by tests to stand in for a finished craft
+    pub fn fill_fluid_output(&mut self, entity: EntityId, fluid: FluidId, amount: u32) -> u32 {
+        let Some(i) = self.index_of(entity) else {
+            return 0;
+        };
+        self.cold.fluid_out[i].fill(fluid, amount, MACHINE_TANK_CAPACITY)
+    }
+
+    /// Remove up to `max` volume from the output tank.
+    pub fn drain_fluid_output(&mut self, entity: EntityId, max: u32) -> Option<(FluidId, u32)> {
+        let i = self.index_of(entity)?;
+        let drained = self.cold.fluid_out[i].drain(max)?;
+        self.requeue(i);
+        Some(drained)
+    }
+
     /// Get crafting progress [0.0 .. 1.0] for an entity.
     pub fn progress(&self, entity: EntityId) -> Option<f32> {
         self.index_of(entity).map(|i| self.hot.progress[i])
@@ -227,13 +465,13 @@ impl MachinePool {
         if s.count == 0 {
             s.item = item;
             s.count = count;
-            true
         } else if s.item == item {
             s.count += count;
-            true
         } else {
-            false // slot occupied by different item
+            return false; // slot occupied by different item
         }
+        self.requeue(i);
+        true
     }
 
     /// Try to insert an item into a machine's input slots. Returns true if accepted.
@@ -243,22 +481,18 @@ impl MachinePool {
         };
         let slots = &mut self.cold.input_slots[i];
 
-        // Try to stack into an existing slot with the same item
-        for slot in slots.iter_mut() {
-            if slot.item == item && slot.count > 0 {
-                slot.count += count;
-                return true;
-            }
-        }
-        // Try to place into an empty slot
-        for slot in slots.iter_mut() {
-            if slot.count == 0 {
-                slot.item = item;
-                slot.count = count;
-                return true;
-            }
-        }
-        false // all slots occupied by different items
+        // Try to stack into an existing slot with the same item, else an empty one
+        let slot = match slots.iter().position(|s| s.item == item && s.count > 0) {
+            Some(j) => j,
+            None => match slots.iter().position(|s| s.count == 0) {
+                Some(j) => j,
+                None => return false, // all slots occupied by different items
+            },
+        };
+        slots[slot].item = item;
+        slots[slot].count += count;
+        self.requeue(i);
+        true
     }
 
     /// Try to take an item from a specific output slot. Returns the item taken, if any.
@@ -275,6 +509,7 @@ impl MachinePool {
             if self.hot.state[i] == MachineState::OutputFull {
                 self.hot.state[i] = MachineState::Idle;
             }
+            self.requeue(i);
             Some(item)
         } else {
             None
@@ -282,6 +517,7 @@ impl MachinePool {
     }
 
     /// Try to take an item from a machine's output slots. Returns the item taken, if any.
+    #[allow(dead_code)] // inserters and belts pull from a named slot instead
     pub fn take_output(&mut self, entity: EntityId) -> Option<ItemId> {
         let i = self.index_of(entity)?;
         let slots = &mut self.cold.output_slots[i];
@@ -294,90 +530,225 @@ impl MachinePool {
                 if self.hot.state[i] == MachineState::OutputFull {
                     self.hot.state[i] = MachineState::Idle;
                 }
+                self.requeue(i);
                 return Some(item);
             }
         }
         None
     }
 
-    /// Run one simulation tick for all machines.
+    /// Run one simulation tick for the active machines.
     ///
     /// State machine per machine:
     ///   Idle / NoInput  -> check inputs -> Working (consume inputs)
     ///   Working         -> decrement ticks -> check output room -> Idle or OutputFull
     ///   OutputFull      -> (woken by take_output setting state to Idle)
     ///   NoPower         -> (woken by power network setting power_draw > 0)
+    ///
+    /// A machine that ends its tick blocked (see `is_blocked`) drops off the
+    /// active list until an input, output, recipe, enable, or power change
+    /// re-queues it, so starved machines cost nothing.
+    ///
+    /// Machines step independently, each its own component, so the active
+    /// set is halved into runs of dense indices that idle workers steal.
     pub fn tick(&mut self, recipes: &RecipeIndex) {
-        for i in 0..self.count {
-            let recipe_idx = match self.cold.recipe[i] {
-                Some(r) => r,
-                None => continue, // no recipe set — skip
+        self.tick_with(recipes, parallel::MIN_PART);
+    }
+
+    /// `tick`, halving the active set until runs hold at most `min_part`
+    /// machines.
+    fn tick_with(&mut self, recipes: &RecipeIndex, min_part: usize) {
+        let active = std::mem::take(&mut self.active);
+        let mut indices: Vec<usize> = active.iter().filter_map(|&e| self.index_of(e)).collect();
+        indices.sort_unstable();
+        step_split(self.range(), &indices, recipes, min_part);
+
+        for entity in active {
+            let Some(i) = self.index_of(entity) else {
+                continue;
             };
-            let recipe = &recipes.all[recipe_idx];
+            if self.is_blocked(i) {
+                self.hot.idle[i] = true;
+            } else {
+                self.active.push(entity);
+            }
+        }
+    }
 
+    /// Mutable view of the whole pool, to split among workers.
+    fn range(&mut self) -> MachineRange<'_> {
+        MachineRange {
+            start: 0,
+            progress: &mut self.hot.progress,
+            recipe_ticks: &mut self.hot.recipe_ticks,
+            recipe_total_ticks: &mut self.hot.recipe_total_ticks,
+            power_draw: &self.hot.power_draw,
+            state: &mut self.hot.state,
+            enabled: &self.hot.enabled,
+            asleep: &self.hot.asleep,
+            machine_type: &self.cold.machine_type,
+            recipe: &self.cold.recipe,
+            input_slots: &mut self.cold.input_slots,
+            output_slots: &mut self.cold.output_slots,
+            fluid_in: &mut self.cold.fluid_in,
+            fluid_out: &mut self.cold.fluid_out,
+        }
+    }
+
+    /// Whether ticking the machine would change nothing until one of the
+    /// events that re-queue it.
+    fn is_blocked(&self, i: usize) -> bool {
+        if !self.hot.enabled[i] || self.hot.asleep[i] || self.cold.recipe[i].is_none() {
+            return true;
+        }
+        match self.hot.state[i] {
+            MachineState::Idle | MachineState::Working => false,
+            MachineState::NoInput | MachineState::OutputFull => true,
+            MachineState::NoPower => self.hot.power_draw[i] <= 0.0,
+        }
+    }
+
+    /// Crafting duration for a machine type.
+    fn craft_ticks(machine_type: MachineType) -> u16 {
+        if machine_type == MachineType::Source {
+            SOURCE_CRAFT_TICKS
+        } else {
+            DEFAULT_CRAFT_TICKS
+        }
+    }
+
+    /// Advance one machine `ticks` ticks in closed form, assuming no items
+    /// arrive or leave meanwhile and power holds steady. Produces the same
+    /// state as ticking one by one, but runs whole craft cycles (one start
+    /// tick plus the craft duration) in a single step.
+    fn catch_up(&mut self, i: usize, mut ticks: u64, recipe: &Recipe) {
+        let craft = Self::craft_ticks(self.cold.machine_type[i]);
+        let powered = self.hot.power_draw[i] > 0.0;
+        while ticks > 0 {
             match self.hot.state[i] {
-                MachineState::Idle | MachineState::NoInput => {
-                    // Try to start crafting if inputs are available
-                    if Self::has_inputs(&self.cold.input_slots[i], &recipe.inputs) {
-                        Self::consume_inputs(&mut self.cold.input_slots[i], &recipe.inputs);
-                        let ticks = if self.cold.machine_type[i] == MachineType::Source {
-                            SOURCE_CRAFT_TICKS
-                        } else {
-                            DEFAULT_CRAFT_TICKS
-                        };
-                        self.hot.recipe_ticks[i] = ticks;
-                        self.hot.recipe_total_ticks[i] = ticks;
-                        self.hot.progress[i] = 0.0;
-                        self.hot.state[i] = MachineState::Working;
-                    } else {
-                        self.hot.state[i] = MachineState::NoInput;
+                MachineState::NoPower => {
+                    if !powered {
+                        return;
+                    }
+                    self.hot.state[i] = MachineState::Working;
+                    ticks -= 1;
+                }
+                MachineState::OutputFull => {
+                    if !Self::try_produce(&mut self.cold.output_slots[i], &mut self.cold.fluid_out[i], recipe) {
+                        return;
                     }
+                    self.hot.progress[i] = 0.0;
+                    self.hot.state[i] = MachineState::Idle;
+                    ticks -= 1;
                 }
                 MachineState::Working => {
-                    if self.hot.power_draw[i] <= 0.0 {
+                    if !powered {
                         self.hot.state[i] = MachineState::NoPower;
-                        continue;
+                        return;
                     }
-                    self.hot.recipe_ticks[i] = self.hot.recipe_ticks[i].saturating_sub(1);
-                    let total = self.hot.recipe_total_ticks[i].max(1) as f32;
-                    self.hot.progress[i] = 1.0 - (self.hot.recipe_ticks[i] as f32 / total);
-
-                    if self.hot.recipe_ticks[i] == 0 {
-                        // Craft complete — try to deposit output
-                        if Self::try_produce_output(
-                            &mut self.cold.output_slots[i],
-                            recipe.output,
-                            recipe.output_count as u16,
-                        ) {
-                            self.hot.progress[i] = 0.0;
-                            self.hot.state[i] = MachineState::Idle;
-                        } else {
-                            self.hot.progress[i] = 1.0;
-                            self.hot.state[i] = MachineState::OutputFull;
-                        }
+                    let remaining = self.hot.recipe_ticks[i] as u64;
+                    if ticks < remaining {
+                        self.hot.recipe_ticks[i] -= ticks as u16;
+                        let total = self.hot.recipe_total_ticks[i].max(1) as f32;
+                        self.hot.progress[i] = 1.0 - (self.hot.recipe_ticks[i] as f32 / total);
+                        return;
                     }
-                }
-                MachineState::OutputFull => {
-                    // Try to deposit the pending output (inserter may have drained a slot)
-                    if Self::try_produce_output(
-                        &mut self.cold.output_slots[i],
-                        recipe.output,
-                        recipe.output_count as u16,
-                    ) {
+                    ticks -= remaining.max(1);
+                    self.hot.recipe_ticks[i] = 0;
+                    if Self::try_produce(&mut self.cold.output_slots[i], &mut self.cold.fluid_out[i], recipe) {
                         self.hot.progress[i] = 0.0;
                         self.hot.state[i] = MachineState::Idle;
+                    } else {
+                        self.hot.progress[i] = 1.0;
+                        self.hot.state[i] = MachineState::OutputFull;
                     }
                 }
-                MachineState::NoPower => {
-                    // Wake up if power has been restored
-                    if self.hot.power_draw[i] > 0.0 {
+                MachineState::Idle | MachineState::NoInput => {
+                    // Whole cycles in one step, bounded by time, inputs, and output room
+                    let cycle = craft as u64 + 1;
+                    let crafts = if powered {
+                        (ticks / cycle).min(self.crafts_possible(i, recipe))
+                    } else {
+                        0
+                    };
+                    if crafts > 0 {
+                        let scaled: Vec<(ItemId, u32)> =
+                            recipe.inputs.iter().map(|&(item, n)| (item, n * crafts as u32)).collect();
+                        Self::consume_inputs(&mut self.cold.input_slots[i], &scaled);
+                        if let Some((_, amount)) = recipe.fluid_input {
+                            self.cold.fluid_in[i].drain(amount * crafts as u32);
+                        }
+                        if recipe.output_count > 0 {
+                            let count = recipe.output_count as u64 * crafts;
+                            Self::try_produce_output(&mut self.cold.output_slots[i], recipe.output, count as u16);
+                        }
+                        if let Some((fluid, amount)) = recipe.fluid_output {
+                            self.cold.fluid_out[i].fill(fluid, amount * crafts as u32, MACHINE_TANK_CAPACITY);
+                        }
+                        self.hot.progress[i] = 0.0;
+                        self.hot.state[i] = MachineState::Idle;
+                        ticks -= crafts * cycle;
+                        continue;
+                    }
+                    // Not enough time or material for a whole cycle: start one and leave it running
+                    if Self::has_inputs(&self.cold.input_slots[i], &recipe.inputs)
+                        && Self::has_fluid_input(&self.cold.fluid_in[i], recipe.fluid_input)
+                    {
+                        Self::consume_inputs(&mut self.cold.input_slots[i], &recipe.inputs);
+                        if let Some((_, amount)) = recipe.fluid_input {
+                            self.cold.fluid_in[i].drain(amount);
+                        }
+                        self.hot.recipe_ticks[i] = craft;
+                        self.hot.recipe_total_ticks[i] = craft;
+                        self.hot.progress[i] = 0.0;
                         self.hot.state[i] = MachineState::Working;
+                    } else {
+                        self.hot.state[i] = MachineState::NoInput;
+                        return;
                     }
+                    ticks -= 1;
                 }
             }
         }
     }
 
+    /// How many crafts the machine's buffers allow: limited by each item and
+    /// fluid input, and by room for the outputs.
+    fn crafts_possible(&self, i: usize, recipe: &Recipe) -> u64 {
+        let slots = &self.cold.input_slots[i];
+        let mut crafts = u64::MAX;
+        for &(item, count) in &recipe.inputs {
+            let have: u64 = slots.iter().filter(|s| s.item == item && s.count > 0).map(|s| s.count as u64).sum();
+            crafts = crafts.min(have / count.max(1) as u64);
+        }
+        if let Some((fluid, amount)) = recipe.fluid_input {
+            let tank = &self.cold.fluid_in[i];
+            let have = if tank.fluid == Some(fluid) { tank.amount } else { 0 };
+            crafts = crafts.min((have / amount.max(1)) as u64);
+        }
+        if recipe.output_count > 0 {
+            let out = &self.cold.output_slots[i];
+            let stacked = out
+                .iter()
+                .find(|s| s.item == recipe.output && s.count > 0)
+                .map(|s| s.count)
+                .or_else(|| out.iter().any(|s| s.count == 0).then_some(0));
+            crafts = match stacked {
+                Some(count) => crafts.min(((u16::MAX - count) / recipe.output_count as u16) as u64),
+                None => 0,
+            };
+        }
+        if let Some((fluid, amount)) = recipe.fluid_output {
+            let tank = &self.cold.fluid_out[i];
+            crafts = if tank.accepts(fluid) {
+                crafts.min(((MACHINE_TANK_CAPACITY - tank.amount) / amount.max(1)) as u64)
+            } else {
+                0
+            };
+        }
+        crafts
+    }
+
     /// Check if input slots contain all required recipe ingredients.
     fn has_inputs(slots: &[ItemStack; MAX_SLOTS], inputs: &[(ItemId, u32)]) -> bool {
         inputs.iter().all(|&(item, count)| {
@@ -390,6 +761,37 @@ impl MachinePool {
         })
     }
 
+    /// Check if the fluid input tank holds enough of the recipe's fluid.
+    fn has_fluid_input(tank: &FluidTank, fluid_input: Option<(FluidId, u32)>) -> bool {
+        match fluid_input {
+            Some((fluid, amount)) => tank.fluid == Some(fluid) && tank.amount >= amount,
+            None => true,
+        }
+    }
+
+    /// Deposit a finished craft's item and fluid outputs. Nothing is deposited
+    /// unless both fit. Returns true on success.
+    fn try_produce(
+        slots: &mut [ItemStack; MAX_SLOTS],
+        tank: &mut FluidTank,
+        recipe: &Recipe,
+    ) -> bool {
+        if let Some((fluid, amount)) = recipe.fluid_output {
+            if !tank.accepts(fluid) || tank.amount + amount > MACHINE_TANK_CAPACITY {
+                return false;
+            }
+        }
+        if recipe.output_count > 0
+            && !Self::try_produce_output(slots, recipe.output, recipe.output_count as u16)
+        {
+            return false;
+        }
+        if let Some((fluid, amount)) = recipe.fluid_output {
+            tank.fill(fluid, amount, MACHINE_TANK_CAPACITY);
+        }
+        true
+    }
+
     /// Consume recipe inputs from slots.
     fn consume_inputs(slots: &mut [ItemStack; MAX_SLOTS], inputs: &[(ItemId, u32)]) {
         for &(item, mut needed) in inputs {
@@ -431,6 +833,173 @@ impl MachinePool {
     }
 }
 
+/// Step the machines at `indices` (ascending, all inside `range`), halving
+/// the run across the worker pool while it holds more than `min_part`.
+fn step_split(mut range: MachineRange<'_>, indices: &[usize], recipes: &RecipeIndex, min_part: usize) {
+    if indices.len() <= min_part {
+        for &i in indices {
+            range.step(i, recipes);
+        }
+        return;
+    }
+    let (left, right) = indices.split_at(indices.len() / 2);
+    let (head, tail) = range.split_at(right[0]);
+    parallel::join(
+        || step_split(head, left, recipes, min_part),
+        || step_split(tail, right, recipes, min_part),
+    );
+}
+
+/// Mutable view of a contiguous range of machines, so disjoint ranges can
+/// step on separate threads.
+struct MachineRange<'a> {
+    /// Dense index of the first machine in the range.
+    start: usize,
+    progress: &'a mut [f32],
+    recipe_ticks: &'a mut [u16],
+    recipe_total_ticks: &'a mut [u16],
+    power_draw: &'a [f32],
+    state: &'a mut [MachineState],
+    enabled: &'a [bool],
+    asleep: &'a [bool],
+    machine_type: &'a [MachineType],
+    recipe: &'a [Option<usize>],
+    input_slots: &'a mut [[ItemStack; MAX_SLOTS]],
+    output_slots: &'a mut [[ItemStack; MAX_SLOTS]],
+    fluid_in: &'a mut [FluidTank],
+    fluid_out: &'a mut [FluidTank],
+}
+
+impl<'a> MachineRange<'a> {
+    /// Split at dense index `at` into the machines before it and from it on.
+    fn split_at(self, at: usize) -> (Self, Self) {
+        let mid = at - self.start;
+        let (progress, progress_rest) = self.progress.split_at_mut(mid);
+        let (recipe_ticks, recipe_ticks_rest) = self.recipe_ticks.split_at_mut(mid);
+        let (recipe_total_ticks, recipe_total_ticks_rest) = self.recipe_total_ticks.split_at_mut(mid);
+        let (power_draw, power_draw_rest) = self.power_draw.split_at(mid);
+        let (state, state_rest) = self.state.split_at_mut(mid);
+        let (enabled, enabled_rest) = self.enabled.split_at(mid);
+        let (asleep, asleep_rest) = self.asleep.split_at(mid);
+        let (machine_type, machine_type_rest) = self.machine_type.split_at(mid);
+        let (recipe, recipe_rest) = self.recipe.split_at(mid);
+        let (input_slots, input_slots_rest) = self.input_slots.split_at_mut(mid);
+        let (output_slots, output_slots_rest) = self.output_slots.split_at_mut(mid);
+        let (fluid_in, fluid_in_rest) = self.fluid_in.split_at_mut(mid);
+        let (fluid_out, fluid_out_rest) = self.fluid_out.split_at_mut(mid);
+        (
+            Self {
+                start: self.start,
+                progress,
+                recipe_ticks,
+                recipe_total_ticks,
+                power_draw,
+                state,
+                enabled,
+                asleep,
+                machine_type,
+                recipe,
+                input_slots,
+                output_slots,
+                fluid_in,
+                fluid_out,
+            },
+            Self {
+                start: at,
+                progress: progress_rest,
+                recipe_ticks: recipe_ticks_rest,
+                recipe_total_ticks: recipe_total_ticks_rest,
+                power_draw: power_draw_rest,
+                state: state_rest,
+                enabled: enabled_rest,
+                asleep: asleep_rest,
+                machine_type: machine_type_rest,
+                recipe: recipe_rest,
+                input_slots: input_slots_rest,
+                output_slots: output_slots_rest,
+                fluid_in: fluid_in_rest,
+                fluid_out: fluid_out_rest,
+            },
+        )
+    }
+
+    /// Advance machine `i` (a dense pool index inside this range) by a tick.
+    /// Machines disabled by a circuit condition or asleep are skipped entirely.
+    fn step(&mut self, i: usize, recipes: &RecipeIndex) {
+        let j = i - self.start;
+        if !self.enabled[j] || self.asleep[j] {
+            return;
+        }
+        let recipe_idx = match self.recipe[j] {
+            Some(r) => r,
+            None => return, // no recipe set — skip
+        };
+        let recipe = &recipes.all[recipe_idx];
+
+        match self.state[j] {
+            MachineState::Idle | MachineState::NoInput => {
+                // Try to start crafting if inputs are available
+                if MachinePool::has_inputs(&self.input_slots[j], &recipe.inputs)
+                    && MachinePool::has_fluid_input(&self.fluid_in[j], recipe.fluid_input)
+                {
+                    MachinePool::consume_inputs(&mut self.input_slots[j], &recipe.inputs);
+                    if let Some((_, amount)) = recipe.fluid_input {
+                        self.fluid_in[j].drain(amount);
+                    }
+                    let ticks = MachinePool::craft_ticks(self.machine_type[j]);
+                    self.recipe_ticks[j] = ticks;
+                    self.recipe_total_ticks[j] = ticks;
+                    self.progress[j] = 0.0;
+                    self.state[j] = MachineState::Working;
+                } else {
+                    self.state[j] = MachineState::NoInput;
+                }
+            }
+            MachineState::Working => {
+                if self.power_draw[j] <= 0.0 {
+                    self.state[j] = MachineState::NoPower;
+                    return;
+                }
+                self.recipe_ticks[j] = self.recipe_ticks[j].saturating_sub(1);
+                let total = self.recipe_total_ticks[j].max(1) as f32;
+                self.progress[j] = 1.0 - (self.recipe_ticks[j] as f32 / total);
+
+                if self.recipe_ticks[j] == 0 {
+                    // Craft complete — try to deposit output
+                    if MachinePool::try_produce(
+                        &mut self.output_slots[j],
+                        &mut self.fluid_out[j],
+                        recipe,
+                    ) {
+                        self.progress[j] = 0.0;
+                        self.state[j] = MachineState::Idle;
+                    } else {
+                        self.progress[j] = 1.0;
+                        self.state[j] = MachineState::OutputFull;
+                    }
+                }
+            }
+            MachineState::OutputFull => {
+                // Try to deposit the pending output (inserter may have drained a slot)
+                if MachinePool::try_produce(
+                    &mut self.output_slots[j],
+                    &mut self.fluid_out[j],
+                    recipe,
+                ) {
+                    self.progress[j] = 0.0;
+                    self.state[j] = MachineState::Idle;
+                }
+            }
+            MachineState::NoPower => {
+                // Wake up if power has been restored
+                if self.power_draw[j] > 0.0 {
+                    self.state[j] = MachineState::Working;
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -749,8 +1318,9 @@ mod tests {
         }
         assert_eq!(pool.state(e1), Some(MachineState::OutputFull));
 
-        // Drain one output slot
+        // Drain one output slot (directly, so re-queue by hand)
         pool.cold.output_slots[i][0].count = 0;
+        pool.requeue(i);
         pool.tick(&recipes);
         // Should have deposited output and returned to Idle (or NoInput)
         assert!(
@@ -772,15 +1342,134 @@ mod tests {
         assert_eq!(pool.state(e1), Some(MachineState::Working));
 
         // Cut power
-        let i = pool.index_of(e1).unwrap();
-        pool.hot.power_draw[i] = 0.0;
+        pool.set_power_satisfaction(e1, 0.0);
         pool.tick(&recipes);
         assert_eq!(pool.state(e1), Some(MachineState::NoPower));
 
         // Restore power
-        pool.hot.power_draw[i] = 1.0;
+        pool.set_power_satisfaction(e1, 1.0);
+        pool.tick(&recipes);
+        assert_eq!(pool.state(e1), Some(MachineState::Working));
+    }
+
+    #[test]
+    fn starved_machine_leaves_active_set_until_fed() {
+        let (mut pool, e1, recipes) = setup_composer_with_recipe();
+        pool.tick(&recipes);
+        assert_eq!(pool.state(e1), Some(MachineState::NoInput));
+        assert_eq!(pool.active_count(), 0);
+
+        // One Point is not enough, but any insert re-queues for a recheck
+        assert!(pool.insert_input_at_slot(e1, 0, ItemId::Point, 1));
+        assert_eq!(pool.active_count(), 1);
+        pool.tick(&recipes);
+        assert_eq!(pool.active_count(), 0);
+
+        assert!(pool.insert_input_at_slot(e1, 0, ItemId::Point, 1));
         pool.tick(&recipes);
         assert_eq!(pool.state(e1), Some(MachineState::Working));
+        assert_eq!(pool.active_count(), 1);
+    }
+
+    #[test]
+    fn blocked_machines_requeue_on_output_take_recipe_and_power() {
+        let (mut pool, e1, recipes) = setup_composer_with_recipe();
+        let i = pool.index_of(e1).unwrap();
+        for (j, item) in [ItemId::NullSet, ItemId::Preimage, ItemId::Wavelet, ItemId::Identity]
+            .iter()
+            .enumerate()
+        {
+            pool.cold.output_slots[i][j] = ItemStack { item: *item, count: 1 };
+        }
+        pool.insert_input(e1, ItemId::Point, 2);
+        for _ in 0..=DEFAULT_CRAFT_TICKS {
+            pool.tick(&recipes);
+        }
+        assert_eq!(pool.state(e1), Some(MachineState::OutputFull));
+        assert_eq!(pool.active_count(), 0);
+        pool.take_output_from_slot(e1, 0);
+        assert_eq!(pool.active_count(), 1);
+
+        pool.set_recipe(e1, None);
+        pool.tick(&recipes);
+        assert_eq!(pool.active_count(), 0);
+        pool.set_recipe(e1, Some(0));
+        assert_eq!(pool.active_count(), 1);
+
+        pool.insert_input(e1, ItemId::Point, 2);
+        pool.tick(&recipes);
+        pool.set_power_satisfaction(e1, 0.0);
+        pool.tick(&recipes);
+        assert_eq!(pool.state(e1), Some(MachineState::NoPower));
+        assert_eq!(pool.active_count(), 0);
+        pool.set_power_satisfaction(e1, 0.5);
+        assert_eq!(pool.active_count(), 1);
+    }
+
+    #[test]
+    fn thousands_of_starved_machines_are_not_visited() {
+        let mut pool = MachinePool::new();
+        let mut sm: SlotMap<EntityId, ()> = SlotMap::with_key();
+        let recipes = RecipeIndex::new();
+        let entities: Vec<EntityId> = (0..5000).map(|_| sm.insert(())).collect();
+        for &e in &entities {
+            pool.add(e, MachineType::Composer);
+            pool.set_recipe(e, Some(0));
+        }
+        pool.tick(&recipes);
+        assert_eq!(pool.active_count(), 0);
+
+        pool.insert_input(entities[42], ItemId::Point, 2);
+        pool.tick(&recipes);
+        assert_eq!(pool.active_count(), 1);
+        assert_eq!(pool.state(entities[42]), Some(MachineState::Working));
+    }
+
+    #[test]
+    fn parallel_tick_matches_sequential() {
+        let recipes = RecipeIndex::new();
+        let build = || {
+            let mut pool = MachinePool::new();
+            let mut sm: SlotMap<EntityId, ()> = SlotMap::with_key();
+            for k in 0..3000u16 {
+                let e = sm.insert(());
+                pool.add(e, MachineType::Composer);
+                pool.set_recipe(e, (k % 5 != 0).then_some(0));
+                pool.insert_input(e, ItemId::Point, k % 9);
+                if k % 11 == 0 {
+                    pool.set_power_satisfaction(e, 0.0);
+                }
+            }
+            pool
+        };
+        let mut parallel = build();
+        let mut sequential = build();
+        for _ in 0..400 {
+            // Small runs, so the work spreads over many threads
+            parallel.tick_with(&recipes, 97);
+            sequential.tick_with(&recipes, usize::MAX);
+        }
+        assert_eq!(parallel.hot.state, sequential.hot.state);
+        assert_eq!(parallel.hot.recipe_ticks, sequential.hot.recipe_ticks);
+        assert_eq!(
+            parallel.hot.progress.iter().map(|p| p.to_bits()).collect::<Vec<_>>(),
+            sequential.hot.progress.iter().map(|p| p.to_bits()).collect::<Vec<_>>()
+        );
+        let counts = |pool: &MachinePool| {
+            pool.cold.output_slots.iter().chain(&pool.cold.input_slots).map(|s| s.map(|s| s.count)).collect::<Vec<_>>()
+        };
+        assert_eq!(counts(&parallel), counts(&sequential));
+        assert_eq!(parallel.active, sequential.active);
+    }
+
+    #[test]
+    fn removed_machine_leaves_active_set() {
+        let (mut pool, e1, recipes) = setup_composer_with_recipe();
+        assert!(pool.remove(e1));
+        assert_eq!(pool.active_count(), 0);
+        pool.add(e1, MachineType::Composer);
+        pool.tick(&recipes);
+        assert_eq!(pool.active_count(), 0);
     }
 
     #[test]
@@ -803,6 +1492,46 @@ mod tests {
         assert_eq!(total, 3);
     }
 
+    #[test]
+    fn wake_catch_up_matches_ticking() {
+        let cycle = DEFAULT_CRAFT_TICKS as u64 + 1;
+        for ticks in [0, 1, 7, cycle, 2 * cycle + 3, 3 * cycle + 10, 10 * cycle] {
+            let (mut ticked, e1, recipes) = setup_composer_with_recipe();
+            let (mut slept, _, _) = setup_composer_with_recipe();
+            for pool in [&mut ticked, &mut slept] {
+                // Enough for 3 crafts, plus one Point left over
+                pool.insert_input(e1, ItemId::Point, 7);
+                pool.tick(&recipes); // start the first craft before sleeping
+            }
+
+            for _ in 0..ticks {
+                ticked.tick(&recipes);
+            }
+            slept.sleep(e1);
+            slept.tick(&recipes);
+            slept.wake(e1, ticks, &recipes);
+
+            let (a, b) = (ticked.index_of(e1).unwrap(), slept.index_of(e1).unwrap());
+            assert_eq!(ticked.hot.state[a], slept.hot.state[b], "state after {ticks} ticks");
+            assert_eq!(ticked.hot.recipe_ticks[a], slept.hot.recipe_ticks[b], "after {ticks} ticks");
+            assert!((ticked.hot.progress[a] - slept.hot.progress[b]).abs() < 1e-6);
+            let counts = |slots: &[ItemStack; MAX_SLOTS]| slots.map(|s| (s.item, s.count));
+            assert_eq!(counts(&ticked.cold.input_slots[a]), counts(&slept.cold.input_slots[b]));
+            assert_eq!(counts(&ticked.cold.output_slots[a]), counts(&slept.cold.output_slots[b]));
+        }
+    }
+
+    #[test]
+    fn asleep_machine_does_not_tick() {
+        let (mut pool, e1, recipes) = setup_composer_with_recipe();
+        pool.insert_input(e1, ItemId::Point, 2);
+        pool.sleep(e1);
+        for _ in 0..10 {
+            pool.tick(&recipes);
+        }
+        assert_eq!(pool.state(e1), Some(MachineState::Idle));
+    }
+
     #[test]
     fn source_machine_produces_without_inputs() {
         let mut pool = MachinePool::new();
@@ -856,4 +1585,116 @@ mod tests {
         assert_eq!(pool.hot.recipe_ticks[i], SOURCE_CRAFT_TICKS);
         assert_eq!(pool.hot.recipe_total_ticks[i], SOURCE_CRAFT_TICKS);
     }
+
+    #[test]
+    fn move_port_updates_rotated_layout() {
+        let mut pool = MachinePool::new();
+        let (_, e1) = make_entity();
+        pool.add(e1, MachineType::Composer);
+
+        // Composer input 0 defaults to South@(0,1); move it to East@(1,1)
+        assert!(pool.move_port(e1, 0, (1, 1), Direction::East));
+        let ports = pool.rotated_ports(e1, Direction::North);
+        assert_eq!(ports[0].side, Direction::East);
+        assert_eq!(ports[0].cell_offset, (1, 1));
+
+        // Rotation still applies on top of the custom layout: East → South
+        let ports = pool.rotated_ports(e1, Direction::East);
+        assert_eq!(ports[0].side, Direction::South);
+    }
+
+    #[test]
+    fn move_port_rejects_invalid_positions() {
+        let mut pool = MachinePool::new();
+        let (_, e1) = make_entity();
+        pool.add(e1, MachineType::Inverter);
+
+        // Interior cell faces nothing
+        assert!(!pool.move_port(e1, 0, (1, 1), Direction::South));
+        // Occupied by the output port
+        assert!(!pool.move_port(e1, 0, (1, 0), Direction::North));
+        // Out of range port index
+        assert!(!pool.move_port(e1, 5, (0, 0), Direction::West));
+        assert_eq!(pool.ports(e1).unwrap()[0].cell_offset, (1, 2));
+    }
+
+    #[test]
+    fn reset_ports_and_remove_keep_layouts_aligned() {
+        let mut pool = MachinePool::new();
+        let (mut sm, e1) = make_entity();
+        let e2 = sm.insert(());
+        pool.add(e1, MachineType::Composer);
+        pool.add(e2, MachineType::Inverter);
+        assert!(pool.move_port(e2, 0, (0, 1), Direction::West));
+
+        // Removing e1 swaps e2 into slot 0; its custom layout must follow
+        pool.remove(e1);
+        assert_eq!(pool.ports(e2).unwrap()[0].side, Direction::West);
+
+        pool.reset_ports(e2);
+        assert_eq!(pool.ports(e2).unwrap()[0].side, Direction::South);
+    }
+
+    fn recipe_with_fluid(recipes: &RecipeIndex, machine: MachineType) -> usize {
+        recipes
+            .recipes_for_machine(machine)
+            .into_iter()
+            .find(|(_, r)| r.fluid_input.is_some() || r.fluid_output.is_some())
+            .map(|(i, _)| i)
+            .unwrap()
+    }
+
+    #[test]
+    fn fluid_recipe_waits_for_fluid_and_produces_fluid() {
+        let mut pool = MachinePool::new();
+        let (_, e1) = make_entity();
+        pool.add(e1, MachineType::Transformer);
+        let recipes = RecipeIndex::new();
+        let idx = recipe_with_fluid(&recipes, MachineType::Transformer);
+        let recipe = recipes.all[idx].clone();
+        let (fluid_in, need) = recipe.fluid_input.unwrap();
+        let (fluid_out, made) = recipe.fluid_output.unwrap();
+        pool.set_recipe(e1, Some(idx));
+        for &(item, count) in &recipe.inputs {
+            pool.insert_input(e1, item, count as u16);
+        }
+
+        // Items alone are not enough
+        pool.tick(&recipes);
+        assert_eq!(pool.state(e1), Some(MachineState::NoInput));
+
+        assert_eq!(pool.fill_fluid_input(e1, fluid_in, need), need);
+        pool.tick(&recipes);
+        assert_eq!(pool.state(e1), Some(MachineState::Working));
+        assert_eq!(pool.fluid_input_tank(e1).unwrap().amount, 0);
+
+        for _ in 0..DEFAULT_CRAFT_TICKS {
+            pool.tick(&recipes);
+        }
+        let out = pool.fluid_output_tank(e1).unwrap();
+        assert_eq!(out.fluid, Some(fluid_out));
+        assert_eq!(out.amount, made);
+    }
+
+    #[test]
+    fn fluid_output_full_blocks_craft() {
+        let mut pool = MachinePool::new();
+        let (_, e1) = make_entity();
+        pool.add(e1, MachineType::Source);
+        let recipes = RecipeIndex::new();
+        let idx = recipe_with_fluid(&recipes, MachineType::Source);
+        let (fluid, made) = recipes.all[idx].fluid_output.unwrap();
+        pool.set_recipe(e1, Some(idx));
+        pool.fill_fluid_output(e1, fluid, MACHINE_TANK_CAPACITY - made + 1);
+
+        for _ in 0..=SOURCE_CRAFT_TICKS {
+            pool.tick(&recipes);
+        }
+        assert_eq!(pool.state(e1), Some(MachineState::OutputFull));
+
+        pool.drain_fluid_output(e1, made);
+        pool.tick(&recipes);
+        assert_eq!(pool.state(e1), Some(MachineState::Idle));
+        assert!(pool.output_slots(e1).unwrap().iter().all(|s| s.count == 0));
+    }
 }
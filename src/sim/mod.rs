@@ -1,7 +1,15 @@
 pub mod belt;
+pub mod circuit;
+pub mod fluid;
 pub mod inserter;
+pub mod loader;
 pub mod machine;
+pub mod parallel;
 pub mod power;
+pub mod rail;
+pub mod sink;
+pub mod sleep;
 pub mod splitter;
+pub mod state_hash;
 pub mod storage;
 pub mod tick;
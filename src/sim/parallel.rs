@@ -0,0 +1,118 @@
+//! Data-parallel helpers for the simulation tick.
+//!
+//! Within one phase of a tick, each transport line advances and each machine
+//! steps using only its own state, so the work splits into independent
+//! parts that can run on separate threads. Everything that crosses from
+//! one line or machine to another — belt handoffs, port transfers, power,
+//! circuits — stays in the sequential phases around them, which act as the
+//! deterministic merge. The result is bit-identical to running every part
+//! on one thread.
+//!
+//! Parts run on a pool of worker threads started on first use and kept for
+//! the life of the process, so a tick costs no thread spawns. Idle workers
+//! steal queued parts, which evens out parts of uneven size.
+
+use std::sync::OnceLock;
+
+use rayon::prelude::*;
+
+/// Smallest amount of work (lines or machines) worth handing to another
+/// worker. Below this the handoff costs more than it saves.
+pub const MIN_PART: usize = 256;
+
+/// The simulation's worker pool, one thread per available core.
+fn pool() -> &'static rayon::ThreadPool {
+    static POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .thread_name(|i| format!("sim-worker-{i}"))
+            .build()
+            .expect("failed to start simulation worker pool")
+    })
+}
+
+/// Run `f` on every part on the worker pool. A single part runs on the
+/// calling thread.
+pub fn run<W: Send>(parts: Vec<W>, f: impl Fn(W) + Sync + Send) {
+    if parts.len() <= 1 {
+        parts.into_iter().for_each(f);
+        return;
+    }
+    pool().install(|| parts.into_par_iter().for_each(f));
+}
+
+/// Run `a` and `b`, in parallel when a worker is free. Nested calls split
+/// work recursively, and idle workers steal the halves.
+pub fn join(a: impl FnOnce() + Send, b: impl FnOnce() + Send) {
+    pool().install(|| rayon::join(a, b));
+}
+
+/// Pack groups of work into parts of at least `min_part` items, never
+/// splitting a group. `group_of[i]` is item `i`'s group, itself an item
+/// index (such as a union-find root). Returns each item's part and the
+/// number of parts.
+pub fn pack_groups(group_of: &[usize], min_part: usize) -> (Vec<usize>, usize) {
+    let mut size = vec![0usize; group_of.len()];
+    for &g in group_of {
+        size[g] += 1;
+    }
+    let mut part_of_group = vec![usize::MAX; group_of.len()];
+    let (mut parts, mut filled) = (0, 0);
+    let part_of = group_of
+        .iter()
+        .map(|&g| {
+            if part_of_group[g] == usize::MAX {
+                if filled >= min_part {
+                    parts += 1;
+                    filled = 0;
+                }
+                part_of_group[g] = parts;
+                filled += size[g];
+            }
+            part_of_group[g]
+        })
+        .collect();
+    (part_of, if group_of.is_empty() { 0 } else { parts + 1 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn groups_pack_whole_into_parts() {
+        assert_eq!(pack_groups(&[], 2), (vec![], 0));
+        // Groups 0 (three items), 1 (one) and 2 (two), interleaved
+        let (part_of, parts) = pack_groups(&[0, 1, 0, 2, 0, 2], 2);
+        assert_eq!(parts, 2);
+        assert_eq!(part_of, vec![0, 1, 0, 1, 0, 1]);
+        assert_eq!(pack_groups(&[0, 1, 2], usize::MAX).1, 1);
+    }
+
+    #[test]
+    fn run_visits_every_part_once() {
+        let mut data: Vec<u32> = (0..5000).collect();
+        let visited = AtomicUsize::new(0);
+        let parts: Vec<&mut [u32]> = data.chunks_mut(700).collect();
+        run(parts, |chunk| {
+            for x in chunk {
+                *x *= 2;
+                visited.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        assert_eq!(visited.into_inner(), 5000);
+        assert!(data.iter().enumerate().all(|(i, &x)| x == 2 * i as u32));
+    }
+
+    #[test]
+    fn parts_run_on_pool_workers() {
+        let on_workers = AtomicUsize::new(0);
+        run((0..64).collect(), |_: usize| {
+            if std::thread::current().name().is_some_and(|n| n.starts_with("sim-worker-")) {
+                on_workers.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        assert_eq!(on_workers.into_inner(), 64);
+    }
+}
@@ -1,7 +1,12 @@
 use std::collections::HashMap;
 
-use crate::game::world::EntityId;
-use crate::hyperbolic::tiling::TileAddr;
+use serde::{Deserialize, Serialize};
+
+use crate::game::edge::EdgeTransfer;
+use crate::game::world::{Direction, EntityId};
+use crate::hyperbolic::cell_id::{CellId, CellInterner, CellKey};
+use crate::hyperbolic::rewrite::RewriteRule;
+use crate::sim::state_hash::StateHasher;
 
 /// Power connection radius in grid squares.
 pub const POWER_RADIUS: f32 = 8.0;
@@ -15,7 +20,7 @@ pub const DYNAMO_RATE: f32 = 8.0;
 /// Power consumption rate for a machine.
 pub const MACHINE_CONSUMPTION: f32 = 1.0;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PowerNodeKind {
     Producer,
     /// Relay nodes extend the power graph but produce no power.
@@ -23,13 +28,13 @@ pub enum PowerNodeKind {
     Consumer,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct PowerNode {
     pub entity: EntityId,
     pub kind: PowerNodeKind,
     pub rate: f32,
-    pub tile: TileAddr,
+    pub cell: CellKey,
     pub gx: i16,
     pub gy: i16,
     /// Whether this node is exempt from power requirements (e.g. Source machines).
@@ -39,7 +44,10 @@ pub struct PowerNode {
 /// Power network: tracks all power-relevant entities, builds a connection
 /// graph based on proximity, and solves connected-component ratio-based
 /// power distribution each tick.
+#[derive(Serialize, Deserialize)]
 pub struct PowerNetwork {
+    /// Cells holding power nodes.
+    cells: CellInterner,
     nodes: Vec<PowerNode>,
     entity_to_idx: HashMap<EntityId, usize>,
     /// Per-node power satisfaction [0.0 .. 1.0].
@@ -48,16 +56,40 @@ pub struct PowerNetwork {
     adjacency: Vec<Vec<usize>>,
     /// Whether the graph needs rebuilding.
     dirty: bool,
+    /// Rewrite rules for finding neighbor cells. Without them nodes only
+    /// connect within their own cell.
+    /// Not part of a snapshot; `with_rules` supplies them again.
+    #[serde(skip)]
+    rules: Vec<RewriteRule>,
 }
 
 impl PowerNetwork {
     pub fn new() -> Self {
         Self {
+            cells: CellInterner::new(),
             nodes: Vec::new(),
             entity_to_idx: HashMap::new(),
             satisfaction: Vec::new(),
             adjacency: Vec::new(),
             dirty: false,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Also connect nodes across cell edges, using `rules` to find neighbors.
+    pub fn with_rules(mut self, rules: Vec<RewriteRule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Feed every node's satisfaction into a state hash, in entity order.
+    pub fn hash_state(&self, h: &mut StateHasher) {
+        let mut order: Vec<usize> = (0..self.nodes.len()).collect();
+        order.sort_by_key(|&i| self.nodes[i].entity);
+        h.u32(order.len() as u32);
+        for i in order {
+            h.entity(self.nodes[i].entity);
+            h.f32(self.satisfaction[i]);
         }
     }
 
@@ -68,7 +100,7 @@ impl PowerNetwork {
         entity: EntityId,
         kind: PowerNodeKind,
         rate: f32,
-        tile: &[u8],
+        cell: &CellId,
         gx: i16,
         gy: i16,
         exempt: bool,
@@ -78,7 +110,7 @@ impl PowerNetwork {
             entity,
             kind,
             rate,
-            tile: TileAddr::from_slice(tile),
+            cell: self.cells.intern(cell),
             gx,
             gy,
             exempt,
@@ -130,7 +162,8 @@ impl PowerNetwork {
         self.nodes.len()
     }
 
-    /// Rebuild the adjacency graph based on proximity within same tile.
+    /// Rebuild the adjacency graph based on proximity, within a tile and
+    /// across tile edges.
     fn rebuild_connections(&mut self) {
         let n = self.nodes.len();
         self.adjacency.clear();
@@ -141,7 +174,7 @@ impl PowerNetwork {
         for i in 0..n {
             for j in (i + 1)..n {
                 // Only connect nodes in the same tile
-                if self.nodes[i].tile != self.nodes[j].tile {
+                if self.nodes[i].cell != self.nodes[j].cell {
                     continue;
                 }
                 let dx = (self.nodes[i].gx - self.nodes[j].gx) as f32;
@@ -155,9 +188,49 @@ impl PowerNetwork {
             }
         }
 
+        if !self.rules.is_empty() {
+            self.connect_across_edges(radius_sq);
+        }
+
         self.dirty = false;
     }
 
+    /// Connect nodes within reach of a tile edge to nodes in the neighboring
+    /// tile, measuring in the neighbor's grid.
+    fn connect_across_edges(&mut self, radius_sq: f32) {
+        let mut by_tile: HashMap<CellKey, Vec<usize>> = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            by_tile.entry(node.cell).or_default().push(i);
+        }
+
+        let mut transfers: HashMap<(CellKey, Direction), EdgeTransfer> = HashMap::new();
+        for i in 0..self.nodes.len() {
+            let (gx, gy) = (self.nodes[i].gx as i32, self.nodes[i].gy as i32);
+            for edge in [Direction::North, Direction::East, Direction::South, Direction::West] {
+                let (dx, dy) = edge.grid_offset_i32();
+                if (32 - (gx * dx + gy * dy)) as f32 > POWER_RADIUS {
+                    continue;
+                }
+                let key = self.nodes[i].cell;
+                let transfer = transfers
+                    .entry((key, edge))
+                    .or_insert_with(|| EdgeTransfer::across(self.cells.cell(key), edge, &self.rules));
+                let (fx, fy) = transfer.grid((gx, gy));
+                let Some(others) = self.cells.key(&transfer.cell).and_then(|k| by_tile.get(&k)) else {
+                    continue;
+                };
+                for &j in others {
+                    let dx = (fx - self.nodes[j].gx as i32) as f32;
+                    let dy = (fy - self.nodes[j].gy as i32) as f32;
+                    if dx * dx + dy * dy <= radius_sq && !self.adjacency[i].contains(&j) {
+                        self.adjacency[i].push(j);
+                        self.adjacency[j].push(i);
+                    }
+                }
+            }
+        }
+    }
+
     /// Solve power distribution: BFS connected components, ratio-based.
     /// Updates satisfaction for all nodes.
     pub fn solve(&mut self) {
@@ -232,6 +305,10 @@ mod tests {
     use super::*;
     use slotmap::SlotMap;
 
+    fn cell(word: &[u8]) -> CellId {
+        CellId::from_canonical(word.to_vec())
+    }
+
     fn make_entities(n: usize) -> (SlotMap<EntityId, ()>, Vec<EntityId>) {
         let mut sm = SlotMap::with_key();
         let ids: Vec<EntityId> = (0..n).map(|_| sm.insert(())).collect();
@@ -249,7 +326,7 @@ mod tests {
     fn single_producer_no_consumers() {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(1);
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 0, 0, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 0, 0, false);
         net.solve();
         assert_eq!(net.satisfaction(ids[0]), Some(1.0));
     }
@@ -258,7 +335,7 @@ mod tests {
     fn single_consumer_no_producer() {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(1);
-        net.add(ids[0], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 0, 0, false);
+        net.add(ids[0], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 0, 0, false);
         net.solve();
         assert_eq!(net.satisfaction(ids[0]), Some(0.0));
     }
@@ -268,17 +345,17 @@ mod tests {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(9);
         // Dynamo at center, rate 8.0
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 4, 4, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 4, 4, false);
         // 8 machines around it
-        for i in 1..=8 {
+        for (i, &id) in ids.iter().enumerate().skip(1) {
             let gx = 4 + ((i as i16 - 1) % 3) - 1;
             let gy = 4 + ((i as i16 - 1) / 3) - 1;
-            net.add(ids[i], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], gx, gy, false);
+            net.add(id, PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), gx, gy, false);
         }
         net.solve();
         // 8.0 / 8.0 = 1.0
-        for i in 1..=8 {
-            assert_eq!(net.satisfaction(ids[i]), Some(1.0));
+        for &id in &ids[1..=8] {
+            assert_eq!(net.satisfaction(id), Some(1.0));
         }
     }
 
@@ -287,10 +364,10 @@ mod tests {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(10);
         // Dynamo at (0,0), rate 8.0
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 0, 0, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 0, 0, false);
         // 9 machines nearby, total consumption 9.0
-        for i in 1..=9 {
-            net.add(ids[i], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], i as i16, 0, false);
+        for (i, &id) in ids.iter().enumerate().skip(1) {
+            net.add(id, PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), i as i16, 0, false);
         }
         net.solve();
         // 8.0 / 9.0 ≈ 0.889
@@ -304,9 +381,9 @@ mod tests {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(2);
         // Dynamo at (0,0)
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 0, 0, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 0, 0, false);
         // Machine at (20,20) — way out of range
-        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 20, 20, false);
+        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 20, 20, false);
         net.solve();
         // Machine not connected to any producer
         assert_eq!(net.satisfaction(ids[1]), Some(0.0));
@@ -317,21 +394,46 @@ mod tests {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(2);
         // Dynamo in tile [0]
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 0, 0, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 0, 0, false);
         // Machine in tile [1], same grid coords but different tile
-        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[1], 0, 0, false);
+        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[1]), 0, 0, false);
         net.solve();
         assert_eq!(net.satisfaction(ids[1]), Some(0.0));
     }
 
+    #[test]
+    fn neighboring_tiles_connect_across_edges() {
+        let rules = crate::hyperbolic::rewrite::rules_for(5);
+        // A cell two steps out, so some of its neighbors are turned
+        let origin = CellId::origin();
+        let east = EdgeTransfer::across(&origin, Direction::East, &rules).cell;
+        let cell = EdgeTransfer::across(&east, Direction::North, &rules).cell;
+        for edge in [Direction::North, Direction::East, Direction::South, Direction::West] {
+            let mut net = PowerNetwork::new().with_rules(rules.clone());
+            let (_sm, ids) = make_entities(3);
+            let (dx, dy) = edge.grid_offset_i32();
+            let transfer = EdgeTransfer::across(&cell, edge, &rules);
+            // Dynamo 2 squares inside the edge, machine 3 squares past it
+            net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell, (30 * dx) as i16, (30 * dy) as i16, false);
+            let (mx, my) = transfer.grid((35 * dx, 35 * dy));
+            net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &transfer.cell, mx as i16, my as i16, false);
+            // Another 20 squares further on is out of reach
+            let (fx, fy) = transfer.grid((55 * dx, 55 * dy));
+            net.add(ids[2], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &transfer.cell, fx as i16, fy as i16, false);
+            net.solve();
+            assert_eq!(net.satisfaction(ids[1]), Some(1.0), "{cell} {edge:?}");
+            assert_eq!(net.satisfaction(ids[2]), Some(0.0), "{cell} {edge:?}");
+        }
+    }
+
     #[test]
     fn exempt_consumer_always_full() {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(2);
         // No producer. Exempt consumer (Source machine).
-        net.add(ids[0], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 0, 0, true);
+        net.add(ids[0], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 0, 0, true);
         // Non-exempt consumer
-        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 1, 0, false);
+        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 1, 0, false);
         net.solve();
         assert_eq!(net.satisfaction(ids[0]), Some(1.0)); // exempt
         assert_eq!(net.satisfaction(ids[1]), Some(0.0)); // no power
@@ -342,11 +444,11 @@ mod tests {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(3);
         // Dynamo, rate 8.0
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 0, 0, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 0, 0, false);
         // Exempt consumer (should not count toward consumption)
-        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 1, 0, true);
+        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 1, 0, true);
         // Regular consumer, rate 1.0
-        net.add(ids[2], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 2, 0, false);
+        net.add(ids[2], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 2, 0, false);
         net.solve();
         // 8.0 production / 1.0 consumption = 1.0 (exempt doesn't count)
         assert_eq!(net.satisfaction(ids[1]), Some(1.0)); // exempt
@@ -357,9 +459,9 @@ mod tests {
     fn remove_node() {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(3);
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 0, 0, false);
-        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 1, 0, false);
-        net.add(ids[2], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 2, 0, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 0, 0, false);
+        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 1, 0, false);
+        net.add(ids[2], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 2, 0, false);
 
         assert!(net.remove(ids[1]));
         assert_eq!(net.node_count(), 2);
@@ -375,15 +477,15 @@ mod tests {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(7);
         // Component 1: well-powered dynamo at gx=0
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 0, 0, false);
-        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 1, 0, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 0, 0, false);
+        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 1, 0, false);
         // Component 2: underpowered dynamo at gx=50 with 4 consumers
-        net.add(ids[2], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 50, 0, false);
-        net.add(ids[3], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 51, 0, false);
+        net.add(ids[2], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 50, 0, false);
+        net.add(ids[3], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 51, 0, false);
 
         // Add extra consumers to component 2 (total 4 consumers = 4.0 consumption)
         for i in 0..3 {
-            net.add(ids[4 + i], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 52 + i as i16, 0, false);
+            net.add(ids[4 + i], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 52 + i as i16, 0, false);
         }
 
         net.solve();
@@ -400,8 +502,8 @@ mod tests {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(2);
         // Relay (Quadrupole) alone with a machine — no power produced
-        net.add(ids[0], PowerNodeKind::Relay, QUADRUPOLE_RATE, &[0], 0, 0, false);
-        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 1, 0, false);
+        net.add(ids[0], PowerNodeKind::Relay, QUADRUPOLE_RATE, &cell(&[0]), 0, 0, false);
+        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 1, 0, false);
         net.solve();
         assert_eq!(net.satisfaction(ids[1]), Some(0.0));
     }
@@ -413,9 +515,9 @@ mod tests {
         // Dynamo at (0,0), relay at (7,0), machine at (14,0)
         // Dynamo<->Relay within radius 8, Relay<->Machine within radius 8
         // Dynamo<->Machine NOT within radius 8 (distance 14 > 8)
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 0, 0, false);
-        net.add(ids[1], PowerNodeKind::Relay, QUADRUPOLE_RATE, &[0], 7, 0, false);
-        net.add(ids[2], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 14, 0, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 0, 0, false);
+        net.add(ids[1], PowerNodeKind::Relay, QUADRUPOLE_RATE, &cell(&[0]), 7, 0, false);
+        net.add(ids[2], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 14, 0, false);
         net.solve();
         // Relay bridges the connection: 8.0 / 1.0 = 1.0
         assert_eq!(net.satisfaction(ids[2]), Some(1.0));
@@ -426,8 +528,8 @@ mod tests {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(2);
         // Dynamo at (0,0), machine at (14,0) — too far apart (distance 14 > 8)
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 0, 0, false);
-        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 14, 0, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 0, 0, false);
+        net.add(ids[1], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 14, 0, false);
         net.solve();
         assert_eq!(net.satisfaction(ids[1]), Some(0.0));
     }
@@ -437,10 +539,10 @@ mod tests {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(4);
         // Chain: Dynamo(0,0) -> Relay(7,0) -> Relay(14,0) -> Machine(21,0)
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 0, 0, false);
-        net.add(ids[1], PowerNodeKind::Relay, QUADRUPOLE_RATE, &[0], 7, 0, false);
-        net.add(ids[2], PowerNodeKind::Relay, QUADRUPOLE_RATE, &[0], 14, 0, false);
-        net.add(ids[3], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 21, 0, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 0, 0, false);
+        net.add(ids[1], PowerNodeKind::Relay, QUADRUPOLE_RATE, &cell(&[0]), 7, 0, false);
+        net.add(ids[2], PowerNodeKind::Relay, QUADRUPOLE_RATE, &cell(&[0]), 14, 0, false);
+        net.add(ids[3], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 21, 0, false);
         net.solve();
         // Connected via relay chain: 8.0 / 1.0 = 1.0
         assert_eq!(net.satisfaction(ids[3]), Some(1.0));
@@ -450,9 +552,9 @@ mod tests {
     fn node_kind_query() {
         let mut net = PowerNetwork::new();
         let (_sm, ids) = make_entities(3);
-        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &[0], 0, 0, false);
-        net.add(ids[1], PowerNodeKind::Relay, QUADRUPOLE_RATE, &[0], 4, 0, false);
-        net.add(ids[2], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &[0], 1, 0, false);
+        net.add(ids[0], PowerNodeKind::Producer, DYNAMO_RATE, &cell(&[0]), 0, 0, false);
+        net.add(ids[1], PowerNodeKind::Relay, QUADRUPOLE_RATE, &cell(&[0]), 4, 0, false);
+        net.add(ids[2], PowerNodeKind::Consumer, MACHINE_CONSUMPTION, &cell(&[0]), 1, 0, false);
         assert_eq!(net.node_kind(ids[0]), Some(PowerNodeKind::Producer));
         assert_eq!(net.node_kind(ids[1]), Some(PowerNodeKind::Relay));
         assert_eq!(net.node_kind(ids[2]), Some(PowerNodeKind::Consumer));
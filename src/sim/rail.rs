@@ -0,0 +1,1019 @@
+//! Rail network: track, stations, and scheduled trains.
+//!
+//! Every rail is a one-cell track segment that links to its neighbours in
+//! the four grid directions, across cell and tile edges alike. Stations
+//! stop any train on a rail touching their footprint and move goods between
+//! its wagons and the storage touching the station.
+//!
+//! Routes are planned in two passes. A coarse search over the cell graph —
+//! cells joined wherever track crosses their shared edge — picks the
+//! corridor of cells a train passes through; the fine search then walks
+//! individual rails inside that corridor. On the hyperbolic plane almost
+//! every long route is a frontier-to-origin spoke, so the corridor is narrow
+//! and the fine search touches only a sliver of the track.
+//!
+//! Trains do not collide or signal; several trains may share a rail.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::items::ItemId;
+use crate::game::world::{Direction, EntityId};
+use crate::hyperbolic::cell_id::{self, CellId, CellInterner, CellKey};
+use crate::hyperbolic::rewrite::RewriteRule;
+use crate::sim::state_hash::StateHasher;
+use crate::sim::storage::StoragePool;
+
+/// Ticks a train takes to advance one rail.
+pub const TICKS_PER_RAIL: u32 = 3;
+
+/// Ticks a train waits at each scheduled station.
+pub const STATION_DWELL: u32 = 120;
+
+/// Items moved between a stopped train and station storage per tick.
+pub const STATION_TRANSFER_RATE: u32 = 4;
+
+/// Items a single cargo wagon holds, across all item types.
+pub const WAGON_CAPACITY: u32 = 200;
+
+/// Maximum wagons behind one locomotive.
+pub const MAX_WAGONS: usize = 4;
+
+/// Ticks a train without a route waits before planning again.
+pub const REPLAN_INTERVAL: u32 = 60;
+
+/// Per-rail state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RailSegment {
+    pub entity: EntityId,
+    /// The cell (tile) this rail lies in.
+    pub cell: CellKey,
+    /// Neighbouring rail in each direction (N, E, S, W), in this rail's frame.
+    pub links: [Option<EntityId>; 4],
+}
+
+/// Whether a station fills trains from its storage or empties them into it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StationMode {
+    /// Move items from adjacent storage into stopped trains.
+    Load,
+    /// Move items from stopped trains into adjacent storage.
+    Unload,
+}
+
+/// Per-station state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StationState {
+    pub entity: EntityId,
+    pub name: String,
+    pub mode: StationMode,
+    /// Rails touching the station footprint. A train on any of them is "at" the station.
+    pub stops: Vec<EntityId>,
+    /// Storage touching the station footprint.
+    pub storages: Vec<EntityId>,
+}
+
+/// Stable handle to a train. Trains are not world structures, so they are
+/// not keyed by `EntityId`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TrainId(pub u32);
+
+/// One cargo wagon: a bag of items with a shared capacity.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Wagon {
+    pub cargo: Vec<(ItemId, u32)>,
+}
+
+impl Wagon {
+    /// Total items held.
+    pub fn total(&self) -> u32 {
+        self.cargo.iter().map(|&(_, n)| n).sum()
+    }
+
+    /// Add one item. Returns false if the wagon is full.
+    pub fn push(&mut self, item: ItemId) -> bool {
+        if self.total() >= WAGON_CAPACITY {
+            return false;
+        }
+        match self.cargo.iter_mut().find(|(i, _)| *i == item) {
+            Some((_, n)) => *n += 1,
+            None => self.cargo.push((item, 1)),
+        }
+        true
+    }
+
+    /// Remove one item, oldest type first.
+    pub fn pop(&mut self) -> Option<ItemId> {
+        let (item, n) = self.cargo.first_mut()?;
+        let item = *item;
+        *n -= 1;
+        if *n == 0 {
+            self.cargo.remove(0);
+        }
+        Some(item)
+    }
+}
+
+/// What a train is doing.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrainState {
+    /// Not running, or running with an empty schedule.
+    Idle,
+    /// Following `route`; `route[step]` is the next rail to enter.
+    Travelling {
+        route: Vec<EntityId>,
+        step: usize,
+        progress: u32,
+    },
+    /// Stopped at a station, transferring cargo.
+    AtStation { station: EntityId, dwell: u32 },
+    /// No track leads to the next stop; retries after a delay.
+    NoPath { retry: u32 },
+}
+
+/// A locomotive, its wagons, and its schedule.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Train {
+    pub id: TrainId,
+    /// Rail the locomotive occupies.
+    pub head: EntityId,
+    /// Rails behind the locomotive, nearest first: one per wagon once the
+    /// train has moved far enough.
+    pub trail: VecDeque<EntityId>,
+    /// Direction of travel in the head rail's frame.
+    pub heading: Direction,
+    pub wagons: Vec<Wagon>,
+    /// Stations visited in order, looping.
+    pub schedule: Vec<EntityId>,
+    /// Index into `schedule` of the station the train is heading for.
+    pub next_stop: usize,
+    /// Whether the train follows its schedule.
+    pub running: bool,
+    pub state: TrainState,
+}
+
+impl Train {
+    /// Total items across all wagons.
+    pub fn cargo_total(&self) -> u32 {
+        self.wagons.iter().map(Wagon::total).sum()
+    }
+}
+
+/// Index of a direction into `RailSegment::links`.
+fn dir_index(d: Direction) -> usize {
+    d.rotations_from_north() as usize
+}
+
+const DIRECTIONS: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+/// All track, stations, and trains.
+#[derive(Serialize, Deserialize)]
+pub struct RailNetwork {
+    segments: Vec<RailSegment>,
+    entity_to_idx: HashMap<EntityId, usize>,
+    stations: Vec<StationState>,
+    station_to_idx: HashMap<EntityId, usize>,
+    next_station_number: u32,
+    trains: Vec<Train>,
+    next_train_id: u32,
+    /// Cells holding rails.
+    cells: CellInterner,
+    /// Cell graph: for each cell, the neighbouring cells reachable by track
+    /// and how many rail links cross into each.
+    cell_links: HashMap<CellKey, BTreeMap<CellKey, u32>>,
+    /// Rewrite rules for measuring how far apart cells are, which steers the
+    /// cell route search toward its target. Without them the search falls
+    /// back to breadth-first.
+    /// Not part of a snapshot; `with_rules` supplies them again.
+    #[serde(skip)]
+    rules: Vec<RewriteRule>,
+}
+
+impl RailNetwork {
+    pub fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+            entity_to_idx: HashMap::new(),
+            stations: Vec::new(),
+            station_to_idx: HashMap::new(),
+            next_station_number: 1,
+            trains: Vec::new(),
+            next_train_id: 0,
+            cells: CellInterner::new(),
+            cell_links: HashMap::new(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Guide cell route searches by cell distance, using `rules`.
+    pub fn with_rules(mut self, rules: Vec<RewriteRule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Feed all track, stations and trains into a state hash, in entity
+    /// and train id order.
+    pub fn hash_state(&self, h: &mut StateHasher) {
+        let mut segments: Vec<&RailSegment> = self.segments.iter().collect();
+        segments.sort_by_key(|s| s.entity);
+        h.u32(segments.len() as u32);
+        for rail in segments {
+            h.entity(rail.entity);
+            for &link in &rail.links {
+                h.option_entity(link);
+            }
+        }
+
+        let mut stations: Vec<&StationState> = self.stations.iter().collect();
+        stations.sort_by_key(|s| s.entity);
+        h.u32(stations.len() as u32);
+        for station in stations {
+            h.entity(station.entity);
+            h.str(&station.name);
+            h.u8(station.mode as u8);
+            for list in [&station.stops, &station.storages] {
+                h.u32(list.len() as u32);
+                for &e in list {
+                    h.entity(e);
+                }
+            }
+        }
+        h.u32(self.next_station_number);
+
+        let mut trains: Vec<&Train> = self.trains.iter().collect();
+        trains.sort_by_key(|t| t.id.0);
+        h.u32(trains.len() as u32);
+        for train in trains {
+            h.u32(train.id.0);
+            h.entity(train.head);
+            h.u32(train.trail.len() as u32);
+            for &rail in &train.trail {
+                h.entity(rail);
+            }
+            h.u8(train.heading.rotations_from_north());
+            h.u32(train.wagons.len() as u32);
+            for wagon in &train.wagons {
+                h.u32(wagon.cargo.len() as u32);
+                for &(item, n) in &wagon.cargo {
+                    h.item(item);
+                    h.u32(n);
+                }
+            }
+            h.u32(train.schedule.len() as u32);
+            for &stop in &train.schedule {
+                h.entity(stop);
+            }
+            h.u32(train.next_stop as u32);
+            h.bool(train.running);
+            match &train.state {
+                TrainState::Idle => h.u8(0),
+                TrainState::Travelling { route, step, progress } => {
+                    h.u8(1);
+                    h.u32(route.len() as u32);
+                    for &rail in route {
+                        h.entity(rail);
+                    }
+                    h.u32(*step as u32);
+                    h.u32(*progress);
+                }
+                TrainState::AtStation { station, dwell } => {
+                    h.u8(2);
+                    h.entity(*station);
+                    h.u32(*dwell);
+                }
+                TrainState::NoPath { retry } => {
+                    h.u8(3);
+                    h.u32(*retry);
+                }
+            }
+        }
+        h.u32(self.next_train_id);
+    }
+
+    // --- Track ---
+
+    /// Register a newly placed rail in `cell`. Links are added with `link_rails`.
+    pub fn add_rail(&mut self, entity: EntityId, cell: &CellId) {
+        let idx = self.segments.len();
+        self.segments.push(RailSegment {
+            entity,
+            cell: self.cells.intern(cell),
+            links: [None; 4],
+        });
+        self.entity_to_idx.insert(entity, idx);
+    }
+
+    /// Remove a rail by EntityId, dropping its links and station stops.
+    /// Trains whose locomotive stood on it are derailed and returned so the
+    /// caller can refund them. Swap-removes with the last element.
+    pub fn remove_rail(&mut self, entity: EntityId) -> Vec<Train> {
+        let Some(idx) = self.entity_to_idx.remove(&entity) else {
+            return Vec::new();
+        };
+        for link in self.segments[idx].links.into_iter().flatten() {
+            let Some(&n) = self.entity_to_idx.get(&link) else {
+                continue;
+            };
+            for slot in &mut self.segments[n].links {
+                if *slot == Some(entity) {
+                    *slot = None;
+                }
+            }
+            let (a, b) = (self.segments[idx].cell, self.segments[n].cell);
+            if a != b {
+                self.uncount_cell_link(a, b);
+                self.uncount_cell_link(b, a);
+            }
+        }
+        for station in &mut self.stations {
+            station.stops.retain(|&r| r != entity);
+        }
+
+        let (derailed, kept): (Vec<Train>, Vec<Train>) =
+            std::mem::take(&mut self.trains).into_iter().partition(|t| t.head == entity);
+        self.trains = kept;
+        for train in &mut self.trains {
+            if let Some(pos) = train.trail.iter().position(|&r| r == entity) {
+                train.trail.truncate(pos);
+            }
+        }
+
+        let last = self.segments.len() - 1;
+        if idx != last {
+            self.segments.swap(idx, last);
+            let swapped_entity = self.segments[idx].entity;
+            self.entity_to_idx.insert(swapped_entity, idx);
+        }
+        self.segments.pop();
+        derailed
+    }
+
+    /// Join rail `a` to rail `b`, where `b` lies in `direction` from `a`
+    /// (in `a`'s frame) and `a` lies in `back` from `b` (in `b`'s frame).
+    /// Returns false if either rail is unknown.
+    pub fn link_rails(&mut self, a: EntityId, b: EntityId, direction: Direction, back: Direction) -> bool {
+        let (Some(&ia), Some(&ib)) = (self.entity_to_idx.get(&a), self.entity_to_idx.get(&b)) else {
+            return false;
+        };
+        let already = self.segments[ia].links[dir_index(direction)] == Some(b);
+        self.segments[ia].links[dir_index(direction)] = Some(b);
+        self.segments[ib].links[dir_index(back)] = Some(a);
+
+        let (ca, cb) = (self.segments[ia].cell, self.segments[ib].cell);
+        if !already && ca != cb {
+            *self.cell_links.entry(ca).or_default().entry(cb).or_insert(0) += 1;
+            *self.cell_links.entry(cb).or_default().entry(ca).or_insert(0) += 1;
+        }
+        true
+    }
+
+    fn uncount_cell_link(&mut self, from: CellKey, to: CellKey) {
+        let Some(neighbors) = self.cell_links.get_mut(&from) else {
+            return;
+        };
+        if let Some(n) = neighbors.get_mut(&to) {
+            *n -= 1;
+            if *n == 0 {
+                neighbors.remove(&to);
+            }
+        }
+        if neighbors.is_empty() {
+            self.cell_links.remove(&from);
+        }
+    }
+
+    /// Get a reference to the segment state for a rail.
+    pub fn segment(&self, entity: EntityId) -> Option<&RailSegment> {
+        self.entity_to_idx.get(&entity).map(|&i| &self.segments[i])
+    }
+
+    /// Bitmask (bit 0 = N, 1 = E, 2 = S, 3 = W) of the directions in which a
+    /// rail joins another. Used by the renderer.
+    pub fn connection_mask(&self, entity: EntityId) -> u32 {
+        let Some(seg) = self.segment(entity) else {
+            return 0;
+        };
+        seg.links
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.is_some())
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+
+    // --- Stations ---
+
+    /// Register a newly placed station with an automatic name.
+    pub fn add_station(&mut self, entity: EntityId) {
+        let idx = self.stations.len();
+        self.stations.push(StationState {
+            entity,
+            name: format!("Station {}", self.next_station_number),
+            mode: StationMode::Unload,
+            stops: Vec::new(),
+            storages: Vec::new(),
+        });
+        self.next_station_number += 1;
+        self.station_to_idx.insert(entity, idx);
+    }
+
+    /// Remove a station by EntityId. Schedules keep the entry; trains skip
+    /// stations that no longer exist. Swap-removes with the last element.
+    pub fn remove_station(&mut self, entity: EntityId) -> bool {
+        let Some(idx) = self.station_to_idx.remove(&entity) else {
+            return false;
+        };
+        let last = self.stations.len() - 1;
+        if idx != last {
+            self.stations.swap(idx, last);
+            let swapped_entity = self.stations[idx].entity;
+            self.station_to_idx.insert(swapped_entity, idx);
+        }
+        self.stations.pop();
+        true
+    }
+
+    /// Get a reference to a station's state.
+    pub fn station(&self, entity: EntityId) -> Option<&StationState> {
+        self.station_to_idx.get(&entity).map(|&i| &self.stations[i])
+    }
+
+    /// All stations, in placement order (modulo removals).
+    pub fn stations(&self) -> &[StationState] {
+        &self.stations
+    }
+
+    /// Replace a station's adjacent rails and storage (after nearby placement
+    /// or removal). Only rails already in the network are kept.
+    pub fn set_station_neighbors(&mut self, entity: EntityId, stops: Vec<EntityId>, storages: Vec<EntityId>) {
+        let Some(&i) = self.station_to_idx.get(&entity) else {
+            return;
+        };
+        let stops = stops.into_iter().filter(|r| self.entity_to_idx.contains_key(r)).collect();
+        let station = &mut self.stations[i];
+        station.stops = stops;
+        station.storages = storages;
+    }
+
+    /// Switch a station between loading and unloading.
+    pub fn set_station_mode(&mut self, entity: EntityId, mode: StationMode) {
+        if let Some(&i) = self.station_to_idx.get(&entity) {
+            self.stations[i].mode = mode;
+        }
+    }
+
+    // --- Trains ---
+
+    /// Put a new locomotive on a rail. Fails if the rail is unknown or
+    /// another locomotive already stands there.
+    pub fn spawn_train(&mut self, rail: EntityId) -> Option<TrainId> {
+        if !self.entity_to_idx.contains_key(&rail) || self.train_at(rail).is_some() {
+            return None;
+        }
+        let id = TrainId(self.next_train_id);
+        self.next_train_id += 1;
+        self.trains.push(Train {
+            id,
+            head: rail,
+            trail: VecDeque::new(),
+            heading: Direction::North,
+            wagons: Vec::new(),
+            schedule: Vec::new(),
+            next_stop: 0,
+            running: false,
+            state: TrainState::Idle,
+        });
+        Some(id)
+    }
+
+    /// Remove a train, returning it so the caller can refund it.
+    pub fn remove_train(&mut self, id: TrainId) -> Option<Train> {
+        let idx = self.trains.iter().position(|t| t.id == id)?;
+        Some(self.trains.remove(idx))
+    }
+
+    /// The train whose locomotive stands on `rail`, if any.
+    pub fn train_at(&self, rail: EntityId) -> Option<TrainId> {
+        self.trains.iter().find(|t| t.head == rail).map(|t| t.id)
+    }
+
+    pub fn train(&self, id: TrainId) -> Option<&Train> {
+        self.trains.iter().find(|t| t.id == id)
+    }
+
+    fn train_mut(&mut self, id: TrainId) -> Option<&mut Train> {
+        self.trains.iter_mut().find(|t| t.id == id)
+    }
+
+    pub fn trains(&self) -> &[Train] {
+        &self.trains
+    }
+
+    /// Couple an empty wagon behind a train. Returns false at `MAX_WAGONS`.
+    pub fn add_wagon(&mut self, id: TrainId) -> bool {
+        match self.train_mut(id) {
+            Some(t) if t.wagons.len() < MAX_WAGONS => {
+                t.wagons.push(Wagon::default());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Append a station to a train's schedule.
+    pub fn push_stop(&mut self, id: TrainId, station: EntityId) {
+        if let Some(t) = self.train_mut(id) {
+            t.schedule.push(station);
+        }
+    }
+
+    /// Remove the schedule entry at `index`.
+    pub fn remove_stop(&mut self, id: TrainId, index: usize) {
+        let Some(t) = self.train_mut(id) else {
+            return;
+        };
+        if index >= t.schedule.len() {
+            return;
+        }
+        t.schedule.remove(index);
+        if t.next_stop > index || t.next_stop >= t.schedule.len() {
+            t.next_stop = t.next_stop.saturating_sub(1).min(t.schedule.len().saturating_sub(1));
+        }
+        if t.schedule.is_empty() {
+            t.state = TrainState::Idle;
+        }
+    }
+
+    /// Start or stop a train following its schedule.
+    pub fn set_running(&mut self, id: TrainId, running: bool) {
+        if let Some(t) = self.train_mut(id) {
+            t.running = running;
+            if !running {
+                t.state = TrainState::Idle;
+            }
+        }
+    }
+
+    // --- Routing ---
+
+    /// Cells on a shortest cell-graph path from `from` to any of `targets`,
+    /// both ends included. `None` if track never connects them.
+    ///
+    /// A* over the cell graph: every track link crosses a single edge, so the
+    /// fewest edge crossings to the nearest target never overestimates the
+    /// hops left. Ties go to the smaller `CellId`, so peers that interned the
+    /// same cells in a different order still pick the same route.
+    pub fn cell_route(&self, from: CellKey, targets: &HashSet<CellKey>) -> Option<Vec<CellKey>> {
+        let estimate = |cell: CellKey| -> usize {
+            if self.rules.is_empty() {
+                return 0;
+            }
+            let cell = self.cells.cell(cell);
+            targets
+                .iter()
+                .map(|&t| cell_id::distance(cell, self.cells.cell(t), &self.rules))
+                .min()
+                .unwrap_or(0)
+        };
+        let mut prev: HashMap<CellKey, CellKey> = HashMap::new();
+        let mut hops: HashMap<CellKey, usize> = HashMap::from([(from, 0)]);
+        let mut closed: HashSet<CellKey> = HashSet::new();
+        let mut open = BinaryHeap::from([Reverse((estimate(from), self.cells.cell(from), from))]);
+        while let Some(Reverse((_, _, cell))) = open.pop() {
+            if !closed.insert(cell) {
+                continue;
+            }
+            if targets.contains(&cell) {
+                let mut path = vec![cell];
+                let mut cur = cell;
+                while let Some(&p) = prev.get(&cur) {
+                    path.push(p);
+                    cur = p;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let Some(neighbors) = self.cell_links.get(&cell) else {
+                continue;
+            };
+            let next_hops = hops[&cell] + 1;
+            for &next in neighbors.keys() {
+                if hops.get(&next).is_some_and(|&h| h <= next_hops) {
+                    continue;
+                }
+                hops.insert(next, next_hops);
+                prev.insert(next, cell);
+                open.push(Reverse((next_hops + estimate(next), self.cells.cell(next), next)));
+            }
+        }
+        None
+    }
+
+    /// Shortest rail path from `from` to any rail in `targets`, excluding
+    /// `from` itself. The search is confined to the cell corridor found by
+    /// `cell_route`, falling back to all track if the corridor's rails turn
+    /// out not to connect inside it.
+    pub fn plan_route(&self, from: EntityId, targets: &[EntityId]) -> Option<Vec<EntityId>> {
+        let start = self.segment(from)?;
+        if targets.contains(&from) {
+            return Some(Vec::new());
+        }
+        let target_cells: HashSet<CellKey> = targets.iter().filter_map(|&t| self.segment(t)).map(|s| s.cell).collect();
+        let corridor: HashSet<CellKey> = self.cell_route(start.cell, &target_cells)?.into_iter().collect();
+        self.rail_route(from, targets, Some(&corridor))
+            .or_else(|| self.rail_route(from, targets, None))
+    }
+
+    fn rail_route(
+        &self,
+        from: EntityId,
+        targets: &[EntityId],
+        corridor: Option<&HashSet<CellKey>>,
+    ) -> Option<Vec<EntityId>> {
+        let mut prev: HashMap<EntityId, EntityId> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(rail) = queue.pop_front() {
+            if targets.contains(&rail) {
+                let mut path = vec![rail];
+                let mut cur = rail;
+                while let Some(&p) = prev.get(&cur) {
+                    if p == from {
+                        break;
+                    }
+                    path.push(p);
+                    cur = p;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.segment(rail)?.links.into_iter().flatten() {
+                if next == from || prev.contains_key(&next) {
+                    continue;
+                }
+                let Some(seg) = self.segment(next) else {
+                    continue;
+                };
+                if corridor.is_some_and(|c| !c.contains(&seg.cell)) {
+                    continue;
+                }
+                prev.insert(next, rail);
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+
+    // --- Simulation ---
+
+    /// Advance every train by one tick: move along routes, dwell and
+    /// transfer cargo at stations, and plan the next leg.
+    pub fn tick(&mut self, storage: &mut StoragePool) {
+        let mut trains = std::mem::take(&mut self.trains);
+        for train in &mut trains {
+            self.step_train(train, storage);
+        }
+        self.trains = trains;
+    }
+
+    fn step_train(&self, train: &mut Train, storage: &mut StoragePool) {
+        if !train.running || train.schedule.is_empty() {
+            train.state = TrainState::Idle;
+            return;
+        }
+        match &mut train.state {
+            TrainState::Idle => self.depart(train),
+            TrainState::NoPath { retry } => {
+                *retry = retry.saturating_sub(1);
+                if *retry == 0 {
+                    self.depart(train);
+                }
+            }
+            TrainState::Travelling { route, step, progress } => {
+                *progress += 1;
+                if *progress < TICKS_PER_RAIL {
+                    return;
+                }
+                *progress = 0;
+                let next = route[*step];
+                let linked = self.segment(train.head).is_some_and(|s| s.links.contains(&Some(next)));
+                if !linked {
+                    // Track changed under the route
+                    self.depart(train);
+                    return;
+                }
+                *step += 1;
+                let arrived = *step == route.len();
+                self.advance(train, next);
+                if arrived {
+                    let station = train.schedule[train.next_stop];
+                    train.state = TrainState::AtStation { station, dwell: STATION_DWELL };
+                }
+            }
+            TrainState::AtStation { station, dwell } => {
+                let station = *station;
+                *dwell = dwell.saturating_sub(1);
+                let done = *dwell == 0;
+                if let Some(s) = self.station(station) {
+                    transfer(s, train, storage);
+                }
+                if done {
+                    train.next_stop = (train.next_stop + 1) % train.schedule.len();
+                    self.depart(train);
+                }
+            }
+        }
+    }
+
+    /// Move the locomotive onto `next`, dragging the wagons behind it.
+    fn advance(&self, train: &mut Train, next: EntityId) {
+        let prev = train.head;
+        if let Some(seg) = self.segment(next) {
+            if let Some(back) = DIRECTIONS.into_iter().find(|&d| seg.links[dir_index(d)] == Some(prev)) {
+                train.heading = back.opposite();
+            }
+        }
+        train.head = next;
+        train.trail.push_front(prev);
+        train.trail.truncate(train.wagons.len());
+    }
+
+    /// Plan a route to the next existing station in the schedule.
+    fn depart(&self, train: &mut Train) {
+        let len = train.schedule.len();
+        for _ in 0..len {
+            let station = train.schedule[train.next_stop];
+            if let Some(s) = self.station(station) {
+                train.state = match self.plan_route(train.head, &s.stops) {
+                    Some(route) if route.is_empty() => TrainState::AtStation { station, dwell: STATION_DWELL },
+                    Some(route) => TrainState::Travelling { route, step: 0, progress: 0 },
+                    None => TrainState::NoPath { retry: REPLAN_INTERVAL },
+                };
+                return;
+            }
+            train.next_stop = (train.next_stop + 1) % len;
+        }
+        train.state = TrainState::NoPath { retry: REPLAN_INTERVAL };
+    }
+}
+
+/// Move up to `STATION_TRANSFER_RATE` items between a stopped train and
+/// the station's storage, in the station's direction.
+fn transfer(station: &StationState, train: &mut Train, storage: &mut StoragePool) {
+    for _ in 0..STATION_TRANSFER_RATE {
+        let moved = match station.mode {
+            StationMode::Load => station.storages.iter().any(|&s| {
+                let Some(wagon) = train.wagons.iter_mut().find(|w| w.total() < WAGON_CAPACITY) else {
+                    return false;
+                };
+                match storage.take_item(s, None) {
+                    Some(item) => wagon.push(item),
+                    None => false,
+                }
+            }),
+            StationMode::Unload => {
+                let Some(wagon) = train.wagons.iter_mut().find(|w| w.total() > 0) else {
+                    return;
+                };
+                let item = wagon.cargo[0].0;
+                let stored = station.storages.iter().any(|&s| storage.accept_input(s, item, 1));
+                if stored {
+                    wagon.pop();
+                }
+                stored
+            }
+        };
+        if !moved {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slotmap::SlotMap;
+
+    fn make_entities(n: usize) -> Vec<EntityId> {
+        let mut sm: SlotMap<EntityId, ()> = SlotMap::with_key();
+        (0..n).map(|_| sm.insert(())).collect()
+    }
+
+    /// A cell named by a one-letter word; the tests only need distinct cells.
+    fn cell(n: u8) -> CellId {
+        CellId::from_canonical(vec![n])
+    }
+
+    /// A straight east-running line of rails, `per_cell` rails to a cell,
+    /// with cells named `cell(0)`, `cell(1)`, ...
+    fn rail_line(net: &mut RailNetwork, rails: &[EntityId], per_cell: usize) {
+        for (i, &r) in rails.iter().enumerate() {
+            net.add_rail(r, &cell((i / per_cell) as u8));
+        }
+        for w in rails.windows(2) {
+            net.link_rails(w[0], w[1], Direction::East, Direction::West);
+        }
+    }
+
+    fn run(net: &mut RailNetwork, storage: &mut StoragePool, ticks: u32) {
+        for _ in 0..ticks {
+            net.tick(storage);
+        }
+    }
+
+    #[test]
+    fn cell_graph_tracks_crossings() {
+        let e = make_entities(6);
+        let mut net = RailNetwork::new();
+        rail_line(&mut net, &e, 2);
+
+        let key = |n| net.cells.key(&cell(n)).unwrap();
+        let targets = HashSet::from([key(2)]);
+        let route = net.cell_route(key(0), &targets).unwrap();
+        assert_eq!(route, vec![key(0), key(1), key(2)]);
+
+        // Cutting the only crossing between cells 1 and 2 splits the graph
+        let from = key(0);
+        net.remove_rail(e[4]);
+        assert!(net.cell_route(from, &targets).is_none());
+    }
+
+    #[test]
+    fn cell_route_with_rules_finds_fewest_crossings() {
+        use crate::hyperbolic::cell_id::canonicalize;
+        use crate::hyperbolic::rewrite::{A, B};
+
+        // Real cells: a straight run east of the origin, and a longer run
+        // west that loops round to the same far cell
+        let rules = crate::hyperbolic::rewrite::rules_45();
+        let walk = |turn: &[u8], steps: usize| -> Vec<CellId> {
+            let mut word = turn.to_vec();
+            (0..steps)
+                .map(|_| {
+                    // Step across, then turn round to face onward again
+                    word.push(A);
+                    let id = canonicalize(&word, &rules).id;
+                    word.extend([B, B]);
+                    id
+                })
+                .collect()
+        };
+        let east = walk(&[], 3);
+        let west = walk(&[B, B], 3);
+        let far = east[2].clone();
+        assert_eq!(cell_id::distance(&CellId::origin(), &far, &rules), 3);
+
+        let mut route = vec![CellId::origin()];
+        route.extend(east.iter().cloned());
+        let mut detour = vec![CellId::origin()];
+        detour.extend(west.iter().cloned());
+        detour.push(far.clone());
+
+        let e = make_entities(route.len() + detour.len());
+        let (main, side) = e.split_at(route.len());
+        for mut net in [RailNetwork::new(), RailNetwork::new().with_rules(rules.clone())] {
+            for (&r, c) in main.iter().zip(&route).chain(side.iter().zip(&detour)) {
+                net.add_rail(r, c);
+            }
+            for w in main.windows(2).chain(side.windows(2)) {
+                net.link_rails(w[0], w[1], Direction::East, Direction::West);
+            }
+            let key = |c: &CellId| net.cells.key(c).unwrap();
+            let found = net.cell_route(key(&CellId::origin()), &HashSet::from([key(&far)])).unwrap();
+            assert_eq!(found, route.iter().map(key).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn plan_route_follows_track() {
+        let e = make_entities(5);
+        let mut net = RailNetwork::new();
+        rail_line(&mut net, &e, 2);
+
+        assert_eq!(net.plan_route(e[0], &[e[4]]), Some(vec![e[1], e[2], e[3], e[4]]));
+        assert_eq!(net.plan_route(e[4], &[e[1]]), Some(vec![e[3], e[2], e[1]]));
+        assert_eq!(net.plan_route(e[2], &[e[2]]), Some(vec![]));
+    }
+
+    #[test]
+    fn plan_route_stays_in_corridor() {
+        // Cell 0 joins cell 1 directly along a long run, and by a short cut
+        // through cell 2. The corridor has the fewest crossings, so the
+        // train takes the long run.
+        let e = make_entities(6);
+        let (start, a1, a2, a3, target, cut) = (e[0], e[1], e[2], e[3], e[4], e[5]);
+        let mut net = RailNetwork::new();
+        net.add_rail(start, &cell(0));
+        for r in [a1, a2, a3, target] {
+            net.add_rail(r, &cell(1));
+        }
+        net.add_rail(cut, &cell(2));
+        net.link_rails(start, a1, Direction::East, Direction::West);
+        net.link_rails(a1, a2, Direction::South, Direction::North);
+        net.link_rails(a2, a3, Direction::South, Direction::North);
+        net.link_rails(a3, target, Direction::West, Direction::East);
+        net.link_rails(start, cut, Direction::South, Direction::North);
+        net.link_rails(cut, target, Direction::South, Direction::North);
+
+        assert_eq!(net.plan_route(start, &[target]), Some(vec![a1, a2, a3, target]));
+
+        // Breaking the run inside the corridor falls back to the short cut
+        net.remove_rail(a2);
+        assert_eq!(net.plan_route(start, &[target]), Some(vec![cut, target]));
+    }
+
+    #[test]
+    fn plan_route_fails_without_track() {
+        let e = make_entities(2);
+        let mut net = RailNetwork::new();
+        net.add_rail(e[0], &cell(0));
+        net.add_rail(e[1], &cell(1));
+        assert!(net.plan_route(e[0], &[e[1]]).is_none());
+    }
+
+    #[test]
+    fn train_shuttles_cargo_between_stations() {
+        let e = make_entities(10);
+        let (rails, rest) = e.split_at(6);
+        let (pickup, dropoff, src, dst) = (rest[0], rest[1], rest[2], rest[3]);
+
+        let mut net = RailNetwork::new();
+        rail_line(&mut net, rails, 3);
+        net.add_station(pickup);
+        net.add_station(dropoff);
+        net.set_station_neighbors(pickup, vec![rails[0]], vec![src]);
+        net.set_station_neighbors(dropoff, vec![rails[5]], vec![dst]);
+        net.set_station_mode(pickup, StationMode::Load);
+
+        let mut storage = StoragePool::new();
+        storage.add(src);
+        storage.add(dst);
+        storage.accept_input(src, ItemId::Cube, 10);
+
+        let id = net.spawn_train(rails[0]).unwrap();
+        assert!(net.add_wagon(id));
+        net.push_stop(id, pickup);
+        net.push_stop(id, dropoff);
+        net.set_running(id, true);
+
+        // Dwell at pickup, then five rails to the drop-off, then dwell again
+        run(&mut net, &mut storage, 1 + STATION_DWELL);
+        assert_eq!(net.train(id).unwrap().cargo_total(), 10);
+        run(&mut net, &mut storage, 5 * TICKS_PER_RAIL + STATION_DWELL);
+
+        let train = net.train(id).unwrap();
+        assert_eq!(train.head, rails[5]);
+        assert_eq!(train.heading, Direction::East);
+        assert_eq!(train.trail, VecDeque::from([rails[4]]));
+        assert_eq!(train.cargo_total(), 0);
+        assert_eq!(storage.get(dst).unwrap().count_of(ItemId::Cube), 10);
+    }
+
+    #[test]
+    fn train_waits_when_unreachable() {
+        let e = make_entities(3);
+        let mut net = RailNetwork::new();
+        net.add_rail(e[0], &cell(0));
+        net.add_rail(e[1], &cell(0));
+        net.add_station(e[2]);
+        net.set_station_neighbors(e[2], vec![e[1]], vec![]);
+
+        let id = net.spawn_train(e[0]).unwrap();
+        net.push_stop(id, e[2]);
+        net.set_running(id, true);
+        let mut storage = StoragePool::new();
+        net.tick(&mut storage);
+        assert_eq!(net.train(id).unwrap().state, TrainState::NoPath { retry: REPLAN_INTERVAL });
+
+        // Laying the missing link lets the next retry find a route
+        net.link_rails(e[0], e[1], Direction::East, Direction::West);
+        run(&mut net, &mut storage, REPLAN_INTERVAL);
+        assert!(matches!(net.train(id).unwrap().state, TrainState::Travelling { .. }));
+    }
+
+    #[test]
+    fn removing_rail_derails_train() {
+        let e = make_entities(2);
+        let mut net = RailNetwork::new();
+        rail_line(&mut net, &e, 2);
+        let id = net.spawn_train(e[1]).unwrap();
+        assert!(net.spawn_train(e[1]).is_none());
+
+        assert!(net.remove_rail(e[0]).is_empty());
+        let derailed = net.remove_rail(e[1]);
+        assert_eq!(derailed.len(), 1);
+        assert_eq!(derailed[0].id, id);
+        assert!(net.trains().is_empty());
+    }
+
+    #[test]
+    fn wagon_capacity_is_shared() {
+        let mut w = Wagon::default();
+        for _ in 0..WAGON_CAPACITY {
+            assert!(w.push(ItemId::Point));
+        }
+        assert!(!w.push(ItemId::Square));
+        assert_eq!(w.pop(), Some(ItemId::Point));
+        assert!(w.push(ItemId::Square));
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::items::ItemId;
+use crate::game::world::EntityId;
+use crate::sim::state_hash::StateHasher;
+
+/// Per-sink state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SinkState {
+    pub entity: EntityId,
+    /// Only destroy this item. `None` destroys anything.
+    pub filter: Option<ItemId>,
+    /// How many of each item this sink has destroyed.
+    pub consumed: HashMap<ItemId, u64>,
+}
+
+impl SinkState {
+    /// Total number of items destroyed, across all types.
+    pub fn total_consumed(&self) -> u64 {
+        self.consumed.values().sum()
+    }
+}
+
+/// Pool of all placed sinks (item voids). Dense storage indexed by EntityId.
+/// The counterpart to the Source machine: anything delivered to a sink's
+/// input port is destroyed and tallied.
+#[derive(Serialize, Deserialize)]
+pub struct SinkPool {
+    sinks: Vec<SinkState>,
+    entity_to_idx: HashMap<EntityId, usize>,
+}
+
+impl SinkPool {
+    pub fn new() -> Self {
+        Self {
+            sinks: Vec::new(),
+            entity_to_idx: HashMap::new(),
+        }
+    }
+
+    /// Feed every sink's filter and tallies into a state hash, in entity order.
+    pub fn hash_state(&self, h: &mut StateHasher) {
+        let mut order: Vec<&SinkState> = self.sinks.iter().collect();
+        order.sort_by_key(|s| s.entity);
+        h.u32(order.len() as u32);
+        for sink in order {
+            h.entity(sink.entity);
+            h.option_item(sink.filter);
+            let mut consumed: Vec<(&ItemId, &u64)> = sink.consumed.iter().collect();
+            consumed.sort();
+            h.u32(consumed.len() as u32);
+            for (&item, &count) in consumed {
+                h.item(item);
+                h.u64(count);
+            }
+        }
+    }
+
+    /// Register a newly placed sink.
+    pub fn add(&mut self, entity: EntityId) {
+        let idx = self.sinks.len();
+        self.sinks.push(SinkState {
+            entity,
+            filter: None,
+            consumed: HashMap::new(),
+        });
+        self.entity_to_idx.insert(entity, idx);
+    }
+
+    /// Remove a sink by EntityId. Swap-removes with the last element.
+    pub fn remove(&mut self, entity: EntityId) -> bool {
+        let Some(idx) = self.entity_to_idx.remove(&entity) else {
+            return false;
+        };
+        let last = self.sinks.len() - 1;
+
+        if idx != last {
+            self.sinks.swap(idx, last);
+            let swapped_entity = self.sinks[idx].entity;
+            self.entity_to_idx.insert(swapped_entity, idx);
+        }
+
+        self.sinks.pop();
+        true
+    }
+
+    /// Get a reference to the sink state for an entity.
+    pub fn get(&self, entity: EntityId) -> Option<&SinkState> {
+        self.entity_to_idx.get(&entity)
+            .map(|&i| &self.sinks[i])
+    }
+
+    /// Destroy an item delivered to the sink's input port.
+    /// Returns false (leaving the item on the belt) if the sink does not
+    /// exist or its filter rejects the item.
+    pub fn accept_input(&mut self, entity: EntityId, item: ItemId) -> bool {
+        let Some(&i) = self.entity_to_idx.get(&entity) else {
+            return false;
+        };
+        let state = &mut self.sinks[i];
+        if state.filter.is_some_and(|f| f != item) {
+            return false;
+        }
+        *state.consumed.entry(item).or_insert(0) += 1;
+        true
+    }
+
+    /// Set the item filter, or clear it with `None`.
+    pub fn set_filter(&mut self, entity: EntityId, filter: Option<ItemId>) {
+        if let Some(&i) = self.entity_to_idx.get(&entity) {
+            self.sinks[i].filter = filter;
+        }
+    }
+
+    /// Reset the consumed tallies.
+    pub fn reset_counts(&mut self, entity: EntityId) {
+        if let Some(&i) = self.entity_to_idx.get(&entity) {
+            self.sinks[i].consumed.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slotmap::SlotMap;
+
+    fn make_entities(n: usize) -> Vec<EntityId> {
+        let mut sm: SlotMap<EntityId, ()> = SlotMap::with_key();
+        (0..n).map(|_| sm.insert(())).collect()
+    }
+
+    #[test]
+    fn accepts_and_counts_anything() {
+        let e = make_entities(1);
+        let mut pool = SinkPool::new();
+        pool.add(e[0]);
+
+        assert!(pool.accept_input(e[0], ItemId::Point));
+        assert!(pool.accept_input(e[0], ItemId::Point));
+        assert!(pool.accept_input(e[0], ItemId::Square));
+
+        let state = pool.get(e[0]).unwrap();
+        assert_eq!(state.consumed[&ItemId::Point], 2);
+        assert_eq!(state.consumed[&ItemId::Square], 1);
+        assert_eq!(state.total_consumed(), 3);
+    }
+
+    #[test]
+    fn filter_rejects_other_items() {
+        let e = make_entities(1);
+        let mut pool = SinkPool::new();
+        pool.add(e[0]);
+        pool.set_filter(e[0], Some(ItemId::Wavelet));
+
+        assert!(!pool.accept_input(e[0], ItemId::Point));
+        assert!(pool.accept_input(e[0], ItemId::Wavelet));
+        assert_eq!(pool.get(e[0]).unwrap().total_consumed(), 1);
+
+        pool.set_filter(e[0], None);
+        assert!(pool.accept_input(e[0], ItemId::Point));
+    }
+
+    #[test]
+    fn reset_counts_clears_tallies() {
+        let e = make_entities(1);
+        let mut pool = SinkPool::new();
+        pool.add(e[0]);
+        pool.accept_input(e[0], ItemId::Point);
+        pool.reset_counts(e[0]);
+        assert_eq!(pool.get(e[0]).unwrap().total_consumed(), 0);
+    }
+
+    #[test]
+    fn remove_swaps_correctly() {
+        let e = make_entities(3);
+        let mut pool = SinkPool::new();
+        for &id in &e {
+            pool.add(id);
+        }
+        pool.accept_input(e[2], ItemId::Cube);
+
+        assert!(pool.remove(e[0]));
+        assert!(pool.get(e[0]).is_none());
+        assert!(!pool.accept_input(e[0], ItemId::Cube));
+        assert_eq!(pool.get(e[2]).unwrap().consumed[&ItemId::Cube], 1);
+        assert!(!pool.remove(e[0]));
+    }
+}
@@ -0,0 +1,86 @@
+//! Cell sleep: cells far from the camera stop ticking and catch up in bulk
+//! when they come back into range.
+//!
+//! A sleeping cell's transport lines and machines are frozen. On waking,
+//! lines compact their items in closed form and machines run whole craft
+//! cycles from their buffers (`BeltNetwork::wake_belts`,
+//! `MachinePool::wake`), so catching up costs O(items + machines) rather
+//! than O(ticks). Belt links crossing into or out of a sleeping cell keep
+//! moving at the flow measured before it slept, so a distant outpost still
+//! feeds (or drains from) the awake cells next to it.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hyperbolic::cell_id::CellId;
+
+/// Cells whose centre lies within this Poincaré-disk radius of the camera
+/// stay awake (the app measures it as a hyperbolic distance, so it holds
+/// however far the camera has flown). Matches the render culling radius, so
+/// every cell on screen ticks.
+pub const ACTIVE_RADIUS: f64 = 0.98;
+
+/// Which cells are asleep, and since when.
+#[derive(Serialize, Deserialize)]
+pub struct CellSleep {
+    /// Simulation ticks run so far.
+    tick: u64,
+    /// Asleep cell → tick at which it fell asleep.
+    asleep: HashMap<CellId, u64>,
+}
+
+impl CellSleep {
+    pub fn new() -> Self {
+        Self {
+            tick: 0,
+            asleep: HashMap::new(),
+        }
+    }
+
+    /// Count one simulation tick.
+    pub fn tick(&mut self) {
+        self.tick += 1;
+    }
+
+    /// Number of cells currently asleep.
+    pub fn asleep_count(&self) -> usize {
+        self.asleep.len()
+    }
+
+    /// Put a cell to sleep. Returns false if it was already asleep.
+    pub fn sleep(&mut self, cell: &CellId) -> bool {
+        if self.asleep.contains_key(cell) {
+            return false;
+        }
+        self.asleep.insert(cell.clone(), self.tick);
+        true
+    }
+
+    /// Wake a cell. Returns the number of ticks it slept, or `None` if it
+    /// was awake.
+    pub fn wake(&mut self, cell: &CellId) -> Option<u64> {
+        self.asleep.remove(cell).map(|since| self.tick - since)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wake_reports_ticks_slept() {
+        let mut cells = CellSleep::new();
+        let cell = CellId::from_canonical(vec![0, 1, 0]);
+        cells.tick();
+        assert!(cells.sleep(&cell));
+        assert!(!cells.sleep(&cell));
+        for _ in 0..50 {
+            cells.tick();
+        }
+        assert_eq!(cells.asleep_count(), 1);
+        assert_eq!(cells.wake(&cell), Some(50));
+        assert_eq!(cells.wake(&cell), None);
+        assert_eq!(cells.asleep_count(), 0);
+    }
+}
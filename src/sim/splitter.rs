@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
+use crate::game::items::ItemId;
 use crate::game::world::{Direction, EntityId, WorldState};
 use crate::sim::belt::BeltNetwork;
+use crate::sim::state_hash::StateHasher;
 
 /// Bit shift for encoding a direction in the connection bitmask (2 bits per side).
 fn side_shift(dir: Direction) -> u8 {
@@ -14,7 +18,7 @@ fn side_shift(dir: Direction) -> u8 {
 }
 
 /// How a splitter behaves, auto-detected from connected belt directions.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SplitterMode {
     /// No inputs or no outputs connected.
     Inactive,
@@ -26,8 +30,19 @@ pub enum SplitterMode {
     Balancer,
 }
 
+/// Per-port configuration for a belt connected to a splitter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitterPortConfig {
+    /// Only this item may pass through the port. `None` accepts anything.
+    /// On an input, non-matching items back up on the belt. On an output,
+    /// matching items are routed here exclusively; unfiltered outputs take the rest.
+    pub filter: Option<ItemId>,
+    /// Priority ports are tried before the round-robin ports.
+    pub priority: bool,
+}
+
 /// Per-splitter state.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SplitterState {
     pub entity: EntityId,
     /// Belt entities feeding items into this splitter (output_end = Splitter).
@@ -38,9 +53,59 @@ pub struct SplitterState {
     pub mode: SplitterMode,
     /// Round-robin index for fair distribution across outputs (or inputs for merger).
     pub round_robin_idx: usize,
+    /// Filter/priority settings keyed by connected belt entity.
+    /// Belts without an entry use the default (no filter, no priority).
+    pub port_config: HashMap<EntityId, SplitterPortConfig>,
+    /// Cleared by a circuit condition; a disabled splitter moves nothing.
+    pub enabled: bool,
+}
+
+impl SplitterState {
+    /// Configuration for a connected belt (default if never configured).
+    pub fn port(&self, belt: EntityId) -> SplitterPortConfig {
+        self.port_config.get(&belt).copied().unwrap_or_default()
+    }
+
+    /// Whether an input belt's filter lets `item` into the splitter.
+    fn input_accepts(&self, belt: EntityId, item: ItemId) -> bool {
+        self.port(belt).filter.is_none_or(|f| f == item)
+    }
+
+    /// Output indices that `item` may be routed to, in the order they should
+    /// be tried: priority outputs first, then the rest, each group rotated to
+    /// begin at `start`. If any output filters for `item`, only those outputs
+    /// are eligible; otherwise only unfiltered outputs are.
+    fn output_order(&self, item: ItemId, start: usize) -> Vec<usize> {
+        let no = self.outputs.len();
+        if no == 0 {
+            return Vec::new();
+        }
+        let has_match = self.outputs.iter().any(|&b| self.port(b).filter == Some(item));
+        let eligible = |idx: usize| {
+            let filter = self.port(self.outputs[idx]).filter;
+            if has_match { filter == Some(item) } else { filter.is_none() }
+        };
+        let rotated = (0..no).map(|k| (start + k) % no).filter(|&i| eligible(i));
+        let (mut order, rest): (Vec<usize>, Vec<usize>) =
+            rotated.partition(|&i| self.port(self.outputs[i]).priority);
+        order.extend(rest);
+        order
+    }
+
+    /// Input indices in the order they should be pulled from:
+    /// priority inputs first, then the rest, rotated to begin at `start`.
+    fn input_order(&self, start: usize) -> Vec<usize> {
+        let ni = self.inputs.len();
+        let (mut order, rest): (Vec<usize>, Vec<usize>) = (0..ni)
+            .map(|k| (start + k) % ni)
+            .partition(|&i| self.port(self.inputs[i]).priority);
+        order.extend(rest);
+        order
+    }
 }
 
 /// Pool of all placed splitters. Dense storage indexed by EntityId.
+#[derive(Serialize, Deserialize)]
 pub struct SplitterPool {
     splitters: Vec<SplitterState>,
     entity_to_idx: HashMap<EntityId, usize>,
@@ -54,6 +119,34 @@ impl SplitterPool {
         }
     }
 
+    /// Feed every splitter's ports and round-robin position into a state
+    /// hash, in entity order.
+    pub fn hash_state(&self, h: &mut StateHasher) {
+        let mut order: Vec<&SplitterState> = self.splitters.iter().collect();
+        order.sort_by_key(|s| s.entity);
+        h.u32(order.len() as u32);
+        for splitter in order {
+            h.entity(splitter.entity);
+            for belts in [&splitter.inputs, &splitter.outputs] {
+                h.u32(belts.len() as u32);
+                for &belt in belts {
+                    h.entity(belt);
+                }
+            }
+            h.u8(splitter.mode as u8);
+            h.u64(splitter.round_robin_idx as u64);
+            let mut ports: Vec<(&EntityId, &SplitterPortConfig)> = splitter.port_config.iter().collect();
+            ports.sort_by_key(|&(&belt, _)| belt);
+            h.u32(ports.len() as u32);
+            for (&belt, config) in ports {
+                h.entity(belt);
+                h.option_item(config.filter);
+                h.bool(config.priority);
+            }
+            h.bool(splitter.enabled);
+        }
+    }
+
     /// Number of active splitters.
     pub fn count(&self) -> usize {
         self.splitters.len()
@@ -68,6 +161,8 @@ impl SplitterPool {
             outputs: Vec::new(),
             mode: SplitterMode::Inactive,
             round_robin_idx: 0,
+            port_config: HashMap::new(),
+            enabled: true,
         });
         self.entity_to_idx.insert(entity, idx);
         idx
@@ -184,35 +279,76 @@ impl SplitterPool {
         if let Some(s) = self.get_mut(splitter) {
             s.inputs.retain(|&e| e != belt);
             s.outputs.retain(|&e| e != belt);
+            s.port_config.remove(&belt);
+        }
+    }
+
+    /// Set the item filter on a connected belt's port. `None` clears it.
+    pub fn set_filter(&mut self, splitter: EntityId, belt: EntityId, filter: Option<ItemId>) {
+        if let Some(s) = self.get_mut(splitter) {
+            if s.inputs.contains(&belt) || s.outputs.contains(&belt) {
+                s.port_config.entry(belt).or_default().filter = filter;
+            }
+        }
+    }
+
+    /// Mark or unmark a connected belt's port as priority.
+    pub fn set_priority(&mut self, splitter: EntityId, belt: EntityId, priority: bool) {
+        if let Some(s) = self.get_mut(splitter) {
+            if s.inputs.contains(&belt) || s.outputs.contains(&belt) {
+                s.port_config.entry(belt).or_default().priority = priority;
+            }
+        }
+    }
+
+    /// Enable or disable a splitter. Returns false if the entity is not a splitter.
+    pub fn set_enabled(&mut self, splitter: EntityId, enabled: bool) -> bool {
+        match self.get_mut(splitter) {
+            Some(s) => {
+                s.enabled = enabled;
+                true
+            }
+            None => false,
         }
     }
 
     /// Run one simulation tick for all splitters.
     /// Transfers items between input and output belts based on each splitter's mode.
+    /// Port filters restrict which items pass; priority ports are served first.
     /// Called each tick after belt advance.
     pub fn tick(&mut self, belt_network: &mut BeltNetwork) {
-        for splitter in &mut self.splitters {
+        for splitter in self.splitters.iter_mut().filter(|s| s.enabled) {
             match splitter.mode {
                 SplitterMode::Inactive => {}
                 SplitterMode::Merger => {
-                    // Round-robin pull from input lines, push to single output.
+                    // Priority inputs first, then round-robin pull from the rest.
                     let ni = splitter.inputs.len();
                     if ni == 0 || splitter.outputs.is_empty() {
                         continue;
                     }
-                    let output_belt = splitter.outputs[0];
-                    if !belt_network.can_accept_at_entity_input(output_belt) {
-                        continue;
-                    }
                     let start = splitter.round_robin_idx % ni;
-                    for attempt in 0..ni {
-                        let idx = (start + attempt) % ni;
+                    for idx in splitter.input_order(start) {
                         let input_belt = splitter.inputs[idx];
-                        if let Some(item) = belt_network.take_front_item(input_belt) {
+                        let item = match belt_network.peek_front_item(input_belt) {
+                            Some(it) => it,
+                            None => continue,
+                        };
+                        if !splitter.input_accepts(input_belt, item) {
+                            continue;
+                        }
+                        let target = splitter.output_order(item, 0)
+                            .into_iter()
+                            .map(|o| splitter.outputs[o])
+                            .find(|&b| belt_network.can_accept_at_entity_input(b));
+                        if let Some(output_belt) = target {
+                            belt_network.take_front_item(input_belt);
                             belt_network.push_to_entity_input(output_belt, item);
                             // Advance based on intended input, not fallback,
-                            // so each input gets fair priority.
-                            splitter.round_robin_idx = start + 1;
+                            // so each input gets fair priority. Priority inputs
+                            // don't consume a round-robin turn.
+                            if !splitter.port(input_belt).priority {
+                                splitter.round_robin_idx = start + 1;
+                            }
                             break;
                         }
                     }
@@ -228,9 +364,11 @@ impl SplitterPool {
                         Some(it) => it,
                         None => continue,
                     };
+                    if !splitter.input_accepts(input_belt, item) {
+                        continue;
+                    }
                     let start = splitter.round_robin_idx % no;
-                    for attempt in 0..no {
-                        let idx = (start + attempt) % no;
+                    for idx in splitter.output_order(item, start) {
                         let output_belt = splitter.outputs[idx];
                         if belt_network.can_accept_at_entity_input(output_belt) {
                             belt_network.take_front_item(input_belt);
@@ -255,25 +393,19 @@ impl SplitterPool {
                             Some(it) => it,
                             None => continue,
                         };
-                        // Primary: corresponding output
-                        let primary = i % no;
-                        if belt_network.can_accept_at_entity_input(splitter.outputs[primary]) {
-                            belt_network.take_front_item(input_belt);
-                            belt_network.push_to_entity_input(splitter.outputs[primary], item);
+                        if !splitter.input_accepts(input_belt, item) {
                             continue;
                         }
-                        // Overflow: try other outputs
-                        let mut transferred = false;
-                        for overflow in 1..no {
-                            let alt = (primary + overflow) % no;
-                            if belt_network.can_accept_at_entity_input(splitter.outputs[alt]) {
+                        // Primary: corresponding output. Overflow: the others in order.
+                        let primary = i % no;
+                        for idx in splitter.output_order(item, primary) {
+                            let output_belt = splitter.outputs[idx];
+                            if belt_network.can_accept_at_entity_input(output_belt) {
                                 belt_network.take_front_item(input_belt);
-                                belt_network.push_to_entity_input(splitter.outputs[alt], item);
-                                transferred = true;
+                                belt_network.push_to_entity_input(output_belt, item);
                                 break;
                             }
                         }
-                        let _ = transferred;
                     }
                 }
             }
@@ -284,6 +416,7 @@ impl SplitterPool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hyperbolic::cell_id::CellId;
     use slotmap::SlotMap;
     use crate::game::items::ItemId;
     use crate::game::world::{Direction, WorldState};
@@ -296,7 +429,7 @@ mod tests {
     }
 
     /// Place a belt entity and register it with the belt network.
-    fn place_belt(world: &mut WorldState, net: &mut BeltNetwork, addr: &[u8], gx: i32, gy: i32, dir: Direction) -> EntityId {
+    fn place_belt(world: &mut WorldState, net: &mut BeltNetwork, addr: &CellId, gx: i32, gy: i32, dir: Direction) -> EntityId {
         let entity = world.place(addr, (gx, gy), ItemId::Belt, dir).unwrap();
         net.on_belt_placed(entity, addr, gx, gy, dir, world);
         entity
@@ -318,7 +451,7 @@ mod tests {
     /// Set up a merger: 2 input belts → splitter → 1 output belt.
     /// Returns (pool, input_belt_1, input_belt_2, output_belt, splitter_entity).
     fn setup_merger(world: &mut WorldState, net: &mut BeltNetwork) -> (SplitterPool, EntityId, EntityId, EntityId, EntityId) {
-        let addr: &[u8] = &[0];
+        let addr = &CellId::from_canonical(vec![0]);
         // Splitter at (5, 5). Input belts point toward it, output belt points away.
         let splitter_entity = world.place(addr, (5, 5), ItemId::Splitter, Direction::North).unwrap();
 
@@ -348,7 +481,7 @@ mod tests {
 
     /// Set up a splitter: 1 input belt → splitter → 2 output belts.
     fn setup_splitter(world: &mut WorldState, net: &mut BeltNetwork) -> (SplitterPool, EntityId, EntityId, EntityId, EntityId) {
-        let addr: &[u8] = &[0];
+        let addr = &CellId::from_canonical(vec![0]);
         let splitter_entity = world.place(addr, (5, 5), ItemId::Splitter, Direction::North).unwrap();
 
         // Input belt: going East at (4, 5)
@@ -376,7 +509,7 @@ mod tests {
 
     /// Set up a balancer: 2 input belts → splitter → 2 output belts.
     fn setup_balancer(world: &mut WorldState, net: &mut BeltNetwork) -> (SplitterPool, EntityId, EntityId, EntityId, EntityId, EntityId) {
-        let addr: &[u8] = &[0];
+        let addr = &CellId::from_canonical(vec![0]);
         let splitter_entity = world.place(addr, (5, 5), ItemId::Splitter, Direction::North).unwrap();
 
         // Input belts
@@ -574,7 +707,7 @@ mod tests {
     fn merger_alternates_inputs() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let (mut pool, in1, in2, out1, _) = setup_merger(&mut world, &mut net);
+        let (mut pool, in1, in2, _out1, _) = setup_merger(&mut world, &mut net);
 
         // Place items on both input belts
         net.spawn_item_on_entity(in1, ItemId::NullSet);
@@ -749,7 +882,7 @@ mod tests {
     fn inactive_splitter_does_nothing() {
         let mut world = WorldState::new();
         let mut net = BeltNetwork::new();
-        let addr: &[u8] = &[0];
+        let addr = &CellId::from_canonical(vec![0]);
         let splitter_entity = world.place(addr, (5, 5), ItemId::Splitter, Direction::North).unwrap();
 
         // Input belt only, no output
@@ -769,4 +902,136 @@ mod tests {
         pool.tick(&mut net);
         assert_eq!(item_count(&net, in1), 1, "item should remain — splitter is inactive");
     }
+
+    // ── Filter / priority tests ─────────────────────────────────────────
+
+    #[test]
+    fn set_filter_ignores_unconnected_belt() {
+        let mut pool = SplitterPool::new();
+        let (mut sm, e1) = make_entity();
+        pool.add(e1);
+        let b1 = sm.insert(());
+
+        pool.set_filter(e1, b1, Some(ItemId::Point));
+        assert!(pool.get(e1).unwrap().port_config.is_empty());
+    }
+
+    #[test]
+    fn disconnect_belt_clears_port_config() {
+        let mut pool = SplitterPool::new();
+        let (mut sm, e1) = make_entity();
+        pool.add(e1);
+        let b1 = sm.insert(());
+        pool.add_output(e1, b1);
+
+        pool.set_filter(e1, b1, Some(ItemId::Point));
+        pool.set_priority(e1, b1, true);
+        assert_eq!(
+            pool.get(e1).unwrap().port(b1),
+            SplitterPortConfig { filter: Some(ItemId::Point), priority: true },
+        );
+
+        pool.disconnect_belt(e1, b1);
+        assert_eq!(pool.get(e1).unwrap().port(b1), SplitterPortConfig::default());
+    }
+
+    #[test]
+    fn filtered_output_receives_matching_items() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let (mut pool, in1, out1, out2, s) = setup_splitter(&mut world, &mut net);
+        pool.set_filter(s, out2, Some(ItemId::Point));
+
+        // Round-robin would send the first item to out1; the filter overrides it.
+        net.spawn_item_on_entity(in1, ItemId::Point);
+        advance_to_output(&mut net, in1);
+        pool.tick(&mut net);
+        net.tick();
+        assert_eq!(item_count(&net, out1), 0);
+        assert_eq!(item_count(&net, out2), 1, "Point should take the filtered output");
+    }
+
+    #[test]
+    fn non_matching_items_skip_filtered_output() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let (mut pool, in1, out1, out2, s) = setup_splitter(&mut world, &mut net);
+        pool.set_filter(s, out1, Some(ItemId::Point));
+
+        for _ in 0..2 {
+            net.spawn_item_on_entity(in1, ItemId::NullSet);
+            advance_to_output(&mut net, in1);
+            pool.tick(&mut net);
+        }
+        net.tick();
+        assert_eq!(item_count(&net, out1), 0, "filtered output should not get NullSet");
+        assert_eq!(item_count(&net, out2), 2);
+        assert_eq!(item_count(&net, in1), 0);
+    }
+
+    #[test]
+    fn filtered_output_full_backs_up_matching_items() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let (mut pool, in1, out1, _out2, s) = setup_splitter(&mut world, &mut net);
+        pool.set_filter(s, out1, Some(ItemId::Point));
+
+        for _ in 0..4 {
+            net.spawn_item_on_entity(out1, ItemId::Point);
+        }
+        net.push_to_entity_input(out1, ItemId::Point);
+
+        net.spawn_item_on_entity(in1, ItemId::Point);
+        advance_to_output(&mut net, in1);
+        pool.tick(&mut net);
+        assert_eq!(item_count(&net, in1), 1, "Point must wait for its filtered output");
+    }
+
+    #[test]
+    fn input_filter_blocks_non_matching_items() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let (mut pool, in1, _out1, _out2, s) = setup_splitter(&mut world, &mut net);
+        pool.set_filter(s, in1, Some(ItemId::Point));
+
+        net.spawn_item_on_entity(in1, ItemId::NullSet);
+        advance_to_output(&mut net, in1);
+        pool.tick(&mut net);
+        assert_eq!(item_count(&net, in1), 1, "NullSet should back up on the filtered input");
+    }
+
+    #[test]
+    fn merger_priority_input_served_first() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let (mut pool, in1, in2, _out1, s) = setup_merger(&mut world, &mut net);
+        pool.set_priority(s, in2, true);
+
+        net.spawn_item_on_entity(in1, ItemId::NullSet);
+        net.spawn_item_on_entity(in2, ItemId::Point);
+        advance_to_output(&mut net, in1);
+
+        // Round-robin would start with in1; priority makes in2 go first.
+        pool.tick(&mut net);
+        assert_eq!(item_count(&net, in2), 0);
+        assert_eq!(item_count(&net, in1), 1);
+    }
+
+    #[test]
+    fn splitter_priority_output_preferred() {
+        let mut world = WorldState::new();
+        let mut net = BeltNetwork::new();
+        let (mut pool, in1, out1, out2, s) = setup_splitter(&mut world, &mut net);
+        pool.set_priority(s, out2, true);
+
+        for _ in 0..2 {
+            net.spawn_item_on_entity(in1, ItemId::NullSet);
+            advance_to_output(&mut net, in1);
+            pool.tick(&mut net);
+            for _ in 0..20 {
+                net.tick();
+            }
+        }
+        assert_eq!(item_count(&net, out1), 0, "priority output had room both times");
+    }
 }
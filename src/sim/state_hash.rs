@@ -0,0 +1,395 @@
+//! Deterministic hash over the simulation state.
+//!
+//! Each pool feeds its state into a `StateHasher` in a fixed order — by
+//! entity id, never `HashMap` iteration order — and the hasher itself is a
+//! fixed algorithm over little-endian bytes, so the same state hashes to the
+//! same value on every run and platform. Used for desync detection and for
+//! regression tests that pin a scenario to a known hash.
+
+use slotmap::Key;
+
+use crate::game::items::{FluidId, ItemId};
+use crate::game::world::EntityId;
+use crate::sim::belt::BeltNetwork;
+use crate::sim::circuit::CircuitNetwork;
+use crate::sim::fluid::{FluidNetwork, FluidTank};
+use crate::sim::loader::LoaderPool;
+use crate::sim::machine::{ItemStack, MachinePool};
+use crate::sim::power::PowerNetwork;
+use crate::sim::rail::RailNetwork;
+use crate::sim::sink::SinkPool;
+use crate::sim::splitter::SplitterPool;
+use crate::sim::storage::StoragePool;
+
+/// Ticks between state hashes in the running game (ten seconds at 60 UPS).
+pub const STATE_HASH_INTERVAL: u64 = 600;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64-bit FNV-1a over the bytes written to it.
+pub struct StateHasher {
+    state: u64,
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self { state: FNV_OFFSET }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.state ^= b as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn u8(&mut self, v: u8) {
+        self.bytes(&[v]);
+    }
+
+    pub fn u16(&mut self, v: u16) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, v: u32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, v: u64) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn i32(&mut self, v: i32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn bool(&mut self, v: bool) {
+        self.u8(v as u8);
+    }
+
+    /// Floats hash by bit pattern, so -0.0 and 0.0 differ.
+    pub fn f32(&mut self, v: f32) {
+        self.u32(v.to_bits());
+    }
+
+    /// Length-prefixed, so adjacent strings can't run together.
+    pub fn str(&mut self, s: &str) {
+        self.u32(s.len() as u32);
+        self.bytes(s.as_bytes());
+    }
+
+    pub fn entity(&mut self, entity: EntityId) {
+        self.u64(entity.data().as_ffi());
+    }
+
+    pub fn option_entity(&mut self, entity: Option<EntityId>) {
+        match entity {
+            Some(entity) => {
+                self.u8(1);
+                self.entity(entity);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    pub fn item(&mut self, item: ItemId) {
+        self.u16(item as u16);
+    }
+
+    pub fn option_item(&mut self, item: Option<ItemId>) {
+        match item {
+            Some(item) => {
+                self.u8(1);
+                self.item(item);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    /// An empty stack hashes the same whatever item it last held.
+    pub fn stack(&mut self, stack: &ItemStack) {
+        self.u16(stack.count);
+        if stack.count > 0 {
+            self.item(stack.item);
+        }
+    }
+
+    pub fn tank(&mut self, tank: &FluidTank) {
+        self.u8(tank.fluid.map_or(0, |f: FluidId| f as u8 + 1));
+        self.u32(tank.amount);
+    }
+}
+
+/// Hash the state of every simulated pool.
+#[allow(clippy::too_many_arguments)]
+pub fn state_hash(
+    belts: &BeltNetwork,
+    machines: &MachinePool,
+    storage: &StoragePool,
+    splitters: &SplitterPool,
+    sinks: &SinkPool,
+    power: &PowerNetwork,
+    fluids: &FluidNetwork,
+    rails: &RailNetwork,
+    circuits: &CircuitNetwork,
+    loaders: &LoaderPool,
+) -> u64 {
+    let mut h = StateHasher::new();
+    belts.hash_state(&mut h);
+    machines.hash_state(&mut h);
+    storage.hash_state(&mut h);
+    splitters.hash_state(&mut h);
+    sinks.hash_state(&mut h);
+    power.hash_state(&mut h);
+    fluids.hash_state(&mut h);
+    rails.hash_state(&mut h);
+    circuits.hash_state(&mut h);
+    loaders.hash_state(&mut h);
+    h.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperbolic::cell_id::CellId;
+    use crate::game::world::{Direction, WorldState};
+    use crate::game::items::MachineType;
+    use crate::sim::circuit::{Comparator, Condition, Operand};
+    use crate::sim::loader::LoaderMode;
+    use crate::sim::power::{PowerNodeKind, DYNAMO_RATE, MACHINE_CONSUMPTION};
+    use crate::game::recipes::RecipeIndex;
+
+    /// Every simulated pool, wired into a small factory.
+    struct Scenario {
+        _world: WorldState,
+        recipes: RecipeIndex,
+        belts: BeltNetwork,
+        machines: MachinePool,
+        storage: StoragePool,
+        splitters: SplitterPool,
+        sinks: SinkPool,
+        power: PowerNetwork,
+        fluids: FluidNetwork,
+        rails: RailNetwork,
+        circuits: CircuitNetwork,
+        loaders: LoaderPool,
+        chest: EntityId,
+        void: EntityId,
+        composer: EntityId,
+        pipes: [EntityId; 2],
+        track: [EntityId; 2],
+        loader: EntityId,
+    }
+
+    impl Scenario {
+        /// A Source feeds a belt into a splitter that fans out to storage and
+        /// a sink; a second Source feeds a Composer whose output runs onto an
+        /// open belt. A dynamo powers both machines. Two pipes, two rails and
+        /// a loader stand unconnected.
+        fn build() -> Self {
+            let addr = &CellId::from_canonical(vec![0]);
+            let mut world = WorldState::new();
+            let recipes = RecipeIndex::new();
+            let mut belts = BeltNetwork::new();
+            let mut machines = MachinePool::new();
+            let mut storage = StoragePool::new();
+            let mut splitters = SplitterPool::new();
+            let mut sinks = SinkPool::new();
+            let mut power = PowerNetwork::new();
+            let mut fluids = FluidNetwork::new();
+            let mut rails = RailNetwork::new();
+            let mut loaders = LoaderPool::new();
+
+            let belt = |world: &mut WorldState, belts: &mut BeltNetwork, gx, gy, dir| {
+                let e = world.place(addr, (gx, gy), ItemId::Belt, dir).unwrap();
+                belts.on_belt_placed(e, addr, gx, gy, dir, world);
+                e
+            };
+            let source_recipe = |output: ItemId| {
+                recipes
+                    .recipes_for_machine(MachineType::Source)
+                    .iter()
+                    .find(|(_, r)| r.output == output)
+                    .unwrap()
+                    .0
+            };
+
+            // Source → belt → splitter → storage / sink
+            let source = world.place(addr, (0, 10), ItemId::SourceMachine, Direction::North).unwrap();
+            machines.add(source, MachineType::Source);
+            machines.set_recipe(source, Some(source_recipe(ItemId::NullSet)));
+            let feed = belt(&mut world, &mut belts, 0, 0, Direction::East);
+            belt(&mut world, &mut belts, 1, 0, Direction::East);
+            let into_splitter = belt(&mut world, &mut belts, 2, 0, Direction::East);
+            belts.connect_machine_output_to_belt(feed, source, 0);
+
+            let splitter = world.place(addr, (3, 0), ItemId::Splitter, Direction::North).unwrap();
+            let to_storage = belt(&mut world, &mut belts, 4, 0, Direction::East);
+            let to_sink = belt(&mut world, &mut belts, 3, 1, Direction::South);
+            belts.connect_belt_to_splitter(into_splitter, splitter);
+            belts.connect_splitter_to_belt(to_storage, splitter);
+            belts.connect_splitter_to_belt(to_sink, splitter);
+            splitters.add(splitter);
+            splitters.add_input(splitter, into_splitter);
+            splitters.add_output(splitter, to_storage);
+            splitters.add_output(splitter, to_sink);
+            splitters.detect_mode(splitter);
+
+            let chest = world.place(addr, (5, 0), ItemId::Storage, Direction::North).unwrap();
+            storage.add(chest);
+            belts.connect_belt_to_storage_input(to_storage, chest, 0);
+            let void = world.place(addr, (3, 2), ItemId::Void, Direction::North).unwrap();
+            sinks.add(void);
+            belts.connect_belt_to_sink_input(to_sink, void);
+
+            // Source → belt → Composer → open belt
+            let points = world.place(addr, (10, 10), ItemId::SourceMachine, Direction::North).unwrap();
+            machines.add(points, MachineType::Source);
+            machines.set_recipe(points, Some(source_recipe(ItemId::Point)));
+            let composer = world.place(addr, (20, 10), ItemId::Composer, Direction::North).unwrap();
+            machines.add(composer, MachineType::Composer);
+            machines.set_recipe(composer, Some(0));
+            let point_belt = belt(&mut world, &mut belts, 0, 5, Direction::East);
+            belts.connect_machine_output_to_belt(point_belt, points, 0);
+            belts.connect_belt_to_machine_input(point_belt, composer, 0);
+            let product_belt = belt(&mut world, &mut belts, 0, 7, Direction::East);
+            belt(&mut world, &mut belts, 1, 7, Direction::East);
+            belts.connect_machine_output_to_belt(product_belt, composer, 0);
+
+            let dynamo = world.place(addr, (30, 10), ItemId::Dynamo, Direction::North).unwrap();
+            power.add(dynamo, PowerNodeKind::Producer, DYNAMO_RATE, addr, 4, 4, false);
+            for (i, &m) in [source, points, composer].iter().enumerate() {
+                power.add(m, PowerNodeKind::Consumer, MACHINE_CONSUMPTION, addr, 5, 3 + i as i16, false);
+            }
+
+            let pipes = [0, 1].map(|gx| world.place(addr, (gx, 20), ItemId::Pipe, Direction::North).unwrap());
+            for pipe in pipes {
+                fluids.add_pipe(pipe);
+            }
+            let track = [0, 1].map(|gx| world.place(addr, (gx, 25), ItemId::Rail, Direction::North).unwrap());
+            for rail in track {
+                rails.add_rail(rail, addr);
+            }
+            let loader = world.place(addr, (10, 25), ItemId::Loader, Direction::North).unwrap();
+            loaders.add(loader);
+
+            Self {
+                _world: world,
+                recipes,
+                belts,
+                machines,
+                storage,
+                splitters,
+                sinks,
+                power,
+                fluids,
+                rails,
+                circuits: CircuitNetwork::new(),
+                loaders,
+                chest,
+                void,
+                composer,
+                pipes,
+                track,
+                loader,
+            }
+        }
+
+        /// One tick in the same phase order as the running game.
+        fn tick(&mut self) {
+            self.power.solve();
+            for i in 0..self.machines.count {
+                let entity = self.machines.cold.entity_id[i];
+                if let Some(sat) = self.power.satisfaction(entity) {
+                    self.machines.set_power_satisfaction(entity, sat);
+                }
+            }
+            self.machines.tick(&self.recipes);
+            self.belts.tick();
+            self.splitters.tick(&mut self.belts);
+            self.belts.tick_port_transfers(&mut self.machines, &mut self.storage, &mut self.sinks);
+        }
+
+        fn hash(&self) -> u64 {
+            state_hash(
+                &self.belts,
+                &self.machines,
+                &self.storage,
+                &self.splitters,
+                &self.sinks,
+                &self.power,
+                &self.fluids,
+                &self.rails,
+                &self.circuits,
+                &self.loaders,
+            )
+        }
+    }
+
+    #[test]
+    fn hasher_is_fnv1a() {
+        let mut h = StateHasher::new();
+        assert_eq!(h.finish(), FNV_OFFSET);
+        h.bytes(b"a");
+        assert_eq!(h.finish(), 0xaf63_dc4c_8601_ec8c);
+        let mut h = StateHasher::new();
+        h.bytes(b"foobar");
+        assert_eq!(h.finish(), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn identical_runs_hash_equal_and_diverge_on_change() {
+        let mut a = Scenario::build();
+        let mut b = Scenario::build();
+        assert_eq!(a.hash(), b.hash());
+        for _ in 0..500 {
+            a.tick();
+            b.tick();
+        }
+        assert_eq!(a.hash(), b.hash());
+
+        let before = b.hash();
+        let chest = b.chest;
+        b.storage.accept_input(chest, ItemId::Square, 1);
+        assert_ne!(b.hash(), before);
+        assert_ne!(a.hash(), b.hash());
+    }
+
+    #[test]
+    fn fluid_rail_circuit_and_loader_state_alone_alter_hash() {
+        let base = Scenario::build().hash();
+        let changed = |change: fn(&mut Scenario)| {
+            let mut s = Scenario::build();
+            change(&mut s);
+            s.hash()
+        };
+        assert_ne!(changed(|s| assert!(s.fluids.link_pipes(s.pipes[0], s.pipes[1], Direction::East, Direction::West))), base);
+        assert_ne!(changed(|s| assert!(s.rails.link_rails(s.track[0], s.track[1], Direction::East, Direction::West))), base);
+        assert_ne!(
+            changed(|s| {
+                let condition = Condition { left: ItemId::Point, cmp: Comparator::Less, right: Operand::Constant(5) };
+                s.circuits.set_condition(s.composer, Some(condition));
+            }),
+            base
+        );
+        assert_ne!(changed(|s| s.loaders.set_mode(s.loader, LoaderMode::Unload)), base);
+    }
+
+    #[test]
+    fn scenario_hash_after_ten_thousand_ticks() {
+        let mut s = Scenario::build();
+        for _ in 0..10_000 {
+            s.tick();
+        }
+        // The factory really ran: both branches of the splitter delivered.
+        assert!(s.sinks.get(s.void).unwrap().total_consumed() > 0);
+        assert!(s.storage.fill_fraction(s.chest) > 0.0);
+        // Pinned: if a change alters simulation results, this value moves.
+        assert_eq!(s.hash(), 0x963b_ddcc_04f5_01cb);
+    }
+}
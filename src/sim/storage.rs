@@ -1,22 +1,61 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
 use crate::game::items::ItemId;
 use crate::game::world::EntityId;
 use crate::sim::machine::ItemStack;
+use crate::sim::state_hash::StateHasher;
 
 /// Maximum number of storage slots per building.
 pub const STORAGE_SLOTS: usize = 20;
 /// Maximum items per stack in a storage slot.
 pub const STORAGE_STACK_SIZE: u16 = 50;
+/// Number of output ports on a storage building (see `storage_port_layout`).
+pub const STORAGE_OUTPUT_PORTS: usize = 2;
+
+/// How a storage output port decides what to hand out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageOutputMode {
+    /// Only emit this item. `None` emits whatever is stored, first slot first.
+    pub filter: Option<ItemId>,
+    /// Hold back the last `keep` items of each type: an item is only emitted
+    /// while more than `keep` of it are stored.
+    pub keep: u16,
+}
 
 /// Per-storage state.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StorageState {
     pub entity: EntityId,
     pub slots: [ItemStack; STORAGE_SLOTS],
+    /// Per-slot item locks. A locked slot only ever holds its item, and stays
+    /// reserved for it while empty.
+    pub slot_locks: [Option<ItemId>; STORAGE_SLOTS],
+    /// Items this building accepts from input ports. Empty accepts anything.
+    pub whitelist: Vec<ItemId>,
+    /// Behaviour of each output port, indexed by port slot.
+    pub output_modes: [StorageOutputMode; STORAGE_OUTPUT_PORTS],
+}
+
+impl StorageState {
+    /// Total count of `item` across all slots.
+    pub fn count_of(&self, item: ItemId) -> u32 {
+        self.slots
+            .iter()
+            .filter(|s| s.item == item && s.count > 0)
+            .map(|s| s.count as u32)
+            .sum()
+    }
+
+    /// Whether the building-wide whitelist admits `item`.
+    pub fn accepts(&self, item: ItemId) -> bool {
+        self.whitelist.is_empty() || self.whitelist.contains(&item)
+    }
 }
 
 /// Pool of all placed storage buildings. Dense storage indexed by EntityId.
+#[derive(Serialize, Deserialize)]
 pub struct StoragePool {
     storages: Vec<StorageState>,
     entity_to_idx: HashMap<EntityId, usize>,
@@ -30,12 +69,40 @@ impl StoragePool {
         }
     }
 
+    /// Feed every storage's contents and settings into a state hash, in
+    /// entity order.
+    pub fn hash_state(&self, h: &mut StateHasher) {
+        let mut order: Vec<&StorageState> = self.storages.iter().collect();
+        order.sort_by_key(|s| s.entity);
+        h.u32(order.len() as u32);
+        for storage in order {
+            h.entity(storage.entity);
+            for stack in &storage.slots {
+                h.stack(stack);
+            }
+            for &lock in &storage.slot_locks {
+                h.option_item(lock);
+            }
+            h.u32(storage.whitelist.len() as u32);
+            for &item in &storage.whitelist {
+                h.item(item);
+            }
+            for mode in &storage.output_modes {
+                h.option_item(mode.filter);
+                h.u16(mode.keep);
+            }
+        }
+    }
+
     /// Register a newly placed storage building.
     pub fn add(&mut self, entity: EntityId) {
         let idx = self.storages.len();
         self.storages.push(StorageState {
             entity,
             slots: [ItemStack { item: ItemId::NullSet, count: 0 }; STORAGE_SLOTS],
+            slot_locks: [None; STORAGE_SLOTS],
+            whitelist: Vec::new(),
+            output_modes: [StorageOutputMode::default(); STORAGE_OUTPUT_PORTS],
         });
         self.entity_to_idx.insert(entity, idx);
     }
@@ -70,32 +137,70 @@ impl StoragePool {
     }
 
     /// Try to store an item in the storage building.
-    /// Finds the first slot with the matching item that has room, or the first empty slot.
-    /// Returns true if the item was accepted, false if all 20 slots are full.
+    /// Finds the first slot with the matching item that has room, then the first
+    /// empty slot locked to this item, then the first empty unlocked slot.
+    /// Returns true if the item was accepted, false if it is not whitelisted or
+    /// no permitted slot has room.
     pub fn accept_input(&mut self, entity: EntityId, item: ItemId, count: u16) -> bool {
         let state = match self.get_mut(entity) {
             Some(s) => s,
             None => return false,
         };
+        if !state.accepts(item) {
+            return false;
+        }
+        let locks = state.slot_locks;
 
         // First pass: try to stack into an existing slot with the same item
-        for slot in state.slots.iter_mut() {
-            if slot.item == item && slot.count > 0 && slot.count + count <= STORAGE_STACK_SIZE {
+        for (slot, lock) in state.slots.iter_mut().zip(locks) {
+            if slot.item == item
+                && slot.count > 0
+                && slot.count + count <= STORAGE_STACK_SIZE
+                && lock.is_none_or(|l| l == item)
+            {
                 slot.count += count;
                 return true;
             }
         }
 
-        // Second pass: try to place into an empty slot
-        for slot in state.slots.iter_mut() {
-            if slot.count == 0 {
-                slot.item = item;
-                slot.count = count;
-                return true;
+        // Second pass: empty slots reserved for this item, then unlocked ones
+        for wanted in [Some(item), None] {
+            for (slot, lock) in state.slots.iter_mut().zip(locks) {
+                if slot.count == 0 && lock == wanted {
+                    slot.item = item;
+                    slot.count = count;
+                    return true;
+                }
             }
         }
 
-        false // all slots full or occupied by different items at max stack
+        false // all permitted slots full or occupied by different items at max stack
+    }
+
+    /// Lock a slot to an item, or unlock it with `None`.
+    /// Items already in the slot stay until drained.
+    pub fn set_slot_lock(&mut self, entity: EntityId, slot: usize, lock: Option<ItemId>) {
+        if let Some(state) = self.get_mut(entity) {
+            if slot < STORAGE_SLOTS {
+                state.slot_locks[slot] = lock;
+            }
+        }
+    }
+
+    /// Replace the building-wide input whitelist. An empty list accepts anything.
+    pub fn set_whitelist(&mut self, entity: EntityId, whitelist: Vec<ItemId>) {
+        if let Some(state) = self.get_mut(entity) {
+            state.whitelist = whitelist;
+        }
+    }
+
+    /// Configure how an output port selects items.
+    pub fn set_output_mode(&mut self, entity: EntityId, port: usize, mode: StorageOutputMode) {
+        if let Some(state) = self.get_mut(entity) {
+            if port < STORAGE_OUTPUT_PORTS {
+                state.output_modes[port] = mode;
+            }
+        }
     }
 
     /// Returns the fill fraction (0.0 = empty, 1.0 = full) for a storage entity.
@@ -108,21 +213,35 @@ impl StoragePool {
         total as f32 / max as f32
     }
 
-    /// Try to take one item from the storage for output.
-    /// Scans slots sequentially and takes from the first non-empty stack.
-    /// Returns the ItemId taken, or None if the storage is empty.
-    pub fn provide_output(&mut self, entity: EntityId) -> Option<ItemId> {
+    /// Try to take one item from the storage for the given output port.
+    /// Scans slots sequentially and takes from the first non-empty stack that
+    /// the port's `StorageOutputMode` allows.
+    /// Returns the ItemId taken, or None if nothing may be emitted.
+    pub fn provide_output(&mut self, entity: EntityId, port: usize) -> Option<ItemId> {
         let state = self.get_mut(entity)?;
+        let mode = state.output_modes.get(port).copied().unwrap_or_default();
+
+        let idx = (0..STORAGE_SLOTS).find(|&i| {
+            let slot = &state.slots[i];
+            slot.count > 0
+                && mode.filter.is_none_or(|f| f == slot.item)
+                && state.count_of(slot.item) > mode.keep as u32
+        })?;
+        let slot = &mut state.slots[idx];
+        slot.count -= 1;
+        Some(slot.item)
+    }
 
-        for slot in state.slots.iter_mut() {
-            if slot.count > 0 {
-                let item = slot.item;
-                slot.count -= 1;
-                return Some(item);
-            }
-        }
-
-        None
+    /// Take one item, ignoring output port modes. Used by loaders, which carry
+    /// their own filter. Returns the first stored item matching `filter`.
+    pub fn take_item(&mut self, entity: EntityId, filter: Option<ItemId>) -> Option<ItemId> {
+        let state = self.get_mut(entity)?;
+        let slot = state
+            .slots
+            .iter_mut()
+            .find(|s| s.count > 0 && filter.is_none_or(|f| f == s.item))?;
+        slot.count -= 1;
+        Some(slot.item)
     }
 }
 
@@ -271,7 +390,7 @@ mod tests {
         pool.add(e1);
 
         pool.accept_input(e1, ItemId::Point, 3);
-        let item = pool.provide_output(e1);
+        let item = pool.provide_output(e1, 0);
         assert_eq!(item, Some(ItemId::Point));
         assert_eq!(pool.get(e1).unwrap().slots[0].count, 2);
     }
@@ -282,7 +401,7 @@ mod tests {
         let (_, e1) = make_entity();
         pool.add(e1);
 
-        assert_eq!(pool.provide_output(e1), None);
+        assert_eq!(pool.provide_output(e1, 0), None);
     }
 
     #[test]
@@ -295,10 +414,97 @@ mod tests {
         pool.accept_input(e1, ItemId::LineSegment, 1);
 
         // Should drain from slot 0 (Point) first
-        assert_eq!(pool.provide_output(e1), Some(ItemId::Point));
+        assert_eq!(pool.provide_output(e1, 0), Some(ItemId::Point));
         // Slot 0 is now empty, next should be slot 1 (LineSegment)
-        assert_eq!(pool.provide_output(e1), Some(ItemId::LineSegment));
+        assert_eq!(pool.provide_output(e1, 0), Some(ItemId::LineSegment));
         // Now both empty
-        assert_eq!(pool.provide_output(e1), None);
+        assert_eq!(pool.provide_output(e1, 0), None);
+    }
+
+    #[test]
+    fn whitelist_rejects_other_items() {
+        let mut pool = StoragePool::new();
+        let (_, e1) = make_entity();
+        pool.add(e1);
+        pool.set_whitelist(e1, vec![ItemId::Point]);
+
+        assert!(pool.accept_input(e1, ItemId::Point, 1));
+        assert!(!pool.accept_input(e1, ItemId::LineSegment, 1));
+
+        pool.set_whitelist(e1, Vec::new());
+        assert!(pool.accept_input(e1, ItemId::LineSegment, 1));
+    }
+
+    #[test]
+    fn locked_slot_reserved_for_its_item() {
+        let mut pool = StoragePool::new();
+        let (_, e1) = make_entity();
+        pool.add(e1);
+        pool.set_slot_lock(e1, 0, Some(ItemId::Point));
+
+        // LineSegment skips the locked slot 0
+        assert!(pool.accept_input(e1, ItemId::LineSegment, 1));
+        // Point goes into its reserved slot rather than the next free one
+        assert!(pool.accept_input(e1, ItemId::Point, 1));
+
+        let state = pool.get(e1).unwrap();
+        assert_eq!(state.slots[0].item, ItemId::Point);
+        assert_eq!(state.slots[1].item, ItemId::LineSegment);
+    }
+
+    #[test]
+    fn fully_locked_storage_rejects_other_items() {
+        let mut pool = StoragePool::new();
+        let (_, e1) = make_entity();
+        pool.add(e1);
+        for slot in 0..STORAGE_SLOTS {
+            pool.set_slot_lock(e1, slot, Some(ItemId::Point));
+        }
+
+        assert!(!pool.accept_input(e1, ItemId::NullSet, 1));
+        assert!(pool.accept_input(e1, ItemId::Point, 1));
+    }
+
+    #[test]
+    fn locked_slot_keeps_reservation_when_drained() {
+        let mut pool = StoragePool::new();
+        let (_, e1) = make_entity();
+        pool.add(e1);
+        pool.set_slot_lock(e1, 0, Some(ItemId::Point));
+
+        pool.accept_input(e1, ItemId::Point, 1);
+        assert_eq!(pool.provide_output(e1, 0), Some(ItemId::Point));
+        assert!(pool.accept_input(e1, ItemId::NullSet, 1));
+        assert_eq!(pool.get(e1).unwrap().slots[0].count, 0);
+        assert_eq!(pool.get(e1).unwrap().slots[1].item, ItemId::NullSet);
+    }
+
+    #[test]
+    fn output_filter_only_emits_selected_item() {
+        let mut pool = StoragePool::new();
+        let (_, e1) = make_entity();
+        pool.add(e1);
+        pool.accept_input(e1, ItemId::Point, 1);
+        pool.accept_input(e1, ItemId::LineSegment, 1);
+        pool.set_output_mode(e1, 0, StorageOutputMode { filter: Some(ItemId::LineSegment), keep: 0 });
+
+        assert_eq!(pool.provide_output(e1, 0), Some(ItemId::LineSegment));
+        assert_eq!(pool.provide_output(e1, 0), None);
+        // Port 1 is unfiltered and still sees the Point
+        assert_eq!(pool.provide_output(e1, 1), Some(ItemId::Point));
+    }
+
+    #[test]
+    fn output_keep_at_least_holds_back_stock() {
+        let mut pool = StoragePool::new();
+        let (_, e1) = make_entity();
+        pool.add(e1);
+        pool.accept_input(e1, ItemId::Point, 5);
+        pool.set_output_mode(e1, 0, StorageOutputMode { filter: None, keep: 3 });
+
+        assert_eq!(pool.provide_output(e1, 0), Some(ItemId::Point));
+        assert_eq!(pool.provide_output(e1, 0), Some(ItemId::Point));
+        assert_eq!(pool.provide_output(e1, 0), None);
+        assert_eq!(pool.get(e1).unwrap().count_of(ItemId::Point), 3);
     }
 }
@@ -64,6 +64,8 @@ impl GameLoop {
     }
 
     /// Accumulate frame time and return how many sim ticks should run.
+    /// `sim_tick` only moves when a tick actually runs (`advance_tick`): a
+    /// multiplayer client can be held back waiting for the host.
     pub fn accumulate(&mut self, frame_dt: f64) -> u32 {
         self.accumulator += frame_dt;
 
@@ -73,10 +75,7 @@ impl GameLoop {
         let mut ticks = 0u32;
         while self.accumulator >= SIM_DT {
             self.accumulator -= SIM_DT;
-            self.sim_tick += 1;
             ticks += 1;
-
-            self.ups_ticks += 1;
         }
 
         if self.ups_timer >= 1.0 {
@@ -88,6 +87,13 @@ impl GameLoop {
         ticks
     }
 
+    /// Count a simulation tick as run and return its number (from 1).
+    pub fn advance_tick(&mut self) -> u64 {
+        self.sim_tick += 1;
+        self.ups_ticks += 1;
+        self.sim_tick
+    }
+
     /// Alpha for interpolating between prev and curr camera snapshots.
     pub fn interpolation_alpha(&self) -> f64 {
         self.accumulator / SIM_DT
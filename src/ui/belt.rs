@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use crate::game::items::ItemId;
+use crate::game::world::EntityId;
+use crate::sim::belt::BeltNetwork;
+
+/// Actions the belt panel can produce.
+pub enum BeltAction {
+    /// User switched the segment's throughput sensor on or off.
+    SetSensor(EntityId, bool),
+    /// User closed the panel.
+    Close,
+}
+
+/// Draw the belt inspection panel: items currently on this belt segment and,
+/// with the sensor on, the throughput past it. Returns an action if the user
+/// interacted.
+pub fn belt_panel(
+    ctx: &egui::Context,
+    entity: EntityId,
+    belt_network: &BeltNetwork,
+) -> Option<BeltAction> {
+    let (items, _) = belt_network.entity_items(entity)?;
+
+    let mut counts: BTreeMap<ItemId, u32> = BTreeMap::new();
+    for bi in items {
+        *counts.entry(bi.item).or_insert(0) += 1;
+    }
+
+    let sensor = belt_network.sensor(entity);
+
+    let mut open = true;
+    let mut action = None;
+    let grey = egui::Color32::from_rgb(150, 150, 150);
+
+    egui::Window::new("Belt")
+        .id(egui::Id::new("belt_panel"))
+        .open(&mut open)
+        .collapsible(true)
+        .resizable(false)
+        .default_width(180.0)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("On segment:");
+                ui.label(format!("{}", items.len()));
+            });
+            if counts.is_empty() {
+                ui.colored_label(grey, "  (empty)");
+            }
+            for (item, count) in &counts {
+                ui.label(format!("  {} x{}", item.display_name(), count));
+            }
+
+            ui.separator();
+
+            // --- Throughput sensor ---
+            let mut on = sensor.is_some();
+            if ui.checkbox(&mut on, "Throughput sensor").changed() {
+                action = Some(BeltAction::SetSensor(entity, on));
+            }
+            if let Some(sensor) = sensor {
+                ui.horizontal(|ui| {
+                    ui.label("Rate:");
+                    ui.label(format!("{:.0} items/min", sensor.items_per_minute()));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Passed:");
+                    ui.label(format!("{}", sensor.total));
+                });
+            }
+        });
+
+    if !open {
+        return Some(BeltAction::Close);
+    }
+
+    action
+}
+
+/// Hover tooltip for a belt segment with its sensor on: items on the
+/// segment and items per minute passing. `pos` is in egui points.
+pub fn sensor_tooltip(
+    ctx: &egui::Context,
+    pos: egui::Pos2,
+    entity: EntityId,
+    belt_network: &BeltNetwork,
+) {
+    let (Some(sensor), Some((items, _))) = (belt_network.sensor(entity), belt_network.entity_items(entity)) else {
+        return;
+    };
+    egui::Area::new(egui::Id::new("belt_sensor_tooltip"))
+        .order(egui::Order::Tooltip)
+        .fixed_pos(pos + egui::vec2(16.0, 16.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(format!("{} on segment", items.len()));
+                ui.label(format!("{:.0} items/min", sensor.items_per_minute()));
+            });
+        });
+}
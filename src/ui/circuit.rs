@@ -0,0 +1,267 @@
+use crate::game::items::ItemId;
+use crate::game::world::EntityId;
+use crate::sim::circuit::{
+    ArithOp, CircuitNetwork, CombinatorConfig, Comparator, Condition, Operand, Signals, WireEnd,
+};
+
+/// Actions the circuit and combinator panels can produce.
+pub enum CircuitAction {
+    /// User set or cleared an entity's enable condition.
+    SetCondition(EntityId, Option<Condition>),
+    /// User edited a combinator.
+    SetCombinator(EntityId, CombinatorConfig),
+}
+
+/// Draw the circuit window for an inspected machine, splitter, storage, or
+/// belt: the signals on its network and, for entities that can be switched,
+/// the enable condition editor. Shown alongside the entity's own panel.
+pub fn circuit_panel(
+    ctx: &egui::Context,
+    entity: EntityId,
+    circuit: &CircuitNetwork,
+    switchable: bool,
+) -> Option<CircuitAction> {
+    let wires = circuit.wire_count(entity);
+    let condition = circuit.condition(entity).copied();
+    if wires == 0 && !switchable {
+        return None;
+    }
+
+    let mut action = None;
+    let grey = egui::Color32::from_rgb(150, 150, 150);
+
+    egui::Window::new("Circuit")
+        .id(egui::Id::new("circuit_panel"))
+        .collapsible(true)
+        .resizable(false)
+        .default_width(220.0)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Wires:");
+                ui.label(format!("{wires}"));
+                let size = circuit.network_size(WireEnd::main(entity));
+                if size > 0 {
+                    ui.colored_label(grey, format!("({size} on network)"));
+                }
+            });
+            signal_list(ui, "Network", circuit.network_signals(WireEnd::main(entity)));
+
+            if !switchable {
+                return;
+            }
+            ui.separator();
+
+            // --- Enable condition ---
+            let mut enabled = condition.is_some();
+            if ui.checkbox(&mut enabled, "Enable condition").changed() {
+                let new = enabled.then(Condition::default);
+                action = Some(CircuitAction::SetCondition(entity, new));
+            }
+            if let Some(cond) = condition {
+                if let Some(new) = condition_editor(ui, "circuit_condition", cond) {
+                    action = Some(CircuitAction::SetCondition(entity, Some(new)));
+                }
+                if wires == 0 {
+                    ui.colored_label(grey, "Not wired: always enabled.");
+                }
+            }
+        });
+
+    action
+}
+
+/// Draw the combinator inspection panel. Returns `(action, closed)`.
+pub fn combinator_panel(
+    ctx: &egui::Context,
+    entity: EntityId,
+    circuit: &CircuitNetwork,
+) -> (Option<CircuitAction>, bool) {
+    let Some(state) = circuit.combinator(entity) else {
+        return (None, true);
+    };
+
+    let mut open = true;
+    let mut action = None;
+    let title = match state.config {
+        CombinatorConfig::Arithmetic { .. } => "Arithmetic Combinator",
+        CombinatorConfig::Decider { .. } => "Decider Combinator",
+    };
+
+    egui::Window::new(title)
+        .id(egui::Id::new("combinator_panel"))
+        .open(&mut open)
+        .collapsible(true)
+        .resizable(false)
+        .default_width(240.0)
+        .show(ctx, |ui| {
+            // --- Configuration ---
+            match state.config {
+                CombinatorConfig::Arithmetic { left, op, right, output } => {
+                    let mut cfg = (left, op, right, output);
+                    let mut changed = false;
+                    ui.horizontal(|ui| {
+                        if let Some(item) = signal_combo(ui, "arith_left", cfg.0) {
+                            cfg.0 = item;
+                            changed = true;
+                        }
+                        egui::ComboBox::from_id_salt("arith_op")
+                            .selected_text(cfg.1.symbol())
+                            .width(40.0)
+                            .show_ui(ui, |ui| {
+                                for o in ArithOp::ALL {
+                                    if ui.selectable_label(cfg.1 == o, o.symbol()).clicked() && cfg.1 != o {
+                                        cfg.1 = o;
+                                        changed = true;
+                                    }
+                                }
+                            });
+                        if let Some(operand) = operand_editor(ui, "arith_right", cfg.2) {
+                            cfg.2 = operand;
+                            changed = true;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Output:");
+                        if let Some(item) = signal_combo(ui, "arith_output", cfg.3) {
+                            cfg.3 = item;
+                            changed = true;
+                        }
+                    });
+                    if changed {
+                        let (left, op, right, output) = cfg;
+                        action = Some(CircuitAction::SetCombinator(
+                            entity,
+                            CombinatorConfig::Arithmetic { left, op, right, output },
+                        ));
+                    }
+                }
+                CombinatorConfig::Decider { condition, output, copy_input } => {
+                    let mut cfg = (condition, output, copy_input);
+                    let mut changed = false;
+                    if let Some(c) = condition_editor(ui, "decider_condition", cfg.0) {
+                        cfg.0 = c;
+                        changed = true;
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Output:");
+                        if let Some(item) = signal_combo(ui, "decider_output", cfg.1) {
+                            cfg.1 = item;
+                            changed = true;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        for (copy, label) in [(false, "1"), (true, "Input count")] {
+                            if ui.selectable_label(cfg.2 == copy, label).clicked() && cfg.2 != copy {
+                                cfg.2 = copy;
+                                changed = true;
+                            }
+                        }
+                    });
+                    if changed {
+                        let (condition, output, copy_input) = cfg;
+                        action = Some(CircuitAction::SetCombinator(
+                            entity,
+                            CombinatorConfig::Decider { condition, output, copy_input },
+                        ));
+                    }
+                }
+            }
+
+            ui.separator();
+
+            // --- Signals ---
+            signal_list(ui, "Input", circuit.network_signals(WireEnd::main(entity)));
+            signal_list(ui, "Output", Some(&state.output));
+        });
+
+    (action, !open)
+}
+
+/// Labelled list of signal values (or "Not wired").
+fn signal_list(ui: &mut egui::Ui, label: &str, signals: Option<&Signals>) {
+    let grey = egui::Color32::from_rgb(150, 150, 150);
+    ui.label(format!("{label}:"));
+    match signals {
+        None => {
+            ui.colored_label(grey, "  Not wired");
+        }
+        Some(s) if s.is_empty() => {
+            ui.colored_label(grey, "  (no signals)");
+        }
+        Some(s) => {
+            for (item, value) in s {
+                ui.label(format!("  {} {}", item.display_name(), value));
+            }
+        }
+    }
+}
+
+/// Combo box over every item. Returns the new item if the user picked one.
+fn signal_combo(ui: &mut egui::Ui, id_salt: &str, current: ItemId) -> Option<ItemId> {
+    let mut picked = None;
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(current.display_name())
+        .width(110.0)
+        .show_ui(ui, |ui| {
+            for &item in ItemId::all() {
+                if ui.selectable_label(current == item, item.display_name()).clicked() && current != item {
+                    picked = Some(item);
+                }
+            }
+        });
+    picked
+}
+
+/// Constant or signal picker. Returns the new operand if it changed.
+fn operand_editor(ui: &mut egui::Ui, id_salt: &str, current: Operand) -> Option<Operand> {
+    let mut picked = None;
+    let selected = match current {
+        Operand::Constant(_) => "Constant",
+        Operand::Signal(item) => item.display_name(),
+    };
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(selected)
+        .width(90.0)
+        .show_ui(ui, |ui| {
+            let is_const = matches!(current, Operand::Constant(_));
+            if ui.selectable_label(is_const, "Constant").clicked() && !is_const {
+                picked = Some(Operand::Constant(0));
+            }
+            for &item in ItemId::all() {
+                let is_this = current == Operand::Signal(item);
+                if ui.selectable_label(is_this, item.display_name()).clicked() && !is_this {
+                    picked = Some(Operand::Signal(item));
+                }
+            }
+        });
+    if let Operand::Constant(mut value) = current {
+        if ui.add(egui::DragValue::new(&mut value)).changed() {
+            picked = Some(Operand::Constant(value));
+        }
+    }
+    picked
+}
+
+/// `signal cmp operand` editor. Returns the new condition if it changed.
+fn condition_editor(ui: &mut egui::Ui, id_salt: &str, current: Condition) -> Option<Condition> {
+    let mut cond = current;
+    ui.horizontal(|ui| {
+        if let Some(item) = signal_combo(ui, &format!("{id_salt}_left"), cond.left) {
+            cond.left = item;
+        }
+        egui::ComboBox::from_id_salt(format!("{id_salt}_cmp"))
+            .selected_text(cond.cmp.symbol())
+            .width(40.0)
+            .show_ui(ui, |ui| {
+                for c in Comparator::ALL {
+                    if ui.selectable_label(cond.cmp == c, c.symbol()).clicked() {
+                        cond.cmp = c;
+                    }
+                }
+            });
+        if let Some(operand) = operand_editor(ui, &format!("{id_salt}_right"), cond.right) {
+            cond.right = operand;
+        }
+    });
+    (cond != current).then_some(cond)
+}
@@ -0,0 +1,92 @@
+use crate::game::items::ItemId;
+use crate::game::world::EntityId;
+use crate::sim::loader::{LoaderMode, LoaderPool, LoaderTarget};
+
+/// Actions the loader panel can produce.
+pub enum LoaderAction {
+    /// User flipped between loading and unloading.
+    ToggleMode(EntityId),
+    /// User changed the item filter (`None` = move anything).
+    SetFilter(EntityId, Option<ItemId>),
+    /// User closed the panel.
+    Close,
+}
+
+/// Draw the loader inspection panel. Returns an action if the user interacted.
+pub fn loader_panel(
+    ctx: &egui::Context,
+    entity: EntityId,
+    loader_pool: &LoaderPool,
+) -> Option<LoaderAction> {
+    let state = loader_pool.get(entity)?;
+
+    let mut open = true;
+    let mut action = None;
+    let grey = egui::Color32::from_rgb(150, 150, 150);
+
+    egui::Window::new("Loader")
+        .open(&mut open)
+        .collapsible(true)
+        .resizable(false)
+        .default_width(200.0)
+        .show(ctx, |ui| {
+            // --- Attachments ---
+            ui.horizontal(|ui| {
+                ui.label("Attached to:");
+                match state.target {
+                    Some(LoaderTarget::Storage(_)) => ui.label("Storage"),
+                    Some(LoaderTarget::Machine(_)) => ui.label("Machine"),
+                    None => ui.colored_label(grey, "Nothing"),
+                };
+            });
+            ui.horizontal(|ui| {
+                ui.label("Belt:");
+                if state.belt.is_some() {
+                    ui.label("Connected");
+                } else {
+                    ui.colored_label(grey, "None");
+                }
+            });
+
+            ui.separator();
+
+            // --- Direction ---
+            ui.horizontal(|ui| {
+                ui.label("Mode:");
+                let text = match state.mode {
+                    LoaderMode::Load => "Load (belt → building)",
+                    LoaderMode::Unload => "Unload (building → belt)",
+                };
+                if ui.button(text).clicked() {
+                    action = Some(LoaderAction::ToggleMode(entity));
+                }
+            });
+
+            // --- Filter ---
+            ui.horizontal(|ui| {
+                ui.label("Filter:");
+                let current = state.filter;
+                egui::ComboBox::from_id_salt("loader_filter")
+                    .selected_text(current.map(|i| i.display_name()).unwrap_or("Any"))
+                    .width(120.0)
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(current.is_none(), "Any").clicked() && current.is_some() {
+                            action = Some(LoaderAction::SetFilter(entity, None));
+                        }
+                        for &item in ItemId::all() {
+                            if ui.selectable_label(current == Some(item), item.display_name()).clicked()
+                                && current != Some(item)
+                            {
+                                action = Some(LoaderAction::SetFilter(entity, Some(item)));
+                            }
+                        }
+                    });
+            });
+        });
+
+    if !open {
+        return Some(LoaderAction::Close);
+    }
+
+    action
+}
@@ -1,5 +1,6 @@
 use crate::game::recipes::RecipeIndex;
-use crate::game::world::EntityId;
+use crate::game::world::{Direction, EntityId};
+use crate::sim::inserter::{port_positions, PortKind};
 use crate::sim::machine::{MachinePool, MachineState, MAX_SLOTS};
 use super::icons::IconAtlas;
 
@@ -7,6 +8,10 @@ use super::icons::IconAtlas;
 pub enum MachineAction {
     /// User selected a recipe (or cleared it with None).
     SetRecipe(EntityId, Option<usize>),
+    /// User moved a port (index into the machine's layout) to a new canonical cell and side.
+    MovePort { machine: EntityId, port: usize, cell_offset: (i32, i32), side: Direction },
+    /// User restored the machine type's default port layout.
+    ResetPorts(EntityId),
     /// User closed the panel.
     Close,
 }
@@ -20,13 +25,18 @@ pub fn machine_panel(
     icons: &IconAtlas,
 ) -> Option<MachineAction> {
     let idx = machine_pool.index_of(entity)?;
-    let machine_type = machine_pool.cold.machine_type[idx];
+    let machine_type = machine_pool.machine_type(entity)?;
     let state = machine_pool.hot.state[idx];
     let progress = machine_pool.hot.progress[idx];
     let power_draw = machine_pool.hot.power_draw[idx];
-    let current_recipe = machine_pool.cold.recipe[idx];
-    let input_slots = &machine_pool.cold.input_slots[idx];
-    let output_slots = &machine_pool.cold.output_slots[idx];
+    let enabled = machine_pool.hot.enabled[idx];
+    let current_recipe = machine_pool.recipe(entity)?;
+    let input_slots = machine_pool.input_slots(entity)?;
+    let output_slots = machine_pool.output_slots(entity)?;
+    let ports = &machine_pool.cold.ports[idx];
+    let fluid_in = machine_pool.fluid_input_tank(entity)?;
+    let fluid_out = machine_pool.fluid_output_tank(entity)?;
+    let fluid_ports = crate::sim::inserter::fluid_port_layout(machine_type);
 
     let mut action = None;
 
@@ -42,7 +52,11 @@ pub fn machine_panel(
             // --- Status ---
             ui.horizontal(|ui| {
                 ui.label("Status:");
-                let (status_text, status_color) = state_display(state);
+                let (status_text, status_color) = if !enabled {
+                    ("Disabled (circuit)", egui::Color32::from_rgb(200, 100, 100))
+                } else {
+                    state_display(state)
+                };
                 ui.colored_label(status_color, status_text);
             });
 
@@ -111,6 +125,73 @@ pub fn machine_panel(
             // --- Output slots ---
             ui.label("Outputs:");
             slot_grid(ui, output_slots, icons);
+
+            // --- Fluid tanks ---
+            if !fluid_ports.is_empty() {
+                ui.add_space(4.0);
+                for port in fluid_ports {
+                    let (label, tank) = match port.kind {
+                        PortKind::Input => ("Fluid in:", fluid_in),
+                        PortKind::Output => ("Fluid out:", fluid_out),
+                    };
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        tank_bar(ui, tank);
+                    });
+                }
+            }
+
+            ui.separator();
+
+            // --- Port layout ---
+            egui::CollapsingHeader::new("Ports")
+                .id_salt("machine_ports")
+                .show(ui, |ui| {
+                    ui.label(
+                        egui::RichText::new("Sides are relative to the machine's facing.")
+                            .color(egui::Color32::from_rgb(150, 150, 150))
+                            .size(11.0),
+                    );
+                    let positions = port_positions(machine_type.footprint());
+                    egui::Grid::new("machine_port_grid")
+                        .num_columns(2)
+                        .spacing([8.0, 2.0])
+                        .show(ui, |ui| {
+                            for (i, port) in ports.iter().enumerate() {
+                                let name = match port.kind {
+                                    PortKind::Input => format!("In {}", port.slot + 1),
+                                    PortKind::Output => format!("Out {}", port.slot + 1),
+                                };
+                                ui.label(name);
+                                egui::ComboBox::from_id_salt(("machine_port", i))
+                                    .selected_text(position_label(port.cell_offset, port.side))
+                                    .width(140.0)
+                                    .show_ui(ui, |ui| {
+                                        for &(cell, side) in &positions {
+                                            let current = cell == port.cell_offset && side == port.side;
+                                            let taken = !current
+                                                && ports.iter().any(|p| p.cell_offset == cell && p.side == side);
+                                            let response = ui.add_enabled(
+                                                !taken,
+                                                egui::Button::selectable(current, position_label(cell, side)),
+                                            );
+                                            if response.clicked() && !current {
+                                                action = Some(MachineAction::MovePort {
+                                                    machine: entity,
+                                                    port: i,
+                                                    cell_offset: cell,
+                                                    side,
+                                                });
+                                            }
+                                        }
+                                    });
+                                ui.end_row();
+                            }
+                        });
+                    if ui.button("Reset to default").clicked() {
+                        action = Some(MachineAction::ResetPorts(entity));
+                    }
+                });
         });
 
     if !open {
@@ -120,6 +201,11 @@ pub fn machine_panel(
     action
 }
 
+/// Human-readable port position, e.g. "North (1,0)".
+fn position_label(cell: (i32, i32), side: Direction) -> String {
+    format!("{:?} ({},{})", side, cell.0, cell.1)
+}
+
 /// Render a row of item slots.
 fn slot_grid(
     ui: &mut egui::Ui,
@@ -171,13 +257,39 @@ fn slot_grid(
     });
 }
 
+/// Render a fluid tank as a filled bar labelled with its contents.
+fn tank_bar(ui: &mut egui::Ui, tank: crate::sim::fluid::FluidTank) {
+    let cap = crate::sim::machine::MACHINE_TANK_CAPACITY;
+    let (text, color) = match tank.fluid {
+        Some(fluid) => {
+            let [r, g, b] = fluid.color();
+            (
+                format!("{} {}/{}", fluid.display_name(), tank.amount, cap),
+                egui::Color32::from_rgb((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8),
+            )
+        }
+        None => (format!("Empty 0/{}", cap), egui::Color32::from_rgb(60, 60, 60)),
+    };
+    ui.add(
+        egui::ProgressBar::new(tank.amount as f32 / cap as f32)
+            .text(text)
+            .fill(color)
+            .desired_width(180.0),
+    );
+}
+
 /// Format a recipe as a label for the dropdown.
 fn recipe_label(recipe: &crate::game::items::Recipe) -> String {
-    if recipe.inputs.is_empty() {
-        // Source machine: just show the output item name
-        return recipe.output.display_name().to_string();
+    let mut outputs = Vec::new();
+    if recipe.output_count > 0 {
+        outputs.push(recipe.output.display_name().to_string());
     }
-    let inputs: Vec<String> = recipe
+    if let Some((fluid, amount)) = recipe.fluid_output {
+        outputs.push(format!("{} {}", amount, fluid.display_name()));
+    }
+    let outputs = outputs.join(" + ");
+
+    let mut inputs: Vec<String> = recipe
         .inputs
         .iter()
         .map(|(id, count)| {
@@ -188,11 +300,14 @@ fn recipe_label(recipe: &crate::game::items::Recipe) -> String {
             }
         })
         .collect();
-    format!(
-        "{} -> {}",
-        inputs.join(" + "),
-        recipe.output.display_name()
-    )
+    if let Some((fluid, amount)) = recipe.fluid_input {
+        inputs.push(format!("{} {}", amount, fluid.display_name()));
+    }
+    if inputs.is_empty() {
+        // Source machine: just show the output
+        return outputs;
+    }
+    format!("{} -> {}", inputs.join(" + "), outputs)
 }
 
 /// Status text and color for each machine state.
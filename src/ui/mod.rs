@@ -8,3 +8,8 @@ pub mod placement;
 pub mod machine;
 pub mod splitter;
 pub mod storage;
+pub mod sink;
+pub mod loader;
+pub mod rail;
+pub mod belt;
+pub mod circuit;
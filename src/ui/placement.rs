@@ -12,12 +12,16 @@ pub struct PlacementMode {
     pub direction: Direction,
 }
 
-/// All items that have a StructureKind (i.e. can be placed on the grid).
+/// All items that have a StructureKind (i.e. can be placed on the grid),
+/// plus rolling stock, which is placed onto rails.
 fn all_placeable_items() -> Vec<ItemId> {
     ItemId::all()
         .iter()
         .copied()
-        .filter(|id| StructureKind::from_item(*id).is_some())
+        .filter(|id| {
+            StructureKind::from_item(*id).is_some()
+                || matches!(id, ItemId::Locomotive | ItemId::CargoWagon)
+        })
         .collect()
 }
 
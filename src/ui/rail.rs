@@ -0,0 +1,193 @@
+use crate::game::world::EntityId;
+use crate::sim::rail::{RailNetwork, StationMode, TrainId, TrainState, MAX_WAGONS, WAGON_CAPACITY};
+
+/// Actions the station panel can produce.
+pub enum StationAction {
+    /// User switched between loading and unloading.
+    SetMode(EntityId, StationMode),
+    /// User closed the panel.
+    Close,
+}
+
+/// Actions the train panel can produce.
+pub enum TrainAction {
+    /// User appended a station to the schedule.
+    AddStop(TrainId, EntityId),
+    /// User removed the schedule entry at an index.
+    RemoveStop(TrainId, usize),
+    /// User started or stopped the train.
+    SetRunning(TrainId, bool),
+    /// User closed the panel.
+    Close,
+}
+
+/// Draw the train station inspection panel. Returns an action if the user interacted.
+pub fn station_panel(
+    ctx: &egui::Context,
+    entity: EntityId,
+    rail_network: &RailNetwork,
+) -> Option<StationAction> {
+    let state = rail_network.station(entity)?;
+
+    let mut open = true;
+    let mut action = None;
+    let grey = egui::Color32::from_rgb(150, 150, 150);
+
+    egui::Window::new(&state.name)
+        .id(egui::Id::new("station_panel"))
+        .open(&mut open)
+        .collapsible(true)
+        .resizable(false)
+        .default_width(200.0)
+        .show(ctx, |ui| {
+            // --- Mode ---
+            ui.horizontal(|ui| {
+                ui.label("Mode:");
+                for (mode, label) in [(StationMode::Load, "Load"), (StationMode::Unload, "Unload")] {
+                    if ui.selectable_label(state.mode == mode, label).clicked() && state.mode != mode {
+                        action = Some(StationAction::SetMode(entity, mode));
+                    }
+                }
+            });
+
+            ui.separator();
+
+            // --- Attachments ---
+            ui.horizontal(|ui| {
+                ui.label("Stop rails:");
+                if state.stops.is_empty() {
+                    ui.colored_label(grey, "None");
+                } else {
+                    ui.label(format!("{}", state.stops.len()));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Storage:");
+                if state.storages.is_empty() {
+                    ui.colored_label(grey, "None");
+                } else {
+                    ui.label(format!("{}", state.storages.len()));
+                }
+            });
+
+            // --- Trains currently stopped here ---
+            let stopped = rail_network
+                .trains()
+                .iter()
+                .filter(|t| matches!(t.state, TrainState::AtStation { station, .. } if station == entity))
+                .count();
+            ui.horizontal(|ui| {
+                ui.label("Trains stopped:");
+                ui.label(format!("{stopped}"));
+            });
+        });
+
+    if !open {
+        return Some(StationAction::Close);
+    }
+
+    action
+}
+
+/// Draw the train inspection panel: cargo, status, and schedule editor.
+/// Returns an action if the user interacted.
+pub fn train_panel(
+    ctx: &egui::Context,
+    id: TrainId,
+    rail_network: &RailNetwork,
+) -> Option<TrainAction> {
+    let train = rail_network.train(id)?;
+
+    let mut open = true;
+    let mut action = None;
+    let grey = egui::Color32::from_rgb(150, 150, 150);
+    let station_name = |e: EntityId| {
+        rail_network
+            .station(e)
+            .map(|s| s.name.clone())
+            .unwrap_or_else(|| "(removed)".to_string())
+    };
+
+    egui::Window::new(format!("Train {}", id.0 + 1))
+        .id(egui::Id::new("train_panel"))
+        .open(&mut open)
+        .collapsible(true)
+        .resizable(false)
+        .default_width(220.0)
+        .show(ctx, |ui| {
+            // --- Status ---
+            let status = match &train.state {
+                TrainState::Idle => "Idle".to_string(),
+                TrainState::Travelling { .. } => match train.schedule.get(train.next_stop) {
+                    Some(&s) => format!("To {}", station_name(s)),
+                    None => "Travelling".to_string(),
+                },
+                TrainState::AtStation { station, .. } => format!("At {}", station_name(*station)),
+                TrainState::NoPath { .. } => "No route".to_string(),
+            };
+            ui.horizontal(|ui| {
+                ui.label("Status:");
+                if matches!(train.state, TrainState::NoPath { .. }) {
+                    ui.colored_label(egui::Color32::from_rgb(220, 90, 70), status);
+                } else {
+                    ui.label(status);
+                }
+            });
+            let label = if train.running { "Stop" } else { "Start" };
+            if ui.button(label).clicked() {
+                action = Some(TrainAction::SetRunning(id, !train.running));
+            }
+
+            ui.separator();
+
+            // --- Cargo ---
+            ui.label(format!(
+                "Wagons: {}/{}  ({} items)",
+                train.wagons.len(),
+                MAX_WAGONS,
+                train.cargo_total(),
+            ));
+            if train.wagons.is_empty() {
+                ui.colored_label(grey, "Place cargo wagons on this rail to couple them.");
+            }
+            for (i, wagon) in train.wagons.iter().enumerate() {
+                ui.label(format!("  Wagon {}: {}/{}", i + 1, wagon.total(), WAGON_CAPACITY));
+                for &(item, count) in &wagon.cargo {
+                    ui.label(format!("    {} x{}", item.display_name(), count));
+                }
+            }
+
+            ui.separator();
+
+            // --- Schedule ---
+            ui.label("Schedule:");
+            if train.schedule.is_empty() {
+                ui.colored_label(grey, "  (empty)");
+            }
+            for (i, &stop) in train.schedule.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let marker = if i == train.next_stop { "▶" } else { " " };
+                    ui.label(format!("{marker} {}", station_name(stop)));
+                    if ui.small_button("✕").clicked() {
+                        action = Some(TrainAction::RemoveStop(id, i));
+                    }
+                });
+            }
+            egui::ComboBox::from_id_salt("train_add_stop")
+                .selected_text("Add stop…")
+                .width(160.0)
+                .show_ui(ui, |ui| {
+                    for station in rail_network.stations() {
+                        if ui.selectable_label(false, &station.name).clicked() {
+                            action = Some(TrainAction::AddStop(id, station.entity));
+                        }
+                    }
+                });
+        });
+
+    if !open {
+        return Some(TrainAction::Close);
+    }
+
+    action
+}
@@ -1,5 +1,6 @@
 use crate::game::config::GameConfig;
 use crate::game::input::{GameAction, InputState};
+use crate::hyperbolic::embedding::Projection;
 
 pub fn settings_menu(
     ctx: &egui::Context,
@@ -69,14 +70,32 @@ pub fn settings_menu(
                         ui.label("Frame Rate Cap:");
                         ui.add(egui::Slider::new(&mut config.graphics.frame_rate_cap, 30..=144));
                     });
+                    ui.horizontal(|ui| {
+                        ui.label("Projection:");
+                        egui::ComboBox::from_id_salt("projection")
+                            .selected_text(config.graphics.projection.display_name())
+                            .show_ui(ui, |ui| {
+                                for projection in Projection::ALL {
+                                    ui.selectable_value(
+                                        &mut config.graphics.projection,
+                                        projection,
+                                        projection.display_name(),
+                                    );
+                                }
+                            });
+                    });
                 }
                 SettingsTab::Gameplay => {
                     ui.horizontal(|ui| {
                         ui.label("Tiling n (in {4,n}):");
-                        ui.add_enabled(false, egui::Slider::new(&mut config.gameplay.tiling_n, 5..=8));
+                        ui.add(egui::Slider::new(&mut config.gameplay.tiling_n, 5..=8));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Corner exclusion radius:");
+                        ui.add(egui::Slider::new(&mut config.gameplay.corner_exclusion, 0..=8));
                     });
                     ui.label(
-                        egui::RichText::new("Determined by in-game tiling.")
+                        egui::RichText::new("Takes effect on next launch.")
                             .small()
                             .weak(),
                     );
@@ -0,0 +1,80 @@
+use crate::game::items::ItemId;
+use crate::game::world::EntityId;
+use crate::sim::sink::SinkPool;
+
+/// Actions the sink panel can produce.
+pub enum SinkAction {
+    /// User changed the item filter (`None` = destroy anything).
+    SetFilter(EntityId, Option<ItemId>),
+    /// User reset the consumed tallies.
+    ResetCounts(EntityId),
+    /// User closed the panel.
+    Close,
+}
+
+/// Draw the sink inspection panel. Returns an action if the user interacted.
+pub fn sink_panel(
+    ctx: &egui::Context,
+    entity: EntityId,
+    sink_pool: &SinkPool,
+) -> Option<SinkAction> {
+    let state = sink_pool.get(entity)?;
+
+    let mut open = true;
+    let mut action = None;
+
+    // Sort tallies by item order for a stable display
+    let mut consumed: Vec<(ItemId, u64)> = ItemId::all()
+        .iter()
+        .filter_map(|&item| state.consumed.get(&item).map(|&n| (item, n)))
+        .collect();
+    consumed.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+
+    egui::Window::new("Void")
+        .open(&mut open)
+        .collapsible(true)
+        .resizable(false)
+        .default_width(200.0)
+        .show(ctx, |ui| {
+            // --- Filter ---
+            ui.horizontal(|ui| {
+                ui.label("Destroys:");
+                let current = state.filter;
+                egui::ComboBox::from_id_salt("sink_filter")
+                    .selected_text(current.map(|i| i.display_name()).unwrap_or("Anything"))
+                    .width(120.0)
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(current.is_none(), "Anything").clicked() && current.is_some() {
+                            action = Some(SinkAction::SetFilter(entity, None));
+                        }
+                        for &item in ItemId::all() {
+                            if ui.selectable_label(current == Some(item), item.display_name()).clicked()
+                                && current != Some(item)
+                            {
+                                action = Some(SinkAction::SetFilter(entity, Some(item)));
+                            }
+                        }
+                    });
+            });
+
+            ui.separator();
+
+            // --- Consumed tallies ---
+            ui.horizontal(|ui| {
+                ui.label("Consumed:");
+                ui.label(format!("{}", state.total_consumed()));
+            });
+            for (item, count) in &consumed {
+                ui.label(format!("  {} x{}", item.display_name(), count));
+            }
+            if !consumed.is_empty() && ui.button("Reset").clicked() {
+                action = Some(SinkAction::ResetCounts(entity));
+            }
+        });
+
+    if !open {
+        return Some(SinkAction::Close);
+    }
+
+    action
+}
@@ -1,8 +1,13 @@
+use crate::game::items::ItemId;
 use crate::game::world::{Direction, EntityId, WorldState};
-use crate::sim::splitter::{SplitterMode, SplitterPool};
+use crate::sim::splitter::{SplitterMode, SplitterPool, SplitterState};
 
 /// Actions the splitter panel can produce.
 pub enum SplitterAction {
+    /// User set (or cleared) the item filter on a connected belt's port.
+    SetFilter { splitter: EntityId, belt: EntityId, filter: Option<ItemId> },
+    /// User toggled the priority flag on a connected belt's port.
+    SetPriority { splitter: EntityId, belt: EntityId, priority: bool },
     /// User closed the panel.
     Close,
 }
@@ -17,6 +22,7 @@ pub fn splitter_panel(
     let state = splitter_pool.get(entity)?;
 
     let mut open = true;
+    let mut action = None;
 
     egui::Window::new("Splitter")
         .open(&mut open)
@@ -73,6 +79,26 @@ pub fn splitter_panel(
                         }
                     });
                 }
+
+                ui.separator();
+
+                // --- Per-port filter and priority ---
+                ui.label("Ports:");
+                egui::Grid::new("splitter_ports")
+                    .num_columns(3)
+                    .spacing([8.0, 4.0])
+                    .show(ui, |ui| {
+                        for &belt in &state.inputs {
+                            if let Some(a) = port_row(ui, state, entity, belt, true, world) {
+                                action = Some(a);
+                            }
+                        }
+                        for &belt in &state.outputs {
+                            if let Some(a) = port_row(ui, state, entity, belt, false, world) {
+                                action = Some(a);
+                            }
+                        }
+                    });
             }
         });
 
@@ -80,7 +106,51 @@ pub fn splitter_panel(
         return Some(SplitterAction::Close);
     }
 
-    None
+    action
+}
+
+/// Draw one grid row for a connected belt: side label, filter selector, priority toggle.
+fn port_row(
+    ui: &mut egui::Ui,
+    state: &SplitterState,
+    splitter: EntityId,
+    belt: EntityId,
+    is_input: bool,
+    world: &WorldState,
+) -> Option<SplitterAction> {
+    let mut action = None;
+    let config = state.port(belt);
+
+    let (kind, color) = if is_input {
+        ("In", egui::Color32::from_rgb(80, 130, 255))
+    } else {
+        ("Out", egui::Color32::from_rgb(255, 153, 51))
+    };
+    let side = belt_direction_label(belt, splitter, world).unwrap_or_default();
+    ui.colored_label(color, format!("{} {}", kind, side));
+
+    let filter_text = config.filter.map(|f| f.display_name()).unwrap_or("Any");
+    egui::ComboBox::from_id_salt(("splitter_filter", belt))
+        .selected_text(filter_text)
+        .width(130.0)
+        .show_ui(ui, |ui| {
+            if ui.selectable_label(config.filter.is_none(), "Any").clicked() {
+                action = Some(SplitterAction::SetFilter { splitter, belt, filter: None });
+            }
+            for &item in ItemId::all() {
+                if ui.selectable_label(config.filter == Some(item), item.display_name()).clicked() {
+                    action = Some(SplitterAction::SetFilter { splitter, belt, filter: Some(item) });
+                }
+            }
+        });
+
+    let mut priority = config.priority;
+    if ui.checkbox(&mut priority, "Priority").changed() {
+        action = Some(SplitterAction::SetPriority { splitter, belt, priority });
+    }
+    ui.end_row();
+
+    action
 }
 
 /// Determine which side of the splitter a belt is on, return as a label like "N", "E", "S", "W".
@@ -1,9 +1,18 @@
+use crate::game::items::ItemId;
 use crate::game::world::EntityId;
 use crate::sim::belt::BeltNetwork;
-use crate::sim::storage::{StoragePool, STORAGE_SLOTS, STORAGE_STACK_SIZE};
+use crate::sim::storage::{
+    StorageOutputMode, StoragePool, STORAGE_OUTPUT_PORTS, STORAGE_SLOTS, STORAGE_STACK_SIZE,
+};
 
 /// Actions the storage panel can produce.
 pub enum StorageAction {
+    /// User replaced the input whitelist (empty = accept anything).
+    SetWhitelist(EntityId, Vec<ItemId>),
+    /// User locked a slot to an item, or unlocked it with `None`.
+    SetSlotLock(EntityId, usize, Option<ItemId>),
+    /// User changed an output port's mode.
+    SetOutputMode(EntityId, usize, StorageOutputMode),
     /// User closed the panel.
     Close,
 }
@@ -18,6 +27,7 @@ pub fn storage_panel(
     let state = storage_pool.get(entity)?;
 
     let mut open = true;
+    let mut action = None;
     let (input_count, output_count) = belt_network.storage_connection_counts(entity);
 
     // Count total items stored
@@ -86,11 +96,120 @@ pub fn storage_panel(
                     }
                 }
             }
+
+            ui.separator();
+
+            // --- Input whitelist ---
+            ui.horizontal(|ui| {
+                ui.label("Accepts:");
+                if state.whitelist.is_empty() {
+                    ui.colored_label(egui::Color32::from_rgb(150, 150, 150), "Anything");
+                }
+            });
+            for &item in &state.whitelist {
+                ui.horizontal(|ui| {
+                    ui.label(format!("  {}", item.display_name()));
+                    if ui.small_button("x").clicked() {
+                        let list = state.whitelist.iter().copied().filter(|&i| i != item).collect();
+                        action = Some(StorageAction::SetWhitelist(entity, list));
+                    }
+                });
+            }
+            egui::ComboBox::from_id_salt("storage_whitelist_add")
+                .selected_text("Add item...")
+                .width(160.0)
+                .show_ui(ui, |ui| {
+                    for &item in ItemId::all() {
+                        if state.whitelist.contains(&item) {
+                            continue;
+                        }
+                        if ui.selectable_label(false, item.display_name()).clicked() {
+                            let mut list = state.whitelist.clone();
+                            list.push(item);
+                            action = Some(StorageAction::SetWhitelist(entity, list));
+                        }
+                    }
+                });
+
+            ui.separator();
+
+            // --- Output port modes ---
+            ui.label("Outputs:");
+            for port in 0..STORAGE_OUTPUT_PORTS {
+                let mode = state.output_modes[port];
+                ui.horizontal(|ui| {
+                    ui.label(format!("  Port {}", port + 1));
+                    if let Some(filter) = item_selector(ui, ("storage_out", port), mode.filter, "Any") {
+                        action = Some(StorageAction::SetOutputMode(
+                            entity, port, StorageOutputMode { filter, ..mode },
+                        ));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("    Keep at least");
+                    let mut keep = mode.keep;
+                    let max_keep = (STORAGE_SLOTS as u16) * STORAGE_STACK_SIZE;
+                    if ui.add(egui::DragValue::new(&mut keep).range(0..=max_keep)).changed() {
+                        action = Some(StorageAction::SetOutputMode(
+                            entity, port, StorageOutputMode { keep, ..mode },
+                        ));
+                    }
+                });
+            }
+
+            ui.separator();
+
+            // --- Slot locks ---
+            egui::CollapsingHeader::new("Slot locks")
+                .id_salt("storage_slot_locks")
+                .show(ui, |ui| {
+                    egui::Grid::new("storage_slot_lock_grid")
+                        .num_columns(3)
+                        .spacing([8.0, 2.0])
+                        .show(ui, |ui| {
+                            for (i, slot) in state.slots.iter().enumerate() {
+                                ui.label(format!("{:>2}", i + 1));
+                                if slot.count > 0 {
+                                    ui.label(format!("{} x{}", slot.item.display_name(), slot.count));
+                                } else {
+                                    ui.colored_label(egui::Color32::from_rgb(150, 150, 150), "empty");
+                                }
+                                if let Some(lock) = item_selector(ui, ("storage_lock", i), state.slot_locks[i], "Unlocked") {
+                                    action = Some(StorageAction::SetSlotLock(entity, i, lock));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
         });
 
     if !open {
         return Some(StorageAction::Close);
     }
 
-    None
+    action
+}
+
+/// Item drop-down with a "none" entry. Returns the new selection if it changed.
+fn item_selector(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    current: Option<ItemId>,
+    none_label: &str,
+) -> Option<Option<ItemId>> {
+    let mut picked = None;
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(current.map(|i| i.display_name()).unwrap_or(none_label))
+        .width(120.0)
+        .show_ui(ui, |ui| {
+            if ui.selectable_label(current.is_none(), none_label).clicked() {
+                picked = Some(None);
+            }
+            for &item in ItemId::all() {
+                if ui.selectable_label(current == Some(item), item.display_name()).clicked() {
+                    picked = Some(Some(item));
+                }
+            }
+        });
+    picked.filter(|&p| p != current)
 }